@@ -0,0 +1,59 @@
+//! memetool's browser inside another eframe app, next to the app's own panel.
+//!
+//! `cargo run --example embedded`
+
+use eframe::egui;
+use memetool::background::background;
+use memetool::config::Configuration;
+use memetool::MemeTool;
+use tokio::runtime::Runtime;
+
+struct Host {
+    memetool: MemeTool,
+    clicks: usize,
+}
+
+impl eframe::App for Host {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::left("host_panel").show(ctx, |ui| {
+            ui.heading("Host app");
+            if ui.button("Click me").clicked() {
+                self.clicks += 1;
+            }
+            ui.label(format!("Clicked {} times", self.clicks));
+        });
+        // memetool gets whatever's left
+        egui::CentralPanel::default().show(ctx, |ui| self.memetool.ui(ui));
+    }
+}
+
+fn main() -> Result<(), eframe::Error> {
+    // memetool doesn't set up logging, that's up to the app it's in
+    pretty_env_logger::init();
+
+    let rt = Runtime::new().expect("Unable to create Runtime");
+    let _enter = rt.enter();
+
+    let configuration = Configuration::try_new_or_default();
+    let (foreground_tx, foreground_rx) =
+        tokio::sync::mpsc::channel(configuration.channel_capacity());
+    let (background_tx, background_rx) =
+        tokio::sync::mpsc::channel(configuration.channel_capacity());
+    rt.spawn(background(background_rx, foreground_tx));
+
+    eframe::run_native(
+        "memetool, embedded",
+        eframe::NativeOptions::default(),
+        Box::new(|cc| {
+            Box::new(Host {
+                memetool: MemeTool::new_with_config(
+                    cc,
+                    foreground_rx,
+                    background_tx,
+                    Some(configuration),
+                ),
+                clicks: 0,
+            })
+        }),
+    )
+}
@@ -3,96 +3,994 @@
 //!
 //!
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use log::*;
 use tokio::sync::mpsc;
 
-use crate::image_utils::load_image_to_thumbnail_async;
-use crate::{AppMsg, ThumbImageMsg};
+use crate::color_analysis::ColorAnalysis;
+use crate::dir_scan::{scan_directory, SCAN_CHUNK_SIZE};
+use crate::dir_stats::DirStats;
+use crate::file_ops::copy_then_delete;
+use crate::format_sniff::{sniff_file, SniffedFormat};
+use crate::image_utils::{
+    detect_image_issues, generate_contact_sheet, generate_mosaic, load_image_from_url,
+    load_image_to_thumbnail_async, load_image_to_thumbnail_with_stats_async,
+};
+use crate::integrity::{check_file, Verdict};
+use crate::jobs::{run_job, ItemResult, JobRegistry, JobWork};
+use crate::load_stats::PageStatsCollector;
+use crate::optimize::{optimize_file, OptimizeOutcome};
+use crate::s3_upload::{content_hash, upload_key, S3Result, UploadPart};
+use crate::single_instance;
+use crate::trash::remove_trashed;
+use crate::upload_backend::{configured_backend_factory, UploadBackend, UploadBackendFactory};
+use crate::watcher::watch_dir;
+use crate::{
+    AppMsg, AppState, ContactSheetTile, PreviewMsg, ThumbImageMsg, OK_EXTENSIONS, PREVIEW_SIZE,
+};
 
-pub async fn background(mut rx: mpsc::Receiver<AppMsg>, tx: mpsc::Sender<AppMsg>) {
+pub async fn background(rx: mpsc::Receiver<AppMsg>, tx: mpsc::Sender<AppMsg>) {
+    // paths from other instances that get started, see [single_instance]
+    let listener = tokio::spawn(single_instance::listen(tx.clone()));
+    background_with_store(rx, tx, configured_backend_factory()).await;
+    // the frontend's gone, dropping the listener cleans up the socket file
+    listener.abort();
+}
+
+/// [background], but uploads go wherever `store` says instead of S3
+pub async fn background_with_store(
+    mut rx: mpsc::Receiver<AppMsg>,
+    tx: mpsc::Sender<AppMsg>,
+    store: UploadBackendFactory,
+) {
     info!("Background thread started");
+    // only a successful check is trusted, a failure's checked again on the next upload
+    let mut bucket_accessible: Option<bool> = None;
+    let mut page_stats = PageStatsCollector::default();
+    // only held on to so it keeps running, replacing it stops the old one
+    let mut _watcher: Option<notify::RecommendedWatcher> = None;
+    let mut jobs = JobRegistry::default();
+    // the newest directory scan, older ones stop when they see it's changed
+    let latest_scan = Arc::new(AtomicU64::new(0));
     while let Some(msg) = rx.recv().await {
         debug!("Background received message: {:?}", msg);
         let response = match msg {
-            AppMsg::LoadImage(msg) => {
-                let filepath = msg.filepath;
-                match load_image_to_thumbnail_async(&PathBuf::from(filepath.clone()), None).await {
-                    Ok(image) => AppMsg::ThumbImageResponse(ThumbImageMsg {
+            AppMsg::WatchDir(path) => match watch_dir(&path, tx.clone()) {
+                Ok(new_watcher) => {
+                    _watcher = Some(new_watcher);
+                    AppMsg::Echo(format!("Watching {}", path.display()))
+                }
+                Err(err) => {
+                    _watcher = None;
+                    AppMsg::Error(format!(
+                        "Failed to watch {} for changes: {:?}",
+                        path.display(),
+                        err
+                    ))
+                }
+            },
+            AppMsg::StartJob {
+                job,
+                work,
+                filepaths,
+            } => {
+                let cancel = jobs.register(job);
+                let job_tx = tx.clone();
+                let total = filepaths.len();
+                // on its own task so it can be cancelled while it's going
+                tokio::spawn(async move {
+                    let done = run_job(job, filepaths, &job_tx, &cancel, |filepath| {
+                        do_job_work(work, filepath)
+                    })
+                    .await;
+                    if let Err(err) = job_tx.send(done).await {
+                        error!("Background failed to send the end of {}! {}", job, err);
+                    }
+                });
+                AppMsg::Echo(format!("Started {} on {} files", job, total))
+            }
+            AppMsg::MoveFile { from, to } => {
+                let move_tx = tx.clone();
+                // copying to another drive can take a while, so it's off on its own
+                let progress_tx = tx.clone();
+                tokio::spawn(async move {
+                    let done = tokio::task::spawn_blocking(move || {
+                        let result =
+                            copy_then_delete(Path::new(&from), Path::new(&to), |done, total| {
+                                // it's only progress, it doesn't matter if some are dropped
+                                let _ = progress_tx.try_send(AppMsg::MoveProgress {
+                                    from: from.clone(),
+                                    done,
+                                    total,
+                                });
+                            })
+                            .map_err(|err| format!("{:?}", err));
+                        AppMsg::MoveDone { from, to, result }
+                    })
+                    .await;
+                    match done {
+                        Ok(done) => {
+                            if let Err(err) = move_tx.send(done).await {
+                                error!("Background failed to send the end of a move! {}", err);
+                            }
+                        }
+                        Err(err) => error!("Moving a file across filesystems panicked: {:?}", err),
+                    }
+                });
+                AppMsg::Echo("Started copying a file to another filesystem".to_string())
+            }
+            AppMsg::ScanDirectory { path, generation } => {
+                latest_scan.store(generation, Ordering::Relaxed);
+                let scan_tx = tx.clone();
+                let latest = latest_scan.clone();
+                // on its own task so thumbnails keep coming while it's going
+                tokio::spawn(async move {
+                    let chunk_tx = scan_tx.clone();
+                    let scan_path = path.clone();
+                    let scanned = tokio::task::spawn_blocking(move || {
+                        scan_directory(&scan_path, SCAN_CHUNK_SIZE, |entries| {
+                            latest.load(Ordering::Relaxed) == generation
+                                && chunk_tx
+                                    .blocking_send(AppMsg::DirectoryListing {
+                                        entries,
+                                        generation,
+                                        done: false,
+                                    })
+                                    .is_ok()
+                        })
+                    })
+                    .await;
+                    match scanned {
+                        Ok(Ok(Some(found))) => {
+                            debug!("Found {} files scanning {}", found, path.display())
+                        }
+                        Ok(Ok(None)) => debug!("Stopped scanning {}", path.display()),
+                        Ok(Err(err)) => {
+                            let _ = scan_tx
+                                .send(AppMsg::Error(format!(
+                                    "Failed to read {}: {:?}",
+                                    path.display(),
+                                    err
+                                )))
+                                .await;
+                        }
+                        Err(err) => error!("Scanning {} panicked: {:?}", path.display(), err),
+                    }
+                    // the frontend stops waiting however it went
+                    let done = AppMsg::DirectoryListing {
+                        entries: vec![],
+                        generation,
+                        done: true,
+                    };
+                    if let Err(err) = scan_tx.send(done).await {
+                        error!("Background failed to send the end of a scan! {}", err);
+                    }
+                });
+                AppMsg::Echo(format!(
+                    "Scanning {} as scan {}",
+                    path.display(),
+                    generation
+                ))
+            }
+            AppMsg::CancelScan(generation) => {
+                // a newer one might have started already, that one keeps going
+                let stopped = latest_scan
+                    .compare_exchange(generation, 0, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok();
+                AppMsg::Echo(format!("Cancelled scan {}: {}", generation, stopped))
+            }
+            AppMsg::CancelJob(job) => {
+                if jobs.cancel(job) {
+                    AppMsg::Echo(format!("Cancelling {}", job))
+                } else {
+                    AppMsg::Echo(format!("{} isn't running, not cancelling it", job))
+                }
+            }
+            AppMsg::Batch(msgs) => {
+                process_batch(msgs, &tx, &store, &mut bucket_accessible, &mut page_stats).await
+            }
+            msg => process_message(msg, &tx, &store, &mut bucket_accessible, &mut page_stats).await,
+        };
+
+        // ctx.request_repaint_after(Duration::from_millis(500));
+
+        if let Err(err) = tx.send(response).await {
+            error!("Background failed to send echo! {}", err.to_string());
+        }
+    }
+}
+
+/// How long to wait before the first upload retry, it doubles each time after that
+pub const UPLOAD_RETRY_BASE: Duration = Duration::from_millis(500);
+/// The longest we'll wait between upload retries
+pub const UPLOAD_RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// how long to wait before retry number `attempt`, counting from 1
+pub fn upload_backoff(attempt: u32) -> Duration {
+    UPLOAD_RETRY_BASE
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(UPLOAD_RETRY_MAX)
+}
+
+/// Handle a burst of messages in one go, the frontend gets a single [AppMsg::BatchResult] once
+/// they're all done. Nested batches are flattened into the outer one.
+/// `bucket_accessible` is the cached result of [UploadBackend::check_bucket_accessible], and
+/// `page_stats` adds up what thumbnails cost for [AppMsg::PageLoadStats].
+pub async fn process_batch(
+    msgs: Vec<AppMsg>,
+    tx: &mpsc::Sender<AppMsg>,
+    store: &UploadBackendFactory,
+    bucket_accessible: &mut Option<bool>,
+    page_stats: &mut PageStatsCollector,
+) -> AppMsg {
+    debug!("Processing batch of {} messages", msgs.len());
+    let mut queue: std::collections::VecDeque<AppMsg> = msgs.into();
+    let mut results = Vec::with_capacity(queue.len());
+    while let Some(msg) = queue.pop_front() {
+        match msg {
+            AppMsg::Batch(inner) => {
+                for (index, inner_msg) in inner.into_iter().enumerate() {
+                    queue.insert(index, inner_msg);
+                }
+            }
+            msg => {
+                results.push(process_message(msg, tx, store, bucket_accessible, page_stats).await)
+            }
+        }
+    }
+    AppMsg::BatchResult(results)
+}
+
+/// Deal with a single message from the frontend, returning the response
+async fn process_message(
+    msg: AppMsg,
+    tx: &mpsc::Sender<AppMsg>,
+    store: &UploadBackendFactory,
+    bucket_accessible: &mut Option<bool>,
+    page_stats: &mut PageStatsCollector,
+) -> AppMsg {
+    match msg {
+        AppMsg::LoadImage(msg) => {
+            let started = std::time::Instant::now();
+            let filepath = msg.filepath;
+            // grab this before loading, so if it changes while we're loading we'll reload it later
+            let modified = match tokio::fs::metadata(&filepath).await {
+                Ok(metadata) => metadata.modified().ok(),
+                Err(_) => None,
+            };
+            match load_image_to_thumbnail_with_stats_async(
+                &PathBuf::from(filepath.clone()),
+                msg.size,
+                !msg.animate_gif,
+            )
+            .await
+            {
+                Ok((image, decode)) => {
+                    if msg.collect_stats {
+                        let stats = page_stats.record(msg.page, &filepath, started, decode);
+                        if let Err(err) = tx.send(AppMsg::PageLoadStats(stats)).await {
+                            error!("Failed to send page load stats: {}", err);
+                        }
+                    }
+                    AppMsg::ThumbImageResponse(ThumbImageMsg {
                         filepath,
                         page: msg.page,
+                        size: msg.size,
+                        modified,
                         image: Some(Arc::new(image)),
-                    }),
-                    Err(error) => {
-                        error!("Failed to load {} {}", filepath, error);
-                        AppMsg::ImageLoadFailed {
-                            filename: filepath.to_string(),
-                            error,
-                        }
+                        task: msg.task,
+                        animate_gif: msg.animate_gif,
+                        collect_stats: msg.collect_stats,
+                        tab: msg.tab,
+                    })
+                }
+                Err(error) => {
+                    error!("Failed to load {} {}", filepath, error);
+                    AppMsg::ImageLoadFailed {
+                        filename: filepath.to_string(),
+                        error: error.to_string(),
+                        task: msg.task,
+                    }
+                }
+            }
+        }
+        AppMsg::LoadPreview { filepath, task } => {
+            let path = PathBuf::from(&filepath);
+            match load_image_to_thumbnail_async(&path, Some(*PREVIEW_SIZE), true).await {
+                Ok(image) => {
+                    let dimensions =
+                        crate::image_utils::load_image_dimensions_fast(&path).unwrap_or_default();
+                    let file_size = match tokio::fs::metadata(&path).await {
+                        Ok(metadata) => metadata.len(),
+                        Err(_) => 0,
+                    };
+                    AppMsg::PreviewResponse(PreviewMsg {
+                        filepath,
+                        image: Arc::new(image),
+                        dimensions,
+                        file_size,
+                        task,
+                    })
+                }
+                Err(error) => {
+                    error!("Failed to load preview {} {}", filepath, error);
+                    AppMsg::ImageLoadFailed {
+                        filename: filepath,
+                        error: error.to_string(),
+                        task,
+                    }
+                }
+            }
+        }
+        AppMsg::LoadImageFromUrl { url, page, task } => {
+            match load_image_from_url(&url, None).await {
+                Ok(image) => AppMsg::ThumbImageResponse(ThumbImageMsg {
+                    filepath: url,
+                    page,
+                    size: None,
+                    modified: None,
+                    image: Some(Arc::new(image)),
+                    task,
+                    animate_gif: false,
+                    collect_stats: false,
+                    tab: None,
+                }),
+                Err(error) => {
+                    error!("Failed to load {} {}", url, error);
+                    AppMsg::ImageLoadFailed {
+                        filename: url,
+                        error,
+                        task,
                     }
                 }
             }
-            AppMsg::ThumbImageResponse(_) => todo!(),
-            AppMsg::ImageLoadFailed {
-                filename: _,
-                error: _,
-            } => todo!(),
-            AppMsg::NewAppState(xxx) => AppMsg::NewAppState(xxx),
-            AppMsg::Echo(msg) => {
-                todo!("echo: {}", msg);
-            }
-            AppMsg::UploadAborted(_) => panic!("Frontend shouldn't send aborted upload message"),
-            AppMsg::UploadImage(filepath) => {
-                debug!("Starting S3 Upload!");
-                match crate::s3_upload::S3Client::try_new() {
-                    Ok(s3_client) => {
-                        // it's safe to use unwrap here because we know the filepath is valid utf8
-                        #[allow(clippy::unwrap_used)]
-                        let key = filepath.split('/').last().unwrap();
-                        match s3_client.head_object(key).await {
-                            Ok(val) => {
-                                info!("File already exists in S3: {:?}", val);
-                                AppMsg::UploadAborted(format!("File Exists in s3: {:?}", val))
+        }
+        AppMsg::GetDirectoryStats(path) => {
+            let resolved = PathBuf::from(shellexpand::tilde(&path).as_ref());
+            let progress_tx = tx.clone();
+            let progress_path = path.clone();
+            // lots of blocking stat calls, keep them off the async workers
+            let stats = tokio::task::spawn_blocking(move || {
+                DirStats::for_directory_with_progress(&resolved, &OK_EXTENSIONS, |done, total| {
+                    let progress = AppMsg::DirStatsProgress {
+                        path: progress_path.clone(),
+                        done,
+                        total,
+                    };
+                    if let Err(err) = progress_tx.blocking_send(progress) {
+                        error!(
+                            "Background failed to send directory stats progress! {}",
+                            err
+                        );
+                    }
+                })
+            })
+            .await;
+            match stats {
+                Ok(Ok(stats)) => AppMsg::GotDirStats { path, stats },
+                Ok(Err(err)) => AppMsg::Error(format!("Failed to read {}: {:?}", path, err)),
+                Err(err) => AppMsg::Error(format!("Failed to count files in {}: {:?}", path, err)),
+            }
+        }
+        AppMsg::CheckIntegrity(filepath) => {
+            let path = PathBuf::from(&filepath);
+            let modified = match tokio::fs::metadata(&path).await {
+                Ok(metadata) => metadata.modified().ok(),
+                Err(_) => None,
+            };
+            // decoding a big image takes a while, keep it off the async workers
+            let verdict = tokio::task::spawn_blocking(move || check_file(&path))
+                .await
+                .unwrap_or_else(|err| Verdict::Unreadable(format!("{:?}", err)));
+            AppMsg::IntegrityChecked {
+                filepath,
+                modified,
+                verdict,
+            }
+        }
+        AppMsg::IntegrityChecked { filepath, .. } => AppMsg::Error(format!(
+            "The frontend sent IntegrityChecked({filepath}) to the backend!"
+        )),
+        AppMsg::SniffFormats(filepaths) => {
+            // only a few bytes of each, but it's still a lot of opening files
+            let formats = tokio::task::spawn_blocking(move || {
+                filepaths
+                    .into_iter()
+                    .map(|filepath| {
+                        let path = Path::new(&filepath);
+                        let modified = std::fs::metadata(path)
+                            .and_then(|metadata| metadata.modified())
+                            .ok();
+                        let format = sniff_file(path).unwrap_or_else(|err| {
+                            debug!("Couldn't sniff {}: {}", filepath, err);
+                            SniffedFormat::from_extension(path)
+                        });
+                        (filepath, modified, format)
+                    })
+                    .collect()
+            })
+            .await;
+            match formats {
+                Ok(formats) => AppMsg::FormatsSniffed(formats),
+                Err(err) => AppMsg::Error(format!("Sniffing file formats failed: {:?}", err)),
+            }
+        }
+        AppMsg::FormatsSniffed(formats) => AppMsg::Error(format!(
+            "The frontend sent FormatsSniffed({} files) to the backend!",
+            formats.len()
+        )),
+        AppMsg::GotDirStats { path, .. } => AppMsg::Error(format!(
+            "The frontend sent GotDirStats({path}) to the backend!"
+        )),
+        AppMsg::DirStatsProgress { path, .. } => AppMsg::Error(format!(
+            "The frontend sent DirStatsProgress({path}) to the backend!"
+        )),
+        AppMsg::ThumbImageResponse(response) => AppMsg::Error(format!(
+            "The frontend sent ThumbImageResponse({}) to the backend!",
+            response.filepath()
+        )),
+        AppMsg::PreviewResponse(_) => {
+            AppMsg::Error("The frontend sent PreviewResponse to the backend!".to_string())
+        }
+        AppMsg::ImageLoadFailed { filename, .. } => AppMsg::Error(format!(
+            "The frontend sent ImageLoadFailed({filename}) to the backend!"
+        )),
+        AppMsg::NewAppState(xxx) => AppMsg::NewAppState(xxx),
+        AppMsg::Echo(msg) => AppMsg::Echo(msg),
+        AppMsg::UploadAborted { message, .. } => AppMsg::Error(format!(
+            "The frontend sent UploadAborted({message}) to the backend!"
+        )),
+        AppMsg::UploadImage(filepath) => {
+            debug!("Starting S3 Upload!");
+            match store() {
+                Ok(s3_client) => {
+                    if let Err(err) =
+                        ensure_bucket_accessible(s3_client.as_ref(), bucket_accessible).await
+                    {
+                        return AppMsg::UploadAborted {
+                            message: crate::t!("error-bucket-not-accessible", error = err),
+                            details: Some(err.details()),
+                        };
+                    }
+                    let key = match upload_key(&filepath, s3_client.hash_keys()).await {
+                        Ok(key) => key,
+                        Err(err) => {
+                            return AppMsg::UploadAborted {
+                                message: format!(
+                                    "Failed to work out upload key for {}: {:?}",
+                                    filepath, err
+                                ),
+                                details: None,
+                            }
+                        }
+                    };
+                    match s3_client.head_object(&key).await {
+                        Ok(val) => {
+                            info!("File already exists in S3 as {}: {:?}", key, val);
+                            AppMsg::UploadAborted {
+                                message: format!("File Exists in s3 as {}", key),
+                                details: Some(val),
                             }
-                            Err(err) => {
-                                if let crate::s3_upload::S3Result::FileNotFound = err {
-                                    // we didn't find the file
-                                    debug!("Uploading {} to S3", filepath);
-                                    match s3_client.put_object(key, &filepath).await {
-                                        Err(err) => AppMsg::Error(format!("{:?}", err)),
-                                        // panic!("Failed to upload {} {:?}", filepath, err);
-                                        Ok(_) => {
-                                            info!("Successfully uploaded {} to S3", filepath);
-                                            AppMsg::UploadComplete(filepath)
-                                        }
-                                    }
-                                } else {
-                                    AppMsg::Error(format!(
-                                        "Failed to check existence of file in S3: {err:?}"
-                                    ))
+                        }
+                        Err(err) => {
+                            if let S3Result::FileNotFound = err {
+                                // we didn't find the file
+                                upload_to_key(s3_client.as_ref(), tx, filepath, key).await
+                            } else {
+                                AppMsg::DetailedError {
+                                    message: format!(
+                                        "Failed to check existence of file in S3: {err}"
+                                    ),
+                                    details: err.details(),
                                 }
                             }
                         }
                     }
+                }
+                Err(err) => AppMsg::UploadAborted {
+                    message: format!("Failed to create S3 Client: {:?}", err),
+                    details: None,
+                },
+            }
+        }
+        AppMsg::UpdateImage { filepath, key } => {
+            debug!("Overwriting {} in S3 with {}", key, filepath);
+            match store() {
+                Ok(s3_client) => {
+                    if let Err(err) =
+                        ensure_bucket_accessible(s3_client.as_ref(), bucket_accessible).await
+                    {
+                        return AppMsg::UploadAborted {
+                            message: crate::t!("error-bucket-not-accessible", error = err),
+                            details: Some(err.details()),
+                        };
+                    }
+                    upload_to_key(s3_client.as_ref(), tx, filepath, key).await
+                }
+                Err(err) => AppMsg::UploadAborted {
+                    message: format!("Failed to create S3 Client: {:?}", err),
+                    details: None,
+                },
+            }
+        }
+        AppMsg::CheckRemoteSize { filepath, key } => {
+            let size = match store() {
+                Ok(s3_client) => match s3_client.object_size(&key).await {
+                    Ok(size) => Ok(Some(size)),
+                    Err(S3Result::FileNotFound) => Ok(None),
                     Err(err) => {
-                        AppMsg::UploadAborted(format!("Failed to create S3 Client: {:?}", err))
+                        warn!("Couldn't check the size of {}: {}", key, err.details());
+                        Err(err.user_message())
                     }
+                },
+                Err(err) => Err(format!("Failed to create S3 Client: {:?}", err)),
+            };
+            AppMsg::RemoteSize {
+                filepath,
+                key,
+                size,
+            }
+        }
+        AppMsg::RemoteSize { key, .. } => AppMsg::Error(format!(
+            "The frontend sent RemoteSize({key}) to the backend!"
+        )),
+        AppMsg::S3CopyObject { src_key, dest_key } => match store() {
+            Ok(s3_client) => match s3_client.copy_object(&src_key, &dest_key).await {
+                Ok(_) => {
+                    info!("Copied {} to {} in S3", src_key, dest_key);
+                    AppMsg::S3ObjectCopied { src_key, dest_key }
+                }
+                Err(err) => AppMsg::DetailedError {
+                    message: err.user_message(),
+                    details: err.details(),
+                },
+            },
+            Err(err) => AppMsg::Error(format!("Failed to create S3 Client: {:?}", err)),
+        },
+        AppMsg::DownloadAndEdit { key } => match store() {
+            Ok(s3_client) => match s3_client.download_to_temp(&key).await {
+                Ok(path) => {
+                    info!("Downloaded {} to {}", key, path.display());
+                    AppMsg::NewAppState(AppState::Editor {
+                        filepath: path.display().to_string(),
+                    })
+                }
+                Err(err) => AppMsg::DetailedError {
+                    message: err.user_message(),
+                    details: err.details(),
+                },
+            },
+            Err(err) => AppMsg::Error(format!("Failed to create S3 Client: {:?}", err)),
+        },
+        AppMsg::S3ObjectCopied { dest_key, .. } => AppMsg::Error(format!(
+            "The frontend sent S3ObjectCopied({dest_key}) to the backend!"
+        )),
+        AppMsg::S3RenameObject {
+            old_filepath,
+            filepath,
+            src_key,
+            dest_key,
+        } => {
+            let result = match store() {
+                Ok(s3_client) => {
+                    s3_client
+                        .rename_object(&src_key, &dest_key)
+                        .await
+                        .map_err(|err| {
+                            error!(
+                                "Failed to rename {} to {} in S3: {}",
+                                src_key,
+                                dest_key,
+                                err.details()
+                            );
+                            err.user_message()
+                        })
+                }
+                Err(err) => Err(format!("Failed to create S3 Client: {:?}", err)),
+            };
+            AppMsg::S3ObjectRenamed {
+                old_filepath,
+                filepath,
+                src_key,
+                dest_key,
+                result,
+            }
+        }
+        AppMsg::S3ObjectRenamed { dest_key, .. } => AppMsg::Error(format!(
+            "The frontend sent S3ObjectRenamed({dest_key}) to the backend!"
+        )),
+        AppMsg::ExportContactSheet {
+            savepath,
+            layout,
+            tiles,
+        } => {
+            let total = tiles.len();
+            let cell_size = Some(eframe::epaint::vec2(
+                layout.cell_size as f32,
+                layout.cell_size as f32,
+            ));
+            let mut images = Vec::with_capacity(total);
+            for (index, ContactSheetTile { filepath, cached }) in tiles.into_iter().enumerate() {
+                let path = PathBuf::from(&filepath);
+                let image = match cached {
+                    Some(image) => Some(image),
+                    None => match load_image_to_thumbnail_async(&path, cell_size, true).await {
+                        Ok(image) => Some(Arc::new(image)),
+                        Err(error) => {
+                            warn!("Leaving {} off the contact sheet: {}", filepath, error);
+                            None
+                        }
+                    },
+                };
+                if let Some(image) = image {
+                    let caption = match path.file_name() {
+                        Some(filename) => filename.to_string_lossy().to_string(),
+                        None => filepath,
+                    };
+                    images.push((caption, image));
+                }
+                if let Err(err) = tx
+                    .send(AppMsg::ContactSheetProgress {
+                        done: index + 1,
+                        total,
+                    })
+                    .await
+                {
+                    error!("Background failed to send contact sheet progress! {}", err);
                 }
             }
-            AppMsg::UploadComplete(filepath) => {
-                panic!("The frontend sent UploadComplete({filepath})");
+            if images.is_empty() {
+                return AppMsg::ContactSheetDone(Err(
+                    "None of the images could be loaded".to_string()
+                ));
             }
-            AppMsg::Error(err) => {
-                AppMsg::Error(format!("The frontend sent Error({err}) to the backend!"))
+            // compositing and encoding is all blocking work
+            let result = tokio::task::spawn_blocking(move || {
+                let sheet = generate_contact_sheet(&images, &layout);
+                // no alpha, so it can go out as a JPEG too
+                image::DynamicImage::ImageRgba8(sheet)
+                    .to_rgb8()
+                    .save(&savepath)
+                    .map(|_| savepath)
+                    .map_err(|err| err.to_string())
+            })
+            .await
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|result| result);
+            AppMsg::ContactSheetDone(result)
+        }
+        AppMsg::ContactSheetProgress { .. } => {
+            AppMsg::Error("The frontend sent ContactSheetProgress to the backend!".to_string())
+        }
+        AppMsg::ContactSheetDone(_) => {
+            AppMsg::Error("The frontend sent ContactSheetDone to the backend!".to_string())
+        }
+        AppMsg::ExportMosaic {
+            savepath,
+            filepaths,
+            width,
+            height,
+        } => {
+            let result = tokio::task::spawn_blocking(move || {
+                generate_mosaic(&filepaths, width, height).and_then(|mosaic| {
+                    mosaic
+                        .save(&savepath)
+                        .map(|_| savepath)
+                        .map_err(|err| err.to_string())
+                })
+            })
+            .await
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|result| result);
+            AppMsg::MosaicDone(result)
+        }
+        AppMsg::MosaicDone(_) => {
+            AppMsg::Error("The frontend sent MosaicDone to the backend!".to_string())
+        }
+        AppMsg::AnalyzeColors(filepath) => {
+            let path = PathBuf::from(&filepath);
+            let modified = match tokio::fs::metadata(&path).await {
+                Ok(metadata) => metadata.modified().ok(),
+                Err(_) => None,
+            };
+            let analysis = match load_image_to_thumbnail_async(&path, None, true).await {
+                // it's only a thumbnail, but going through every pixel is still blocking work
+                Ok(image) => tokio::task::spawn_blocking(move || ColorAnalysis::from_image(&image))
+                    .await
+                    .map_err(|err| format!("{:?}", err)),
+                Err(error) => Err(error.to_string()),
+            };
+            AppMsg::ColorsAnalyzed {
+                filepath,
+                modified,
+                analysis,
             }
-        };
+        }
+        AppMsg::ColorsAnalyzed { filepath, .. } => AppMsg::Error(format!(
+            "The frontend sent ColorsAnalyzed({filepath}) to the backend!"
+        )),
+        AppMsg::DetectIssues(filepath) => {
+            let path = PathBuf::from(&filepath);
+            let modified = match tokio::fs::metadata(&path).await {
+                Ok(metadata) => metadata.modified().ok(),
+                Err(_) => None,
+            };
+            // it decodes the whole image, so keep it off the async workers
+            let issues = tokio::task::spawn_blocking(move || detect_image_issues(&path))
+                .await
+                .unwrap_or_else(|err| {
+                    error!("Checking {} for issues failed: {:?}", filepath, err);
+                    vec![]
+                });
+            AppMsg::IssuesDetected {
+                filepath,
+                modified,
+                issues,
+            }
+        }
+        AppMsg::IssuesDetected { filepath, .. } => AppMsg::Error(format!(
+            "The frontend sent IssuesDetected({filepath}) to the backend!"
+        )),
+        AppMsg::ApplyImageAction { filepath, action } => {
+            let path = PathBuf::from(&filepath);
+            let result = tokio::task::spawn_blocking(move || action.apply(&path))
+                .await
+                .unwrap_or_else(|err| Err(format!("{:?}", err)));
+            AppMsg::ImageActionDone {
+                filepath,
+                action,
+                result,
+            }
+        }
+        AppMsg::ImageActionDone { filepath, .. } => AppMsg::Error(format!(
+            "The frontend sent ImageActionDone({filepath}) to the backend!"
+        )),
+        AppMsg::StartJob { job, .. } => {
+            AppMsg::Error(format!("StartJob({job}) can't be part of a batch"))
+        }
+        AppMsg::CancelJob(job) => {
+            AppMsg::Error(format!("CancelJob({job}) can't be part of a batch"))
+        }
+        AppMsg::MoveFile { from, .. } => {
+            AppMsg::Error(format!("MoveFile({from}) can't be part of a batch"))
+        }
+        AppMsg::ScanDirectory { path, .. } => AppMsg::Error(format!(
+            "ScanDirectory({}) can't be part of a batch",
+            path.display()
+        )),
+        AppMsg::CancelScan(generation) => {
+            AppMsg::Error(format!("CancelScan({generation}) can't be part of a batch"))
+        }
+        AppMsg::DirectoryListing { generation, .. } => AppMsg::Error(format!(
+            "The frontend sent DirectoryListing({generation}) to the backend!"
+        )),
+        AppMsg::MoveProgress { from, .. } => AppMsg::Error(format!(
+            "The frontend sent MoveProgress({from}) to the backend!"
+        )),
+        AppMsg::MoveDone { from, .. } => AppMsg::Error(format!(
+            "The frontend sent MoveDone({from}) to the backend!"
+        )),
+        AppMsg::JobProgress { job, .. } => AppMsg::Error(format!(
+            "The frontend sent JobProgress({job}) to the backend!"
+        )),
+        AppMsg::JobDone { job, .. } => {
+            AppMsg::Error(format!("The frontend sent JobDone({job}) to the backend!"))
+        }
+        AppMsg::WatchDir(path) => AppMsg::Error(format!(
+            "WatchDir({}) can't be part of a batch",
+            path.display()
+        )),
+        AppMsg::WatcherEvent(kind) => AppMsg::Error(format!(
+            "The frontend sent WatcherEvent({:?}) to the backend!",
+            kind
+        )),
+        AppMsg::PurgeTrash(paths) => {
+            let failures: Vec<String> = paths
+                .iter()
+                .filter_map(|path| remove_trashed(path).err())
+                .collect();
+            if failures.is_empty() {
+                AppMsg::Echo(format!("Purged {} files from the trash", paths.len()))
+            } else {
+                AppMsg::Error(format!(
+                    "Failed to purge from the trash: {}",
+                    failures.join(", ")
+                ))
+            }
+        }
+        AppMsg::OpenPath(path) => AppMsg::Error(format!(
+            "The frontend sent OpenPath({}) to the backend!",
+            path.display()
+        )),
+        AppMsg::PageLoadStats(stats) => AppMsg::Error(format!(
+            "The frontend sent PageLoadStats for page {} to the backend!",
+            stats.page
+        )),
+        AppMsg::CheckBucket => match store() {
+            Ok(s3_client) => {
+                let result = s3_client.check_bucket_accessible().await;
+                *bucket_accessible = Some(result.is_ok());
+                AppMsg::BucketStatus(result.map_err(|err| {
+                    warn!("Bucket isn't accessible: {}", err.details());
+                    err.user_message()
+                }))
+            }
+            Err(err) => AppMsg::BucketStatus(Err(format!("Failed to create S3 Client: {:?}", err))),
+        },
+        AppMsg::BucketStatus(_) => {
+            AppMsg::Error("The frontend sent BucketStatus to the backend!".to_string())
+        }
+        AppMsg::UploadProgress { filepath, .. } => {
+            AppMsg::Error(format!("The frontend sent UploadProgress({filepath})"))
+        }
+        AppMsg::UploadComplete { filepath, .. } => AppMsg::Error(format!(
+            "The frontend sent UploadComplete({filepath}) to the backend!"
+        )),
+        AppMsg::Error(err) => {
+            AppMsg::Error(format!("The frontend sent Error({err}) to the backend!"))
+        }
+        AppMsg::DetailedError { message, .. } => AppMsg::Error(format!(
+            "The frontend sent DetailedError({message}) to the backend!"
+        )),
+        AppMsg::Batch(msgs) => AppMsg::Error(format!(
+            "Batch of {} messages should have been handled by process_batch",
+            msgs.len()
+        )),
+        AppMsg::BatchResult(_) => {
+            AppMsg::Error("The frontend sent BatchResult to the backend!".to_string())
+        }
+    }
+}
 
-        // ctx.request_repaint_after(Duration::from_millis(500));
+/// one file's worth of a [JobWork], the decoding and encoding's kept off the async workers
+async fn do_job_work(work: JobWork, filepath: String) -> ItemResult {
+    match work {
+        JobWork::Optimize(action) => {
+            let path = PathBuf::from(&filepath);
+            match tokio::task::spawn_blocking(move || optimize_file(&path, action)).await {
+                Ok(Ok(OptimizeOutcome::Saved {
+                    bytes_before,
+                    bytes_after,
+                    ..
+                })) => ItemResult::Done {
+                    bytes_before,
+                    bytes_after,
+                },
+                Ok(Ok(OptimizeOutcome::Skipped(reason))) => ItemResult::Skipped(reason),
+                Ok(Err(error)) => ItemResult::Failed(error),
+                Err(err) => ItemResult::Failed(format!("{:?}", err)),
+            }
+        }
+    }
+}
 
-        if let Err(err) = tx.send(response).await {
-            error!("Background failed to send echo! {}", err.to_string());
+/// check the bucket unless it's already passed, remembering how it went in `bucket_accessible`
+async fn ensure_bucket_accessible(
+    store: &dyn UploadBackend,
+    bucket_accessible: &mut Option<bool>,
+) -> Result<(), S3Result> {
+    if *bucket_accessible == Some(true) {
+        return Ok(());
+    }
+    let result = store.check_bucket_accessible().await;
+    *bucket_accessible = Some(result.is_ok());
+    result
+}
+
+/// put `filepath` in the bucket as `key` whether or not there's something there already, with
+/// progress along the way
+async fn upload_to_key(
+    store: &dyn UploadBackend,
+    tx: &mpsc::Sender<AppMsg>,
+    filepath: String,
+    key: String,
+) -> AppMsg {
+    debug!("Uploading {} to S3", filepath);
+    let total_bytes = match tokio::fs::metadata(&filepath).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    };
+    send_progress(tx, &filepath, 0, total_bytes, None).await;
+    match put_with_retries(store, tx, &key, &filepath, total_bytes).await {
+        Err(err) => AppMsg::DetailedError {
+            message: err.user_message(),
+            details: err.details(),
+        },
+        Ok(_) => {
+            info!("Successfully uploaded {} to S3 as {}", filepath, key);
+            send_progress(tx, &filepath, total_bytes, total_bytes, None).await;
+            // so the history can tell if the file's changed since
+            let checksum = match content_hash(&filepath).await {
+                Ok(checksum) => Some(checksum),
+                Err(err) => {
+                    warn!("Couldn't hash {} after uploading it: {:?}", filepath, err);
+                    None
+                }
+            };
+            AppMsg::UploadComplete {
+                filepath,
+                key,
+                checksum,
+            }
+        }
+    }
+}
+
+/// upload, retrying [S3Result::is_retryable] failures with [upload_backoff] between attempts
+async fn put_with_retries(
+    store: &dyn UploadBackend,
+    tx: &mpsc::Sender<AppMsg>,
+    key: &str,
+    filepath: &str,
+    total_bytes: u64,
+) -> Result<String, S3Result> {
+    let max_retries = store.max_retries();
+    let mut attempt = 0;
+    let on_part = |part: UploadPart| {
+        // it's only progress, if the channel's full the next part will catch up
+        if let Err(err) = tx.try_send(AppMsg::UploadProgress {
+            filepath: filepath.to_string(),
+            bytes_sent: part.bytes_sent,
+            total_bytes,
+            message: None,
+            part: Some(part),
+        }) {
+            debug!("Skipped progress for part {}: {}", part.part, err);
+        }
+    };
+    loop {
+        match store.put_object_parts(key, filepath, &on_part).await {
+            Err(err) if err.is_retryable() && attempt < max_retries => {
+                attempt += 1;
+                let wait = upload_backoff(attempt as u32);
+                warn!(
+                    "Upload of {} failed, retry {}/{} in {:?}: {}",
+                    filepath,
+                    attempt,
+                    max_retries,
+                    wait,
+                    err.details()
+                );
+                send_progress(
+                    tx,
+                    filepath,
+                    0,
+                    total_bytes,
+                    Some(format!("retry {attempt}/{max_retries}")),
+                )
+                .await;
+                tokio::time::sleep(wait).await;
+            }
+            result => return result,
         }
     }
 }
+
+/// let the frontend know how an upload's going
+async fn send_progress(
+    tx: &mpsc::Sender<AppMsg>,
+    filepath: &str,
+    bytes_sent: u64,
+    total_bytes: u64,
+    message: Option<String>,
+) {
+    if let Err(err) = tx
+        .send(AppMsg::UploadProgress {
+            filepath: filepath.to_string(),
+            bytes_sent,
+            total_bytes,
+            message,
+            part: None,
+        })
+        .await
+    {
+        error!(
+            "Background failed to send upload progress! {}",
+            err.to_string()
+        );
+    }
+}
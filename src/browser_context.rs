@@ -0,0 +1,136 @@
+//! Where the user is in the browser, kept across trips to the editor
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// which page `filepath` lands on in `files`, which should be sorted and filtered the way the
+/// browser shows them. `None` if it's not in there.
+pub fn page_of(files: &[PathBuf], filepath: &Path, per_page: usize) -> Option<usize> {
+    let index = files.iter().position(|file| file == filepath)?;
+    Some(index.checked_div(per_page).unwrap_or(0))
+}
+
+/// the files on `page` when there's `per_page` to a page, empty if there's no such page. A zero
+/// page size puts everything on the first page.
+pub fn page_slice(files: &[PathBuf], page: usize, per_page: usize) -> &[PathBuf] {
+    if per_page == 0 {
+        return if page == 0 { files } else { &[] };
+    }
+    let start = page.saturating_mul(per_page);
+    if start >= files.len() {
+        return &[];
+    }
+    &files[start..files.len().min(start + per_page)]
+}
+
+/// where each file is in `files`, so the editor can say "file 3 of 500" without searching the
+/// list every frame
+pub fn positions(files: &[PathBuf]) -> HashMap<String, usize> {
+    files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| (file.display().to_string(), index))
+        .collect()
+}
+
+/// how many of a page of `page_len` thumbnails to ask for straight away, when only the first
+/// `eager_rows` rows of `columns` are. `None` or 0 rows is all of them.
+pub fn eager_count(page_len: usize, columns: usize, eager_rows: Option<usize>) -> usize {
+    match eager_rows {
+        Some(rows) if rows > 0 => page_len.min(rows.saturating_mul(columns.max(1))),
+        _ => page_len,
+    }
+}
+
+/// the first file after `current` in `files` that's still there going by `exists`, for moving on
+/// to the next one once `current`'s been dealt with. `None` if `current` isn't in `files` or
+/// there's nothing after it.
+pub fn next_remaining(
+    files: &[PathBuf],
+    current: &str,
+    exists: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    let index = files.iter().position(|file| file == Path::new(current))?;
+    files[index + 1..]
+        .iter()
+        .find(|file| exists(file.as_path()))
+        .cloned()
+}
+
+/// like [next_remaining], but if there's nothing after `current` it's the nearest one before it
+/// that's still there, so deleting the last file still leaves something to look at
+pub fn neighbour_remaining(
+    files: &[PathBuf],
+    current: &str,
+    exists: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    let index = files.iter().position(|file| file == Path::new(current))?;
+    next_remaining(files, current, &exists).or_else(|| {
+        files[..index]
+            .iter()
+            .rev()
+            .find(|file| exists(file.as_path()))
+            .cloned()
+    })
+}
+
+/// The bits of browser state that should survive going to the editor and back. Only reset when
+/// the workdir or search changes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct BrowserContext {
+    pub current_page: usize,
+    /// The file highlighted in the browser grid
+    pub selected_file: Option<String>,
+    /// How far down the infinite scroll view was
+    pub scroll_offset: f32,
+}
+
+impl BrowserContext {
+    /// select `filepath` and go to its page, returns false and leaves things alone if it's not in
+    /// `files`
+    pub fn select_file(&mut self, files: &[PathBuf], filepath: &Path, per_page: usize) -> bool {
+        match page_of(files, filepath, per_page) {
+            Some(page) => {
+                self.current_page = page;
+                self.selected_file = Some(filepath.display().to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `removed` is about to disappear from `files`, so select whatever takes its place (or the one
+    /// before it if it was last) and make sure we're on that file's page.
+    pub fn select_after_removal(&mut self, files: &[PathBuf], removed: &str, per_page: usize) {
+        let files: Vec<String> = files.iter().map(|p| p.display().to_string()).collect();
+        let Some(index) = files.iter().position(|f| f == removed) else {
+            return;
+        };
+
+        let next = files
+            .get(index + 1)
+            .or_else(|| index.checked_sub(1).and_then(|prev| files.get(prev)));
+
+        match next {
+            Some(next) => {
+                // the next file moves up into the removed file's slot
+                let new_index = if index + 1 < files.len() {
+                    index
+                } else {
+                    index - 1
+                };
+                self.selected_file = Some(next.clone());
+                if per_page > 0 {
+                    self.current_page = new_index / per_page;
+                }
+            }
+            None => {
+                self.selected_file = None;
+                self.current_page = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Images pasted into the browser, which get saved into the workdir. egui only hands over pasted
+//! text, so the image itself comes straight from the system clipboard.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eframe::epaint::ColorImage;
+
+/// What's saved when there's no extension on the filename
+pub const DEFAULT_EXTENSION: &str = "png";
+
+/// An image off the clipboard, as RGBA
+#[derive(Clone, PartialEq, Eq)]
+pub struct ClipboardImage {
+    pub width: u32,
+    pub height: u32,
+    /// Shared so the state it's in can be cloned each frame without copying it
+    pub rgba: Arc<Vec<u8>>,
+}
+
+// the pixels would fill the log, and they're not much use in it
+impl std::fmt::Debug for ClipboardImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClipboardImage")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("bytes", &self.rgba.len())
+            .finish()
+    }
+}
+
+impl ClipboardImage {
+    pub fn new(width: u32, height: u32, rgba: Vec<u8>) -> Result<Self, String> {
+        let expected = width as usize * height as usize * 4;
+        if rgba.len() != expected {
+            return Err(format!(
+                "Clipboard image is {}x{} but has {} bytes, expected {}",
+                width,
+                height,
+                rgba.len(),
+                expected
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            rgba: Arc::new(rgba),
+        })
+    }
+
+    /// for showing it before it's saved
+    pub fn color_image(&self) -> ColorImage {
+        ColorImage::from_rgba_unmultiplied([self.width as usize, self.height as usize], &self.rgba)
+    }
+
+    /// write it to `path`, the format's picked from the extension
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if matches!(extension.as_str(), "jpg" | "jpeg") {
+            // JPEGs don't do transparency
+            let rgb = image::RgbaImage::from_raw(self.width, self.height, self.rgba.to_vec())
+                .map(|rgba| image::DynamicImage::ImageRgba8(rgba).to_rgb8())
+                .ok_or_else(|| "Clipboard image has the wrong number of bytes".to_string())?;
+            return rgb.save(path).map_err(|e| e.to_string());
+        }
+        image::save_buffer(
+            path,
+            &self.rgba,
+            self.width,
+            self.height,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// what's on the clipboard if it's an image, `None` if it's something else or empty
+pub fn read_image() -> Result<Option<ClipboardImage>, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    match clipboard.get_image() {
+        Ok(image) => ClipboardImage::new(
+            image.width as u32,
+            image.height as u32,
+            image.bytes.into_owned(),
+        )
+        .map(Some),
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// what to call a pasted image to start with, `pasted-<seconds since the epoch>.png`
+pub fn default_filename() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    format!("pasted-{now}.{DEFAULT_EXTENSION}")
+}
+
+/// where `filename` goes in `workdir`, with [DEFAULT_EXTENSION] if it hasn't got one
+pub fn import_path(workdir: &str, filename: &str) -> PathBuf {
+    let workdir = PathBuf::from(shellexpand::tilde(workdir).as_ref());
+    let mut path = workdir.join(filename.trim());
+    if path.extension().is_none() {
+        path.set_extension(DEFAULT_EXTENSION);
+    }
+    path
+}
@@ -0,0 +1,80 @@
+//! Dominant colours and a histogram for the editor, worked out from thumbnail pixels
+
+use std::collections::HashMap;
+
+use eframe::epaint::{Color32, ColorImage};
+
+/// How many bars each channel's histogram has
+pub const HISTOGRAM_BINS: usize = 16;
+/// How many dominant colours to pick out
+pub const DOMINANT_COLORS: usize = 5;
+/// Bits kept from each channel when bucketing colours, so near enough counts as the same
+const QUANTIZE_BITS: u8 = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorAnalysis {
+    /// Red, green and blue counts, each split into [HISTOGRAM_BINS] bins
+    pub histogram: [[u32; HISTOGRAM_BINS]; 3],
+    /// The most common colours, most common first, with the share of the image they cover
+    pub dominant: Vec<(Color32, f32)>,
+}
+
+impl ColorAnalysis {
+    /// bucket the colours, skipping anything mostly transparent
+    pub fn from_image(image: &ColorImage) -> Self {
+        puffin::profile_function!();
+        let mut histogram = [[0u32; HISTOGRAM_BINS]; 3];
+        // quantized colour to (count, summed r, g, b) so each bucket can be averaged
+        let mut buckets: HashMap<(u8, u8, u8), (u32, u64, u64, u64)> = HashMap::new();
+        let mut counted = 0u32;
+
+        for pixel in image.pixels.iter().filter(|pixel| pixel.a() >= 128) {
+            let [r, g, b, _] = pixel.to_srgba_unmultiplied();
+            for (channel, value) in [r, g, b].into_iter().enumerate() {
+                histogram[channel][value as usize * HISTOGRAM_BINS / 256] += 1;
+            }
+            let shift = 8 - QUANTIZE_BITS;
+            let bucket = buckets
+                .entry((r >> shift, g >> shift, b >> shift))
+                .or_default();
+            bucket.0 += 1;
+            bucket.1 += r as u64;
+            bucket.2 += g as u64;
+            bucket.3 += b as u64;
+            counted += 1;
+        }
+
+        let mut dominant: Vec<(u32, Color32)> = buckets
+            .into_values()
+            .map(|(count, r, g, b)| {
+                let average = |sum: u64| (sum / count as u64) as u8;
+                (count, Color32::from_rgb(average(r), average(g), average(b)))
+            })
+            .collect();
+        // ties are broken on the colour itself, so the order is the same every time
+        dominant.sort_by(|(a_count, a), (b_count, b)| {
+            b_count
+                .cmp(a_count)
+                .then_with(|| a.to_array().cmp(&b.to_array()))
+        });
+
+        Self {
+            histogram,
+            dominant: dominant
+                .into_iter()
+                .take(DOMINANT_COLORS)
+                .map(|(count, color)| (color, count as f32 / counted as f32))
+                .collect(),
+        }
+    }
+
+    /// roughly how much memory this takes, for the cache
+    pub fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.dominant.len() * std::mem::size_of::<(Color32, f32)>()
+    }
+}
+
+/// eg `#FF8800`
+pub fn hex_code(color: Color32) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.r(), color.g(), color.b())
+}
@@ -4,10 +4,54 @@ use anyhow::Context;
 use log::*;
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::tabs::WorkdirTab;
 
 const CONFIG_PATH: &str = "~/.config/memetool.json";
+/// Set this to use a different file than [CONFIG_PATH], handy for tests
+pub const CONFIG_PATH_ENV: &str = "MEMETOOL_CONFIG_PATH";
+/// Shared settings, anything in [CONFIG_PATH] takes precedence
+const SYSTEM_CONFIG_PATH: &str = "/etc/memetool.json";
+
+/// How long an upload can go without progress before we warn about it
+pub const DEFAULT_UPLOAD_STALL_SECS: u64 = 30;
+/// How many times a failed upload's retried if the config doesn't say
+pub const DEFAULT_S3_MAX_RETRIES: u8 = 3;
+/// Images with a side longer than this get a warning in the editor
+pub const DEFAULT_WEB_MAX_DIMENSION: u32 = 4000;
+/// Images bigger than this on disk get a warning in the editor, in megabytes
+pub const DEFAULT_WEB_MAX_FILE_MB: u64 = 5;
+/// How many days deleted files stay in the trash if the config doesn't say
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+/// How many messages can be waiting in each direction between the UI and the background task
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 100;
 
+/// Where uploads go
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadDestination {
+    #[default]
+    S3,
+    /// PUT to [Configuration::http_upload_url], like a WebDAV share
+    Http,
+}
+
+/// What to do when something's imported over a file that's already in the workdir
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportConflict {
+    /// give it the next free numbered name
+    Rename,
+    Overwrite,
+    Skip,
+    #[default]
+    Ask,
+}
+
+// missing fields get their defaults, so a config file can just set the things it cares about
 #[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Configuration {
     pub s3_access_key_id: String,
     pub s3_secret_access_key: String,
@@ -15,34 +59,466 @@ pub struct Configuration {
     pub s3_region: String,
     // Set a custom endpoint, for example if you're using minio or another alternate S3 provider
     pub s3_endpoint: Option<String>,
+    // Seconds without upload progress before the uploading screen shows a stall warning
+    #[serde(default)]
+    pub upload_stall_secs: Option<u64>,
+    // Show the preview pane next to the browser grid
+    #[serde(default)]
+    pub split_view: bool,
+    // Show the whole file list in one scrolling view instead of pages
+    #[serde(default)]
+    pub infinite_scroll: bool,
+    // Width of the browser thumbnails, the height follows the default aspect ratio
+    #[serde(default)]
+    pub thumbnail_width: Option<f32>,
+    // Change how many images are on a page so a page fills the window
+    #[serde(default)]
+    pub scale_per_page: bool,
+    // Lay out a page of thumbnails in rows that fill the window, each as wide as its image needs,
+    // instead of fixed size cells. Infinite scroll always uses fixed size cells.
+    #[serde(default)]
+    pub justified_layout: bool,
+    // How much memory thumbnails can use across all directories, in megabytes
+    #[serde(default)]
+    pub thumbnail_cache_mb: Option<usize>,
+    // Match the search box's case against filenames
+    #[serde(default)]
+    pub default_search_case_sensitive: bool,
+    // Name uploads after a hash of their contents, so the same image only goes up once
+    #[serde(default)]
+    pub hash_upload_keys: bool,
+    // Longest side in pixels before the editor says an image isn't web-friendly
+    #[serde(default)]
+    pub web_max_dimension: Option<u32>,
+    // Size on disk in megabytes before the editor says an image isn't web-friendly
+    #[serde(default)]
+    pub web_max_file_mb: Option<u64>,
+    // Don't work out dominant colours for the editor
+    #[serde(default)]
+    pub disable_color_analysis: bool,
+    // How many times to retry an upload that failed for something that might go away, like a
+    // timeout or being throttled. Defaults to [DEFAULT_S3_MAX_RETRIES] via [Configuration::defaults]
+    pub s3_max_retries: u8,
+    // Decode every frame of GIFs for thumbnails, otherwise it's just the first frame which is
+    // much quicker for big animations
+    #[serde(default)]
+    pub animate_gif_thumbnails: bool,
+    // A thicker, yellow and black outline around the selected thumbnail, for low vision
+    #[serde(default)]
+    pub high_contrast_selection: bool,
+    // Which translation the UI uses, like "de". Unset follows the system's locale
+    #[serde(default)]
+    pub locale: Option<String>,
+    // Don't time thumbnail decoding for the browser footer
+    #[serde(default)]
+    pub disable_load_stats: bool,
+    // Days deleted files are kept in the trash before they're purged at startup, 0 keeps them
+    // until they're purged by hand. Defaults to [DEFAULT_TRASH_RETENTION_DAYS]
+    #[serde(default)]
+    pub trash_retention_days: Option<u32>,
+    // Don't look for blurry, badly exposed or tiny images after their thumbnails load
+    #[serde(default)]
+    pub disable_issue_detection: bool,
+    // After moving or uploading the file that's in the editor, open the next one in the browser's
+    // list instead of going back to the browser
+    #[serde(default)]
+    pub advance_after_action: bool,
+    // After deleting the file that's in the editor, open the next one (or the one before if it
+    // was the last) instead of going back to the browser. Defaults to on via
+    // [Configuration::defaults]
+    pub auto_advance_on_delete: bool,
+    // Tag everything in the working directory with the directory's name, so it can still be
+    // searched for by that after it's moved somewhere else
+    #[serde(default)]
+    pub auto_tag_by_directory: bool,
+    // How many rows of a page's thumbnails are asked for as soon as it opens, the rest wait until
+    // they're on screen or the first ones are in. Unset or 0 asks for the whole page at once
+    #[serde(default)]
+    pub eager_rows: Option<usize>,
+    // The browser's tabs as they were when memetool last saved them
+    #[serde(default)]
+    pub tabs: Vec<WorkdirTab>,
+    // Which of those was showing
+    #[serde(default)]
+    pub active_tab: usize,
+    // Where uploads go, "s3" or "http"
+    #[serde(default)]
+    pub upload_destination: UploadDestination,
+    // Uploads are PUT to this URL with the key on the end, when the destination's "http"
+    #[serde(default)]
+    pub http_upload_url: Option<String>,
+    // Sent as a bearer token with HTTP uploads
+    #[serde(default)]
+    pub http_upload_token: Option<String>,
+    // How many messages can be queued between the UI and the background, read at startup
+    #[serde(default)]
+    pub channel_capacity: Option<usize>,
+    // What happens when a dropped or pasted file has the same name as one in the workdir,
+    // "rename", "overwrite", "skip" or "ask"
+    #[serde(default)]
+    pub on_import_conflict: ImportConflict,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Self::defaults()
+    }
 }
 
 impl Configuration {
+    /// what every field is when it's not been set, [Configuration::merge] only takes things that
+    /// differ from this
+    pub fn defaults() -> Self {
+        Self {
+            s3_access_key_id: String::new(),
+            s3_secret_access_key: String::new(),
+            s3_bucket: String::new(),
+            s3_region: String::new(),
+            s3_endpoint: None,
+            upload_stall_secs: None,
+            split_view: false,
+            infinite_scroll: false,
+            thumbnail_width: None,
+            scale_per_page: false,
+            justified_layout: false,
+            thumbnail_cache_mb: None,
+            default_search_case_sensitive: false,
+            hash_upload_keys: false,
+            web_max_dimension: None,
+            web_max_file_mb: None,
+            disable_color_analysis: false,
+            s3_max_retries: DEFAULT_S3_MAX_RETRIES,
+            animate_gif_thumbnails: false,
+            high_contrast_selection: false,
+            locale: None,
+            disable_load_stats: false,
+            trash_retention_days: None,
+            disable_issue_detection: false,
+            advance_after_action: false,
+            auto_advance_on_delete: true,
+            auto_tag_by_directory: false,
+            eager_rows: None,
+            tabs: vec![],
+            active_tab: 0,
+            upload_destination: UploadDestination::S3,
+            http_upload_url: None,
+            http_upload_token: None,
+            channel_capacity: None,
+            on_import_conflict: ImportConflict::Ask,
+        }
+    }
+
+    /// where the user's config lives, [CONFIG_PATH_ENV] if it's set otherwise [CONFIG_PATH]
+    pub fn config_path() -> PathBuf {
+        match std::env::var_os(CONFIG_PATH_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => PathBuf::from(shellexpand::tilde(CONFIG_PATH).as_ref()),
+        }
+    }
+
+    /// load the system config then the user's config over the top of it
     pub fn try_new() -> anyhow::Result<Self> {
-        let shellpath = shellexpand::tilde(CONFIG_PATH);
-        let configpath = std::path::PathBuf::from(shellpath.as_ref());
-        let mut confighandle = std::fs::File::open(configpath)
-            .with_context(|| format!("Failed to open configuration file {}", CONFIG_PATH))?;
+        Self::load_layered(Path::new(SYSTEM_CONFIG_PATH), &Self::config_path())
+    }
+
+    /// [Configuration::try_new], or the defaults if it can't be loaded, so the app can start
+    /// before there's a config file
+    pub fn try_new_or_default() -> Self {
+        Self::try_new().unwrap_or_else(|err| {
+            if Self::file_exists() {
+                error!(
+                    "Failed to load configuration, using the defaults: {:?}",
+                    err
+                );
+            } else {
+                info!("No config file found, using the defaults");
+            }
+            Self::default()
+        })
+    }
+
+    /// if there's a system or user config file to load
+    pub fn file_exists() -> bool {
+        Path::new(SYSTEM_CONFIG_PATH).exists() || Self::config_path().exists()
+    }
+
+    /// `system` is optional, `user` has to exist unless `system` does
+    pub fn load_layered(system: &Path, user: &Path) -> anyhow::Result<Self> {
+        let base = if system.exists() {
+            Some(Self::load(system)?)
+        } else {
+            None
+        };
+        match (base, user.exists()) {
+            (Some(mut base), true) => {
+                base.merge(&Self::load(user)?);
+                Ok(base)
+            }
+            (Some(base), false) => {
+                debug!(
+                    "No user config at {}, using {}",
+                    user.display(),
+                    system.display()
+                );
+                Ok(base)
+            }
+            (None, _) => Self::load(user),
+        }
+    }
+
+    /// read a single config file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut confighandle = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open configuration file {}", path.display()))?;
         let mut configcontents = String::new();
 
         #[allow(clippy::unwrap_used)]
         confighandle.read_to_string(&mut configcontents)?;
 
         serde_json::from_str(&configcontents)
-            .with_context(|| format!("Failed to parse configuration file {}", CONFIG_PATH))
+            .with_context(|| format!("Failed to parse configuration file {}", path.display()))
+    }
+
+    /// `self` is the base, anything in `other` that isn't the default replaces what's in it.
+    /// A `None` in `other` never replaces a `Some`.
+    pub fn merge(&mut self, other: &Configuration) {
+        let defaults = Self::defaults();
+        // pulled apart so adding a field without merging it doesn't compile
+        let Configuration {
+            s3_access_key_id,
+            s3_secret_access_key,
+            s3_bucket,
+            s3_region,
+            s3_endpoint,
+            upload_stall_secs,
+            split_view,
+            infinite_scroll,
+            thumbnail_width,
+            scale_per_page,
+            justified_layout,
+            thumbnail_cache_mb,
+            default_search_case_sensitive,
+            hash_upload_keys,
+            web_max_dimension,
+            web_max_file_mb,
+            disable_color_analysis,
+            s3_max_retries,
+            animate_gif_thumbnails,
+            high_contrast_selection,
+            locale,
+            disable_load_stats,
+            trash_retention_days,
+            disable_issue_detection,
+            advance_after_action,
+            auto_advance_on_delete,
+            auto_tag_by_directory,
+            eager_rows,
+            tabs,
+            active_tab,
+            upload_destination,
+            http_upload_url,
+            http_upload_token,
+            channel_capacity,
+            on_import_conflict,
+        } = other;
+        merge_field(
+            &mut self.s3_access_key_id,
+            s3_access_key_id,
+            &defaults.s3_access_key_id,
+        );
+        merge_field(
+            &mut self.s3_secret_access_key,
+            s3_secret_access_key,
+            &defaults.s3_secret_access_key,
+        );
+        merge_field(&mut self.s3_bucket, s3_bucket, &defaults.s3_bucket);
+        merge_field(&mut self.s3_region, s3_region, &defaults.s3_region);
+        merge_field(&mut self.s3_endpoint, s3_endpoint, &defaults.s3_endpoint);
+        merge_field(
+            &mut self.upload_stall_secs,
+            upload_stall_secs,
+            &defaults.upload_stall_secs,
+        );
+        merge_field(&mut self.split_view, split_view, &defaults.split_view);
+        merge_field(
+            &mut self.infinite_scroll,
+            infinite_scroll,
+            &defaults.infinite_scroll,
+        );
+        merge_field(
+            &mut self.thumbnail_width,
+            thumbnail_width,
+            &defaults.thumbnail_width,
+        );
+        merge_field(
+            &mut self.scale_per_page,
+            scale_per_page,
+            &defaults.scale_per_page,
+        );
+        merge_field(
+            &mut self.justified_layout,
+            justified_layout,
+            &defaults.justified_layout,
+        );
+        merge_field(
+            &mut self.thumbnail_cache_mb,
+            thumbnail_cache_mb,
+            &defaults.thumbnail_cache_mb,
+        );
+        merge_field(
+            &mut self.default_search_case_sensitive,
+            default_search_case_sensitive,
+            &defaults.default_search_case_sensitive,
+        );
+        merge_field(
+            &mut self.hash_upload_keys,
+            hash_upload_keys,
+            &defaults.hash_upload_keys,
+        );
+        merge_field(
+            &mut self.web_max_dimension,
+            web_max_dimension,
+            &defaults.web_max_dimension,
+        );
+        merge_field(
+            &mut self.web_max_file_mb,
+            web_max_file_mb,
+            &defaults.web_max_file_mb,
+        );
+        merge_field(
+            &mut self.disable_color_analysis,
+            disable_color_analysis,
+            &defaults.disable_color_analysis,
+        );
+        merge_field(
+            &mut self.s3_max_retries,
+            s3_max_retries,
+            &defaults.s3_max_retries,
+        );
+        merge_field(
+            &mut self.animate_gif_thumbnails,
+            animate_gif_thumbnails,
+            &defaults.animate_gif_thumbnails,
+        );
+        merge_field(
+            &mut self.high_contrast_selection,
+            high_contrast_selection,
+            &defaults.high_contrast_selection,
+        );
+        merge_field(&mut self.locale, locale, &defaults.locale);
+        merge_field(
+            &mut self.disable_load_stats,
+            disable_load_stats,
+            &defaults.disable_load_stats,
+        );
+        merge_field(
+            &mut self.trash_retention_days,
+            trash_retention_days,
+            &defaults.trash_retention_days,
+        );
+        merge_field(
+            &mut self.disable_issue_detection,
+            disable_issue_detection,
+            &defaults.disable_issue_detection,
+        );
+        merge_field(
+            &mut self.advance_after_action,
+            advance_after_action,
+            &defaults.advance_after_action,
+        );
+        merge_field(
+            &mut self.auto_advance_on_delete,
+            auto_advance_on_delete,
+            &defaults.auto_advance_on_delete,
+        );
+        merge_field(
+            &mut self.auto_tag_by_directory,
+            auto_tag_by_directory,
+            &defaults.auto_tag_by_directory,
+        );
+        merge_field(&mut self.eager_rows, eager_rows, &defaults.eager_rows);
+        merge_field(&mut self.tabs, tabs, &defaults.tabs);
+        merge_field(&mut self.active_tab, active_tab, &defaults.active_tab);
+        merge_field(
+            &mut self.upload_destination,
+            upload_destination,
+            &defaults.upload_destination,
+        );
+        merge_field(
+            &mut self.http_upload_url,
+            http_upload_url,
+            &defaults.http_upload_url,
+        );
+        merge_field(
+            &mut self.http_upload_token,
+            http_upload_token,
+            &defaults.http_upload_token,
+        );
+        merge_field(
+            &mut self.channel_capacity,
+            channel_capacity,
+            &defaults.channel_capacity,
+        );
+        merge_field(
+            &mut self.on_import_conflict,
+            on_import_conflict,
+            &defaults.on_import_conflict,
+        );
     }
 
     pub fn save(&self) -> anyhow::Result<()> {
-        let shellpath = shellexpand::tilde(CONFIG_PATH);
-        let configpath = std::path::PathBuf::from(shellpath.as_ref());
+        let configpath = Self::config_path();
         let configcontents = serde_json::to_string_pretty(self)?;
-        let mut confighandle = std::fs::File::create(configpath)
-            .with_context(|| format!("Failed to open configuration file {}", CONFIG_PATH))?;
+        let mut confighandle = std::fs::File::create(&configpath).with_context(|| {
+            format!("Failed to open configuration file {}", configpath.display())
+        })?;
         // write the config file to confighandle
         confighandle
             .write(configcontents.as_bytes())
-            .with_context(|| format!("Failed to write configuration file {}", CONFIG_PATH))?;
-        info!("Successfully wrote config to {}", CONFIG_PATH);
+            .with_context(|| {
+                format!(
+                    "Failed to write configuration file {}",
+                    configpath.display()
+                )
+            })?;
+        info!("Successfully wrote config to {}", configpath.display());
         Ok(())
     }
+
+    /// the upload stall threshold, falling back to [DEFAULT_UPLOAD_STALL_SECS]
+    pub fn upload_stall_threshold(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.upload_stall_secs.unwrap_or(DEFAULT_UPLOAD_STALL_SECS))
+    }
+
+    /// the longest side a web-friendly image can have, falling back to [DEFAULT_WEB_MAX_DIMENSION]
+    pub fn web_max_dimension(&self) -> u32 {
+        self.web_max_dimension.unwrap_or(DEFAULT_WEB_MAX_DIMENSION)
+    }
+
+    /// how big a web-friendly image can be on disk in bytes, falling back to
+    /// [DEFAULT_WEB_MAX_FILE_MB]
+    pub fn web_max_bytes(&self) -> u64 {
+        self.web_max_file_mb.unwrap_or(DEFAULT_WEB_MAX_FILE_MB) * 1_000_000
+    }
+
+    /// how many days things stay in the trash, falling back to [DEFAULT_TRASH_RETENTION_DAYS]
+    pub fn trash_retention_days(&self) -> u32 {
+        self.trash_retention_days
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+    }
+
+    /// [Configuration::channel_capacity] or [DEFAULT_CHANNEL_CAPACITY], tokio won't make a
+    /// channel with no room at all
+    pub fn channel_capacity(&self) -> usize {
+        self.channel_capacity
+            .unwrap_or(DEFAULT_CHANNEL_CAPACITY)
+            .max(1)
+    }
+}
+
+/// take `value` if it's been set to something other than the default
+fn merge_field<T: Clone + PartialEq>(target: &mut T, value: &T, default: &T) {
+    if value != default {
+        *target = value.clone();
+    }
 }
@@ -0,0 +1,79 @@
+//! The browser's file list as a CSV, for looking at in a spreadsheet
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use log::*;
+
+pub const CSV_HEADER: [&str; 7] = [
+    "filepath",
+    "filename",
+    "file_size_bytes",
+    "width",
+    "height",
+    "format",
+    "mtime",
+];
+
+/// quote `field` if it's got anything in it that'd break the row up
+pub fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// one row for `path`, anything that can't be worked out is left empty
+fn csv_row(path: &Path) -> [String; 7] {
+    let metadata = std::fs::metadata(path);
+    let size = metadata
+        .as_ref()
+        .map(|metadata| metadata.len().to_string())
+        .unwrap_or_default();
+    // seconds since the epoch, spreadsheets can turn that into whatever they like
+    let mtime = metadata
+        .ok()
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_secs().to_string())
+        .unwrap_or_default();
+    let (width, height) = match crate::image_utils::load_image_dimensions_fast(path) {
+        Ok((width, height)) => (width.to_string(), height.to_string()),
+        Err(err) => {
+            debug!("Couldn't get dimensions of {}: {}", path.display(), err);
+            (String::new(), String::new())
+        }
+    };
+    let format = image::ImageFormat::from_path(path)
+        .map(|format| format!("{:?}", format).to_lowercase())
+        .unwrap_or_else(|_| {
+            path.extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default()
+        });
+    [
+        path.display().to_string(),
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size,
+        width,
+        height,
+        format,
+        mtime,
+    ]
+}
+
+/// write a header then a row per file, returning how many rows there were
+pub fn write_image_list_csv(files: &[PathBuf], mut out: impl Write) -> std::io::Result<usize> {
+    writeln!(out, "{}", CSV_HEADER.join(","))?;
+    for path in files {
+        let row = csv_row(path);
+        let row: Vec<String> = row.iter().map(|field| csv_escape(field)).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    out.flush()?;
+    Ok(files.len())
+}
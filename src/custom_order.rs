@@ -0,0 +1,60 @@
+//! Custom sort order, kept in the config directory with each workdir's order under its path, so
+//! nothing gets written in with the images
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use log::*;
+
+pub const CUSTOM_ORDER_PATH: &str = "~/.config/memetool-order.json";
+
+/// Each workdir's order, as file names
+type Orders = BTreeMap<String, Vec<String>>;
+
+/// what a workdir's filed under, so `~/memes` and `/home/me/memes/` are the same one
+pub fn workdir_key(workdir: &str) -> String {
+    PathBuf::from(shellexpand::tilde(workdir).as_ref())
+        .components()
+        .collect::<PathBuf>()
+        .display()
+        .to_string()
+}
+
+/// everything in the order file at `path`, a missing file is no orders
+fn load_all(path: &Path) -> anyhow::Result<Orders> {
+    if !path.exists() {
+        return Ok(Orders::default());
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to open order file {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse order file {}", path.display()))
+}
+
+/// load the saved order for a workdir from the order file at `path`, as a list of file names
+pub fn load(path: &Path, workdir: &str) -> anyhow::Result<Vec<String>> {
+    load_all(path)?
+        .remove(&workdir_key(workdir))
+        .with_context(|| format!("There's no saved order for {workdir}"))
+}
+
+/// save the order for a workdir, only the file names are stored so the workdir can move. The
+/// other workdirs' orders are left as they are.
+pub fn save(path: &Path, workdir: &str, order: &[String]) -> anyhow::Result<()> {
+    let filenames: Vec<String> = order
+        .iter()
+        .filter_map(|filepath| {
+            PathBuf::from(filepath)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .collect();
+    let mut orders = load_all(path)?;
+    orders.insert(workdir_key(workdir), filenames);
+    let contents = serde_json::to_string_pretty(&orders)?;
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write order file {}", path.display()))?;
+    info!("Saved custom order for {} to {}", workdir, path.display());
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! Listing the workdir. Small directories are read straight away on the UI thread, anything
+//! bigger's read by the background task a chunk at a time with
+//! [AppMsg::ScanDirectory](crate::AppMsg::ScanDirectory), so the browser can show the first page
+//! before it's got to the end.
+//!
+//! Each scan has a generation, the frontend only takes notice of the newest one and the
+//! background stops any older one that's still going.
+
+use std::path::{Path, PathBuf};
+
+use log::*;
+
+use crate::OK_EXTENSIONS;
+
+/// Directories with more entries than this are scanned in the background
+pub const SYNC_SCAN_MAX_ENTRIES: usize = 2000;
+/// How many files the background sends at a time while it's scanning
+pub const SCAN_CHUNK_SIZE: usize = 1000;
+
+/// if the browser lists `path`, going by its extension
+pub fn is_listable(path: &Path) -> bool {
+    let pathstr = path.to_string_lossy().to_lowercase();
+    OK_EXTENSIONS
+        .iter()
+        .any(|ext| pathstr.ends_with(&format!(".{ext}")))
+}
+
+/// put a listing in filename order, which is how the browser starts out
+pub fn sort_listing(files: &mut [PathBuf]) {
+    files.sort_by_cached_key(|path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+}
+
+/// everything listable in `dir` in filename order, or `None` if there's more than `limit`
+/// entries in it so it's worth scanning in the background. A directory that can't be read lists
+/// nothing.
+pub fn read_dir_limited(dir: &Path, limit: usize) -> Option<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("Couldn't read {}: {:?}", dir.display(), err);
+            return Some(vec![]);
+        }
+    };
+    let mut files = vec![];
+    for (index, entry) in entries.enumerate() {
+        if index >= limit {
+            return None;
+        }
+        if let Ok(entry) = entry {
+            let path = entry.path();
+            if is_listable(&path) {
+                files.push(path);
+            } else {
+                trace!("Skipping {} due to extension", path.display());
+            }
+        }
+    }
+    sort_listing(&mut files);
+    Some(files)
+}
+
+/// read `dir`, handing `on_chunk` what's listable `chunk_size` files at a time in whatever order
+/// the filesystem has them. `on_chunk` returns false to stop early. Returns how many files were
+/// found, or `None` if it was stopped.
+pub fn scan_directory(
+    dir: &Path,
+    chunk_size: usize,
+    mut on_chunk: impl FnMut(Vec<PathBuf>) -> bool,
+) -> std::io::Result<Option<usize>> {
+    let mut found = 0;
+    let mut chunk = Vec::with_capacity(chunk_size);
+    for entry in std::fs::read_dir(dir)? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let path = entry.path();
+        if !is_listable(&path) {
+            continue;
+        }
+        chunk.push(path);
+        if chunk.len() >= chunk_size {
+            found += chunk.len();
+            if !on_chunk(std::mem::replace(
+                &mut chunk,
+                Vec::with_capacity(chunk_size),
+            )) {
+                return Ok(None);
+            }
+        }
+    }
+    found += chunk.len();
+    if !chunk.is_empty() && !on_chunk(chunk) {
+        return Ok(None);
+    }
+    Ok(Some(found))
+}
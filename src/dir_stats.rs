@@ -0,0 +1,154 @@
+//! Numbers about the images in a directory, for the browser's info panel
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+/// How many of the biggest files the info panel lists
+pub const LARGEST_FILES: usize = 10;
+
+/// Let the frontend know how it's going every this many files
+pub const PROGRESS_EVERY: usize = 100;
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct DirStats {
+    pub total_files: usize,
+    /// Bytes on disk across all the files
+    pub total_bytes: u64,
+    /// How many files there are of each extension, in upper case
+    pub formats: BTreeMap<String, usize>,
+    /// Modification time of the oldest file
+    pub oldest: Option<SystemTime>,
+    /// Modification time of the newest file
+    pub newest: Option<SystemTime>,
+    /// Every file that could be read and how big it is, biggest first
+    pub files_by_size: Vec<(PathBuf, u64)>,
+}
+
+impl DirStats {
+    /// stat each file, anything that can't be read is still counted but doesn't add a size or time
+    pub fn compute(files: &[PathBuf]) -> Self {
+        Self::compute_with_progress(files, |_, _| {})
+    }
+
+    /// [DirStats::compute], calling `progress` with how many files are done out of how many every
+    /// [PROGRESS_EVERY] files and at the end
+    pub fn compute_with_progress(
+        files: &[PathBuf],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Self {
+        puffin::profile_function!();
+        let mut stats = DirStats {
+            total_files: files.len(),
+            ..Default::default()
+        };
+        for (index, filepath) in files.iter().enumerate() {
+            if index > 0 && index % PROGRESS_EVERY == 0 {
+                progress(index, files.len());
+            }
+            let format = filepath
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_uppercase())
+                .unwrap_or_default();
+            *stats.formats.entry(format).or_default() += 1;
+
+            let metadata = match std::fs::metadata(filepath) {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    debug!("Couldn't stat {}: {:?}", filepath.display(), err);
+                    continue;
+                }
+            };
+            stats.total_bytes += metadata.len();
+            stats.files_by_size.push((filepath.clone(), metadata.len()));
+            if let Ok(modified) = metadata.modified() {
+                stats.oldest = Some(stats.oldest.map_or(modified, |oldest| oldest.min(modified)));
+                stats.newest = Some(stats.newest.map_or(modified, |newest| newest.max(modified)));
+            }
+        }
+        // biggest first, then by name so it doesn't shuffle about
+        stats
+            .files_by_size
+            .sort_by(|(a_path, a_size), (b_path, b_size)| {
+                b_size.cmp(a_size).then_with(|| a_path.cmp(b_path))
+            });
+        progress(files.len(), files.len());
+        stats
+    }
+
+    /// stats for the files in `path` with one of `extensions`, case insensitively
+    pub fn for_directory(path: &Path, extensions: &[&str]) -> std::io::Result<Self> {
+        Self::for_directory_with_progress(path, extensions, |_, _| {})
+    }
+
+    /// [DirStats::for_directory], with `progress` as in [DirStats::compute_with_progress]
+    pub fn for_directory_with_progress(
+        path: &Path,
+        extensions: &[&str],
+        progress: impl FnMut(usize, usize),
+    ) -> std::io::Result<Self> {
+        let files: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|filepath| filepath.is_file())
+            .filter(|filepath| {
+                filepath
+                    .extension()
+                    .map(|ext| {
+                        let ext = ext.to_string_lossy().to_lowercase();
+                        extensions.iter().any(|ok| *ok == ext)
+                    })
+                    .unwrap_or(false)
+            })
+            .collect();
+        Ok(Self::compute_with_progress(&files, progress))
+    }
+
+    /// the `count` biggest files, biggest first
+    pub fn largest(&self, count: usize) -> &[(PathBuf, u64)] {
+        &self.files_by_size[..count.min(self.files_by_size.len())]
+    }
+
+    /// how many files are bigger than `max_bytes`, eg the web-friendly limit
+    pub fn count_over(&self, max_bytes: u64) -> usize {
+        // they're sorted, so it's however many come before the first one that fits
+        self.files_by_size
+            .partition_point(|(_, size)| *size > max_bytes)
+    }
+
+    /// eg "3 JPG, 12 PNG"
+    pub fn formats_summary(&self) -> String {
+        self.formats
+            .iter()
+            .map(|(format, count)| format!("{count} {format}"))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+/// `time` in the local timezone, eg "2023-07-04 15:30:09"
+pub fn format_timestamp(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// roughly how long ago `time` was, eg "3 days ago"
+pub fn format_age(time: SystemTime) -> String {
+    let age = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    let (count, one, other) = match age {
+        0..=59 => return crate::t!("age-just-now"),
+        60..=3599 => (age / 60, "age-minute", "age-minutes"),
+        3600..=86399 => (age / 3600, "age-hour", "age-hours"),
+        86400..=2591999 => (age / 86400, "age-day", "age-days"),
+        2592000..=31535999 => (age / 2592000, "age-month", "age-months"),
+        _ => (age / 31536000, "age-year", "age-years"),
+    };
+    let key = if count == 1 { one } else { other };
+    crate::t!(key, count = count)
+}
@@ -0,0 +1,241 @@
+//! Renaming files somewhere they can't just be renamed to, like another drive, and checking a
+//! rename's target before trying it
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::Timelike;
+
+use crate::image_utils::ExifDateTime;
+
+/// Windows won't open paths this long unless they start with `\\?\`
+pub const WINDOWS_MAX_PATH: usize = 260;
+/// How much is copied at a time when moving across filesystems, progress is sent after each
+pub const COPY_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// what's been typed into the rename box as a path, with `~` expanded
+pub fn expand_path(input: &str) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(input.trim()).as_ref())
+}
+
+/// if `path` is too long for Windows to open without the `\\?\` prefix. Counted in UTF-16 units,
+/// like Windows does, with room for the terminating null.
+pub fn windows_path_too_long(path: &str) -> bool {
+    !path.starts_with(r"\\?\") && path.encode_utf16().count() >= WINDOWS_MAX_PATH
+}
+
+/// why a file can't be renamed to `target`, or `None` if it looks fine
+pub fn rename_target_problem(target: &Path) -> Option<String> {
+    if target.exists() {
+        return Some(crate::t!("file-exists"));
+    }
+    let parent_exists = target
+        .parent()
+        .map(|parent| parent.as_os_str().is_empty() || parent.is_dir())
+        .unwrap_or(false);
+    if !parent_exists {
+        return Some(crate::t!("parent-missing"));
+    }
+    if cfg!(windows) && windows_path_too_long(&target.display().to_string()) {
+        return Some(crate::t!("path-too-long", max = WINDOWS_MAX_PATH - 1));
+    }
+    None
+}
+
+/// why `from` can't be renamed to `target`, like [rename_target_problem] except that changing
+/// only the case of the name is fine on filesystems that think the target's already there
+pub fn rename_problem(from: &Path, target: &Path) -> Option<String> {
+    if is_case_only_rename(from, target) {
+        return None;
+    }
+    rename_target_problem(target)
+}
+
+/// the directories a rename from `from` to `to` moves it between, `None` if it stays where it is
+pub fn moved_between(from: &Path, to: &Path) -> Option<(PathBuf, PathBuf)> {
+    let from_dir = from.parent().unwrap_or(Path::new(""));
+    let to_dir = to.parent().unwrap_or(Path::new(""));
+    // `/memes/../memes` is still `/memes`
+    if from_dir == to_dir || is_same_file(from_dir, to_dir) {
+        return None;
+    }
+    Some((from_dir.to_path_buf(), to_dir.to_path_buf()))
+}
+
+/// the leading directories `a` and `b` have in common, and what's left of each after them
+pub fn split_common_dirs(a: &Path, b: &Path) -> (PathBuf, PathBuf, PathBuf) {
+    let common: PathBuf = a
+        .components()
+        .zip(b.components())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect();
+    let rest = |path: &Path| path.strip_prefix(&common).unwrap_or(path).to_path_buf();
+    let (a_rest, b_rest) = (rest(a), rest(b));
+    (common, a_rest, b_rest)
+}
+
+/// `path` with the home directory shown as `~`, to keep it short
+pub fn home_relative(path: &Path) -> String {
+    let home = PathBuf::from(shellexpand::tilde("~").as_ref());
+    match path.strip_prefix(&home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => Path::new("~").join(rest).display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// if `a` and `b` are the same file on disk, whatever they're called
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// if renaming `from` to `to` only changes the case and the filesystem sees them as the same
+/// file, like `Meme.PNG` to `meme.png` on macOS or Windows
+pub fn is_case_only_rename(from: &Path, to: &Path) -> bool {
+    let (from_str, to_str) = (from.to_string_lossy(), to.to_string_lossy());
+    from_str != to_str && from_str.to_lowercase() == to_str.to_lowercase() && is_same_file(from, to)
+}
+
+/// change the case of `from`'s name to `to`'s by way of a temporary name, some filesystems
+/// ignore a rename between two names they think are the same
+pub fn rename_case_only(from: &Path, to: &Path) -> std::io::Result<()> {
+    let filename = from
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = crate::image_utils::first_free_path(
+        (0..).map(|n| from.with_file_name(format!(".{filename}.memetool-rename-{n}"))),
+    )
+    .ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+                "Couldn't find a free temporary name next to {}",
+                from.display()
+            ),
+        )
+    })?;
+    std::fs::rename(from, &temp)?;
+    std::fs::rename(&temp, to).map_err(|err| {
+        // put it back how it was
+        let _ = std::fs::rename(&temp, from);
+        err
+    })
+}
+
+/// A date and time in the local timezone, what the editor's "Set modified date" control edits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalTimeEdit {
+    pub date: chrono::NaiveDate,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl LocalTimeEdit {
+    pub fn from_time(time: SystemTime) -> Self {
+        let local = chrono::DateTime::<chrono::Local>::from(time).naive_local();
+        Self {
+            date: local.date(),
+            hour: local.hour(),
+            minute: local.minute(),
+            second: local.second(),
+        }
+    }
+
+    /// when EXIF says a photo was taken, which is in whatever timezone the camera was in so it's
+    /// taken to be this one. `None` if it's not a real date.
+    pub fn from_exif(taken: &ExifDateTime) -> Option<Self> {
+        Some(Self {
+            date: chrono::NaiveDate::from_ymd_opt(
+                taken.year.into(),
+                taken.month.into(),
+                taken.day.into(),
+            )?,
+            hour: taken.hour.into(),
+            minute: taken.minute.into(),
+            // leap seconds don't survive the trip
+            second: u32::from(taken.second).min(59),
+        })
+    }
+
+    /// `None` if it's not a time that happens here, like when the clocks go forward
+    pub fn to_time(&self) -> Option<SystemTime> {
+        self.date
+            .and_hms_opt(self.hour, self.minute, self.second)?
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .map(SystemTime::from)
+    }
+}
+
+/// change when `path` was last modified, leaving when it was last accessed alone
+pub fn set_modified(path: &Path, modified: SystemTime) -> std::io::Result<()> {
+    filetime::set_file_mtime(path, filetime::FileTime::from_system_time(modified))
+}
+
+/// if `rename` failed because the target's on a different filesystem
+pub fn is_cross_device(err: &std::io::Error) -> bool {
+    // ERROR_NOT_SAME_DEVICE on Windows, EXDEV everywhere else
+    let cross_device = if cfg!(windows) { 17 } else { 18 };
+    err.raw_os_error() == Some(cross_device)
+}
+
+/// move `from` to `to` by copying it, making sure the copy's on disk, then deleting the original.
+/// `progress` gets (bytes copied, total bytes) after each chunk. If anything goes wrong the
+/// original's left where it was and the partial copy's removed.
+pub fn copy_then_delete(
+    from: &Path,
+    to: &Path,
+    mut progress: impl FnMut(u64, u64),
+) -> std::io::Result<()> {
+    let mut source = File::open(from)?;
+    let metadata = source.metadata()?;
+    // don't copy over the top of something that's turned up since it was checked
+    let mut dest = File::options().write(true).create_new(true).open(to)?;
+    let moved = copy_contents(&mut source, &mut dest, metadata.len(), &mut progress)
+        .and_then(|_| dest.set_permissions(metadata.permissions()))
+        .and_then(|_| std::fs::remove_file(from));
+    if moved.is_err() {
+        let _ = std::fs::remove_file(to);
+    }
+    moved
+}
+
+/// copy everything from `source` to `dest` and wait for it to be written out
+fn copy_contents(
+    source: &mut File,
+    dest: &mut File,
+    total: u64,
+    progress: &mut impl FnMut(u64, u64),
+) -> std::io::Result<()> {
+    let mut buffer = vec![0; COPY_CHUNK_BYTES];
+    let mut done = 0;
+    loop {
+        let read = source.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        dest.write_all(&buffer[..read])?;
+        done += read as u64;
+        progress(done, total);
+    }
+    dest.sync_all()
+}
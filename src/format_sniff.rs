@@ -0,0 +1,202 @@
+//! What's really in a file going by its first few bytes, for the browser's format filter and the
+//! editor's "Fix extension". Extensions lie, there's plenty of `.png`s out there that are JPEGs.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::image_utils::image_format_from_bytes;
+use crate::thumbnail_cache::ThumbnailCache;
+
+/// The formats the browser can be filtered on, anything else is [SniffedFormat::Other]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SniffedFormat {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Other,
+}
+
+impl SniffedFormat {
+    pub const ALL: [SniffedFormat; 5] = [
+        SniffedFormat::Png,
+        SniffedFormat::Jpeg,
+        SniffedFormat::Gif,
+        SniffedFormat::WebP,
+        SniffedFormat::Other,
+    ];
+
+    pub fn from_image_format(format: Option<image::ImageFormat>) -> Self {
+        match format {
+            Some(image::ImageFormat::Png) => SniffedFormat::Png,
+            Some(image::ImageFormat::Jpeg) => SniffedFormat::Jpeg,
+            Some(image::ImageFormat::Gif) => SniffedFormat::Gif,
+            Some(image::ImageFormat::WebP) => SniffedFormat::WebP,
+            _ => SniffedFormat::Other,
+        }
+    }
+
+    /// what the extension says it is, which is what's used until it's been sniffed
+    pub fn from_extension(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "png" => SniffedFormat::Png,
+            "jpg" | "jpeg" => SniffedFormat::Jpeg,
+            "gif" => SniffedFormat::Gif,
+            "webp" => SniffedFormat::WebP,
+            _ => SniffedFormat::Other,
+        }
+    }
+
+    /// the extension a file in this format should have, if there's one we'd pick
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            SniffedFormat::Png => Some("png"),
+            SniffedFormat::Jpeg => Some("jpg"),
+            SniffedFormat::Gif => Some("gif"),
+            SniffedFormat::WebP => Some("webp"),
+            SniffedFormat::Other => None,
+        }
+    }
+
+    /// what it'd be sent as over HTTP
+    pub fn mime_type(&self) -> Option<&'static str> {
+        match self {
+            SniffedFormat::Png => Some("image/png"),
+            SniffedFormat::Jpeg => Some("image/jpeg"),
+            SniffedFormat::Gif => Some("image/gif"),
+            SniffedFormat::WebP => Some("image/webp"),
+            SniffedFormat::Other => None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SniffedFormat::Png => "PNG".to_string(),
+            SniffedFormat::Jpeg => "JPEG".to_string(),
+            SniffedFormat::Gif => "GIF".to_string(),
+            SniffedFormat::WebP => "WebP".to_string(),
+            SniffedFormat::Other => crate::t!("format-other"),
+        }
+    }
+}
+
+/// read the start of `path` and work out what it is, this only reads a dozen bytes so it's cheap
+/// enough to do a whole directory at a time
+pub fn sniff_file(path: &Path) -> std::io::Result<SniffedFormat> {
+    let mut header = [0u8; 12];
+    let mut file = std::fs::File::open(path)?;
+    let mut read = 0;
+    // short files are fine, they're just not any of the formats
+    while read < header.len() {
+        match file.read(&mut header[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(SniffedFormat::from_image_format(image_format_from_bytes(
+        &header,
+    )))
+}
+
+/// Sniffed formats keyed by file path, each one's thrown away if the file changes after it was
+/// sniffed
+#[derive(Debug, Default)]
+pub struct FormatCache {
+    formats: HashMap<String, (Option<SystemTime>, SniffedFormat)>,
+    /// Asked the backend about these, and haven't heard back yet
+    pending: HashSet<String>,
+}
+
+impl FormatCache {
+    /// the ones out of `filepaths` we don't know about and haven't asked about, which are then
+    /// counted as asked about
+    pub fn start<'a>(&mut self, filepaths: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        filepaths
+            .into_iter()
+            .filter(|filepath| {
+                !self.formats.contains_key(*filepath) && !self.pending.contains(*filepath)
+            })
+            .map(|filepath| {
+                self.pending.insert(filepath.to_string());
+                filepath.to_string()
+            })
+            .collect()
+    }
+
+    /// `modified` is when the file was modified as of sniffing it
+    pub fn insert(
+        &mut self,
+        filepath: String,
+        modified: Option<SystemTime>,
+        format: SniffedFormat,
+    ) {
+        self.pending.remove(&filepath);
+        self.formats.insert(filepath, (modified, format));
+    }
+
+    pub fn get(&self, filepath: &str) -> Option<SniffedFormat> {
+        self.formats.get(filepath).map(|(_, format)| *format)
+    }
+
+    /// what `filepath` is, going by its extension if it's not been sniffed yet
+    pub fn get_or_guess(&self, filepath: &Path) -> SniffedFormat {
+        self.get(&filepath.display().to_string())
+            .unwrap_or_else(|| SniffedFormat::from_extension(filepath))
+    }
+
+    /// forget what we know about `filepath`, so it gets sniffed again
+    pub fn invalidate(&mut self, filepath: &str) {
+        self.formats.remove(filepath);
+        self.pending.remove(filepath);
+    }
+
+    /// drop formats for anything that's gone away or changed since it was sniffed, returns how
+    /// many were removed
+    pub fn prune_stale(&mut self) -> usize {
+        let before = self.formats.len();
+        self.formats.retain(|filepath, (modified, _)| {
+            let path = Path::new(filepath);
+            path.exists() && ThumbnailCache::<()>::modified_time(path) == *modified
+        });
+        before - self.formats.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.formats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.formats.is_empty()
+    }
+}
+
+/// Which formats the browser's showing, everything's ticked to start with
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FormatFilter {
+    hidden: HashSet<SniffedFormat>,
+}
+
+impl FormatFilter {
+    /// if anything's being hidden
+    pub fn is_active(&self) -> bool {
+        !self.hidden.is_empty()
+    }
+
+    pub fn shows(&self, format: SniffedFormat) -> bool {
+        !self.hidden.contains(&format)
+    }
+
+    pub fn set(&mut self, format: SniffedFormat, shown: bool) {
+        if shown {
+            self.hidden.remove(&format);
+        } else {
+            self.hidden.insert(format);
+        }
+    }
+}
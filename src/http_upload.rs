@@ -0,0 +1,246 @@
+//! Uploading to a plain HTTP(S) server that takes PUTs, like a WebDAV share, instead of S3. Keys
+//! are paths under the base URL, and there's an optional bearer token.
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use log::*;
+use reqwest::{Method, StatusCode};
+
+use crate::config::Configuration;
+use crate::s3_upload::{is_retryable_status, S3Error, S3ErrorKind, S3Result};
+use crate::upload_backend::UploadBackend;
+
+/// An [UploadBackend] that's an HTTP server
+pub struct HttpStore {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    hash_keys: bool,
+    max_retries: u8,
+}
+
+impl HttpStore {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.filter(|token| !token.is_empty()),
+            hash_keys: false,
+            max_retries: crate::config::DEFAULT_S3_MAX_RETRIES,
+        }
+    }
+
+    /// an [HttpStore] for the URL and token in `config`
+    pub fn from_config(config: &Configuration) -> anyhow::Result<Self> {
+        let base_url = config
+            .http_upload_url
+            .as_deref()
+            .filter(|url| !url.trim().is_empty())
+            .ok_or_else(|| anyhow::anyhow!("http_upload_url isn't set in the configuration"))?;
+        Ok(Self {
+            hash_keys: config.hash_upload_keys,
+            max_retries: config.s3_max_retries,
+            ..Self::new(base_url.trim(), config.http_upload_token.clone())
+        })
+    }
+
+    /// where `key` lives on the server
+    pub fn url_for(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key.trim_start_matches('/'))
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// send it, turning anything but a 2xx into an error
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        context: &str,
+    ) -> Result<reqwest::Response, HttpFailure> {
+        let response = request.send().await.map_err(|error| HttpFailure {
+            status: None,
+            error: S3Error::new(request_error_kind(&error), format!("{context}: {error:?}")),
+        })?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        Err(HttpFailure {
+            status: Some(status),
+            error: S3Error::new(
+                S3ErrorKind::from_parts(None, Some(status.as_u16())),
+                format!("{context}: {status}"),
+            ),
+        })
+    }
+}
+
+/// A request that didn't work, and the status if the server answered
+struct HttpFailure {
+    status: Option<StatusCode>,
+    error: S3Error,
+}
+
+impl HttpFailure {
+    fn not_found(&self) -> bool {
+        self.status == Some(StatusCode::NOT_FOUND)
+    }
+}
+
+/// sort a request that didn't get an answer into something the user can act on
+fn request_error_kind(error: &reqwest::Error) -> S3ErrorKind {
+    if error.is_timeout() {
+        S3ErrorKind::Timeout
+    } else if error.is_connect() {
+        S3ErrorKind::NetworkUnreachable
+    } else {
+        S3ErrorKind::Other
+    }
+}
+
+/// what a failed upload is, going by the status the server sent back if it sent one
+pub fn upload_failure(status: Option<u16>, error: S3Error) -> S3Result {
+    let retryable = match status {
+        Some(status) => is_retryable_status(status),
+        // never got an answer, it might next time
+        None => matches!(
+            error.kind,
+            S3ErrorKind::Timeout | S3ErrorKind::NetworkUnreachable
+        ),
+    };
+    if retryable {
+        S3Result::RetryableUploadFailure(error)
+    } else {
+        S3Result::UploadFailure(error)
+    }
+}
+
+#[async_trait]
+impl UploadBackend for HttpStore {
+    async fn head_object(&self, key: &str) -> Result<String, S3Result> {
+        let url = self.url_for(key);
+        match self.send(self.request(Method::HEAD, &url), &url).await {
+            Ok(response) => Ok(format!("{:?}", response.headers())),
+            Err(failure) if failure.not_found() => Err(S3Result::FileNotFound),
+            Err(failure) => Err(S3Result::HeadError(failure.error)),
+        }
+    }
+
+    async fn put_object(&self, key: &str, filename: &str) -> Result<String, S3Result> {
+        debug!("PUTting {} to {}", filename, self.url_for(key));
+        let body = tokio::fs::read(filename)
+            .await
+            .map_err(|error| S3Result::FileOpenFail(format!("Failed to open file: {:?}", error)))?;
+        let url = self.url_for(key);
+        match self
+            .send(self.request(Method::PUT, &url).body(body), &url)
+            .await
+        {
+            Ok(response) => Ok(format!("{}", response.status())),
+            Err(failure) => Err(upload_failure(
+                failure.status.map(|status| status.as_u16()),
+                failure.error,
+            )),
+        }
+    }
+
+    /// plain HTTP servers don't have buckets, so this just checks the base URL answers and
+    /// doesn't turn us away. Plenty of servers won't HEAD a directory, which is fine.
+    async fn check_bucket_accessible(&self) -> Result<(), S3Result> {
+        match self
+            .send(self.request(Method::HEAD, &self.base_url), &self.base_url)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(HttpFailure { status, error })
+                if status.is_none()
+                    || matches!(
+                        error.kind,
+                        S3ErrorKind::InvalidCredentials | S3ErrorKind::AccessDenied
+                    ) =>
+            {
+                Err(S3Result::BucketNotAccessible(error))
+            }
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// WebDAV's COPY, a server that only does PUT won't manage this
+    async fn copy_object(&self, src_key: &str, dest_key: &str) -> Result<String, S3Result> {
+        let url = self.url_for(src_key);
+        let copy = Method::from_bytes(b"COPY").expect("COPY is a valid method");
+        match self
+            .send(
+                self.request(copy, &url)
+                    .header("Destination", self.url_for(dest_key)),
+                &url,
+            )
+            .await
+        {
+            Ok(response) => Ok(format!("{}", response.status())),
+            Err(failure) => Err(S3Result::CopyFailure(failure.error)),
+        }
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<String, S3Result> {
+        let url = self.url_for(key);
+        match self.send(self.request(Method::DELETE, &url), &url).await {
+            Ok(response) => Ok(format!("{}", response.status())),
+            Err(failure) => Err(S3Result::DeleteFailure(failure.error)),
+        }
+    }
+
+    async fn object_size(&self, key: &str) -> Result<u64, S3Result> {
+        let url = self.url_for(key);
+        match self.send(self.request(Method::HEAD, &url), &url).await {
+            Ok(response) => response
+                .headers()
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|length| length.to_str().ok())
+                .and_then(|length| length.parse().ok())
+                .ok_or_else(|| {
+                    S3Result::HeadError(S3Error::other(format!("{url} didn't say how big it is")))
+                }),
+            Err(failure) if failure.not_found() => Err(S3Result::FileNotFound),
+            Err(failure) => Err(S3Result::HeadError(failure.error)),
+        }
+    }
+
+    async fn get_object(&self, key: &str, dest: &Path) -> Result<(), S3Result> {
+        let url = self.url_for(key);
+        let response = match self.send(self.request(Method::GET, &url), &url).await {
+            Ok(response) => response,
+            Err(failure) if failure.not_found() => return Err(S3Result::FileNotFound),
+            Err(failure) => return Err(S3Result::DownloadFailure(failure.error)),
+        };
+        let body = response.bytes().await.map_err(|error| {
+            S3Result::DownloadFailure(S3Error::other(format!("{url}: {error:?}")))
+        })?;
+        tokio::fs::write(dest, body).await.map_err(|error| {
+            S3Result::DownloadFailure(S3Error::other(format!(
+                "Failed to write {}: {:?}",
+                dest.display(),
+                error
+            )))
+        })
+    }
+
+    fn url_for_key(&self, key: &str) -> Option<String> {
+        Some(self.url_for(key))
+    }
+
+    fn hash_keys(&self) -> bool {
+        self.hash_keys
+    }
+
+    fn max_retries(&self) -> u8 {
+        self.max_retries
+    }
+}
@@ -0,0 +1,123 @@
+//! Translations for everything the UI says, bundled in from `locales/` at compile time
+//!
+//! Each locale's a file of `key = value` lines, values can have `{name}` placeholders which get
+//! filled in by [t!]. Anything a locale's missing comes from English, and anything English is
+//! missing shows up as the key itself.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use log::*;
+
+/// What everything falls back to
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// code, what it's called in its own language, and the translations
+const BUNDLED: &[(&str, &str, &str)] = &[
+    ("en", "English", include_str!("../locales/en.txt")),
+    ("de", "Deutsch", include_str!("../locales/de.txt")),
+];
+
+lazy_static! {
+    static ref BUNDLES: HashMap<&'static str, HashMap<&'static str, &'static str>> = BUNDLED
+        .iter()
+        .map(|(code, _, contents)| (*code, parse(contents)))
+        .collect();
+    static ref CURRENT_LOCALE: RwLock<&'static str> = RwLock::new(DEFAULT_LOCALE);
+}
+
+/// Look up `key` in the current locale, filling in the `name = value` placeholders
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::translate($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+/// `key = value` lines, skipping blank ones and `#` comments
+fn parse(contents: &'static str) -> HashMap<&'static str, &'static str> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match line.split_once('=') {
+            Some((key, value)) => Some((key.trim(), value.trim())),
+            None => {
+                warn!("Skipping translation line without a value: {}", line);
+                None
+            }
+        })
+        .collect()
+}
+
+/// (code, name) for each locale there's a translation for
+pub fn available_locales() -> impl Iterator<Item = (&'static str, &'static str)> {
+    BUNDLED.iter().map(|(code, name, _)| (*code, *name))
+}
+
+/// the bundled locale that best matches `requested`, eg `de_DE.UTF-8` is `de`, or the system's
+/// locale if it's not set. Falls back to [DEFAULT_LOCALE].
+pub fn resolve_locale(requested: Option<&str>) -> &'static str {
+    let requested = match requested {
+        Some(requested) => Some(requested.to_string()),
+        None => sys_locale::get_locale(),
+    };
+    let Some(requested) = requested else {
+        return DEFAULT_LOCALE;
+    };
+    let language = requested
+        .split(['-', '_', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    available_locales()
+        .map(|(code, _)| code)
+        .find(|code| *code == language)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// switch what [t!] translates to, `None` means the system's locale. Returns the one that's used.
+pub fn set_locale(requested: Option<&str>) -> &'static str {
+    let locale = resolve_locale(requested);
+    info!("Using the {} locale", locale);
+    match CURRENT_LOCALE.write() {
+        Ok(mut current) => *current = locale,
+        Err(err) => error!("Failed to set the locale: {:?}", err),
+    }
+    locale
+}
+
+pub fn current_locale() -> &'static str {
+    CURRENT_LOCALE
+        .read()
+        .map(|current| *current)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// what [t!] does, use that instead
+pub fn translate(key: &str, args: &[(&str, String)]) -> String {
+    translate_in(current_locale(), key, args)
+}
+
+/// [translate] for a particular locale
+pub fn translate_in(locale: &str, key: &str, args: &[(&str, String)]) -> String {
+    let template = BUNDLES
+        .get(locale)
+        .and_then(|bundle| bundle.get(key))
+        .or_else(|| {
+            BUNDLES
+                .get(DEFAULT_LOCALE)
+                .and_then(|bundle| bundle.get(key))
+        });
+    let Some(template) = template else {
+        warn!("No translation for {}", key);
+        return key.to_string();
+    };
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
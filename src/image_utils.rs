@@ -1,17 +1,101 @@
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
 use eframe::egui;
-use eframe::epaint::{ColorImage, Vec2};
-use egui_extras::RetainedImage;
+use eframe::epaint::{vec2, ColorImage, Vec2};
 use log::*;
 
+use crate::load_stats::DecodeStats;
 use crate::THUMBNAIL_SIZE;
 
+/// The most images [generate_mosaic] will put in a mosaic
+pub const MOSAIC_MAX_IMAGES: usize = 16;
+/// Size of each tile in a mosaic, before it's scaled to the requested size
+pub const MOSAIC_TILE_SIZE: u32 = 64;
+/// Gap around each cell in a contact sheet
+pub const CONTACT_SHEET_PADDING: u32 = 10;
+/// Room under each cell in a contact sheet for the filename
+pub const CONTACT_SHEET_CAPTION_HEIGHT: u32 = 20;
+/// How big the contact sheet captions are, in pixels
+const CONTACT_SHEET_FONT_SIZE: f32 = 14.0;
+
+/// Why an image couldn't be loaded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageLoadError {
+    /// It doesn't start like any of the formats [image_format_from_bytes] knows, whatever the
+    /// extension says
+    UnsupportedFormat(PathBuf),
+    /// Reading or decoding it went wrong
+    Other(String),
+}
+
+impl std::fmt::Display for ImageLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageLoadError::UnsupportedFormat(path) => {
+                write!(f, "Unsupported format: {}", path.display())
+            }
+            ImageLoadError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for ImageLoadError {
+    fn from(message: String) -> Self {
+        ImageLoadError::Other(message)
+    }
+}
+
+// most of image_utils deals in String errors, so `?` still works there
+impl From<ImageLoadError> for String {
+    fn from(error: ImageLoadError) -> Self {
+        error.to_string()
+    }
+}
+
+/// How to lay out a contact sheet
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContactSheetLayout {
+    pub columns: u32,
+    /// Each image is fitted into a square this big
+    pub cell_size: u32,
+    pub background: [u8; 3],
+}
+
+impl Default for ContactSheetLayout {
+    fn default() -> Self {
+        Self {
+            columns: 4,
+            cell_size: 200,
+            background: [32, 32, 32],
+        }
+    }
+}
+
+/// `first_gif_frame` only decodes the first frame of GIFs, see [extract_first_gif_frame]
 pub async fn load_image_to_thumbnail_async(
     filename: &PathBuf,
     size: Option<Vec2>,
-) -> Result<RetainedImage, String> {
+    first_gif_frame: bool,
+) -> Result<ColorImage, ImageLoadError> {
+    load_image_to_thumbnail_with_stats_async(filename, size, first_gif_frame)
+        .await
+        .map(|(image, _)| image)
+}
+
+/// [load_image_to_thumbnail_async], along with what it cost
+pub async fn load_image_to_thumbnail_with_stats_async(
+    filename: &PathBuf,
+    size: Option<Vec2>,
+    first_gif_frame: bool,
+) -> Result<(ColorImage, DecodeStats), ImageLoadError> {
     debug!("Loading {}", filename.to_string_lossy());
+    if crate::video::is_video(filename) {
+        return Ok(load_video_thumbnail_async(filename, size).await?);
+    }
 
     use tokio::fs::File;
     use tokio::io::AsyncReadExt; // for read_to_end()
@@ -19,18 +103,210 @@ pub async fn load_image_to_thumbnail_async(
         Ok(file) => file,
         Err(e) => {
             error!("Failed to open file: {}", e);
-            return Err(e.to_string());
+            return Err(e.to_string().into());
         }
     };
 
     let mut contents = vec![];
     if let Err(err) = file.read_to_end(&mut contents).await {
         error!("Failed to read file: {}", err);
-        return Err(err.to_string());
+        return Err(err.to_string().into());
+    }
+
+    let decode_started = std::time::Instant::now();
+    // going by what's in the file, there's plenty of .jpg's out there that are really PNGs
+    let is_gif = image_format_from_bytes(&header_of(&contents)) == Some(image::ImageFormat::Gif);
+    let image = {
+        puffin::profile_scope!(
+            "decode",
+            format!("{} {} bytes", filename.display(), contents.len())
+        );
+        if first_gif_frame && is_gif {
+            extract_first_gif_frame(&contents)?
+        } else {
+            decode_image(filename, &contents)?
+        }
+    };
+    let image = match exif_orientation(&contents) {
+        Some(orientation) => apply_exif_orientation(image, orientation),
+        None => image,
+    };
+    let ci = {
+        puffin::profile_scope!("thumbnail", format!("{}x{}", image.width(), image.height()));
+        thumbnail_to_color_image(image, size)
+    };
+    let stats = DecodeStats {
+        source_bytes: contents.len() as u64,
+        pixels: ci.size[0] as u64 * ci.size[1] as u64,
+        decode_time: decode_started.elapsed(),
+    };
+
+    debug!(
+        "Finished loading {} in {:?}",
+        filename.display(),
+        stats.decode_time
+    );
+    Ok((ci, stats))
+}
+
+/// decode `data`, which was read from `path`. HEIF goes through libheif when the `heif` feature's
+/// on, everything else is up to the image crate.
+pub fn decode_image(
+    path: &std::path::Path,
+    data: &[u8],
+) -> Result<image::DynamicImage, ImageLoadError> {
+    #[cfg(feature = "heif")]
+    if is_heif(path) {
+        return decode_heif(data).map_err(ImageLoadError::Other);
+    }
+    match image_format_from_bytes(&header_of(data)) {
+        Some(format) => image::load_from_memory_with_format(data, format)
+            .map_err(|e| ImageLoadError::Other(e.to_string())),
+        None => Err(ImageLoadError::UnsupportedFormat(path.to_path_buf())),
+    }
+}
+
+/// the first 12 bytes of `data`, padded with zeroes if it's shorter than that
+fn header_of(data: &[u8]) -> [u8; 12] {
+    let mut header = [0u8; 12];
+    let len = data.len().min(header.len());
+    header[..len].copy_from_slice(&data[..len]);
+    header
+}
+
+/// work out what kind of image this is from the start of the file, rather than trusting the
+/// extension. Returns None for anything that isn't one of the formats we expect to see.
+pub fn image_format_from_bytes(header: &[u8; 12]) -> Option<image::ImageFormat> {
+    match header {
+        [0xFF, 0xD8, 0xFF, ..] => Some(image::ImageFormat::Jpeg),
+        [0x89, b'P', b'N', b'G', ..] => Some(image::ImageFormat::Png),
+        [b'G', b'I', b'F', ..] => Some(image::ImageFormat::Gif),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P'] => {
+            Some(image::ImageFormat::WebP)
+        }
+        [b'B', b'M', ..] => Some(image::ImageFormat::Bmp),
+        _ => None,
+    }
+}
+
+/// how big the image in `path` is, only reading as much of it as it takes to find out. That's
+/// just the header for most formats.
+pub fn load_image_dimensions_fast(path: &std::path::Path) -> Result<(u32, u32), String> {
+    if crate::video::is_video(path) {
+        return crate::video::probe(path).map(|info| (info.width, info.height));
     }
+    #[cfg(feature = "heif")]
+    if is_heif(path) {
+        let context = libheif_rs::HeifContext::read_from_file(&path.to_string_lossy())
+            .map_err(|e| e.to_string())?;
+        let handle = context.primary_image_handle().map_err(|e| e.to_string())?;
+        return Ok((handle.width(), handle.height()));
+    }
+    image::io::Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| e.to_string())?
+        .into_dimensions()
+        .map_err(|e| e.to_string())
+}
+
+/// if `path` looks like a HEIC/HEIF, going by the extension. They're only shown with the `heif`
+/// feature, see [OK_EXTENSIONS](crate::OK_EXTENSIONS)
+pub fn is_heif(path: &std::path::Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("heic") || ext.eq_ignore_ascii_case("heif"))
+        .unwrap_or(false)
+}
+
+/// HEIF's primary image as RGBA, libheif applies any rotation it's got on the way
+#[cfg(feature = "heif")]
+pub fn decode_heif(data: &[u8]) -> Result<image::DynamicImage, String> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let context = HeifContext::read_from_bytes(data).map_err(|e| e.to_string())?;
+    let handle = context.primary_image_handle().map_err(|e| e.to_string())?;
+    let decoded = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(|e| e.to_string())?;
+    let plane = decoded
+        .planes()
+        .interleaved
+        .ok_or_else(|| "HEIF didn't decode to interleaved RGBA".to_string())?;
+    let (width, height) = (plane.width, plane.height);
+    // rows can have padding on the end
+    let row_bytes = width as usize * 4;
+    let pixels: Vec<u8> = plane
+        .data
+        .chunks(plane.stride)
+        .take(height as usize)
+        .flat_map(|row| &row[..row_bytes])
+        .copied()
+        .collect();
+    image::RgbaImage::from_raw(width, height, pixels)
+        .map(image::DynamicImage::ImageRgba8)
+        .ok_or_else(|| format!("HEIF pixels didn't fit {}x{}", width, height))
+}
+
+/// Just the first frame of a GIF, which is all a still thumbnail needs and saves working through
+/// the rest of a big animation
+pub fn extract_first_gif_frame(data: &[u8]) -> Result<image::DynamicImage, String> {
+    use image::AnimationDecoder;
+
+    let decoder = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(data))
+        .map_err(|e| e.to_string())?;
+    let frame = decoder
+        .into_frames()
+        .next()
+        .ok_or_else(|| "GIF doesn't have any frames".to_string())?
+        .map_err(|e| e.to_string())?;
+    Ok(image::DynamicImage::ImageRgba8(frame.into_buffer()))
+}
+
+/// Grab an image over HTTP and thumbnail it, for browsing things like public S3 buckets
+pub async fn load_image_from_url(url: &str, size: Option<Vec2>) -> Result<ColorImage, String> {
+    debug!("Loading {}", url);
+    let response = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let contents = response.bytes().await.map_err(|e| e.to_string())?;
 
     let image = image::load_from_memory(&contents).map_err(|e| e.to_string())?;
+    let ci = thumbnail_to_color_image(image, size);
+
+    debug!("Finished loading {}", url);
+    Ok(ci)
+}
+
+/// the first frame of a video as the thumbnail, the file's never read in here because it's
+/// ffmpeg's job, see [crate::video]
+async fn load_video_thumbnail_async(
+    filename: &PathBuf,
+    size: Option<Vec2>,
+) -> Result<(ColorImage, DecodeStats), String> {
+    let decode_started = std::time::Instant::now();
+    let path = filename.clone();
+    let image = tokio::task::spawn_blocking(move || crate::video::first_frame(&path))
+        .await
+        .map_err(|e| e.to_string())??;
+    let ci = thumbnail_to_color_image(image, size);
+    let stats = DecodeStats {
+        source_bytes: tokio::fs::metadata(filename)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0),
+        pixels: ci.size[0] as u64 * ci.size[1] as u64,
+        decode_time: decode_started.elapsed(),
+    };
+    debug!(
+        "Finished loading video {} in {:?}",
+        filename.display(),
+        stats.decode_time
+    );
+    Ok((ci, stats))
+}
 
+/// shrink an image down to `size` (or [THUMBNAIL_SIZE]) and turn it into something egui can use
+fn thumbnail_to_color_image(image: image::DynamicImage, size: Option<Vec2>) -> ColorImage {
     let (x, y) = match size {
         Some(size) => (size.x as u32, size.y as u32),
         None => (THUMBNAIL_SIZE.x as u32, THUMBNAIL_SIZE.y as u32),
@@ -42,23 +318,25 @@ pub async fn load_image_to_thumbnail_async(
     let image_buffer = image.to_rgba8();
     let pixels = image_buffer.as_flat_samples();
 
-    let ci = ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
-
-    let response = egui_extras::RetainedImage::from_color_image(filename.to_string_lossy(), ci);
-    debug!("Finished loading {}", filename.display());
-    Ok(response)
+    ColorImage::from_rgba_unmultiplied(size, pixels.as_slice())
 }
 
 pub fn load_image_to_thumbnail(
     filename: &PathBuf,
     size: Option<Vec2>,
-) -> Result<RetainedImage, String> {
+) -> Result<ColorImage, String> {
     debug!("Loading {}", filename.to_string_lossy());
     puffin::profile_function!(filename.display().to_string());
-    let image = image::io::Reader::open(filename)
-        .map_err(|e| e.to_string())?
-        .decode()
-        .map_err(|e| e.to_string())?;
+    if crate::video::is_video(filename) {
+        return crate::video::first_frame(filename)
+            .map(|image| thumbnail_to_color_image(image, size));
+    }
+    let contents = std::fs::read(filename).map_err(|e| e.to_string())?;
+    let image = decode_image(filename, &contents)?;
+    let image = match exif_orientation(&contents) {
+        Some(orientation) => apply_exif_orientation(image, orientation),
+        None => image,
+    };
 
     let (x, y) = match size {
         Some(size) => (size.x as u32, size.y as u32),
@@ -73,9 +351,1027 @@ pub fn load_image_to_thumbnail(
 
     let ci = ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
 
-    let response = egui_extras::RetainedImage::from_color_image(filename.to_string_lossy(), ci);
     debug!("Finished loading {}", filename.display());
-    Ok(response)
+    Ok(ci)
+}
+
+/// The EXIF tag that says which way up a JPEG should be shown
+const EXIF_ORIENTATION_TAG: u16 = 0x0112;
+/// The EXIF tag pointing at the IFD with the camera's settings in, including when it was taken
+const EXIF_SUB_IFD_TAG: u16 = 0x8769;
+/// When the photo was taken, as opposed to when it was last saved
+const EXIF_DATE_TIME_ORIGINAL_TAG: u16 = 0x9003;
+
+/// Things the editor can do to the file itself
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageAction {
+    /// Decode, rotate clockwise and save it again, which loses quality for JPEGs
+    Rotate { degrees: u16 },
+    /// Change which way up a JPEG's shown without touching the image data, see
+    /// [apply_lossless_jpeg_rotation]
+    LosslessRotate { degrees: u16 },
+    /// Save a JPEG copy next to it, see [convert_to_jpeg]
+    ConvertToJpeg,
+    /// Frame it in a solid colour, see [add_border]
+    AddBorder { pixels: u32, color: [u8; 3] },
+    /// Crisp it up after it's been shrunk, see [apply_unsharp_mask]
+    Sharpen {
+        sigma: f32,
+        amount: f32,
+        threshold: u8,
+    },
+    /// Old-photo brown, see [apply_sepia]
+    Sepia,
+}
+
+impl ImageAction {
+    /// if it saves over the file, rather than next to it
+    pub fn overwrites(&self) -> bool {
+        !matches!(self, ImageAction::ConvertToJpeg)
+    }
+
+    pub fn apply(&self, path: &PathBuf) -> Result<(), String> {
+        match self {
+            ImageAction::Rotate { degrees } => rotate_image(path, *degrees),
+            ImageAction::LosslessRotate { degrees } => apply_lossless_jpeg_rotation(path, *degrees),
+            ImageAction::ConvertToJpeg => convert_to_jpeg(path).map(|_| ()),
+            ImageAction::AddBorder { pixels, color } => add_border(path, *pixels, *color),
+            ImageAction::Sharpen {
+                sigma,
+                amount,
+                threshold,
+            } => sharpen_image(path, *sigma, *amount, *threshold),
+            ImageAction::Sepia => sepia_image(path),
+        }
+    }
+}
+
+/// where [convert_to_jpeg] puts its copy, the same name with a `.jpg` on the end
+pub fn jpeg_path_for(path: &std::path::Path) -> PathBuf {
+    path.with_extension("jpg")
+}
+
+/// How many numbered names to try when looking for a free one, before giving up
+pub const MAX_NAME_ATTEMPTS: usize = 10_000;
+
+/// the first of `candidates` that isn't taken, `None` if the first [MAX_NAME_ATTEMPTS] all are
+pub fn first_free_path(candidates: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    candidates
+        .into_iter()
+        .take(MAX_NAME_ATTEMPTS)
+        .find(|candidate| !candidate.exists())
+}
+
+/// somewhere free next to `path` for a copy of it, `{stem}_copy{n}.{ext}` with the first `n`
+/// from 1 up that isn't taken
+pub fn duplicate_path_for(path: &std::path::Path) -> Option<PathBuf> {
+    suffixed_path_for(path, "copy")
+}
+
+/// the first of `{stem}_{suffix}{n}.{ext}` next to `path` that isn't taken, counting from 1
+pub fn suffixed_path_for(path: &std::path::Path, suffix: &str) -> Option<PathBuf> {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    first_free_path((1..).map(|n| path.with_file_name(format!("{stem}_{suffix}{n}{extension}"))))
+}
+
+/// Save a JPEG copy of `path` next to it, eg to turn a HEIC into something that can be shared.
+/// Doesn't overwrite anything, returns where it went.
+pub fn convert_to_jpeg(path: &PathBuf) -> Result<PathBuf, String> {
+    let dest = jpeg_path_for(path);
+    if dest.exists() {
+        return Err(format!("{} already exists", dest.display()));
+    }
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let image = decode_image(path, &data)?;
+    let image = match exif_orientation(&data) {
+        Some(orientation) => apply_exif_orientation(image, orientation),
+        None => image,
+    };
+    // JPEGs don't do transparency
+    image::DynamicImage::ImageRgb8(image.to_rgb8())
+        .save_with_format(&dest, image::ImageFormat::Jpeg)
+        .map_err(|e| e.to_string())?;
+    info!("Converted {} to {}", path.display(), dest.display());
+    Ok(dest)
+}
+
+/// if `path` looks like a JPEG, going by the extension
+pub fn is_jpeg(path: &std::path::Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"))
+        .unwrap_or(false)
+}
+
+/// read two bytes of TIFF data, which can be either endian
+fn tiff_u16(bytes: [u8; 2], big_endian: bool) -> u16 {
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+/// Where the TIFF data in a JPEG's EXIF segment is, if it's got one
+fn exif_tiff_range(data: &[u8]) -> Option<std::ops::Range<usize>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        // padding between segments
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        // the image data's started, there's no more metadata after this
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data.get(pos + 4..pos + 2 + length)?;
+        if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return Some(pos + 4 + 6..pos + 2 + length);
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+/// Where the orientation value is in a JPEG's EXIF data, and whether it's big endian
+fn find_exif_orientation(data: &[u8]) -> Option<(usize, bool)> {
+    let range = exif_tiff_range(data)?;
+    find_tiff_orientation(&data[range.clone()])
+        .map(|(offset, big_endian)| (range.start + offset, big_endian))
+}
+
+/// TIFF data, which is how EXIF's laid out, with offsets relative to the start of it
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let big_endian = match data.get(0..2)? {
+            b"MM" => true,
+            b"II" => false,
+            _ => return None,
+        };
+        Some(Self { data, big_endian })
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        Some(tiff_u16(
+            [*self.data.get(offset)?, *self.data.get(offset + 1)?],
+            self.big_endian,
+        ))
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn first_ifd(&self) -> Option<usize> {
+        self.u32(4).map(|offset| offset as usize)
+    }
+
+    /// where `tag`'s 12 byte entry is in the IFD at `ifd`
+    fn find_entry(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let entries = self.u16(ifd)? as usize;
+        (0..entries)
+            .map(|index| ifd + 2 + index * 12)
+            .find(|entry| self.u16(*entry) == Some(tag))
+    }
+
+    /// the bytes of an ASCII value, without the null on the end
+    fn ascii(&self, entry: usize) -> Option<&'a str> {
+        let count = self.u32(entry + 4)? as usize;
+        // anything longer than four bytes is somewhere else, and the entry says where
+        let offset = match count {
+            0..=4 => entry + 8,
+            _ => self.u32(entry + 8)? as usize,
+        };
+        let value = self.data.get(offset..offset.checked_add(count)?)?;
+        std::str::from_utf8(value)
+            .ok()
+            .map(|value| value.trim_end_matches('\0'))
+    }
+}
+
+/// The orientation entry in the first IFD of some TIFF data, relative to the start of it
+fn find_tiff_orientation(tiff: &[u8]) -> Option<(usize, bool)> {
+    let tiff = Tiff::new(tiff)?;
+    let entry = tiff.find_entry(tiff.first_ifd()?, EXIF_ORIENTATION_TAG)?;
+    Some((entry + 8, tiff.big_endian))
+}
+
+/// When EXIF says a photo was taken, it doesn't say which timezone
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExifDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl ExifDateTime {
+    /// `YYYY:MM:DD HH:MM:SS` like EXIF has it. Cameras that don't know fill it with zeroes or
+    /// spaces, which doesn't parse.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (date, time) = value.trim().split_once(' ')?;
+        let numbers = |part: &str| -> Option<Vec<u16>> {
+            part.split(':').map(|number| number.parse().ok()).collect()
+        };
+        match (numbers(date)?.as_slice(), numbers(time)?.as_slice()) {
+            (&[year, month, day], &[hour, minute, second])
+                if (1..=12).contains(&month)
+                    && (1..=31).contains(&day)
+                    && hour < 24
+                    && minute < 60
+                    && second < 61 =>
+            {
+                Some(Self {
+                    year,
+                    month: month as u8,
+                    day: day as u8,
+                    hour: hour as u8,
+                    minute: minute as u8,
+                    second: second as u8,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// `YYYY-MM-DD_HHmmss`, which sorts by date
+    pub fn filename_stem(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}_{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+impl std::fmt::Display for ExifDateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// The bits of a JPEG's EXIF data memetool cares about
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExifInfo {
+    pub orientation: Option<u16>,
+    /// When the photo was taken
+    pub date_time_original: Option<ExifDateTime>,
+}
+
+/// EXIF has to be in the first 64KiB of a JPEG, there's no need to read the rest
+const EXIF_READ_BYTES: u64 = 128 * 1024;
+
+/// what's in `path`'s EXIF data, anything that isn't a JPEG or hasn't got any has nothing in it
+pub fn read_exif(path: &Path) -> Result<ExifInfo, String> {
+    let mut data = vec![];
+    std::fs::File::open(path)
+        .and_then(|file| file.take(EXIF_READ_BYTES).read_to_end(&mut data))
+        .map_err(|e| e.to_string())?;
+    Ok(exif_info(&data))
+}
+
+/// [read_exif] for a JPEG that's already been read
+pub fn exif_info(data: &[u8]) -> ExifInfo {
+    let date_time_original = exif_tiff_range(data)
+        .and_then(|range| Tiff::new(&data[range]))
+        .and_then(|tiff| {
+            let sub_ifd_entry = tiff.find_entry(tiff.first_ifd()?, EXIF_SUB_IFD_TAG)?;
+            let sub_ifd = tiff.u32(sub_ifd_entry + 8)? as usize;
+            let entry = tiff.find_entry(sub_ifd, EXIF_DATE_TIME_ORIGINAL_TAG)?;
+            ExifDateTime::parse(tiff.ascii(entry)?)
+        });
+    ExifInfo {
+        orientation: exif_orientation(data),
+        date_time_original,
+    }
+}
+
+/// where renaming `path` after when it was taken puts it, `YYYY-MM-DD_HHmmss` with its extension,
+/// and `_1`, `_2` and so on if that's taken. If it's already called that, it's `path`.
+pub fn date_rename_path(path: &Path, taken: &ExifDateTime) -> Option<PathBuf> {
+    let stem = taken.filename_stem();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    std::iter::once(path.with_file_name(format!("{stem}{extension}")))
+        .chain((1..).map(|n| path.with_file_name(format!("{stem}_{n}{extension}"))))
+        .take(MAX_NAME_ATTEMPTS)
+        .find(|candidate| candidate == path || !candidate.exists())
+}
+
+/// Which way up the EXIF data says the image should be shown, 1 to 8, if it says
+pub fn exif_orientation(data: &[u8]) -> Option<u16> {
+    let (offset, big_endian) = find_exif_orientation(data)?;
+    let orientation = tiff_u16([*data.get(offset)?, *data.get(offset + 1)?], big_endian);
+    (1..=8).contains(&orientation).then_some(orientation)
+}
+
+/// turn the image as it's stored into the way `orientation` says it should be shown
+pub fn apply_exif_orientation(image: image::DynamicImage, orientation: u16) -> image::DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.fliph().rotate270(),
+        6 => image.rotate90(),
+        7 => image.fliph().rotate90(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// the orientation you get by turning something shown with `orientation` a further `degrees`
+/// clockwise
+pub fn rotated_orientation(orientation: u16, degrees: u16) -> Result<u16, String> {
+    let quarter_turns = match degrees {
+        90 => 1,
+        180 => 2,
+        270 => 3,
+        _ => {
+            return Err(format!(
+                "Can only rotate by 90, 180 or 270 degrees, not {degrees}"
+            ))
+        }
+    };
+    // each quarter turn clockwise moves one step round these
+    const UNMIRRORED: [u16; 4] = [1, 6, 3, 8];
+    const MIRRORED: [u16; 4] = [2, 7, 4, 5];
+    let (cycle, step) = match UNMIRRORED.iter().position(|value| *value == orientation) {
+        Some(step) => (UNMIRRORED, step),
+        None => match MIRRORED.iter().position(|value| *value == orientation) {
+            Some(step) => (MIRRORED, step),
+            None => (UNMIRRORED, 0),
+        },
+    };
+    Ok(cycle[(step + quarter_turns) % 4])
+}
+
+/// An APP1 segment with just enough EXIF in it to say which way up the image is
+fn orientation_segment(orientation: u16) -> Vec<u8> {
+    let mut segment = vec![0xFF, 0xE1, 0, 34];
+    segment.extend_from_slice(b"Exif\0\0");
+    // big endian TIFF header, the first IFD comes straight after it
+    segment.extend_from_slice(b"MM\0\x2A");
+    segment.extend_from_slice(&8u32.to_be_bytes());
+    segment.extend_from_slice(&1u16.to_be_bytes());
+    segment.extend_from_slice(&EXIF_ORIENTATION_TAG.to_be_bytes());
+    // one SHORT, padded out to fill the value
+    segment.extend_from_slice(&3u16.to_be_bytes());
+    segment.extend_from_slice(&1u32.to_be_bytes());
+    segment.extend_from_slice(&orientation.to_be_bytes());
+    segment.extend_from_slice(&[0, 0]);
+    // no more IFDs
+    segment.extend_from_slice(&0u32.to_be_bytes());
+    segment
+}
+
+/// `tiff` with an orientation entry added to its first IFD. The IFD's rebuilt on the end with the
+/// new entry in it, so nothing that's already there moves and every offset in it stays right.
+fn add_tiff_orientation(tiff: &[u8], orientation: u16) -> Option<Vec<u8>> {
+    let reader = Tiff::new(tiff)?;
+    let ifd = reader.first_ifd()?;
+    let count = reader.u16(ifd)? as usize;
+    let entries = tiff.get(ifd + 2..ifd + 2 + count * 12)?;
+    let next_ifd = tiff.get(ifd + 2 + count * 12..ifd + 6 + count * 12)?;
+    let big_endian = reader.big_endian;
+    let u16_bytes = |value: u16| {
+        if big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        }
+    };
+    let u32_bytes = |value: u32| {
+        if big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        }
+    };
+    // entries have to stay sorted by tag
+    let insert_at = (0..count)
+        .find(|index| {
+            reader
+                .u16(ifd + 2 + index * 12)
+                .map_or(false, |tag| tag > EXIF_ORIENTATION_TAG)
+        })
+        .unwrap_or(count);
+
+    let mut data = tiff.to_vec();
+    // IFDs start on a word boundary
+    if data.len() % 2 == 1 {
+        data.push(0);
+    }
+    let new_ifd = u32::try_from(data.len()).ok()?;
+    data.extend_from_slice(&u16_bytes(u16::try_from(count + 1).ok()?));
+    data.extend_from_slice(&entries[..insert_at * 12]);
+    data.extend_from_slice(&u16_bytes(EXIF_ORIENTATION_TAG));
+    // one SHORT, padded out to fill the value
+    data.extend_from_slice(&u16_bytes(3));
+    data.extend_from_slice(&u32_bytes(1));
+    data.extend_from_slice(&u16_bytes(orientation));
+    data.extend_from_slice(&[0, 0]);
+    data.extend_from_slice(&entries[insert_at * 12..]);
+    data.extend_from_slice(next_ifd);
+    data[4..8].copy_from_slice(&u32_bytes(new_ifd));
+    Some(data)
+}
+
+/// Rotate a JPEG clockwise by changing its EXIF orientation, the image data's left exactly as it
+/// was so nothing's lost. If it's not got any EXIF data a minimal block's added, and if its EXIF
+/// hasn't got an orientation one's added to it. Viewers that ignore EXIF won't show it rotated.
+pub fn apply_lossless_jpeg_rotation(path: &PathBuf, degrees: u16) -> Result<(), String> {
+    let mut data = std::fs::read(path).map_err(|e| e.to_string())?;
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err(format!("{} isn't a JPEG", path.display()));
+    }
+    match find_exif_orientation(&data) {
+        Some((offset, big_endian)) => {
+            let current = exif_orientation(&data).unwrap_or(1);
+            let orientation = rotated_orientation(current, degrees)?;
+            let bytes = if big_endian {
+                orientation.to_be_bytes()
+            } else {
+                orientation.to_le_bytes()
+            };
+            data[offset..offset + 2].copy_from_slice(&bytes);
+        }
+        None => {
+            let orientation = rotated_orientation(1, degrees)?;
+            match exif_tiff_range(&data) {
+                Some(range) => {
+                    let tiff = add_tiff_orientation(&data[range.clone()], orientation).ok_or_else(
+                        || format!("Couldn't read the EXIF data in {}", path.display()),
+                    )?;
+                    // the segment's length counts itself and the Exif header as well
+                    let length = u16::try_from(tiff.len() + 8).map_err(|_| {
+                        format!(
+                            "{} has too much EXIF data to add an orientation to",
+                            path.display()
+                        )
+                    })?;
+                    data[range.start - 8..range.start - 6].copy_from_slice(&length.to_be_bytes());
+                    data.splice(range, tiff);
+                }
+                None => {
+                    // JFIF says its APP0 has to come first, so it goes after that if there is one
+                    let insert_at = match data.get(2..6) {
+                        Some([0xFF, 0xE0, high, low]) => {
+                            4 + u16::from_be_bytes([*high, *low]) as usize
+                        }
+                        _ => 2,
+                    };
+                    data.splice(insert_at..insert_at, orientation_segment(orientation));
+                }
+            }
+        }
+    }
+    std::fs::write(path, data).map_err(|e| e.to_string())?;
+    info!(
+        "Rotated {} by {} degrees losslessly",
+        path.display(),
+        degrees
+    );
+    Ok(())
+}
+
+/// Rotate clockwise by decoding and saving it again. JPEGs lose a bit each time, and any EXIF
+/// orientation's applied to the pixels on the way, as the metadata isn't kept.
+pub fn rotate_image(path: &PathBuf, degrees: u16) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let image = decode_image(path, &data)?;
+    let image = match exif_orientation(&data) {
+        Some(orientation) => apply_exif_orientation(image, orientation),
+        None => image,
+    };
+    let image = match degrees {
+        90 => image.rotate90(),
+        180 => image.rotate180(),
+        270 => image.rotate270(),
+        _ => {
+            return Err(format!(
+                "Can only rotate by 90, 180 or 270 degrees, not {degrees}"
+            ))
+        }
+    };
+    image.save(path).map_err(|e| e.to_string())?;
+    info!("Rotated {} by {} degrees", path.display(), degrees);
+    Ok(())
+}
+
+/// Make the image `pixels` bigger on each side, filled in with `color`, and save it over the
+/// original. Transparent images stay transparent inside the border.
+pub fn add_border(src: &PathBuf, pixels: u32, color: [u8; 3]) -> Result<(), String> {
+    if pixels == 0 {
+        return Err("A border needs to be at least a pixel wide".to_string());
+    }
+    let data = std::fs::read(src).map_err(|e| e.to_string())?;
+    let image = decode_image(src, &data)?;
+    let image = match exif_orientation(&data) {
+        Some(orientation) => apply_exif_orientation(image, orientation),
+        None => image,
+    };
+    let width = pixels
+        .checked_mul(2)
+        .and_then(|border| border.checked_add(image.width()))
+        .ok_or_else(|| format!("A {pixels}px border is too big"))?;
+    let height = pixels
+        .checked_mul(2)
+        .and_then(|border| border.checked_add(image.height()))
+        .ok_or_else(|| format!("A {pixels}px border is too big"))?;
+    let offset = pixels as i64;
+    // keep the colour type, JPEGs can't be saved with an alpha channel
+    let bordered = if image.color().has_alpha() {
+        let [r, g, b] = color;
+        let mut canvas = image::RgbaImage::from_pixel(width, height, image::Rgba([r, g, b, 255]));
+        image::imageops::replace(&mut canvas, &image.to_rgba8(), offset, offset);
+        image::DynamicImage::ImageRgba8(canvas)
+    } else {
+        let mut canvas = image::RgbImage::from_pixel(width, height, image::Rgb(color));
+        image::imageops::replace(&mut canvas, &image.to_rgb8(), offset, offset);
+        image::DynamicImage::ImageRgb8(canvas)
+    };
+    bordered.save(src).map_err(|e| e.to_string())?;
+    info!("Added a {}px border to {}", pixels, src.display());
+    Ok(())
+}
+
+/// Sharpen by adding back how much each pixel differs from a blurred copy. `sigma` is how far the
+/// blur reaches in pixels, `amount` is how much of the difference is added (1.0 is all of it) and
+/// differences of `threshold` or less are left alone so flat areas don't get grainy. Transparency
+/// isn't touched.
+pub fn apply_unsharp_mask(img: &mut image::DynamicImage, sigma: f32, amount: f32, threshold: u8) {
+    // the blur panics without a positive sigma, and there's nothing to add without an amount
+    if !(sigma > 0.0 && amount > 0.0) {
+        return;
+    }
+    let has_alpha = img.color().has_alpha();
+    let mut sharpened = img.to_rgba8();
+    let blurred = imageproc::filter::gaussian_blur_f32(&sharpened, sigma);
+    for (pixel, blurred) in sharpened.pixels_mut().zip(blurred.pixels()) {
+        for channel in 0..3 {
+            let difference = pixel[channel] as f32 - blurred[channel] as f32;
+            if difference.abs() > threshold as f32 {
+                pixel[channel] = (pixel[channel] as f32 + difference * amount)
+                    .round()
+                    .clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    // keep the colour type, JPEGs can't be saved with an alpha channel
+    *img = if has_alpha {
+        image::DynamicImage::ImageRgba8(sharpened)
+    } else {
+        image::DynamicImage::ImageRgb8(image::DynamicImage::ImageRgba8(sharpened).to_rgb8())
+    };
+}
+
+/// [apply_unsharp_mask] to `path` and save it over the original
+pub fn sharpen_image(path: &PathBuf, sigma: f32, amount: f32, threshold: u8) -> Result<(), String> {
+    if !(sigma.is_finite() && sigma > 0.0) {
+        return Err(format!("Can't sharpen with a radius of {sigma}"));
+    }
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let image = decode_image(path, &data)?;
+    let mut image = match exif_orientation(&data) {
+        Some(orientation) => apply_exif_orientation(image, orientation),
+        None => image,
+    };
+    apply_unsharp_mask(&mut image, sigma, amount, threshold);
+    image.save(path).map_err(|e| e.to_string())?;
+    info!(
+        "Sharpened {} (sigma {}, amount {}, threshold {})",
+        path.display(),
+        sigma,
+        amount,
+        threshold
+    );
+    Ok(())
+}
+
+/// tint it brown with the usual sepia matrix, transparency's left as it was
+pub fn apply_sepia(img: &image::DynamicImage) -> image::DynamicImage {
+    let mut sepia = img.to_rgba8();
+    for pixel in sepia.pixels_mut() {
+        let [red, green, blue] = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+        let tint = |r: f32, g: f32, b: f32| {
+            (red * r + green * g + blue * b).round().clamp(0.0, 255.0) as u8
+        };
+        pixel[0] = tint(0.393, 0.769, 0.189);
+        pixel[1] = tint(0.349, 0.686, 0.168);
+        pixel[2] = tint(0.272, 0.534, 0.131);
+    }
+    // keep the colour type, JPEGs can't be saved with an alpha channel
+    if img.color().has_alpha() {
+        image::DynamicImage::ImageRgba8(sepia)
+    } else {
+        image::DynamicImage::ImageRgb8(image::DynamicImage::ImageRgba8(sepia).to_rgb8())
+    }
+}
+
+/// [apply_sepia] to `path` and save it over the original
+pub fn sepia_image(path: &PathBuf) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let image = decode_image(path, &data)?;
+    let image = match exif_orientation(&data) {
+        Some(orientation) => apply_exif_orientation(image, orientation),
+        None => image,
+    };
+    apply_sepia(&image).save(path).map_err(|e| e.to_string())?;
+    info!("Made {} sepia", path.display());
+    Ok(())
+}
+
+/// Images with a side shorter than this get [ImageIssue::SmallDimensions]
+pub const ISSUE_MIN_DIMENSION: u32 = 300;
+/// Variance of the Laplacian below which an image's [ImageIssue::PossiblyBlurry]
+pub const ISSUE_BLUR_THRESHOLD: f32 = 50.0;
+/// How much of an image has to be pure white for it to be [ImageIssue::Overexposed]
+pub const ISSUE_OVEREXPOSED_PERCENT: f32 = 25.0;
+/// Average brightness, out of 255, below which an image's [ImageIssue::Underexposed]
+pub const ISSUE_UNDEREXPOSED_LUMA: f32 = 35.0;
+/// Images are shrunk to fit this before they're checked, so big ones don't take forever
+const ISSUE_CHECK_SIZE: u32 = 512;
+
+/// Something that might be wrong with an image, see [detect_image_issues]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageIssue {
+    /// Not much detail, `score`'s the variance of the Laplacian
+    PossiblyBlurry {
+        score: f32,
+    },
+    Overexposed {
+        percent_blown: f32,
+    },
+    Underexposed,
+    SmallDimensions {
+        width: u32,
+        height: u32,
+    },
+    /// There's an EXIF segment but it doesn't make sense
+    CorruptedMetadata,
+}
+
+impl ImageIssue {
+    /// errors get a red badge in the browser, everything else is a warning
+    pub fn is_error(&self) -> bool {
+        matches!(self, ImageIssue::CorruptedMetadata)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ImageIssue::PossiblyBlurry { score } => {
+                crate::t!("issue-blurry", score = format!("{score:.0}"))
+            }
+            ImageIssue::Overexposed { percent_blown } => {
+                crate::t!("issue-overexposed", percent = format!("{percent_blown:.0}"))
+            }
+            ImageIssue::Underexposed => crate::t!("issue-underexposed"),
+            ImageIssue::SmallDimensions { width, height } => crate::t!(
+                "issue-small",
+                width = width,
+                height = height,
+                min = ISSUE_MIN_DIMENSION
+            ),
+            ImageIssue::CorruptedMetadata => crate::t!("issue-corrupt-metadata"),
+        }
+    }
+}
+
+/// look for common problems with the image in `path`. It has to be decoded, so keep it off the
+/// UI thread. Anything that won't decode is [crate::integrity]'s problem, so it gets no issues.
+pub fn detect_image_issues(path: &PathBuf) -> Vec<ImageIssue> {
+    puffin::profile_function!(path.display().to_string());
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            debug!("Couldn't read {} to check it: {}", path.display(), err);
+            return vec![];
+        }
+    };
+    let mut issues = vec![];
+    if exif_is_corrupt(&data) {
+        issues.push(ImageIssue::CorruptedMetadata);
+    }
+    let image = match decode_image(path, &data) {
+        Ok(image) => image,
+        Err(err) => {
+            debug!("Couldn't decode {} to check it: {}", path.display(), err);
+            return issues;
+        }
+    };
+    let (width, height) = (image.width(), image.height());
+    if width < ISSUE_MIN_DIMENSION || height < ISSUE_MIN_DIMENSION {
+        issues.push(ImageIssue::SmallDimensions { width, height });
+    }
+
+    let luma = image
+        .thumbnail(ISSUE_CHECK_SIZE, ISSUE_CHECK_SIZE)
+        .to_luma8();
+    let pixels = luma.as_raw();
+    if pixels.is_empty() {
+        return issues;
+    }
+    let count = pixels.len() as f32;
+    let mean = pixels.iter().map(|&p| p as f32).sum::<f32>() / count;
+    let percent_blown = pixels.iter().filter(|&&p| p >= 250).count() as f32 * 100.0 / count;
+    if percent_blown > ISSUE_OVEREXPOSED_PERCENT {
+        issues.push(ImageIssue::Overexposed { percent_blown });
+    } else if mean < ISSUE_UNDEREXPOSED_LUMA {
+        issues.push(ImageIssue::Underexposed);
+    }
+
+    // something that's all one colour has no detail to be blurry about
+    let spread = pixels
+        .iter()
+        .map(|&p| (p as f32 - mean).powi(2))
+        .sum::<f32>()
+        / count;
+    if spread > 25.0 {
+        if let Some(score) = laplacian_variance(&luma) {
+            if score < ISSUE_BLUR_THRESHOLD {
+                issues.push(ImageIssue::PossiblyBlurry { score });
+            }
+        }
+    }
+    issues
+}
+
+/// how much the edges stand out, low means there aren't many sharp ones. `None` if it's too
+/// small to tell.
+fn laplacian_variance(luma: &image::GrayImage) -> Option<f32> {
+    let (width, height) = luma.dimensions();
+    if width < 3 || height < 3 {
+        return None;
+    }
+    let at = |x: u32, y: u32| luma.get_pixel(x, y)[0] as f32;
+    let responses: Vec<f32> = (1..height - 1)
+        .flat_map(|y| (1..width - 1).map(move |x| (x, y)))
+        .map(|(x, y)| at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0 * at(x, y))
+        .collect();
+    let count = responses.len() as f32;
+    let mean = responses.iter().sum::<f32>() / count;
+    Some(
+        responses
+            .iter()
+            .map(|response| (response - mean).powi(2))
+            .sum::<f32>()
+            / count,
+    )
+}
+
+/// a JPEG with an EXIF segment that doesn't have a valid TIFF header, points its first IFD
+/// somewhere that isn't there or has an orientation that's not one of the eight
+fn exif_is_corrupt(data: &[u8]) -> bool {
+    let Some(range) = exif_tiff_range(data) else {
+        return false;
+    };
+    let tiff = &data[range];
+    let big_endian = match tiff.get(0..2) {
+        Some([b'M', b'M']) => true,
+        Some([b'I', b'I']) => false,
+        _ => return true,
+    };
+    let Some(&[a, b, c, d, e, f]) = tiff.get(2..8) else {
+        return true;
+    };
+    if tiff_u16([a, b], big_endian) != 42 {
+        return true;
+    }
+    let ifd_offset = if big_endian {
+        u32::from_be_bytes([c, d, e, f])
+    } else {
+        u32::from_le_bytes([c, d, e, f])
+    } as usize;
+    if ifd_offset < 8 || ifd_offset + 2 > tiff.len() {
+        return true;
+    }
+    find_tiff_orientation(tiff).is_some() && exif_orientation(data).is_none()
+}
+
+/// What the editor shows about an image, worked out from its dimensions and size on disk
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// `None` if the file couldn't be looked at
+    pub file_size: Option<u64>,
+    /// What the start of the file says it is
+    pub format: Option<crate::format_sniff::SniffedFormat>,
+    /// How long it plays for, if it's a video
+    pub duration: Option<std::time::Duration>,
+    /// When EXIF says it was taken, if it's a photo that says
+    pub taken: Option<ExifDateTime>,
+    /// When the file was created, not every filesystem keeps this
+    pub created: Option<std::time::SystemTime>,
+    pub modified: Option<std::time::SystemTime>,
+}
+
+impl ImageInfo {
+    pub fn new(width: u32, height: u32, file_size: u64) -> Self {
+        Self {
+            width,
+            height,
+            file_size: Some(file_size),
+            format: None,
+            duration: None,
+            taken: None,
+            created: None,
+            modified: None,
+        }
+    }
+
+    pub fn with_file_size(self, file_size: Option<u64>) -> Self {
+        Self { file_size, ..self }
+    }
+
+    pub fn with_format(self, format: Option<crate::format_sniff::SniffedFormat>) -> Self {
+        Self { format, ..self }
+    }
+
+    pub fn with_duration(self, duration: Option<std::time::Duration>) -> Self {
+        Self { duration, ..self }
+    }
+
+    pub fn with_taken(self, taken: Option<ExifDateTime>) -> Self {
+        Self { taken, ..self }
+    }
+
+    pub fn with_times(
+        self,
+        created: Option<std::time::SystemTime>,
+        modified: Option<std::time::SystemTime>,
+    ) -> Self {
+        Self {
+            created,
+            modified,
+            ..self
+        }
+    }
+
+    /// width:height reduced as far as it goes, eg (16, 9) for 1920x1080
+    pub fn aspect_ratio(&self) -> (u32, u32) {
+        let divisor = gcd(self.width, self.height);
+        if divisor == 0 {
+            return (self.width, self.height);
+        }
+        (self.width / divisor, self.height / divisor)
+    }
+
+    pub fn megapixels(&self) -> f64 {
+        (self.width as f64 * self.height as f64) / 1_000_000.0
+    }
+
+    /// how many bits of the file each pixel takes up, `None` if there aren't any pixels or the
+    /// size isn't known
+    pub fn bits_per_pixel(&self) -> Option<f64> {
+        let pixels = self.width as u64 * self.height as u64;
+        if pixels == 0 {
+            return None;
+        }
+        Some((self.file_size? * 8) as f64 / pixels as f64)
+    }
+
+    /// why this isn't web-friendly, empty if it fits in `max_dimension` pixels on each side and
+    /// `max_bytes` on disk
+    pub fn oversized_reasons(&self, max_dimension: u32, max_bytes: u64) -> Vec<String> {
+        let mut reasons = vec![];
+        if self.width > max_dimension {
+            reasons.push(crate::t!(
+                "oversized-wide",
+                width = self.width,
+                max = max_dimension
+            ));
+        }
+        if self.height > max_dimension {
+            reasons.push(crate::t!(
+                "oversized-tall",
+                height = self.height,
+                max = max_dimension
+            ));
+        }
+        if let Some(file_size) = self.file_size.filter(|size| *size > max_bytes) {
+            reasons.push(crate::t!(
+                "oversized-file",
+                size = humansize::format_size(file_size, humansize::DECIMAL),
+                max = humansize::format_size(max_bytes, humansize::DECIMAL)
+            ));
+        }
+        reasons
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// how big to draw something of `size` so it fits in `max_size`, without scaling it up
+pub fn fit_size(size: Vec2, max_size: Vec2) -> Vec2 {
+    if size.x <= 0.0 || size.y <= 0.0 {
+        return Vec2::ZERO;
+    }
+    let scale = (max_size.x / size.x).min(max_size.y / size.y).min(1.0);
+    size * scale
+}
+
+/// Lay out thumbnails of `images` (width, height) in rows that fill `available_width`, like a
+/// justified gallery. Each row's cells share a height close to `target_row_height`, wider images
+/// get wider cells. Returns the rows as (index into `images`, cell width), the last row's left at
+/// the target height rather than stretched. A cell's height is its width over the image's aspect
+/// ratio, see [justified_aspect].
+pub fn compute_thumbnail_grid_layout(
+    images: &[(u32, u32)],
+    available_width: f32,
+    target_row_height: f32,
+) -> Vec<Vec<(usize, f32)>> {
+    let available_width = available_width.max(1.0);
+    let target_row_height = target_row_height.max(1.0);
+    let mut rows = vec![];
+    let mut row: Vec<(usize, f32)> = vec![];
+    // how wide the row is at the target height
+    let mut row_width = 0.0;
+    // scale a full row to fit exactly
+    let justify = |row: &[(usize, f32)], row_width: f32| -> Vec<(usize, f32)> {
+        let scale = available_width / row_width;
+        row.iter()
+            .map(|(index, width)| (*index, width * scale))
+            .collect()
+    };
+    for (index, (width, height)) in images.iter().enumerate() {
+        let cell_width = justified_aspect(*width, *height) * target_row_height;
+        if row_width + cell_width <= available_width {
+            row.push((index, cell_width));
+            row_width += cell_width;
+            continue;
+        }
+        // it doesn't fit, so finish the row either with or without it, whichever ends up
+        // closer to the target height
+        let height_with = target_row_height * available_width / (row_width + cell_width);
+        let height_without = target_row_height * available_width / row_width.max(f32::EPSILON);
+        if row.is_empty()
+            || (target_row_height - height_with).abs() <= (height_without - target_row_height).abs()
+        {
+            row.push((index, cell_width));
+            rows.push(justify(&row, row_width + cell_width));
+            row.clear();
+            row_width = 0.0;
+        } else {
+            rows.push(justify(&row, row_width));
+            row = vec![(index, cell_width)];
+            row_width = cell_width;
+        }
+    }
+    if row_width > available_width {
+        rows.push(justify(&row, row_width));
+    } else if !row.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
+
+/// width over height for [compute_thumbnail_grid_layout], anything without a size yet is
+/// treated as a square
+pub fn justified_aspect(width: u32, height: u32) -> f32 {
+    if width == 0 || height == 0 {
+        1.0
+    } else {
+        width as f32 / height as f32
+    }
 }
 
 /// throw some pixels at it, get a texture back
@@ -95,3 +1391,200 @@ pub fn optimize_image(filename: impl ToString) {
     // let image_buffer = image_object
     //     .to_rgba8().save_with_format(filename.to_string(), image::ImageFormat::Png).unwrap();
 }
+
+/// Put each image in a grid cell with its caption underneath. Images bigger than the cell are
+/// shrunk to fit, and captions that are too long are cut short.
+pub fn generate_contact_sheet(
+    tiles: &[(String, Arc<ColorImage>)],
+    layout: &ContactSheetLayout,
+) -> image::RgbaImage {
+    puffin::profile_function!();
+    let columns = layout.columns.clamp(1, tiles.len().max(1) as u32);
+    let rows = (tiles.len() as u32 + columns - 1) / columns;
+    let cell = layout.cell_size;
+    let cell_height = cell + CONTACT_SHEET_CAPTION_HEIGHT;
+    let [r, g, b] = layout.background;
+    let mut canvas = image::RgbaImage::from_pixel(
+        columns * (cell + CONTACT_SHEET_PADDING) + CONTACT_SHEET_PADDING,
+        rows * (cell_height + CONTACT_SHEET_PADDING) + CONTACT_SHEET_PADDING,
+        image::Rgba([r, g, b, 255]),
+    );
+
+    let font_data = caption_font_data();
+    let font = font_data
+        .as_ref()
+        .and_then(|data| FontRef::try_from_slice(data).ok());
+    if font.is_none() {
+        warn!("Couldn't load a font, the contact sheet won't have captions");
+    }
+    // light text on dark backgrounds and the other way around
+    let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    let text_color = if luminance < 128.0 {
+        [230, 230, 230]
+    } else {
+        [20, 20, 20]
+    };
+
+    for (index, (caption, tile)) in tiles.iter().enumerate() {
+        let index = index as u32;
+        let cell_x = CONTACT_SHEET_PADDING + (index % columns) * (cell + CONTACT_SHEET_PADDING);
+        let cell_y =
+            CONTACT_SHEET_PADDING + (index / columns) * (cell_height + CONTACT_SHEET_PADDING);
+
+        let size = fit_size(
+            vec2(tile.size[0] as f32, tile.size[1] as f32),
+            vec2(cell as f32, cell as f32),
+        );
+        let pixels: Vec<u8> = tile
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+            .collect();
+        let Some(tile) =
+            image::RgbaImage::from_raw(tile.size[0] as u32, tile.size[1] as u32, pixels)
+        else {
+            warn!(
+                "Skipping {} in contact sheet, its pixels don't add up",
+                caption
+            );
+            continue;
+        };
+        let tile = image::imageops::thumbnail(&tile, size.x as u32, size.y as u32);
+        // center it in the cell, since it keeps its aspect ratio
+        let x = cell_x + (cell - tile.width()) / 2;
+        let y = cell_y + (cell - tile.height()) / 2;
+        image::imageops::overlay(&mut canvas, &tile, x as i64, y as i64);
+
+        if let Some(font) = font.as_ref() {
+            draw_caption(
+                &mut canvas,
+                font,
+                caption,
+                (cell_x + cell / 2) as f32,
+                (cell_y + cell + 2) as f32,
+                cell as f32,
+                text_color,
+            );
+        }
+    }
+    canvas
+}
+
+/// egui's default proportional font, so the captions look like the app
+fn caption_font_data() -> Option<Cow<'static, [u8]>> {
+    let mut definitions = egui::FontDefinitions::default();
+    let name = definitions
+        .families
+        .get(&egui::FontFamily::Proportional)?
+        .first()?
+        .clone();
+    definitions.font_data.remove(&name).map(|data| data.font)
+}
+
+/// write `text` centered on `center_x`, shortening it with "..." if it's wider than `max_width`
+fn draw_caption(
+    canvas: &mut image::RgbaImage,
+    font: &FontRef,
+    text: &str,
+    center_x: f32,
+    top: f32,
+    max_width: f32,
+    color: [u8; 3],
+) {
+    let scaled = font.as_scaled(PxScale::from(CONTACT_SHEET_FONT_SIZE));
+    let width = |chars: &[char]| -> f32 {
+        chars
+            .iter()
+            .map(|c| scaled.h_advance(scaled.glyph_id(*c)))
+            .sum()
+    };
+
+    let mut chars: Vec<char> = text.chars().collect();
+    if width(&chars) > max_width {
+        let ellipsis = width(&['.', '.', '.']);
+        while !chars.is_empty() && width(&chars) + ellipsis > max_width {
+            chars.pop();
+        }
+        chars.extend(['.', '.', '.']);
+    }
+
+    let mut x = center_x - width(&chars) / 2.0;
+    let baseline = top + scaled.ascent();
+    for c in chars {
+        let glyph_id = scaled.glyph_id(c);
+        let glyph = glyph_id.with_scale_and_position(scaled.scale(), point(x, baseline));
+        x += scaled.h_advance(glyph_id);
+        let Some(outline) = font.outline_glyph(glyph) else {
+            // spaces don't have an outline
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|glyph_x, glyph_y, coverage| {
+            let pixel_x = bounds.min.x as i64 + glyph_x as i64;
+            let pixel_y = bounds.min.y as i64 + glyph_y as i64;
+            if pixel_x < 0
+                || pixel_y < 0
+                || pixel_x >= canvas.width() as i64
+                || pixel_y >= canvas.height() as i64
+            {
+                return;
+            }
+            let pixel = canvas.get_pixel_mut(pixel_x as u32, pixel_y as u32);
+            for channel in 0..3 {
+                let under = pixel[channel] as f32;
+                pixel[channel] = (under + (color[channel] as f32 - under) * coverage) as u8;
+            }
+        });
+    }
+}
+
+/// Randomly pick up to [MOSAIC_MAX_IMAGES] of the files and tile them into a `width`x`height` image,
+/// `ceil(sqrt(N))` tiles across.
+pub fn generate_mosaic(
+    filepaths: &[PathBuf],
+    width: u32,
+    height: u32,
+) -> Result<image::DynamicImage, String> {
+    use rand::seq::SliceRandom;
+
+    puffin::profile_function!();
+    let tiles: Vec<image::DynamicImage> = filepaths
+        .choose_multiple(&mut rand::thread_rng(), MOSAIC_MAX_IMAGES)
+        .filter_map(|filepath| match image::open(filepath) {
+            Ok(image) => Some(image.thumbnail(MOSAIC_TILE_SIZE, MOSAIC_TILE_SIZE)),
+            Err(err) => {
+                warn!("Skipping {} in mosaic: {}", filepath.display(), err);
+                None
+            }
+        })
+        .collect();
+
+    if tiles.is_empty() {
+        return Err("No images could be loaded for the mosaic".to_string());
+    }
+
+    let columns = (tiles.len() as f64).sqrt().ceil() as u32;
+    let rows = (tiles.len() as u32 + columns - 1) / columns;
+
+    let mut canvas = image::RgbaImage::new(columns * MOSAIC_TILE_SIZE, rows * MOSAIC_TILE_SIZE);
+    for (index, tile) in tiles.iter().enumerate() {
+        let index = index as u32;
+        // center each tile in its cell, since thumbnails keep their aspect ratio
+        let x = (index % columns) * MOSAIC_TILE_SIZE + (MOSAIC_TILE_SIZE - tile.width()) / 2;
+        let y = (index / columns) * MOSAIC_TILE_SIZE + (MOSAIC_TILE_SIZE - tile.height()) / 2;
+        image::imageops::overlay(&mut canvas, &tile.to_rgba8(), x as i64, y as i64);
+    }
+
+    // scaled to fit rather than stretched, so an uneven grid doesn't squash the tiles
+    let fitted = image::DynamicImage::ImageRgba8(canvas)
+        .resize(width, height, image::imageops::FilterType::Lanczos3)
+        .to_rgba8();
+    let mut mosaic = image::RgbaImage::new(width, height);
+    image::imageops::overlay(
+        &mut mosaic,
+        &fitted,
+        (width.saturating_sub(fitted.width()) / 2) as i64,
+        (height.saturating_sub(fitted.height()) / 2) as i64,
+    );
+    Ok(image::DynamicImage::ImageRgba8(mosaic))
+}
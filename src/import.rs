@@ -0,0 +1,136 @@
+//! Bringing files in from outside the workdir, dropped on the window or pasted. What happens when
+//! there's already a file with the same name is up to
+//! [Configuration::on_import_conflict](crate::config::Configuration::on_import_conflict).
+
+use std::path::{Path, PathBuf};
+
+use log::*;
+
+use crate::config::ImportConflict;
+
+/// What to do with one import, once any conflict's been sorted out
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// write it to the path, `renamed` if that's not where it was meant to go
+    Write {
+        path: PathBuf,
+        renamed: bool,
+    },
+    Skip,
+    /// the user has to pick, see [resolve] again with what they picked
+    Ask,
+}
+
+/// where an import that's meant to go to `target` ends up. `on_conflict` only matters if
+/// something's already there.
+pub fn resolve(target: &Path, on_conflict: ImportConflict) -> Resolution {
+    if !target.exists() {
+        return Resolution::Write {
+            path: target.to_path_buf(),
+            renamed: false,
+        };
+    }
+    match on_conflict {
+        ImportConflict::Rename => match renamed_path_for(target) {
+            Some(path) => Resolution::Write {
+                path,
+                renamed: true,
+            },
+            // every numbered name's taken, so it's up to the user
+            None => Resolution::Ask,
+        },
+        ImportConflict::Overwrite => Resolution::Write {
+            path: target.to_path_buf(),
+            renamed: false,
+        },
+        ImportConflict::Skip => Resolution::Skip,
+        ImportConflict::Ask => Resolution::Ask,
+    }
+}
+
+/// somewhere free next to `path`, numbered the same way as everywhere else that needs a free name
+pub fn renamed_path_for(path: &Path) -> Option<PathBuf> {
+    crate::image_utils::suffixed_path_for(path, "")
+}
+
+/// where a file dropped from `source` goes in `workdir`
+pub fn import_target(workdir: &Path, source: &Path) -> Option<PathBuf> {
+    source.file_name().map(|name| workdir.join(name))
+}
+
+/// How an import went
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportOutcome {
+    Imported(PathBuf),
+    Renamed(PathBuf),
+    Skipped,
+    Failed(String),
+}
+
+/// do what `resolution` says, `write` puts the file at the path it's given
+pub fn import_with(
+    resolution: Resolution,
+    write: impl FnOnce(&Path) -> Result<(), String>,
+) -> ImportOutcome {
+    let (path, renamed) = match resolution {
+        Resolution::Write { path, renamed } => (path, renamed),
+        // nothing's been picked, so leave it be
+        Resolution::Skip | Resolution::Ask => return ImportOutcome::Skipped,
+    };
+    match write(&path) {
+        Ok(()) if renamed => ImportOutcome::Renamed(path),
+        Ok(()) => ImportOutcome::Imported(path),
+        Err(err) => {
+            error!("Failed to import to {}: {}", path.display(), err);
+            ImportOutcome::Failed(err)
+        }
+    }
+}
+
+/// copy `source` in, a file that's already where it'd be copied to is skipped rather than
+/// copied over itself
+pub fn import_file(source: &Path, resolution: Resolution) -> ImportOutcome {
+    if let Resolution::Write { path, .. } = &resolution {
+        if crate::file_ops::is_same_file(source, path) {
+            debug!("{} is already in the workdir", source.display());
+            return ImportOutcome::Skipped;
+        }
+    }
+    import_with(resolution, |path| {
+        std::fs::copy(source, path)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// How many of a batch of imports went which way
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub renamed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// the last file that was written, for showing it afterwards
+    pub last_path: Option<PathBuf>,
+}
+
+impl ImportSummary {
+    pub fn record(&mut self, outcome: ImportOutcome) {
+        match outcome {
+            ImportOutcome::Imported(path) => {
+                self.imported += 1;
+                self.last_path = Some(path);
+            }
+            ImportOutcome::Renamed(path) => {
+                self.renamed += 1;
+                self.last_path = Some(path);
+            }
+            ImportOutcome::Skipped => self.skipped += 1,
+            ImportOutcome::Failed(_) => self.failed += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.imported + self.renamed + self.skipped + self.failed
+    }
+}
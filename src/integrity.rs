@@ -0,0 +1,136 @@
+//! Spotting images that won't decode, like truncated downloads, before they get renamed or uploaded
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::SystemTime;
+
+use log::*;
+
+use crate::thumbnail_cache::ThumbnailCache;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verdict {
+    /// The whole image decoded
+    Ok,
+    /// The file's there but the image in it is broken, with the decoder's complaint
+    Corrupt(String),
+    /// Couldn't open the file to check it
+    Unreadable(String),
+}
+
+impl Verdict {
+    /// the decoder's complaint if the file's corrupt
+    pub fn corruption(&self) -> Option<&str> {
+        match self {
+            Verdict::Corrupt(error) => Some(error),
+            Verdict::Ok | Verdict::Unreadable(_) => None,
+        }
+    }
+}
+
+/// decode the whole of `filepath`, which is slow for big images so keep it off the UI thread
+pub fn check_file(filepath: &Path) -> Verdict {
+    puffin::profile_function!(filepath.display().to_string());
+    #[cfg(feature = "heif")]
+    if crate::image_utils::is_heif(filepath) {
+        return match std::fs::read(filepath) {
+            Ok(data) => match crate::image_utils::decode_heif(&data) {
+                Ok(_) => Verdict::Ok,
+                Err(err) => {
+                    warn!("{} doesn't decode: {}", filepath.display(), err);
+                    Verdict::Corrupt(err)
+                }
+            },
+            Err(err) => Verdict::Unreadable(err.to_string()),
+        };
+    }
+    if crate::video::is_video(filepath) {
+        // no ffmpeg doesn't mean it's broken, just that we can't tell
+        if !crate::video::ffmpeg_available() {
+            return Verdict::Unreadable(crate::t!("video-needs-ffmpeg"));
+        }
+        return match crate::video::first_frame(filepath) {
+            Ok(_) => Verdict::Ok,
+            Err(err) => {
+                warn!("{} doesn't decode: {}", filepath.display(), err);
+                Verdict::Corrupt(err)
+            }
+        };
+    }
+    let reader =
+        match image::io::Reader::open(filepath).and_then(|reader| reader.with_guessed_format()) {
+            Ok(reader) => reader,
+            Err(err) => return Verdict::Unreadable(err.to_string()),
+        };
+    match reader.decode() {
+        Ok(_) => Verdict::Ok,
+        Err(err) => {
+            warn!("{} doesn't decode: {}", filepath.display(), err);
+            Verdict::Corrupt(err.to_string())
+        }
+    }
+}
+
+/// Verdicts keyed by file path, each one's thrown away if the file changes after it was checked
+#[derive(Debug, Default)]
+pub struct IntegrityCache {
+    verdicts: HashMap<String, (Option<SystemTime>, Verdict)>,
+    /// Asked the backend about these, and haven't heard back yet
+    pending: HashSet<String>,
+}
+
+impl IntegrityCache {
+    /// we've not got a verdict for `filepath` and haven't asked for one
+    pub fn needs_check(&self, filepath: &str) -> bool {
+        !self.verdicts.contains_key(filepath) && !self.pending.contains(filepath)
+    }
+
+    /// a check's been sent off for `filepath`
+    pub fn start(&mut self, filepath: &str) {
+        self.pending.insert(filepath.to_string());
+    }
+
+    pub fn is_pending(&self, filepath: &str) -> bool {
+        self.pending.contains(filepath)
+    }
+
+    /// `modified` is when the file was modified as of checking it
+    pub fn insert(&mut self, filepath: String, modified: Option<SystemTime>, verdict: Verdict) {
+        self.pending.remove(&filepath);
+        self.verdicts.insert(filepath, (modified, verdict));
+    }
+
+    pub fn get(&self, filepath: &str) -> Option<&Verdict> {
+        self.verdicts.get(filepath).map(|(_, verdict)| verdict)
+    }
+
+    /// the decoder's complaint if `filepath` is known to be corrupt
+    pub fn corruption(&self, filepath: &str) -> Option<&str> {
+        self.get(filepath).and_then(Verdict::corruption)
+    }
+
+    /// forget what we know about `filepath`, so it gets checked again
+    pub fn invalidate(&mut self, filepath: &str) {
+        self.verdicts.remove(filepath);
+        self.pending.remove(filepath);
+    }
+
+    /// drop verdicts for anything that's gone away or changed since it was checked, returns how
+    /// many were removed
+    pub fn prune_stale(&mut self) -> usize {
+        let before = self.verdicts.len();
+        self.verdicts.retain(|filepath, (modified, _)| {
+            let path = Path::new(filepath);
+            path.exists() && ThumbnailCache::<()>::modified_time(path) == *modified
+        });
+        before - self.verdicts.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.verdicts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.verdicts.is_empty()
+    }
+}
@@ -0,0 +1,183 @@
+//! Long-running work over a list of files, with progress and a way to cancel it
+//!
+//! The frontend picks a [JobId] and sends [AppMsg::StartJob]. The background task runs the job
+//! on its own tokio task so it can keep answering other messages, sending
+//! [AppMsg::JobProgress] before each file and [AppMsg::JobDone] with a [JobReport] at the end.
+//! [AppMsg::CancelJob] stops it before the next file.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use log::*;
+use tokio::sync::mpsc;
+
+use crate::optimize::OptimizeAction;
+use crate::AppMsg;
+
+/// Identifies a job, progress and the report carry the same one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(pub u64);
+
+impl JobId {
+    /// a new one, they only ever go up
+    pub fn next() -> Self {
+        static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+        JobId(NEXT_JOB.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl std::fmt::Display for JobId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "job#{}", self.0)
+    }
+}
+
+/// What a job does to each file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobWork {
+    Optimize(OptimizeAction),
+}
+
+/// How one file in a job went
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ItemResult {
+    /// Done, and how big things were before and after
+    Done {
+        bytes_before: u64,
+        bytes_after: u64,
+    },
+    /// Left alone on purpose, and why
+    Skipped(String),
+    Failed(String),
+}
+
+/// Everything that happened in a job, in the order the files were done
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JobReport {
+    pub items: Vec<(String, ItemResult)>,
+    /// How many files there were to start with, some won't be in `items` if it was cancelled
+    pub total: usize,
+    pub cancelled: bool,
+}
+
+impl JobReport {
+    pub fn done(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|(_, result)| matches!(result, ItemResult::Done { .. }))
+            .count()
+    }
+
+    /// bytes the finished files shrank by, anything that grew counts against it
+    pub fn bytes_saved(&self) -> i64 {
+        self.items
+            .iter()
+            .map(|(_, result)| match result {
+                ItemResult::Done {
+                    bytes_before,
+                    bytes_after,
+                } => *bytes_before as i64 - *bytes_after as i64,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// (filepath, reason) for everything that was skipped
+    pub fn skipped(&self) -> Vec<(&str, &str)> {
+        self.items
+            .iter()
+            .filter_map(|(filepath, result)| match result {
+                ItemResult::Skipped(reason) => Some((filepath.as_str(), reason.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// (filepath, error) for everything that failed
+    pub fn failures(&self) -> Vec<(&str, &str)> {
+        self.items
+            .iter()
+            .filter_map(|(filepath, result)| match result {
+                ItemResult::Failed(error) => Some((filepath.as_str(), error.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// The cancel flags for the jobs the background task's started. A job's flag is dropped once
+/// the job's finished with it.
+#[derive(Debug, Default)]
+pub struct JobRegistry {
+    cancel_flags: HashMap<JobId, Arc<AtomicBool>>,
+}
+
+impl JobRegistry {
+    /// a flag for `job` to check between files
+    pub fn register(&mut self, job: JobId) -> Arc<AtomicBool> {
+        // anything only we're holding on to has finished
+        self.cancel_flags
+            .retain(|_, flag| Arc::strong_count(flag) > 1);
+        let flag = Arc::new(AtomicBool::new(false));
+        self.cancel_flags.insert(job, flag.clone());
+        flag
+    }
+
+    /// ask `job` to stop, returns false if it's not running
+    pub fn cancel(&mut self, job: JobId) -> bool {
+        match self.cancel_flags.remove(&job) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                Arc::strong_count(&flag) > 1
+            }
+            None => false,
+        }
+    }
+}
+
+/// do `work` to each of `filepaths` in turn, telling the frontend how it's going. Stops early
+/// if `cancel` gets set. Returns the [AppMsg::JobDone] to send back.
+pub async fn run_job<F, Fut>(
+    job: JobId,
+    filepaths: Vec<String>,
+    tx: &mpsc::Sender<AppMsg>,
+    cancel: &AtomicBool,
+    mut work: F,
+) -> AppMsg
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = ItemResult>,
+{
+    let total = filepaths.len();
+    let mut report = JobReport {
+        items: Vec::with_capacity(total),
+        total,
+        cancelled: false,
+    };
+    for (done, filepath) in filepaths.into_iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            info!("{} cancelled after {} of {}", job, done, total);
+            report.cancelled = true;
+            break;
+        }
+        if let Err(err) = tx
+            .send(AppMsg::JobProgress {
+                job,
+                done,
+                total,
+                current: filepath.clone(),
+            })
+            .await
+        {
+            error!("Background failed to send job progress! {}", err);
+        }
+        let result = work(filepath.clone()).await;
+        if let ItemResult::Failed(error) = &result {
+            warn!("{} failed on {}: {}", job, filepath, error);
+        }
+        report.items.push((filepath, result));
+    }
+    AppMsg::JobDone { job, report }
+}
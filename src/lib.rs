@@ -1,18 +1,39 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Formatter;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
-use config::Configuration;
+use browser_context::BrowserContext;
+use clipboard::ClipboardImage;
+use color_analysis::ColorAnalysis;
+use config::{Configuration, ImportConflict, UploadDestination};
+use custom_order::CUSTOM_ORDER_PATH;
+use dir_stats::DirStats;
 use eframe::egui::{self, Context, Grid, Key, RichText, TextureOptions};
-use eframe::epaint::{vec2, Vec2};
-use egui_extras::RetainedImage;
-use image_utils::load_image_from_memory;
+use eframe::epaint::{vec2, ColorImage, Pos2, Rect, Vec2};
+use format_sniff::{FormatCache, FormatFilter, SniffedFormat};
+use image_utils::{fit_size, ContactSheetLayout, ImageAction, ImageInfo, ImageIssue};
+use import::{ImportOutcome, ImportSummary, Resolution};
+use integrity::{IntegrityCache, Verdict};
 use itertools::Itertools;
+use jobs::{ItemResult, JobId, JobReport, JobWork};
+use load_stats::{PageLoadStats, ThumbLoadStats, ThumbLoadTracker};
 use log::*;
+use navigation::Navigation;
+use optimize::OptimizeAction;
+use outbox::Outbox;
+use search::SearchQuery;
+use tabs::{TabId, Tabs, WorkdirTab};
+use tags::{TagStore, TAGS_PATH};
+use tasks::{TaskId, TaskPurpose, TaskTracker};
 use text::{configure_text_styles, heading3};
+use thumbnail_cache::ThumbnailCache;
 use tokio::sync::mpsc::{Receiver, Sender};
+use trash::{TrashJournal, TRASH_DIR, TRASH_JOURNAL_PATH};
+use upload_history::{UploadHistory, UploadRecord, UPLOAD_HISTORY_PATH};
+use watcher::{WatcherEventKind, WATCHER_DEBOUNCE};
 
 use crate::image_utils::load_image_to_thumbnail;
 
@@ -20,21 +41,122 @@ use crate::image_utils::load_image_to_thumbnail;
 extern crate lazy_static;
 
 pub mod background;
+pub mod browser_context;
+pub mod clipboard;
+pub mod color_analysis;
 pub mod config;
+pub mod csv_export;
+pub mod custom_order;
+pub mod dir_scan;
+pub mod dir_stats;
+pub mod file_ops;
+pub mod format_sniff;
+pub mod http_upload;
+#[macro_use]
+pub mod i18n;
 pub mod image_utils;
+pub mod import;
+pub mod integrity;
+pub mod jobs;
+pub mod load_stats;
+pub mod navigation;
+pub mod optimize;
+pub mod outbox;
 pub mod s3_upload;
+pub mod search;
+pub mod single_instance;
+pub mod tabs;
+pub mod tags;
+pub mod tasks;
 pub mod text;
+pub mod thumbnail_cache;
+pub mod trash;
+pub mod upload_backend;
+pub mod upload_history;
+pub mod video;
+pub mod watcher;
 
 lazy_static! {
-    pub static ref OK_EXTENSIONS: Vec<&'static str> = vec!["jpg", "gif", "png", "jpeg",];
+    pub static ref OK_EXTENSIONS: Vec<&'static str> = {
+        let mut extensions = vec!["jpg", "gif", "png", "jpeg", "webp"];
+        // iPhone photos, which need libheif
+        if cfg!(feature = "heif") {
+            extensions.extend(["heic", "heif"]);
+        }
+        // short clips, which need ffmpeg to get a thumbnail
+        if cfg!(feature = "video") {
+            extensions.extend(video::VIDEO_EXTENSIONS);
+        }
+        extensions
+    };
     pub static ref PER_PAGE: usize = 20;
     pub static ref GRID_X: u8 = 5;
     pub static ref GRID_Y: u8 = 4;
     pub static ref GRID_SPACING: Vec2 = Vec2 { x: 10.0, y: 10.0 };
     pub static ref THUMBNAIL_SIZE: Vec2 = Vec2 { x: 200.0, y: 150.0 };
+    pub static ref MOSAIC_SIZE: (u32, u32) = (512, 512);
+    pub static ref PREVIEW_SIZE: Vec2 = Vec2 { x: 600.0, y: 600.0 };
+    /// Action | Key pairs shown on the help screen, the actions are translation keys
+    pub static ref KEYBINDINGS: Vec<(&'static str, &'static str)> = vec![
+        ("help-show", "? / F1"),
+        ("help-previous-page", "Left Arrow"),
+        ("help-next-page", "Right Arrow"),
+        ("help-clear-search", "Escape"),
+        ("help-cancel-filename-edit", "Escape"),
+        ("help-back-to-browser", "Escape"),
+        ("help-delete-image", "Delete"),
+        ("help-delete-image-popout", "Delete"),
+        ("help-confirm-rename", "Enter"),
+        ("help-cancel-rename-delete", "Escape"),
+        ("help-close-configuration", "Escape"),
+        ("help-close-help", "Escape"),
+        ("help-go-back", "Backspace / Alt + Left Arrow"),
+        ("help-move-selection", "Arrow Keys"),
+        ("help-open-selection", "Enter"),
+        ("help-delete-selection", "Delete"),
+        ("help-page-up-down", "Page Up / Page Down"),
+        ("help-first-last-page", "Home / End"),
+        ("help-start-slideshow", "P"),
+        ("help-close-slideshow", "Escape"),
+        ("help-paste-image", "Ctrl+V"),
+    ];
 }
 
-#[derive(Clone, Debug)]
+/// Where the browser starts when there aren't any tabs saved
+pub const DEFAULT_WORKDIR: &str = "~/Downloads";
+
+/// How far the pointer has to move before a press on a thumbnail becomes a drag
+pub const DRAG_THRESHOLD: f32 = 3.0;
+
+/// Rows either side of the viewport to load thumbnails for in infinite scroll mode
+pub const INFINITE_SCROLL_PRELOAD_ROWS: usize = 2;
+/// Rows either side of the viewport to keep thumbnails for before evicting them
+pub const INFINITE_SCROLL_KEEP_ROWS: usize = 10;
+
+/// Smallest thumbnail width the browser slider allows
+pub const THUMBNAIL_WIDTH_MIN: f32 = 100.0;
+/// Largest thumbnail width the browser slider allows
+pub const THUMBNAIL_WIDTH_MAX: f32 = 400.0;
+
+/// How many states we remember for the Back button
+pub const MAX_HISTORY_DEPTH: usize = 50;
+
+/// How much memory colour analysis results can use
+pub const COLOR_ANALYSIS_CACHE_BYTES: usize = 1024 * 1024;
+/// How much memory the [ImageIssue]s found in files can use
+pub const IMAGE_ISSUES_CACHE_BYTES: usize = 1024 * 1024;
+
+/// How long the slideshow shows each image for
+pub const SLIDESHOW_INTERVAL_MS: u32 = 5000;
+
+/// Some filesystems only keep modified times to the second or worse, so a directory that changed
+/// this recently might change again without its modified time moving
+pub const DIR_MTIME_GRANULARITY: Duration = Duration::from_secs(2);
+
+/// How long a toast stays up in the corner
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum AppState {
     Browser,
     Editor {
@@ -44,801 +166,7124 @@ pub enum AppState {
         filepath: String,
         newfilepath: String,
     },
-    ShowError {
-        message: String,
-        next_state: Option<Box<AppState>>,
-    },
     DeletePrompt(String),
+    /// Confirm deleting everything that's selected in the browser
+    BatchDeletePrompt(Vec<String>),
     UploadPrompt(String),
+    /// Confirm overwriting the copy of a file that's already in S3
+    UpdatePrompt(String),
     Uploading(String),
     Configuration,
+    Help,
+    /// Every file in the browser's order, one at a time and fullscreen, moving on every
+    /// `interval_ms` unless it's paused
+    Slideshow {
+        index: usize,
+        interval_ms: u32,
+    },
+    /// What's been deleted through memetool, to restore or purge
+    Trash,
+    /// An image that's been pasted into the browser, waiting for a filename to be saved as
+    ImportFromClipboard {
+        image_data: ClipboardImage,
+    },
+}
+
+impl AppState {
+    /// the file this state is looking at, if any
+    pub fn filepath(&self) -> Option<&str> {
+        match self {
+            AppState::Editor { filepath }
+            | AppState::RenameConfirm { filepath, .. }
+            | AppState::DeletePrompt(filepath)
+            | AppState::UploadPrompt(filepath)
+            | AppState::UpdatePrompt(filepath)
+            | AppState::Uploading(filepath) => Some(filepath),
+            AppState::Browser
+            | AppState::BatchDeletePrompt(_)
+            | AppState::Configuration
+            | AppState::Help
+            | AppState::Slideshow { .. }
+            | AppState::Trash
+            | AppState::ImportFromClipboard { .. } => None,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum AppMsg {
     LoadImage(ThumbImageMsg),
     ThumbImageResponse(ThumbImageMsg),
-    ImageLoadFailed { filename: String, error: String },
+    ImageLoadFailed {
+        filename: String,
+        error: String,
+        /// The request that failed
+        task: Option<TaskId>,
+    },
     NewAppState(AppState),
     Echo(String),
     UploadImage(String),
-    UploadAborted(String),
-    UploadComplete(String),
+    /// Overwrite `key` in the bucket with `filepath`, without the exists check
+    /// [AppMsg::UploadImage] does
+    UpdateImage {
+        filepath: String,
+        key: String,
+    },
+    /// How big `key` is in the bucket, for the update confirmation
+    CheckRemoteSize {
+        filepath: String,
+        key: String,
+    },
+    /// How [AppMsg::CheckRemoteSize] went, `None` if it's not in the bucket
+    RemoteSize {
+        filepath: String,
+        key: String,
+        size: Result<Option<u64>, String>,
+    },
+    UploadAborted {
+        message: String,
+        /// What the SDK said, if it got that far
+        details: Option<String>,
+    },
+    UploadProgress {
+        filepath: String,
+        bytes_sent: u64,
+        total_bytes: u64,
+        /// What's going on if it's not just sending, eg "retry 1/3"
+        message: Option<String>,
+        /// Which part's just finished, for a multipart upload
+        part: Option<s3_upload::UploadPart>,
+    },
+    UploadComplete {
+        filepath: String,
+        /// What it's called in S3
+        key: String,
+        /// SHA-256 of the file, if it could be read again after uploading
+        checksum: Option<String>,
+    },
     Error(String),
+    /// An error with something short for the user and the rest for copying into a bug report
+    DetailedError {
+        message: String,
+        details: String,
+    },
+    /// A group of messages for the backend to process together
+    Batch(Vec<AppMsg>),
+    /// The responses to a [AppMsg::Batch], in the same order
+    BatchResult(Vec<AppMsg>),
+    /// Load a file for the split view's preview pane
+    LoadPreview {
+        filepath: String,
+        task: Option<TaskId>,
+    },
+    PreviewResponse(PreviewMsg),
+    /// Load a thumbnail over HTTP, eg from a public S3 bucket. Comes back as a
+    /// [AppMsg::ThumbImageResponse] with the URL as the filepath
+    LoadImageFromUrl {
+        url: String,
+        page: usize,
+        task: Option<TaskId>,
+    },
+    /// Work out the [DirStats] for a directory
+    GetDirectoryStats(String),
+    GotDirStats {
+        path: String,
+        stats: DirStats,
+    },
+    /// How far through a [AppMsg::GetDirectoryStats] the backend is
+    DirStatsProgress {
+        path: String,
+        done: usize,
+        total: usize,
+    },
+    /// Decode the whole file to see if it's corrupt
+    CheckIntegrity(String),
+    IntegrityChecked {
+        filepath: String,
+        /// When the file was modified, as of checking it
+        modified: Option<SystemTime>,
+        verdict: Verdict,
+    },
+    /// Read the start of each file to see what's really in it, see [format_sniff]
+    SniffFormats(Vec<String>),
+    /// (filepath, when it was modified as of sniffing it, what it is)
+    FormatsSniffed(Vec<(String, Option<SystemTime>, SniffedFormat)>),
+    /// See if the configured bucket's there and we can get to it
+    CheckBucket,
+    /// How [AppMsg::CheckBucket] went
+    BucketStatus(Result<(), String>),
+    /// Download `key` from the bucket to a temp file and open it in the editor, see
+    /// [upload_backend::UploadBackend::download_to_temp]
+    DownloadAndEdit {
+        key: String,
+    },
+    /// Copy something that's already in the bucket to another key, without re-uploading it
+    S3CopyObject {
+        src_key: String,
+        dest_key: String,
+    },
+    S3ObjectCopied {
+        src_key: String,
+        dest_key: String,
+    },
+    /// Rename a file onto a different filesystem by copying it then deleting the original, which
+    /// can take a while for big files
+    MoveFile {
+        from: String,
+        to: String,
+    },
+    /// How far along [AppMsg::MoveFile] is
+    MoveProgress {
+        from: String,
+        done: u64,
+        total: u64,
+    },
+    MoveDone {
+        from: String,
+        to: String,
+        result: Result<(), String>,
+    },
+    /// Rename something that's already in the bucket, after the local file it came from was
+    /// renamed from `old_filepath` to `filepath`
+    S3RenameObject {
+        old_filepath: String,
+        filepath: String,
+        src_key: String,
+        dest_key: String,
+    },
+    /// How [AppMsg::S3RenameObject] went, the local rename stands either way
+    S3ObjectRenamed {
+        old_filepath: String,
+        filepath: String,
+        src_key: String,
+        dest_key: String,
+        result: Result<(), String>,
+    },
+    /// Lay these out in a grid with their filenames and save it to `savepath`. Thumbnails the
+    /// frontend already has come along, the rest get loaded.
+    ExportContactSheet {
+        savepath: PathBuf,
+        layout: ContactSheetLayout,
+        tiles: Vec<ContactSheetTile>,
+    },
+    /// How many of the contact sheet's images are ready
+    ContactSheetProgress {
+        done: usize,
+        total: usize,
+    },
+    /// Where the contact sheet was saved, or why it wasn't
+    ContactSheetDone(Result<PathBuf, String>),
+    /// Tile some of `filepaths` into a `width`x`height` mosaic and save it to `savepath`, see
+    /// [image_utils::generate_mosaic]
+    ExportMosaic {
+        savepath: PathBuf,
+        filepaths: Vec<PathBuf>,
+        width: u32,
+        height: u32,
+    },
+    /// Where the mosaic was saved, or why it wasn't
+    MosaicDone(Result<PathBuf, String>),
+    /// Work out the [ColorAnalysis] for a file
+    AnalyzeColors(String),
+    ColorsAnalyzed {
+        filepath: String,
+        /// When the file was modified, as of analysing it
+        modified: Option<SystemTime>,
+        analysis: Result<ColorAnalysis, String>,
+    },
+    /// Look for [ImageIssue]s in a file, after its thumbnail's loaded
+    DetectIssues(String),
+    IssuesDetected {
+        filepath: String,
+        /// When the file was modified, as of checking it
+        modified: Option<SystemTime>,
+        issues: Vec<ImageIssue>,
+    },
+    /// Start watching a directory for changes, instead of whatever was being watched before
+    WatchDir(PathBuf),
+    /// Something changed in the watched directory
+    WatcherEvent(WatcherEventKind),
+    /// Change the file on disk, eg rotating it
+    ApplyImageAction {
+        filepath: String,
+        action: ImageAction,
+    },
+    /// How an [AppMsg::ApplyImageAction] went
+    ImageActionDone {
+        filepath: String,
+        action: ImageAction,
+        result: Result<(), String>,
+    },
+    /// Do `work` to each of `filepaths` on its own task in the background, see [jobs]
+    StartJob {
+        job: JobId,
+        work: JobWork,
+        filepaths: Vec<String>,
+    },
+    /// Stop a [AppMsg::StartJob] before its next file
+    CancelJob(JobId),
+    /// A job's about to start on `current`, with `done` files finished
+    JobProgress {
+        job: JobId,
+        done: usize,
+        total: usize,
+        current: String,
+    },
+    /// A job's finished or been cancelled
+    JobDone {
+        job: JobId,
+        report: JobReport,
+    },
+    /// Remove these from the trash for good, they've already been taken out of the journal
+    PurgeTrash(Vec<PathBuf>),
+    /// A file or directory to show, from the command line or another instance that was started
+    OpenPath(PathBuf),
+    /// What the thumbnails on a page have cost so far, sent after each one's loaded
+    PageLoadStats(PageLoadStats),
+    /// List a directory that's too big to read on the UI thread, see [dir_scan]
+    ScanDirectory {
+        path: PathBuf,
+        generation: u64,
+    },
+    /// Some of what [AppMsg::ScanDirectory] found, `done` is set on the last one
+    DirectoryListing {
+        entries: Vec<PathBuf>,
+        generation: u64,
+        done: bool,
+    },
+    /// Stop the [AppMsg::ScanDirectory] with this generation if it's still going
+    CancelScan(u64),
+}
+
+impl AppMsg {
+    /// give anything that gets a response a [TaskId], so the response can be matched up with it
+    pub fn track(&mut self, tasks: &mut TaskTracker) {
+        match self {
+            AppMsg::LoadImage(msg) => msg.task = Some(tasks.start(TaskPurpose::Thumbnail)),
+            AppMsg::LoadImageFromUrl { task, .. } => {
+                *task = Some(tasks.start(TaskPurpose::Thumbnail))
+            }
+            AppMsg::LoadPreview { task, .. } => *task = Some(tasks.start(TaskPurpose::Preview)),
+            AppMsg::Batch(msgs) => msgs.iter_mut().for_each(|msg| msg.track(tasks)),
+            _ => {}
+        }
+    }
 }
 
 pub struct ThumbImageMsg {
     filepath: String,
     page: usize,
-    image: Option<Arc<RetainedImage>>,
+    /// How big a thumbnail to make, defaults to [THUMBNAIL_SIZE]
+    size: Option<Vec2>,
+    /// When the file was modified, as of loading the thumbnail
+    modified: Option<SystemTime>,
+    /// Pixels from the backend, turned into a texture when the frontend gets them
+    image: Option<Arc<ColorImage>>,
+    /// Set when the request's sent, and copied onto the response
+    task: Option<TaskId>,
+    /// Decode every frame of a GIF instead of just the first one
+    animate_gif: bool,
+    /// Add this one to the [AppMsg::PageLoadStats] for its page
+    collect_stats: bool,
+    /// The browser tab that asked for it
+    tab: Option<TabId>,
 }
 
-impl core::fmt::Debug for ThumbImageMsg {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ThumbImageResponse")
-            .field("filepath", &self.filepath)
-            .field("page", &self.page)
-            .finish()
-    }
+/// A thumbnail that's been turned into a texture, the texture's freed when this is dropped
+pub struct Thumbnail {
+    pub texture: egui::TextureHandle,
+    /// The pixels as well, so things like contact sheets don't have to load it again
+    pub image: Arc<ColorImage>,
 }
 
-pub struct MemeTool {
-    /// Current working directory
-    pub workdir: String,
-    /// Used in the browser to filter the list of files
-    pub search_box: String,
-    pub search_box_last: Option<String>,
-    pub files_list: Vec<PathBuf>,
-    pub current_page: usize,
-    pub app_state: AppState,
-    last_checked_dir: Option<String>,
-    last_checked_page: Option<usize>,
-    pub per_page: usize,
-    pub browser_images: HashMap<String, ThumbImageMsg>,
-    pub background_rx: Receiver<AppMsg>,
-    pub background_tx: Sender<AppMsg>,
-    loading_image: egui::TextureHandle,
-    allow_shortcuts: bool,
-    key_buffer: Vec<egui::Key>,
-    editor_image_cache: Option<RetainedImage>,
-    editor_rename_target: String,
-    editor_rename_has_focus: bool,
-    configuration: Option<Configuration>,
+/// The "Copy in S3" dialog, for reorganising the bucket without downloading and re-uploading
+struct S3CopyPrompt {
+    src_key: String,
+    /// Where it's going, the filename's kept
+    dest_prefix: String,
 }
 
-impl eframe::App for MemeTool {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if let Ok(msg) = self.background_rx.try_recv() {
-            match msg {
-                AppMsg::ThumbImageResponse(image_response) => {
-                    debug!(
-                        "got response for: filepath={} page={}",
-                        image_response.filepath, image_response.page
-                    );
-                    self.browser_images
-                        .insert(image_response.filepath.clone(), image_response);
-                    ctx.request_repaint_after(Duration::from_millis(100));
-                }
-                AppMsg::NewAppState(new_state) => {
-                    self.editor_rename_target = String::new();
-                    self.editor_image_cache = None;
+/// The editor's "Border" controls, open while a border's being picked
+struct BorderPrompt {
+    pixels: u32,
+    /// What [egui::color_picker::color_edit_button_rgb] works with, 0.0 to 1.0
+    color: [f32; 3],
+}
 
-                    self.app_state = new_state;
-                    ctx.request_repaint();
-                }
-                AppMsg::ImageLoadFailed { filename, error } => {
-                    // TODO: some kind of herpaderp image error handler thingy?
-                    error!("Failed to load image: {filename}: {error}");
-                }
-                AppMsg::Echo(msg) => debug!("Echo {}", msg),
-                AppMsg::UploadImage(filepath) => {
-                    error!("Backend sent UploadImage({})", filepath);
-                }
-                AppMsg::LoadImage(_) => {
-                    error!("Backend sent LoadImage() which is bad.");
-                }
-                AppMsg::UploadComplete(filepath) => self.app_state = AppState::Editor { filepath },
-                AppMsg::Error(message) => {
-                    self.app_state = AppState::ShowError {
-                        message,
-                        next_state: None,
-                    }
-                }
-                AppMsg::UploadAborted(message) => {
-                    self.app_state = AppState::ShowError {
-                        message,
-                        next_state: None,
-                    }
-                }
-            }
+impl Default for BorderPrompt {
+    fn default() -> Self {
+        BorderPrompt {
+            pixels: 10,
+            color: [1.0, 1.0, 1.0],
         }
-        ctx.request_repaint_after(Duration::from_micros(100));
-
-        let app_state = self.app_state.clone();
+    }
+}
 
-        match app_state {
-            AppState::Browser => self.show_browser(ctx.clone()),
-            AppState::Editor { filepath } => self.show_editor(ctx.clone(), filepath.as_str()),
-            AppState::RenameConfirm {
-                filepath,
-                newfilepath,
-            } => self.show_rename_confirm(ctx.clone(), filepath, newfilepath),
-            AppState::ShowError {
-                message,
-                next_state,
-            } => self.show_error(ctx.clone(), message, next_state),
-            AppState::DeletePrompt(filepath) => self.show_delete_prompt(ctx.clone(), filepath),
-            AppState::UploadPrompt(filepath) => self.show_upload_prompt(ctx.clone(), filepath),
-            AppState::Uploading(filepath) => self.show_uploading(ctx.clone(), filepath),
-            AppState::Configuration => self.show_config(ctx.clone()),
-        };
+/// The editor's "Sharpen" controls, the editor shows what they'll do while they're open
+struct SharpenPrompt {
+    sigma: f32,
+    amount: f32,
+    threshold: u8,
+    /// The editor's image before it was sharpened, at the size it's shown, and which file it is
+    unsharpened: Option<(String, image::DynamicImage)>,
+    /// The texture the preview's in and what it was made with, so it's only redone when
+    /// something changes
+    previewed: Option<(egui::TextureId, f32, f32, u8)>,
+}
 
-        if self.allow_shortcuts && !ctx.wants_keyboard_input() {
-            self.key_handler(ctx.clone());
-        } else {
-            trace!("Not allowing shorcuts!");
+impl Default for SharpenPrompt {
+    fn default() -> Self {
+        SharpenPrompt {
+            sigma: 1.0,
+            amount: 1.0,
+            threshold: 2,
+            unsharpened: None,
+            previewed: None,
         }
-
-        // ctx.request_repaint_after(Duration::from_millis(100));
     }
 }
 
-impl MemeTool {
-    /// sets some things up
-    pub fn new(
-        cc: &eframe::CreationContext<'_>,
-        background_rx: Receiver<AppMsg>,
-        background_tx: Sender<AppMsg>,
-    ) -> Self {
-        pretty_env_logger::init();
-
-        let loading_image = cc.egui_ctx.load_texture(
-            "loading_image",
-            load_image_from_memory(include_bytes!("../assets/app-icon.png")).unwrap(),
-            TextureOptions::default(),
-        );
+/// The editor's "Sepia" preview, it's only saved once it's applied
+#[derive(Default)]
+struct SepiaPrompt {
+    /// The editor's image before it was tinted, at the size it's shown, and which file it is
+    original: Option<(String, image::DynamicImage)>,
+    /// The texture the preview's in, so it's only redone when the image changes
+    previewed: Option<egui::TextureId>,
+}
 
-        configure_text_styles(&cc.egui_ctx);
+/// The batch optimize window, from picking what to do through to the report
+struct BatchOptimize {
+    /// What was selected in the browser when it was opened
+    selected: Vec<String>,
+    /// Everything the search was showing when it was opened
+    filtered: Vec<String>,
+    /// Do `filtered` rather than `selected`
+    use_filtered: bool,
+    action: OptimizeAction,
+    stage: BatchOptimizeStage,
+}
 
-        Self {
-            background_rx,
-            background_tx,
-            search_box: "".into(),
-            search_box_last: None,
-            workdir: "~/Downloads".into(),
-            files_list: vec![],
-            current_page: 0,
-            app_state: AppState::Browser,
-            last_checked_dir: None,
-            last_checked_page: None,
-            per_page: *PER_PAGE,
-            browser_images: HashMap::new(),
-            loading_image,
-            allow_shortcuts: true,
-            key_buffer: vec![],
-            editor_image_cache: None,
-            editor_rename_target: String::new(),
-            editor_rename_has_focus: false,
-            configuration: None,
+impl BatchOptimize {
+    fn filepaths(&self) -> &[String] {
+        if self.use_filtered {
+            &self.filtered
+        } else {
+            &self.selected
         }
     }
+}
 
-    fn key_handler(&mut self, ctx: Context) {
-        ctx.input(|input| {
-            self.key_buffer.clone().iter().for_each(|key| {
-                if input.key_released(key.to_owned()) {
-                    debug!("released! {:?}", key);
-                    match key {
-                        // Key::ArrowDown => todo!(),
-                        Key::Delete => {
-                            // if we're in the editor, prompt for deletion
-                            if let AppState::Editor { filepath } = &self.app_state {
-                                self.app_state = AppState::DeletePrompt(filepath.clone());
-                            }
-                        }
+enum BatchOptimizeStage {
+    /// Showing what's going to happen before it does
+    Planning,
+    Running {
+        job: JobId,
+        done: usize,
+        total: usize,
+        current: Option<String>,
+        /// Cancel's been clicked, it'll stop after the file it's on
+        cancelling: bool,
+    },
+    Finished(JobReport),
+}
 
-                        Key::Enter => {}
-                        Key::Escape => match &self.app_state {
-                            AppState::Browser => {
-                                self.search_box = "".into();
-                            }
-                            AppState::Editor { .. } => {
-                                debug!("User hit escape in editor...");
-                                self.app_state = AppState::Browser;
-                            }
-                            AppState::RenameConfirm {
-                                filepath,
-                                newfilepath: _,
-                            } => {
-                                debug!("User hit escape in rename confirmation...");
-                                self.app_state = AppState::Editor {
-                                    filepath: filepath.clone(),
-                                };
-                            }
-                            AppState::DeletePrompt(filepath) => {
-                                debug!("User hit escape in delete prompt...");
-                                self.app_state = AppState::Editor {
-                                    filepath: filepath.clone(),
-                                };
-                            }
-                            AppState::Configuration => {
-                                debug!("User hit escape in config...");
-                                // TODO: save config here
-                                self.app_state = AppState::Browser;
-                            }
-                            _ => {}
-                        },
-                        Key::ArrowLeft => {
-                            if let AppState::Browser = self.app_state {
-                                self.browser_prev_page();
-                            }
-                        }
-                        Key::ArrowRight => {
-                            if let AppState::Browser = self.app_state {
-                                self.browser_next_page();
-                            }
-                        }
+/// A rename onto another filesystem that's being copied across by the backend
+struct RenameMove {
+    from: String,
+    done: u64,
+    total: u64,
+}
 
-                        // Key::A => todo!(),
-                        // Key::F1 => todo!(),
-                        _ => {
-                            // debug!("Unhandled key: {:?}", key);
-                        }
-                    }
-                }
-            });
-            self.key_buffer.clear();
+/// A workdir listing the background's reading, see [dir_scan]
+struct DirScan {
+    generation: u64,
+    workdir: String,
+    /// A different directory's shown as it's found. Reading the same one again keeps what it
+    /// had until the new listing's finished, and collects it here in the meantime.
+    stream: bool,
+    found: Vec<PathBuf>,
+    /// The workdir's modified time when the scan started, and when that was
+    mtime: Option<SystemTime>,
+    started_at: SystemTime,
+}
 
-            if !input.keys_down.is_empty() {
-                for key in input.keys_down.iter() {
-                    if !self.key_buffer.contains(key) {
-                        self.key_buffer.push(key.to_owned());
-                    }
-                }
-            }
-        });
-        // let events = ui.input().events.clone();
-        //     for event in &events {
-        //         // match event {
-        //         //     egui::Event::Key{key, pressed, modifiers} => {
-        //         //         println!("{:?} = {:?}", key, pressed);
-        //         //     },
-        //         //     egui::Event::Text(t) => { println!("Text = {:?}", t) }
-        //         //     _ => {}
-        //         // }
-        //         debug!("Event: {event:?}");
-        // }
-    }
+/// The editor's "Set modified date" control while it's open
+struct ModifiedEdit {
+    filepath: String,
+    time: file_ops::LocalTimeEdit,
+}
 
-    /// Get a given page of file results
-    fn get_page(&self) -> Vec<PathBuf> {
-        if self.files_list.len() <= self.per_page {
-            self.files_list.clone()
-        } else {
-            match self.files_list.chunks(self.per_page).nth(self.current_page) {
-                Some(list) => list.to_vec(),
-                None => vec![],
-            }
-        }
-    }
+/// Where to restore a trashed file to, when something's taken its original path
+struct TrashRestore {
+    trash_path: String,
+    destination: String,
+}
 
-    /// returns a list of files in the current working directory
-    fn read_workdir(&self) -> Vec<PathBuf> {
-        let resolvedpath = shellexpand::tilde(&self.workdir);
-        match std::fs::read_dir(resolvedpath.to_string()) {
-            Ok(dirlist) => dirlist
-                .sorted_by_key(|d| {
-                    d.as_ref()
-                        .unwrap()
-                        .file_name()
-                        .into_string()
-                        .unwrap_or("".into()) // if this fails we're having a *really* bad day.
-                })
-                .filter_map(|filename| match filename {
-                    Ok(val) => {
-                        let pathstr = val.path();
-                        let pathstr = pathstr.to_string_lossy().to_lowercase();
-                        if OK_EXTENSIONS
-                            .iter()
-                            .any(|ext| pathstr.ends_with(&format!(".{ext}")))
-                        {
-                            Some(val.path())
-                        } else {
-                            debug!("Skipping {} due to extension", pathstr);
-                            None
-                        }
-                    }
-                    Err(_) => None,
-                })
-                .collect(),
-            Err(_) => vec![],
-        }
-    }
+/// Files dropped on the window that are still being copied into the workdir
+#[derive(Default)]
+struct ImportQueue {
+    pending: VecDeque<PathBuf>,
+    summary: ImportSummary,
+    /// What to do with the rest of the conflicts, once "apply to all" has been ticked
+    apply_to_all: Option<ImportConflict>,
+    /// The "apply to all" checkbox in the dialog
+    apply_to_all_ticked: bool,
+}
 
-    fn update_files_list(&mut self) {
-        self.files_list = self.read_workdir();
+/// An editor that's been popped out into its own window, so the browser can stay visible
+struct EditorPopout {
+    filepath: String,
+    /// Same as [MemeTool::editor_image_cache], but for the popped-out window
+    image_cache: Option<(egui::TextureHandle, ImageInfo)>,
+    /// Delete's been asked for in the popped-out window and needs confirming there
+    confirm_delete: bool,
+}
 
-        let cached_files: Vec<String> = self.browser_images.keys().map(|k| k.to_owned()).collect();
+/// where [UPLOAD_HISTORY_PATH] actually is
+fn upload_history_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde(UPLOAD_HISTORY_PATH).as_ref())
+}
 
-        // clear out the cached_Files that are no longer in the files_list
-        for filename in cached_files {
-            let filepath = PathBuf::from(&filename);
-            if !self.files_list.contains(&filepath) {
-                info!("Removing {} from cached files", filename);
-                self.browser_images.remove(&filename);
-            }
-        }
+/// where [CUSTOM_ORDER_PATH] actually is
+fn custom_order_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde(CUSTOM_ORDER_PATH).as_ref())
+}
 
-        // after we've cleaned up the cache filter based on search
-        if !self.search_box.trim().is_empty() {
-            let search_terms: Vec<String> = self
-                .search_box
-                .trim()
-                .split(' ')
-                .map(str::to_lowercase)
-                .collect();
-            self.files_list = self
-                .files_list
-                .iter()
-                .filter_map(|filepath| {
-                    let filename = filepath
-                        .file_name()
-                        .expect("Failed to parse filename from OsStr to String")
-                        .to_string_lossy() // if you're doing bad things with file paths then too bad
-                        .to_lowercase();
-                    if search_terms.iter().all(|term| filename.contains(term)) {
-                        Some(filepath.clone())
-                    } else {
-                        None
+/// where [TAGS_PATH] actually is
+fn tags_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde(TAGS_PATH).as_ref())
+}
+
+/// where [TRASH_JOURNAL_PATH] actually is
+fn trash_journal_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde(TRASH_JOURNAL_PATH).as_ref())
+}
+
+/// where [TRASH_DIR] actually is
+fn trash_dir() -> PathBuf {
+    PathBuf::from(shellexpand::tilde(TRASH_DIR).as_ref())
+}
+
+/// thumbnails keep the same aspect ratio as [THUMBNAIL_SIZE]
+pub fn thumbnail_size_for_width(width: f32) -> Vec2 {
+    let width = width.clamp(THUMBNAIL_WIDTH_MIN, THUMBNAIL_WIDTH_MAX);
+    Vec2 {
+        x: width,
+        y: width * THUMBNAIL_SIZE.y / THUMBNAIL_SIZE.x,
+    }
+}
+
+/// what a screen reader says for a thumbnail, the badges on it are otherwise only visual
+pub fn thumbnail_label(filepath: &str, loaded: bool, uploaded: bool, corrupt: bool) -> String {
+    let filename = std::path::Path::new(filepath)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| filepath.to_string());
+    let mut label = vec![filename];
+    if !loaded {
+        label.push(t!("thumbnail-loading"));
+    }
+    if uploaded {
+        label.push(t!("thumbnail-uploaded"));
+    }
+    if corrupt {
+        label.push(t!("thumbnail-corrupt"));
+    }
+    label.join(", ")
+}
+
+/// just the filename, or all of it if there isn't one
+fn display_filename(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// a red cross if any of `issues` are errors, otherwise a yellow warning sign
+fn issue_badge(ui: &egui::Ui, issues: &[ImageIssue]) -> (&'static str, egui::Color32) {
+    if issues.iter().any(ImageIssue::is_error) {
+        ("✗", ui.visuals().error_fg_color)
+    } else {
+        ("⚠", ui.visuals().warn_fg_color)
+    }
+}
+
+/// `text` with the `ranges` of it that matched the search picked out
+fn highlight_matches(
+    ui: &egui::Ui,
+    text: &str,
+    ranges: &[std::ops::Range<usize>],
+) -> egui::text::LayoutJob {
+    let normal = egui::TextFormat {
+        font_id: egui::TextStyle::Body.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let highlighted = egui::TextFormat {
+        color: ui.visuals().strong_text_color(),
+        background: ui.visuals().selection.bg_fill,
+        ..normal.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    let mut done = 0;
+    for range in ranges {
+        job.append(&text[done..range.start], 0.0, normal.clone());
+        job.append(&text[range.clone()], 0.0, highlighted.clone());
+        done = range.end;
+    }
+    job.append(&text[done..], 0.0, normal);
+    job
+}
+
+/// filenames and why, for the end of a batch job
+fn report_list(ui: &mut egui::Ui, id: &str, items: &[(&str, &str)]) {
+    egui::ScrollArea::vertical()
+        .id_source(id)
+        .max_height(150.0)
+        .show(ui, |ui| {
+            Grid::new(id).num_columns(2).striped(true).show(ui, |ui| {
+                for (filepath, reason) in items {
+                    ui.label(display_filename(Path::new(filepath)));
+                    ui.label(*reason);
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+/// the directory modified time to remember after reading it at `read_at`, or None if it's too
+/// recent to tell whether it's changed again since, see [DIR_MTIME_GRANULARITY]
+pub fn listing_mtime(mtime: Option<SystemTime>, read_at: SystemTime) -> Option<SystemTime> {
+    let mtime = mtime?;
+    match read_at.duration_since(mtime) {
+        Ok(age) if age >= DIR_MTIME_GRANULARITY => Some(mtime),
+        _ => None,
+    }
+}
+
+/// where the slideshow goes from `index` after moving `step` images through `len` of them,
+/// wrapping around at either end
+pub fn slideshow_index(index: usize, step: isize, len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    (index as isize + step).rem_euclid(len as isize) as usize
+}
+
+/// the editor for `path` if it's an image that can be opened, made absolute so its directory can
+/// be the workdir
+pub fn external_file_state(path: &Path) -> Option<AppState> {
+    if !(path.is_file() && dir_scan::is_listable(path)) {
+        return None;
+    }
+    let path = std::fs::canonicalize(path).ok()?;
+    Some(AppState::Editor {
+        filepath: path.display().to_string(),
+    })
+}
+
+/// what the editor shows under the image, `texture`'s size is used if the file won't say how big
+/// it is
+fn editor_image_info(filepath: &str, texture: &egui::TextureHandle) -> ImageInfo {
+    let path = std::path::Path::new(filepath);
+    let metadata = std::fs::metadata(path).ok();
+    let file_size = metadata.as_ref().map(|metadata| metadata.len());
+    let format = format_sniff::sniff_file(path).ok();
+    let created = metadata
+        .as_ref()
+        .and_then(|metadata| metadata.created().ok());
+    let modified = metadata
+        .as_ref()
+        .and_then(|metadata| metadata.modified().ok());
+    let fallback = (texture.size()[0] as u32, texture.size()[1] as u32);
+    // one ffprobe gets both, rather than asking for the size and then the duration
+    if video::is_video(path) {
+        let info = match video::probe(path) {
+            Ok(info) => ImageInfo::new(info.width, info.height, 0).with_duration(info.duration),
+            Err(_) => ImageInfo::new(fallback.0, fallback.1, 0),
+        };
+        return info
+            .with_file_size(file_size)
+            .with_format(format)
+            .with_times(created, modified);
+    }
+    // the texture's been shrunk to fit, so ask the file how big it really is
+    let (width, height) = image_utils::load_image_dimensions_fast(path).unwrap_or(fallback);
+    let taken = image_utils::read_exif(path)
+        .ok()
+        .and_then(|exif| exif.date_time_original);
+    ImageInfo::new(width, height, 0)
+        .with_file_size(file_size)
+        .with_format(format)
+        .with_taken(taken)
+        .with_times(created, modified)
+}
+
+/// the file at the size the editor's showing it in `texture`, for previews to work on
+fn editor_shown_image(
+    filepath: &str,
+    texture: &egui::TextureHandle,
+) -> Option<image::DynamicImage> {
+    let image =
+        load_image_to_thumbnail(&PathBuf::from(filepath), Some(texture.size_vec2())).ok()?;
+    let pixels = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+        .collect();
+    image::RgbaImage::from_raw(image.size[0] as u32, image.size[1] as u32, pixels)
+        .map(image::DynamicImage::ImageRgba8)
+}
+
+/// show `preview` in the editor instead of the file, until the texture's reloaded
+fn set_editor_preview(texture: &mut egui::TextureHandle, preview: &image::DynamicImage) {
+    let preview = preview.to_rgba8();
+    texture.set(
+        egui::ColorImage::from_rgba_unmultiplied(
+            [preview.width() as usize, preview.height() as usize],
+            preview.as_flat_samples().as_slice(),
+        ),
+        TextureOptions::default(),
+    );
+}
+
+/// what each [ImportConflict] is called in the UI
+fn import_conflict_label(choice: ImportConflict) -> String {
+    match choice {
+        ImportConflict::Rename => t!("import-conflict-rename"),
+        ImportConflict::Overwrite => t!("import-conflict-overwrite"),
+        ImportConflict::Skip => t!("import-conflict-skip"),
+        ImportConflict::Ask => t!("import-conflict-ask"),
+    }
+}
+
+/// "Rename", "Overwrite" and "Skip" for something that's being imported over another file
+fn import_conflict_buttons(ui: &mut egui::Ui) -> Option<ImportConflict> {
+    let mut picked = None;
+    for choice in [
+        ImportConflict::Rename,
+        ImportConflict::Overwrite,
+        ImportConflict::Skip,
+    ] {
+        if ui.button(import_conflict_label(choice)).clicked() {
+            picked = Some(choice);
+        }
+    }
+    picked
+}
+
+/// give `button` the keyboard focus if nothing else has it, so confirmations can be answered
+/// without reaching for the mouse
+fn focus_if_unfocused(button: &egui::Response) {
+    if button.ctx.memory(|memory| memory.focus().is_none()) {
+        button.request_focus();
+    }
+}
+
+/// How the browser orders files
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortField {
+    Name,
+    /// The order the user's dragged thumbnails into
+    Custom,
+}
+
+impl SortField {
+    /// what it's called in the sort dropdown
+    pub fn label(&self) -> String {
+        match self {
+            SortField::Name => t!("sort-name"),
+            SortField::Custom => t!("sort-custom"),
+        }
+    }
+}
+
+/// An error window over whatever's showing
+struct PendingError {
+    message: String,
+    /// The whole story, for the "Copy details" button
+    details: Option<String>,
+    /// Where to go once it's dismissed
+    next_state: Option<Box<AppState>>,
+}
+
+/// A thumbnail that's being pressed on, and maybe dragged
+#[derive(Clone, Debug)]
+struct DragState {
+    filepath: String,
+    origin: Pos2,
+    dragging: bool,
+}
+
+/// The current page's display names, and which page they were built for
+struct PageFilenames {
+    page: usize,
+    per_page: usize,
+    filenames: Arc<Vec<String>>,
+}
+
+/// A larger image and some metadata for the split view's preview pane
+pub struct PreviewMsg {
+    pub filepath: String,
+    pub image: Arc<ColorImage>,
+    /// dimensions of the original image, not the preview
+    pub dimensions: (u32, u32),
+    pub file_size: u64,
+    /// The [AppMsg::LoadPreview] this answers
+    pub task: Option<TaskId>,
+}
+
+impl core::fmt::Debug for PreviewMsg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreviewMsg")
+            .field("filepath", &self.filepath)
+            .field("dimensions", &self.dimensions)
+            .field("file_size", &self.file_size)
+            .field("task", &self.task)
+            .finish()
+    }
+}
+
+/// A file for the contact sheet, with its thumbnail if the browser's already got one
+pub struct ContactSheetTile {
+    pub filepath: String,
+    pub cached: Option<Arc<ColorImage>>,
+}
+
+impl core::fmt::Debug for ContactSheetTile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContactSheetTile")
+            .field("filepath", &self.filepath)
+            .field("cached", &self.cached.is_some())
+            .finish()
+    }
+}
+
+/// What the uploading screen knows about the upload in flight
+#[derive(Clone, Debug)]
+pub struct UploadStatus {
+    pub started: Instant,
+    pub last_progress: Instant,
+    pub bytes_sent: u64,
+    pub total_bytes: Option<u64>,
+    /// The latest message from [AppMsg::UploadProgress], like a retry
+    pub message: Option<String>,
+    /// The last part that finished, if it's a multipart upload
+    pub part: Option<s3_upload::UploadPart>,
+}
+
+impl UploadStatus {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last_progress: now,
+            bytes_sent: 0,
+            total_bytes: None,
+            message: None,
+            part: None,
+        }
+    }
+
+    /// how much of it's been sent, from 0 to 1, `None` until the size is known
+    pub fn fraction(&self) -> Option<f32> {
+        match self.total_bytes {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.bytes_sent as f64 / total as f64).min(1.0) as f32),
+            None => None,
+        }
+    }
+
+    /// how much longer it'll take at the rate it's going, `None` until something's been sent
+    pub fn remaining(&self) -> Option<Duration> {
+        let total = self.total_bytes?;
+        let rate = self.rate();
+        if self.bytes_sent == 0 || rate <= 0.0 {
+            return None;
+        }
+        let left = total.saturating_sub(self.bytes_sent) as f64;
+        Some(Duration::from_secs_f64(left / rate))
+    }
+
+    /// bytes per second since the upload started
+    pub fn rate(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.bytes_sent as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for UploadStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A file the browser's showing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageData {
+    pub filepath: String,
+}
+
+impl ImageData {
+    pub fn new(filepath: impl AsRef<Path>) -> Self {
+        Self {
+            filepath: filepath.as_ref().display().to_string(),
+        }
+    }
+
+    /// ask the backend for this file's thumbnail on `page`. The browser adds its settings with
+    /// [MemeTool::with_thumbnail_settings].
+    pub fn to_thumbnail_request(&self, page: usize) -> ThumbImageMsg {
+        ThumbImageMsg::request(&self.filepath, page, None)
+    }
+}
+
+impl ThumbImageMsg {
+    /// ask the backend for a thumbnail of `filepath`
+    pub fn request(filepath: impl ToString, page: usize, size: Option<Vec2>) -> Self {
+        Self {
+            filepath: filepath.to_string(),
+            page,
+            size,
+            modified: None,
+            image: None,
+            task: None,
+            animate_gif: false,
+            collect_stats: false,
+            tab: None,
+        }
+    }
+
+    /// how big a thumbnail to make, instead of [THUMBNAIL_SIZE]
+    pub fn with_size(mut self, size: Vec2) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// which browser tab it's for, so the response goes to the right one
+    pub fn with_tab(mut self, tab: TabId) -> Self {
+        self.tab = Some(tab);
+        self
+    }
+
+    pub fn tab(&self) -> Option<TabId> {
+        self.tab
+    }
+
+    /// see [Configuration::animate_gif_thumbnails]
+    pub fn with_animate_gif(mut self, animate_gif: bool) -> Self {
+        self.animate_gif = animate_gif;
+        self
+    }
+
+    /// see [Configuration::disable_load_stats]
+    pub fn with_collect_stats(mut self, collect_stats: bool) -> Self {
+        self.collect_stats = collect_stats;
+        self
+    }
+
+    /// tag the request with a task, normally [MemeTool::sendmessage] does this
+    pub fn with_task(mut self, task: TaskId) -> Self {
+        self.task = Some(task);
+        self
+    }
+
+    pub fn task(&self) -> Option<TaskId> {
+        self.task
+    }
+
+    pub fn filepath(&self) -> &str {
+        &self.filepath
+    }
+
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    /// the thumbnail, if this is a response
+    pub fn image(&self) -> Option<&ColorImage> {
+        self.image.as_deref()
+    }
+}
+
+impl core::fmt::Debug for ThumbImageMsg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThumbImageResponse")
+            .field("filepath", &self.filepath)
+            .field("page", &self.page)
+            .field("size", &self.size)
+            .field("task", &self.task)
+            .field("tab", &self.tab)
+            .finish()
+    }
+}
+
+pub struct MemeTool {
+    /// Current working directory
+    pub workdir: String,
+    /// The browser's tabs, the active one's workdir, search and [BrowserContext] are what's in
+    /// the fields here while it's showing
+    tabs: Tabs,
+    /// What's in the workdir box in the browser, applied by [MemeTool::set_workdir]
+    workdir_input: String,
+    /// Used in the browser to filter the list of files
+    pub search_box: String,
+    pub search_box_last: Option<String>,
+    pub files_list: Vec<PathBuf>,
+    /// Everything in the workdir that can be shown, before searching and hiding uploaded files
+    workdir_files: Vec<PathBuf>,
+    /// The workdir's modified time when [MemeTool::workdir_files] was read, if it can be trusted.
+    /// While it's the same the directory doesn't need reading again.
+    files_list_mtime: Option<SystemTime>,
+    /// Which directory [MemeTool::workdir_files] is a listing of
+    listed_workdir: Option<String>,
+    /// The workdir's being read in the background, it's too big to do here
+    dir_scan: Option<DirScan>,
+    /// Goes up with each [AppMsg::ScanDirectory]
+    scan_generation: u64,
+    /// Page, selection and scroll position in the browser
+    pub browser_context: BrowserContext,
+    /// Which screen we're on, and the history for the back button
+    navigation: Navigation,
+    last_checked_dir: Option<String>,
+    last_checked_page: Option<usize>,
+    pub per_page: usize,
+    /// Thumbnails for every directory we've looked at, up to the memory budget
+    pub browser_images: ThumbnailCache<Thumbnail>,
+    pub background_rx: Receiver<AppMsg>,
+    pub background_tx: Sender<AppMsg>,
+    allow_shortcuts: bool,
+    key_buffer: Vec<egui::Key>,
+    /// The editor's image, and what it is at full size
+    editor_image_cache: Option<(egui::TextureHandle, ImageInfo)>,
+    editor_rename_target: String,
+    editor_rename_has_focus: bool,
+    /// What a pasted image is going to be saved as
+    clipboard_filename: String,
+    /// The pasted image, for showing before it's saved
+    clipboard_preview: Option<egui::TextureHandle>,
+    /// The slideshow's current image, and which file it is
+    slideshow_image: Option<(String, egui::TextureHandle)>,
+    slideshow_paused: bool,
+    /// When the slideshow last moved, for working out when it moves next
+    slideshow_advanced_at: Instant,
+    /// Whether we've asked for fullscreen for the slideshow
+    slideshow_fullscreen: bool,
+    configuration: Option<Configuration>,
+    /// Tracks the upload shown by [AppState::Uploading]
+    upload_status: Option<UploadStatus>,
+    /// Errors shown in floating windows over the current state
+    pending_errors: Vec<PendingError>,
+    /// A rename that's copying the file to another drive
+    rename_move: Option<RenameMove>,
+    modified_edit: Option<ModifiedEdit>,
+    /// Something that worked, shown in the corner until the time's up
+    toast: Option<(String, Instant)>,
+    /// Where the toast's "Show in browser" link goes, if it's got one
+    toast_link: Option<PathBuf>,
+    /// The target of a rename that moves the file to another directory, once they've ticked
+    /// that they meant to
+    rename_move_confirmed: Option<String>,
+    /// Files ticked in the browser for batch actions
+    pub selected_images: HashSet<String>,
+    /// Show the preview pane next to the browser grid
+    split_view: bool,
+    /// Match case when searching, set by the "Aa" button in the browser
+    search_case_sensitive: bool,
+    /// What [MemeTool::files_list] was last filtered on, thumbnails highlight where it matched
+    search_query: Option<SearchQuery>,
+    /// Show the directory info panel at the top of the browser
+    show_dir_info: bool,
+    /// Worked out when the info panel's open, thrown away when the directory changes
+    dir_stats: Option<DirStats>,
+    /// We've asked the backend for [MemeTool::dir_stats]
+    dir_stats_requested: bool,
+    /// (done, total) files while [MemeTool::dir_stats] is being worked out
+    dir_stats_progress: Option<(usize, usize)>,
+    /// What the thumbnails on the current page cost to load, shown in the footer
+    page_load_stats: Option<PageLoadStats>,
+    /// Counting thumbnails in and out for [MemeTool::last_load_stats]
+    thumb_load: ThumbLoadTracker,
+    /// How the last complete round of thumbnails went, shown in the footer of debug builds
+    last_load_stats: Option<ThumbLoadStats>,
+    /// Everything that's been uploaded, for the badges on thumbnails
+    upload_history: UploadHistory,
+    /// Tags on files, searching matches them as well as filenames
+    tags: TagStore,
+    /// (tag, how many files) [Configuration::auto_tag_by_directory] would tag in the workdir,
+    /// from the config screen's dry run
+    auto_tag_preview: Option<(String, usize)>,
+    /// What's been deleted, and where it went
+    trash: TrashJournal,
+    /// Restoring something whose original path is taken, asking where to put it instead
+    trash_restore: Option<TrashRestore>,
+    /// Files that have been dropped on the window and aren't in the workdir yet
+    import_queue: Option<ImportQueue>,
+    /// Leave uploaded files out of the browser
+    hide_uploaded: bool,
+    /// What's really in each file, going by the first few bytes
+    formats: FormatCache,
+    /// How big each image is, from [image_utils::load_image_dimensions_fast], filled in as
+    /// they're asked for
+    dimensions_cache: HashMap<String, (u32, u32)>,
+    /// What's waiting to go to the background when its channel's full
+    outbox: Outbox,
+    /// Which of those the browser's showing
+    format_filter: FormatFilter,
+    /// When renaming something that's been uploaded, rename its S3 object as well
+    rename_in_s3: bool,
+    /// Which files decode and which are corrupt
+    integrity: IntegrityCache,
+    /// They've ticked the box to upload a file that looks corrupt
+    upload_corrupt_confirmed: bool,
+    /// What's in the bucket for the file in [AppState::UpdatePrompt], once the backend's checked
+    remote_size: Option<(String, Result<Option<u64>, String>)>,
+    /// How much [AppState::BatchDeletePrompt] is deleting, worked out once when it's opened
+    batch_total_size: u64,
+    /// Whether the bucket could be reached, shown on the config screen
+    bucket_status: Option<Result<(), String>>,
+    /// We've asked the backend for [MemeTool::bucket_status]
+    bucket_check_pending: bool,
+    s3_copy_prompt: Option<S3CopyPrompt>,
+    /// The key being typed into the "Edit from S3" dialog, while it's open
+    s3_edit_prompt: Option<String>,
+    /// Temp files downloaded from the bucket to be edited, and the key each one came from
+    s3_edits: HashMap<String, String>,
+    border_prompt: Option<BorderPrompt>,
+    sharpen_prompt: Option<SharpenPrompt>,
+    sepia_prompt: Option<SepiaPrompt>,
+    /// The editor window that's been popped out, if there is one
+    editor_popout: Option<EditorPopout>,
+    /// What'll go on the contact sheet, while the dialog's open
+    contact_sheet_files: Option<Vec<String>>,
+    /// The contact sheet dialog's knobs, kept for next time
+    contact_sheet_layout: ContactSheetLayout,
+    /// How far along the contact sheet export is, `None` when there isn't one going
+    contact_sheet_progress: Option<(usize, usize)>,
+    /// The backend's making a mosaic
+    mosaic_exporting: bool,
+    batch_optimize: Option<BatchOptimize>,
+    /// Colour analysis for the editor, thrown away when the file changes
+    color_analyses: ThumbnailCache<Result<ColorAnalysis, String>>,
+    /// Files we've asked the backend to analyse and haven't heard back about
+    color_analysis_requested: HashSet<String>,
+    /// What [image_utils::detect_image_issues] found, thrown away when the file changes
+    image_issues: ThumbnailCache<Vec<ImageIssue>>,
+    /// Files we've asked the backend to check for issues and haven't heard back about
+    image_issues_requested: HashSet<String>,
+    /// The file we've asked the backend to load into the preview pane
+    preview_requested: Option<String>,
+    preview_image: Option<(PreviewMsg, egui::TextureHandle)>,
+    pub sort_field: SortField,
+    /// Requests the background task hasn't answered yet
+    tasks: TaskTracker,
+    /// display names for the page the browser's showing, so it's not rebuilt every frame
+    page_filenames: Option<PageFilenames>,
+    /// display names for the whole of [MemeTool::ordered_files], for the infinite scroll view
+    ordered_filenames: Option<Arc<Vec<String>>>,
+    /// where each file is in [MemeTool::ordered_files], for the editor's "file 3 of 500"
+    file_positions: Option<HashMap<String, usize>>,
+    /// File paths in the order the user's arranged them, used when sorting by [SortField::Custom]
+    pub custom_order: Vec<String>,
+    drag_state: Option<DragState>,
+    /// Show the whole file list in one scrolling view instead of pages
+    infinite_scroll: bool,
+    /// Thumbnails the infinite scroll view has asked the backend for
+    requested_thumbnails: HashSet<String>,
+    /// Which rows the infinite scroll view showed last frame
+    infinite_visible_rows: Range<usize>,
+    /// Jump the infinite scroll view to this offset on the next frame
+    pending_scroll_offset: Option<f32>,
+    /// Current thumbnail size, set by the slider in the browser
+    thumbnail_size: Vec2,
+    /// How many thumbnails fit across the browser, worked out each frame
+    grid_columns: usize,
+    /// Resize the page to fill the window
+    scale_per_page: bool,
+    /// The workdir the backend's been asked to watch
+    watched_dir: Option<String>,
+    /// Refresh the browser once this comes around, pushed back by each watcher event
+    watcher_refresh_at: Option<Instant>,
+    /// The file an [ImageAction] is being done to, the editor's controls wait for it
+    image_action_pending: Option<String>,
+    /// Thumbnails on this page that weren't asked for straight away, in page order, see
+    /// [Configuration::eager_rows]
+    lazy_thumbnails: Vec<String>,
+}
+
+impl eframe::App for MemeTool {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| self.ui(ui));
+    }
+}
+
+impl MemeTool {
+    /// draw memetool into `ui` and handle everything that's come from the background, once a
+    /// frame. This is all [eframe::App::update] does, so an app embedding memetool can call it
+    /// from wherever it wants memetool to be. Prompts, errors and the popped-out editor are still
+    /// shown as windows over the whole of `ui`'s [Context].
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let ctx = &ui.ctx().clone();
+        if let Ok(msg) = self.background_rx.try_recv() {
+            self.handle_message(ctx, msg);
+        }
+        self.outbox.flush(&self.background_tx);
+        self.flush_lazy_thumbnails();
+        ctx.request_repaint_after(Duration::from_micros(100));
+
+        self.watch_workdir();
+        if matches!(self.watcher_refresh_at, Some(refresh_at) if refresh_at <= Instant::now()) {
+            debug!("Workdir changed on disk, refreshing");
+            self.watcher_refresh_at = None;
+            self.start_update(ctx);
+        }
+
+        self.backpressure_bar(ui);
+
+        // anything that changes the state while it's being drawn shows up next frame
+        let app_state = self.navigation.frame_state();
+
+        match app_state.as_ref() {
+            AppState::Browser => self.show_browser(ui),
+            AppState::Editor { filepath } => self.show_editor(ui, filepath),
+            AppState::RenameConfirm {
+                filepath,
+                newfilepath,
+            } => self.show_rename_confirm(ui, filepath, newfilepath),
+            AppState::DeletePrompt(filepath) => self.show_delete_prompt(ui, filepath),
+            AppState::BatchDeletePrompt(filepaths) => self.show_batch_delete_prompt(ui, filepaths),
+            AppState::UploadPrompt(filepath) => self.show_upload_prompt(ui, filepath),
+            AppState::UpdatePrompt(filepath) => self.show_update_prompt(ui, filepath),
+            AppState::Uploading(filepath) => self.show_uploading(ui, filepath),
+            AppState::Configuration => self.show_config(ui),
+            AppState::Help => self.show_help(ui),
+            AppState::Slideshow { index, interval_ms } => {
+                self.show_slideshow(ui, *index, *interval_ms)
+            }
+            AppState::Trash => self.show_trash(ui),
+            AppState::ImportFromClipboard { image_data } => {
+                self.show_import_clipboard(ui, image_data)
+            }
+        };
+
+        // however the slideshow was left, the window goes back to how it was
+        let in_slideshow = matches!(self.navigation.current(), AppState::Slideshow { .. });
+        if in_slideshow != self.slideshow_fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(in_slideshow));
+            self.slideshow_fullscreen = in_slideshow;
+            if !in_slideshow {
+                self.slideshow_image = None;
+            }
+        }
+
+        self.show_s3_copy_prompt(ctx);
+        self.show_s3_edit_prompt(ctx);
+        self.show_contact_sheet_prompt(ctx);
+        self.show_batch_optimize(ctx);
+        self.show_editor_popout(ctx);
+        self.show_pending_errors(&ctx);
+        self.show_toast(&ctx);
+
+        // keys pressed in the popped-out editor are its business, not the browser's
+        let focused = ctx.input(|input| input.focused);
+        if self.allow_shortcuts && focused && !ctx.wants_keyboard_input() {
+            self.key_handler(ctx.clone());
+            self.check_clipboard_paste(&ctx);
+        } else {
+            trace!("Not allowing shorcuts!");
+        }
+
+        // ctx.request_repaint_after(Duration::from_millis(100));
+    }
+
+    /// sets some things up, loading the configuration from disk or starting with the defaults
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        background_rx: Receiver<AppMsg>,
+        background_tx: Sender<AppMsg>,
+    ) -> Self {
+        Self::new_with_config(
+            cc,
+            background_rx,
+            background_tx,
+            Some(Configuration::try_new_or_default()),
+        )
+    }
+
+    /// sets some things up with a configuration that's already been loaded (or not)
+    pub fn new_with_config(
+        cc: &eframe::CreationContext<'_>,
+        background_rx: Receiver<AppMsg>,
+        background_tx: Sender<AppMsg>,
+        configuration: Option<Configuration>,
+    ) -> Self {
+        let mut app = Self::with_context(&cc.egui_ctx, background_rx, background_tx, configuration);
+        if let Some(AppState::Editor { filepath }) = Self::handle_external_file_arg() {
+            app.open_file_in_editor(Path::new(&filepath));
+        }
+        app
+    }
+
+    /// the editor for the image in the first command line argument, if there is one, so it
+    /// starts there instead of in the browser, eg when it's the "open with" for a file
+    pub fn handle_external_file_arg() -> Option<AppState> {
+        let path = PathBuf::from(std::env::args_os().nth(1)?);
+        let state = external_file_state(&path);
+        if state.is_none() {
+            debug!("{} isn't an image to open", path.display());
+        }
+        state
+    }
+
+    /// [MemeTool::new_with_config] for when there's no [eframe::CreationContext], like when it's
+    /// embedded in something that's already running. Setting up logging's left to whatever's
+    /// running it.
+    pub fn with_context(
+        ctx: &egui::Context,
+        background_rx: Receiver<AppMsg>,
+        background_tx: Sender<AppMsg>,
+        configuration: Option<Configuration>,
+    ) -> Self {
+        configure_text_styles(ctx);
+        i18n::set_locale(
+            configuration
+                .as_ref()
+                .and_then(|config| config.locale.as_deref()),
+        );
+
+        let split_view = configuration
+            .as_ref()
+            .map(|config| config.split_view)
+            .unwrap_or(false);
+        let search_case_sensitive = configuration
+            .as_ref()
+            .map(|config| config.default_search_case_sensitive)
+            .unwrap_or(false);
+        let infinite_scroll = configuration
+            .as_ref()
+            .map(|config| config.infinite_scroll)
+            .unwrap_or(false);
+        let thumbnail_size = configuration
+            .as_ref()
+            .and_then(|config| config.thumbnail_width)
+            .map(thumbnail_size_for_width)
+            .unwrap_or(*THUMBNAIL_SIZE);
+        let cache_budget = configuration
+            .as_ref()
+            .and_then(|config| config.thumbnail_cache_mb)
+            .map(|megabytes| megabytes.saturating_mul(1024 * 1024))
+            .unwrap_or(thumbnail_cache::DEFAULT_THUMBNAIL_CACHE_BYTES);
+        let scale_per_page = configuration
+            .as_ref()
+            .map(|config| config.scale_per_page)
+            .unwrap_or(false);
+
+        let upload_history = match UploadHistory::load(&upload_history_path()) {
+            Ok(history) => history,
+            Err(err) => {
+                error!("Failed to load upload history: {:?}", err);
+                UploadHistory::default()
+            }
+        };
+        let tags = match TagStore::load(&tags_path()) {
+            Ok(tags) => tags,
+            Err(err) => {
+                error!("Failed to load tags: {:?}", err);
+                TagStore::default()
+            }
+        };
+        let trash = match TrashJournal::load(&trash_journal_path()) {
+            Ok(trash) => trash,
+            Err(err) => {
+                error!("Failed to load trash journal: {:?}", err);
+                TrashJournal::default()
+            }
+        };
+        let trash_retention_days = configuration
+            .as_ref()
+            .map(|config| config.trash_retention_days())
+            .unwrap_or(config::DEFAULT_TRASH_RETENTION_DAYS);
+
+        let tabs = match configuration.as_ref() {
+            Some(config) => Tabs::new(config.tabs.clone(), config.active_tab, DEFAULT_WORKDIR),
+            None => Tabs::new(vec![], 0, DEFAULT_WORKDIR),
+        };
+
+        let mut app = Self {
+            background_rx,
+            background_tx,
+            tabs,
+            search_box: "".into(),
+            search_box_last: None,
+            workdir: DEFAULT_WORKDIR.into(),
+            workdir_input: DEFAULT_WORKDIR.into(),
+            files_list: vec![],
+            workdir_files: vec![],
+            listed_workdir: None,
+            dir_scan: None,
+            scan_generation: 0,
+            files_list_mtime: None,
+            browser_context: BrowserContext::default(),
+            navigation: Navigation::default(),
+            last_checked_dir: None,
+            last_checked_page: None,
+            per_page: *PER_PAGE,
+            browser_images: ThumbnailCache::new(cache_budget),
+            allow_shortcuts: true,
+            key_buffer: vec![],
+            editor_image_cache: None,
+            slideshow_image: None,
+            slideshow_paused: false,
+            slideshow_advanced_at: Instant::now(),
+            slideshow_fullscreen: false,
+            editor_rename_target: String::new(),
+            editor_rename_has_focus: false,
+            clipboard_filename: String::new(),
+            clipboard_preview: None,
+            configuration,
+            upload_status: None,
+            pending_errors: vec![],
+            rename_move: None,
+            modified_edit: None,
+            toast: None,
+            toast_link: None,
+            rename_move_confirmed: None,
+            selected_images: HashSet::new(),
+            split_view,
+            search_case_sensitive,
+            search_query: None,
+            show_dir_info: false,
+            dir_stats: None,
+            page_load_stats: None,
+            thumb_load: ThumbLoadTracker::default(),
+            last_load_stats: None,
+            dir_stats_requested: false,
+            dir_stats_progress: None,
+            upload_history,
+            tags,
+            auto_tag_preview: None,
+            trash,
+            trash_restore: None,
+            import_queue: None,
+            hide_uploaded: false,
+            formats: FormatCache::default(),
+            dimensions_cache: HashMap::new(),
+            outbox: Outbox::default(),
+            format_filter: FormatFilter::default(),
+            rename_in_s3: false,
+            integrity: IntegrityCache::default(),
+            upload_corrupt_confirmed: false,
+            remote_size: None,
+            batch_total_size: 0,
+            bucket_status: None,
+            bucket_check_pending: false,
+            s3_copy_prompt: None,
+            s3_edit_prompt: None,
+            s3_edits: HashMap::new(),
+            border_prompt: None,
+            sharpen_prompt: None,
+            sepia_prompt: None,
+            editor_popout: None,
+            contact_sheet_files: None,
+            contact_sheet_layout: ContactSheetLayout::default(),
+            contact_sheet_progress: None,
+            mosaic_exporting: false,
+            batch_optimize: None,
+            color_analyses: ThumbnailCache::new(COLOR_ANALYSIS_CACHE_BYTES),
+            color_analysis_requested: HashSet::new(),
+            image_issues: ThumbnailCache::new(IMAGE_ISSUES_CACHE_BYTES),
+            image_issues_requested: HashSet::new(),
+            preview_requested: None,
+            preview_image: None,
+            sort_field: SortField::Name,
+            tasks: TaskTracker::default(),
+            page_filenames: None,
+            ordered_filenames: None,
+            file_positions: None,
+            custom_order: vec![],
+            drag_state: None,
+            infinite_scroll,
+            requested_thumbnails: HashSet::new(),
+            infinite_visible_rows: 0..0,
+            pending_scroll_offset: None,
+            thumbnail_size,
+            grid_columns: *GRID_X as usize,
+            scale_per_page,
+            watched_dir: None,
+            watcher_refresh_at: None,
+            image_action_pending: None,
+            lazy_thumbnails: vec![],
+        };
+        app.purge_expired_trash(trash_retention_days);
+        app.load_tab();
+        app
+    }
+
+    /// deal with a message from the backend
+    fn handle_message(&mut self, ctx: &egui::Context, msg: AppMsg) {
+        match msg {
+            AppMsg::ThumbImageResponse(image_response) => {
+                debug!(
+                    "got response for: filepath={} page={}",
+                    image_response.filepath, image_response.page
+                );
+                if let Some(task) = image_response.task {
+                    if self.tasks.finish(task).is_none() {
+                        debug!(
+                            "Dropping stale thumbnail for {} from {}",
+                            image_response.filepath, task
+                        );
+                        return;
+                    }
+                }
+                // the cache is shared, so another tab's thumbnails are still worth keeping
+                let this_tab = image_response
+                    .tab
+                    .map(|tab| tab == self.tabs.active().id)
+                    .unwrap_or(true);
+                if this_tab
+                    && self.infinite_scroll
+                    && !self.requested_thumbnails.contains(&image_response.filepath)
+                {
+                    debug!(
+                        "Dropping thumbnail for {}, it's been scrolled away",
+                        image_response.filepath
+                    );
+                    return;
+                }
+                let Some(image) = image_response.image else {
+                    error!(
+                        "Got a thumbnail response with no image for {}",
+                        image_response.filepath
+                    );
+                    let finished = self.thumb_load.failed(&image_response.filepath);
+                    self.thumb_load_finished(finished);
+                    return;
+                };
+                let finished = self.thumb_load.loaded(&image_response.filepath);
+                self.thumb_load_finished(finished);
+                // it was deleted or moved while it was loading, URLs don't have a modified time
+                if image_response.modified.is_some()
+                    && !Path::new(&image_response.filepath).exists()
+                {
+                    debug!(
+                        "Dropping thumbnail for {}, it's gone away",
+                        image_response.filepath
+                    );
+                    return;
+                }
+                // making the thumbnail decoded the whole thing, so it's not corrupt
+                self.integrity.insert(
+                    image_response.filepath.clone(),
+                    image_response.modified,
+                    Verdict::Ok,
+                );
+                let size_bytes = image.width() * image.height() * 4;
+                // upload it once here, rather than every time it's drawn
+                let texture = ctx.load_texture(
+                    &image_response.filepath,
+                    (*image).clone(),
+                    TextureOptions::default(),
+                );
+                // it's been decoded once already, so it's likely to decode again
+                self.request_issue_detection(&image_response.filepath);
+                self.browser_images.insert(
+                    image_response.filepath,
+                    image_response.modified,
+                    Thumbnail { texture, image },
+                    size_bytes,
+                );
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+            AppMsg::NewAppState(new_state) => {
+                self.editor_rename_target = String::new();
+                self.editor_image_cache = None;
+
+                self.push_state(new_state);
+                ctx.request_repaint();
+            }
+            AppMsg::ImageLoadFailed {
+                filename,
+                error,
+                task,
+            } => {
+                let purpose = task.and_then(|task| self.tasks.finish(task));
+                error!("Failed to load image: {filename}: {error}");
+                if purpose == Some(TaskPurpose::Thumbnail) {
+                    let finished = self.thumb_load.failed(&filename);
+                    self.thumb_load_finished(finished);
+                }
+                // find out if it's broken or just couldn't be read, for the thumbnail's warning
+                if purpose == Some(TaskPurpose::Thumbnail) && PathBuf::from(&filename).exists() {
+                    self.request_integrity_check(&filename);
+                }
+            }
+            AppMsg::Echo(msg) => debug!("Echo {}", msg),
+            AppMsg::UploadImage(filepath) => {
+                error!("Backend sent UploadImage({})", filepath);
+            }
+            AppMsg::UpdateImage { filepath, .. } => {
+                error!("Backend sent UpdateImage({}) which is bad.", filepath);
+            }
+            AppMsg::CheckRemoteSize { filepath, .. } => {
+                error!("Backend sent CheckRemoteSize({}) which is bad.", filepath);
+            }
+            AppMsg::RemoteSize {
+                filepath,
+                key,
+                size,
+            } => {
+                debug!("{} is in S3 as {}: {:?}", filepath, key, size);
+                self.remote_size = Some((filepath, size));
+            }
+            AppMsg::LoadImage(_) => {
+                error!("Backend sent LoadImage() which is bad.");
+            }
+            AppMsg::Batch(_) => {
+                error!("Backend sent Batch() which is bad.");
+            }
+            AppMsg::LoadPreview { .. } => {
+                error!("Backend sent LoadPreview() which is bad.");
+            }
+            AppMsg::LoadImageFromUrl { url, .. } => {
+                error!("Backend sent LoadImageFromUrl({}) which is bad.", url);
+            }
+            AppMsg::GetDirectoryStats(path) => {
+                error!("Backend sent GetDirectoryStats({}) which is bad.", path);
+            }
+            AppMsg::CheckIntegrity(filepath) => {
+                error!("Backend sent CheckIntegrity({}) which is bad.", filepath);
+            }
+            AppMsg::IntegrityChecked {
+                filepath,
+                modified,
+                verdict,
+            } => {
+                debug!("Integrity of {}: {:?}", filepath, verdict);
+                self.integrity.insert(filepath, modified, verdict);
+            }
+            AppMsg::SniffFormats(filepaths) => {
+                error!(
+                    "Backend sent SniffFormats({} files) which is bad.",
+                    filepaths.len()
+                );
+            }
+            AppMsg::FormatsSniffed(formats) => {
+                debug!("Sniffed the formats of {} files", formats.len());
+                let sniffed_any = !formats.is_empty();
+                for (filepath, modified, format) in formats {
+                    self.formats.insert(filepath, modified, format);
+                }
+                // the guesses from the extensions might've been wrong
+                if sniffed_any && self.format_filter.is_active() {
+                    self.start_update(ctx);
+                }
+            }
+            AppMsg::CheckBucket => {
+                error!("Backend sent CheckBucket which is bad.");
+            }
+            AppMsg::S3CopyObject { src_key, .. } => {
+                error!("Backend sent S3CopyObject({}) which is bad.", src_key);
+            }
+            AppMsg::DownloadAndEdit { key } => {
+                error!("Backend sent DownloadAndEdit({}) which is bad.", key);
+            }
+            AppMsg::S3ObjectCopied { src_key, dest_key } => {
+                info!("Copied {} to {} in S3", src_key, dest_key);
+            }
+            AppMsg::S3RenameObject { src_key, .. } => {
+                error!("Backend sent S3RenameObject({}) which is bad.", src_key);
+            }
+            AppMsg::MoveFile { from, .. } => {
+                error!("Backend sent MoveFile({}) which is bad.", from);
+            }
+            AppMsg::MoveProgress { from, done, total } => {
+                if let Some(moving) = self
+                    .rename_move
+                    .as_mut()
+                    .filter(|moving| moving.from == from)
+                {
+                    moving.done = done;
+                    moving.total = total;
+                }
+            }
+            AppMsg::MoveDone { from, to, result } => {
+                self.rename_move = None;
+                match result {
+                    Ok(()) => self.renamed(ctx, &from, &to),
+                    Err(err) => self.push_error(
+                        t!("error-rename", error = err),
+                        Some(Box::new(AppState::Editor { filepath: from })),
+                    ),
+                }
+            }
+            AppMsg::S3ObjectRenamed {
+                old_filepath,
+                filepath,
+                src_key,
+                dest_key,
+                result,
+            } => match result {
+                Ok(()) => {
+                    info!("Renamed {} to {} in S3", src_key, dest_key);
+                    // it's still the same upload, just under a new name
+                    let mut record = UploadRecord::now(&filepath, &dest_key);
+                    if let Some(previous) = self.upload_history.get(&old_filepath) {
+                        record.uploaded_at = previous.uploaded_at;
+                    }
+                    self.upload_history.record(record);
+                    if let Err(err) = self.upload_history.save(&upload_history_path()) {
+                        error!("Failed to save upload history: {:?}", err);
+                    }
+                }
+                Err(error) => self.push_error(
+                    t!(
+                        "error-rename-in-s3",
+                        path = filepath,
+                        key = src_key,
+                        error = error
+                    ),
+                    None,
+                ),
+            },
+            AppMsg::AnalyzeColors(filepath) => {
+                error!("Backend sent AnalyzeColors({}) which is bad.", filepath);
+            }
+            AppMsg::ExportContactSheet { savepath, .. } => {
+                error!(
+                    "Backend sent ExportContactSheet({}) which is bad.",
+                    savepath.display()
+                );
+            }
+            AppMsg::ContactSheetProgress { done, total } => {
+                self.contact_sheet_progress = Some((done, total));
+            }
+            AppMsg::ContactSheetDone(result) => {
+                self.contact_sheet_progress = None;
+                match result {
+                    Ok(savepath) => info!("Saved contact sheet to {}", savepath.display()),
+                    Err(err) => {
+                        self.push_error(t!("error-export-contact-sheet", error = err), None)
+                    }
+                }
+            }
+            AppMsg::ExportMosaic { savepath, .. } => {
+                error!(
+                    "Backend sent ExportMosaic({}) which is bad.",
+                    savepath.display()
+                );
+            }
+            AppMsg::MosaicDone(result) => {
+                self.mosaic_exporting = false;
+                match result {
+                    Ok(savepath) => info!("Saved mosaic to {}", savepath.display()),
+                    Err(err) => self.push_error(t!("error-export-mosaic", error = err), None),
+                }
+            }
+            AppMsg::StartJob { job, .. } => {
+                error!("Backend sent StartJob({}) which is bad.", job);
+            }
+            AppMsg::CancelJob(job) => {
+                error!("Backend sent CancelJob({}) which is bad.", job);
+            }
+            AppMsg::JobProgress {
+                job,
+                done,
+                total,
+                current,
+            } => {
+                if let Some(BatchOptimize {
+                    stage:
+                        BatchOptimizeStage::Running {
+                            job: running,
+                            done: shown_done,
+                            total: shown_total,
+                            current: shown_current,
+                            ..
+                        },
+                    ..
+                }) = self.batch_optimize.as_mut()
+                {
+                    if *running == job {
+                        *shown_done = done;
+                        *shown_total = total;
+                        *shown_current = Some(current);
+                    }
+                }
+            }
+            AppMsg::JobDone { job, report } => self.batch_optimize_done(ctx, job, report),
+            AppMsg::ColorsAnalyzed {
+                filepath,
+                modified,
+                analysis,
+            } => {
+                self.color_analysis_requested.remove(&filepath);
+                let size_bytes = match &analysis {
+                    Ok(analysis) => analysis.size_bytes(),
+                    Err(err) => err.len(),
+                };
+                self.color_analyses
+                    .insert(filepath, modified, analysis, size_bytes);
+            }
+            AppMsg::DetectIssues(filepath) => {
+                error!("Backend sent DetectIssues({}) which is bad.", filepath);
+            }
+            AppMsg::IssuesDetected {
+                filepath,
+                modified,
+                issues,
+            } => {
+                self.image_issues_requested.remove(&filepath);
+                if !issues.is_empty() {
+                    debug!("Issues with {}: {:?}", filepath, issues);
+                }
+                // empty ones still take up a slot
+                let size_bytes = std::mem::size_of::<ImageIssue>() * issues.len().max(1);
+                self.image_issues
+                    .insert(filepath, modified, issues, size_bytes);
+            }
+            AppMsg::ApplyImageAction { filepath, .. } => {
+                error!("Backend sent ApplyImageAction({}) which is bad.", filepath);
+            }
+            AppMsg::ImageActionDone {
+                filepath,
+                action,
+                result,
+            } => {
+                self.image_action_pending = None;
+                match result {
+                    Ok(_) => {
+                        info!("Did {:?} to {}", action, filepath);
+                        self.reload_current_image(ctx, &filepath);
+                        // it came from the bucket, so see if it should go back
+                        if let Some(key) = self.s3_edits.get(&filepath).cloned() {
+                            self.show_update(&filepath, key);
+                        }
+                    }
+                    Err(err) => self.push_error(
+                        t!(
+                            "error-image-action",
+                            action = format!("{:?}", action),
+                            path = filepath,
+                            error = err
+                        ),
+                        None,
+                    ),
+                }
+            }
+            AppMsg::WatchDir(path) => {
+                error!("Backend sent WatchDir({}) which is bad.", path.display());
+            }
+            AppMsg::OpenPath(path) => self.open_path(ctx, path),
+            AppMsg::PurgeTrash(paths) => {
+                error!(
+                    "Backend sent PurgeTrash({} files) which is bad.",
+                    paths.len()
+                )
+            }
+            AppMsg::PageLoadStats(stats) => {
+                trace!("Page load stats: {:?}", stats);
+                self.page_load_stats = Some(stats);
+            }
+            AppMsg::ScanDirectory { path, .. } => {
+                error!(
+                    "Backend sent ScanDirectory({}) which is bad.",
+                    path.display()
+                );
+            }
+            AppMsg::CancelScan(generation) => {
+                error!("Backend sent CancelScan({}) which is bad.", generation);
+            }
+            AppMsg::DirectoryListing {
+                entries,
+                generation,
+                done,
+            } => self.directory_listing(ctx, entries, generation, done),
+            AppMsg::WatcherEvent(kind) => {
+                debug!("Watcher saw {:?}", kind);
+                // don't wait for the refresh to get rid of it
+                if let WatcherEventKind::Removed(path) = &kind {
+                    self.remove_watched_file(path);
+                }
+                self.watcher_refresh_at = Some(Instant::now() + WATCHER_DEBOUNCE);
+            }
+            AppMsg::BucketStatus(status) => {
+                if let Err(err) = &status {
+                    warn!("Bucket isn't accessible: {}", err);
+                }
+                self.bucket_status = Some(status);
+                self.bucket_check_pending = false;
+            }
+            AppMsg::GotDirStats { path, stats } => {
+                // they might've changed directory while it was working
+                if path == self.workdir {
+                    self.dir_stats = Some(stats);
+                    self.dir_stats_progress = None;
+                } else {
+                    debug!("Dropping stats for {}, we're in {} now", path, self.workdir);
+                }
+            }
+            AppMsg::DirStatsProgress { path, done, total } => {
+                if path == self.workdir && self.dir_stats.is_none() {
+                    self.dir_stats_progress = Some((done, total));
+                }
+            }
+            AppMsg::PreviewResponse(preview) => {
+                // only keep it if it's still the one we want
+                let current = match preview.task {
+                    Some(task) => self.tasks.finish(task).is_some(),
+                    None => true,
+                };
+                if current && self.preview_requested.as_ref() == Some(&preview.filepath) {
+                    let texture = ctx.load_texture(
+                        format!("preview:{}", preview.filepath),
+                        (*preview.image).clone(),
+                        TextureOptions::default(),
+                    );
+                    self.preview_image = Some((preview, texture));
+                    ctx.request_repaint();
+                }
+            }
+            AppMsg::BatchResult(results) => {
+                debug!("Got batch result with {} responses", results.len());
+                results
+                    .into_iter()
+                    .for_each(|result| self.handle_message(ctx, result));
+            }
+            AppMsg::UploadProgress {
+                filepath,
+                bytes_sent,
+                total_bytes,
+                message,
+                part,
+            } => {
+                debug!("Upload progress for {filepath}: {bytes_sent}/{total_bytes} {message:?}");
+                let status = self.upload_status.get_or_insert_with(UploadStatus::new);
+                status.bytes_sent = bytes_sent;
+                status.total_bytes = Some(total_bytes);
+                status.message = message;
+                status.part = part;
+                status.last_progress = Instant::now();
+            }
+            AppMsg::UploadComplete {
+                filepath,
+                key,
+                checksum,
+            } => {
+                if self.s3_edits.remove(&filepath).is_some() {
+                    // it was only downloaded to be edited, the bucket's got it now
+                    info!(
+                        "Uploaded the edited {} back to S3, removing {}",
+                        key, filepath
+                    );
+                    if let Err(err) = std::fs::remove_file(&filepath) {
+                        warn!("Failed to remove {}: {:?}", filepath, err);
+                    }
+                    self.forget_file(&filepath);
+                    if let AppState::Uploading(_) = self.navigation.current() {
+                        self.upload_status = None;
+                        self.pop_state();
+                    }
+                    self.push_toast(t!("s3-edit-uploaded", key = key));
+                    return;
+                }
+                self.upload_history
+                    .record(UploadRecord::now(&filepath, &key).with_checksum(checksum));
+                if let Err(err) = self.upload_history.save(&upload_history_path()) {
+                    error!("Failed to save upload history: {:?}", err);
+                }
+                if self.hide_uploaded {
+                    self.start_update(ctx);
+                }
+                // batch uploads don't go through the uploading screen
+                if let AppState::Uploading(_) = self.navigation.current() {
+                    debug!("Upload of {filepath} as {key} complete, going back");
+                    self.upload_status = None;
+                    let next = self.next_in_list(&filepath);
+                    self.pop_state();
+                    let in_editor = matches!(
+                        self.navigation.current(),
+                        AppState::Editor { filepath: current } if *current == filepath
+                    );
+                    if in_editor && self.advance_after_action() {
+                        self.advance_to(next);
+                    } else if matches!(self.navigation.current(), AppState::Browser) {
+                        self.jump_to_file(&filepath);
+                    }
+                } else {
+                    info!("Upload of {filepath} as {key} complete");
+                }
+            }
+            AppMsg::Error(message) => self.push_error(message, None),
+            AppMsg::DetailedError { message, details } => {
+                self.push_error_with_details(message, Some(details), None)
+            }
+            AppMsg::UploadAborted { message, details } => {
+                if let AppState::Uploading(_) = self.navigation.current() {
+                    self.upload_status = None;
+                    self.pop_state();
+                }
+                self.push_error_with_details(message, details, None);
+            }
+        }
+    }
+
+    fn key_handler(&mut self, ctx: Context) {
+        // the folder picker blocks, so don't open it while egui's input is borrowed
+        let new_tab = ctx.input(|input| input.modifiers.command && input.key_pressed(Key::T));
+        if new_tab && matches!(self.navigation.current(), AppState::Browser) {
+            self.new_tab();
+        }
+        ctx.input(|input| {
+            // "?" doesn't have a Key of its own, so look for it in the text events
+            let help_requested = input
+                .events
+                .iter()
+                .any(|event| matches!(event, egui::Event::Text(text) if text == "?"));
+            if help_requested {
+                self.show_help_state();
+            }
+
+            self.key_buffer.clone().iter().for_each(|key| {
+                if input.key_released(key.to_owned()) {
+                    debug!("released! {:?}", key);
+                    match key {
+                        // Key::ArrowDown => todo!(),
+                        Key::Delete => match self.navigation.current() {
+                            // if we're in the editor, prompt for deletion
+                            AppState::Editor { filepath } => {
+                                self.push_state(AppState::DeletePrompt(filepath.clone()));
+                            }
+                            AppState::Browser => {
+                                if let Some(filepath) = self.browser_context.selected_file.clone() {
+                                    self.push_state(AppState::DeletePrompt(filepath));
+                                }
+                            }
+                            _ => {}
+                        },
+
+                        Key::Enter => {
+                            if let AppState::Browser = self.navigation.current() {
+                                if let Some(filepath) = self.browser_context.selected_file.clone() {
+                                    self.push_state(AppState::Editor { filepath });
+                                }
+                            }
+                        }
+                        Key::W if input.modifiers.command => {
+                            if let AppState::Browser = self.navigation.current() {
+                                self.close_tab(self.tabs.active_index());
+                            }
+                        }
+                        Key::P => {
+                            if let AppState::Browser = self.navigation.current() {
+                                self.start_slideshow();
+                            }
+                        }
+                        Key::PageUp => {
+                            if let AppState::Browser = self.navigation.current() {
+                                self.browser_prev_page();
+                            }
+                        }
+                        Key::PageDown => {
+                            if let AppState::Browser = self.navigation.current() {
+                                self.browser_next_page();
+                            }
+                        }
+                        Key::Home => {
+                            if let AppState::Browser = self.navigation.current() {
+                                self.browser_first_page();
+                            }
+                        }
+                        Key::End => {
+                            if let AppState::Browser = self.navigation.current() {
+                                self.browser_last_page();
+                            }
+                        }
+                        Key::ArrowUp => {
+                            if let (AppState::Browser, true) =
+                                (self.navigation.current(), self.selection_keys())
+                            {
+                                self.move_selection(-(self.grid_columns as isize));
+                            }
+                        }
+                        Key::ArrowDown => {
+                            if let (AppState::Browser, true) =
+                                (self.navigation.current(), self.selection_keys())
+                            {
+                                self.move_selection(self.grid_columns as isize);
+                            }
+                        }
+                        Key::Escape if !self.pending_errors.is_empty() => {
+                            debug!("User hit escape with an error showing...");
+                            self.dismiss_error(self.pending_errors.len() - 1);
+                        }
+                        Key::Escape => match self.navigation.current() {
+                            AppState::Browser => {
+                                self.search_box = "".into();
+                            }
+                            AppState::Editor { filepath }
+                                if *filepath != self.editor_rename_target =>
+                            {
+                                debug!("User hit escape while renaming in editor...");
+                                let filepath = filepath.clone();
+                                self.cancel_rename_edit(&filepath);
+                            }
+                            AppState::Editor { .. } => {
+                                debug!("User hit escape in editor...");
+                                self.pop_state();
+                            }
+                            // it can't be stopped once it's copying to another drive
+                            AppState::RenameConfirm { .. } if self.rename_move.is_some() => {}
+                            AppState::RenameConfirm { .. } => {
+                                debug!("User hit escape in rename confirmation...");
+                                self.pop_state();
+                            }
+                            AppState::DeletePrompt(_) | AppState::BatchDeletePrompt(_) => {
+                                debug!("User hit escape in delete prompt...");
+                                self.pop_state();
+                            }
+                            AppState::Configuration => {
+                                debug!("User hit escape in config...");
+                                // TODO: save config here
+                                self.pop_state();
+                            }
+                            AppState::Help => {
+                                debug!("User hit escape in help...");
+                                self.close_help();
+                            }
+                            AppState::Slideshow { .. } => {
+                                debug!("User hit escape in the slideshow...");
+                                self.pop_state();
+                            }
+                            AppState::ImportFromClipboard { .. } => {
+                                debug!("User hit escape importing from the clipboard...");
+                                self.clipboard_preview = None;
+                                self.pop_state();
+                            }
+                            AppState::Trash => {
+                                debug!("User hit escape in the trash...");
+                                if self.trash_restore.take().is_none() {
+                                    self.pop_state();
+                                }
+                            }
+                            _ => {}
+                        },
+                        Key::F1 => self.show_help_state(),
+                        Key::Backspace => self.go_back(),
+                        Key::ArrowLeft => {
+                            if input.modifiers.alt {
+                                self.go_back();
+                            } else if let AppState::Browser = self.navigation.current() {
+                                if self.selection_keys() {
+                                    self.move_selection(-1);
+                                } else {
+                                    self.browser_prev_page();
+                                }
+                            }
+                        }
+                        Key::ArrowRight => {
+                            if let AppState::Browser = self.navigation.current() {
+                                if self.selection_keys() {
+                                    self.move_selection(1);
+                                } else {
+                                    self.browser_next_page();
+                                }
+                            }
+                        }
+
+                        // Key::A => todo!(),
+                        // Key::F1 => todo!(),
+                        _ => {
+                            // debug!("Unhandled key: {:?}", key);
+                        }
+                    }
+                }
+            });
+            self.key_buffer.clear();
+
+            if !input.keys_down.is_empty() {
+                for key in input.keys_down.iter() {
+                    if !self.key_buffer.contains(key) {
+                        self.key_buffer.push(key.to_owned());
+                    }
+                }
+            }
+        });
+        // let events = ui.input().events.clone();
+        //     for event in &events {
+        //         // match event {
+        //         //     egui::Event::Key{key, pressed, modifiers} => {
+        //         //         println!("{:?} = {:?}", key, pressed);
+        //         //     },
+        //         //     egui::Event::Text(t) => { println!("Text = {:?}", t) }
+        //         //     _ => {}
+        //         // }
+        //         debug!("Event: {event:?}");
+        // }
+    }
+
+    /// which screen we're on
+    pub fn app_state(&self) -> &AppState {
+        self.navigation.current()
+    }
+
+    /// switch to the help screen, remembering where we came from
+    fn show_help_state(&mut self) {
+        self.navigation.show_help();
+    }
+
+    /// leave the help screen and go back to whatever was showing before
+    fn close_help(&mut self) {
+        self.navigation.close_help();
+    }
+
+    /// move to a new state, remembering the current one so we can come back to it
+    fn push_state(&mut self, new_state: AppState) {
+        self.navigation.push(new_state);
+    }
+
+    /// go back to the last state we were in, or the browser if there's nothing to go back to
+    fn pop_state(&mut self) {
+        self.editor_rename_target = String::new();
+        self.editor_image_cache = None;
+        if let AppState::Browser = self.navigation.pop() {
+            // put the scroll view back where it was
+            self.pending_scroll_offset = Some(self.browser_context.scroll_offset);
+        }
+    }
+
+    /// the universal back button
+    fn go_back(&mut self) {
+        match self.navigation.current() {
+            AppState::Help => self.close_help(),
+            _ => self.pop_state(),
+        }
+    }
+
+    /// ask the backend to watch the workdir, if it's not already
+    fn watch_workdir(&mut self) {
+        if self.watched_dir.as_deref() == Some(self.workdir.as_str()) {
+            return;
+        }
+        self.watched_dir = Some(self.workdir.clone());
+        let resolvedpath = PathBuf::from(shellexpand::tilde(&self.workdir).as_ref());
+        self.sendmessage(AppMsg::WatchDir(resolvedpath));
+    }
+
+    /// something else deleted `path`, so take it out of the browser straight away
+    fn remove_watched_file(&mut self, path: &std::path::Path) {
+        let filepath = path.display().to_string();
+        self.files_list.retain(|file| file != path);
+        self.list_changed();
+        self.browser_images.remove(&filepath);
+        self.selected_images.remove(&filepath);
+        self.forget_file(&filepath);
+    }
+
+    /// drop any history pointing at a file that's been moved or deleted
+    fn forget_file(&mut self, filepath: &str) {
+        self.navigation.forget_file(filepath);
+        self.dimensions_cache.remove(filepath);
+        if self
+            .editor_popout
+            .as_ref()
+            .map(|popout| popout.filepath == filepath)
+            .unwrap_or(false)
+        {
+            debug!("{} has gone away, closing its editor window", filepath);
+            self.editor_popout = None;
+        }
+    }
+
+    /// Get a given page of file results
+    pub fn get_page(&self) -> Vec<PathBuf> {
+        let files_list = self.ordered_files();
+        // everything fits on one page, so show it even if the page number's out of date
+        if files_list.len() <= self.per_page {
+            files_list
+        } else {
+            browser_context::page_slice(
+                &files_list,
+                self.browser_context.current_page,
+                self.per_page,
+            )
+            .to_vec()
+        }
+    }
+
+    /// display names for the current page, only rebuilt when the page or the files list changes
+    fn page_filenames(&mut self) -> Arc<Vec<String>> {
+        if let Some(cached) = &self.page_filenames {
+            if cached.page == self.browser_context.current_page && cached.per_page == self.per_page
+            {
+                return cached.filenames.clone();
+            }
+        }
+        let filenames: Arc<Vec<String>> = Arc::new(
+            self.get_page()
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+        );
+        self.page_filenames = Some(PageFilenames {
+            page: self.browser_context.current_page,
+            per_page: self.per_page,
+            filenames: filenames.clone(),
+        });
+        filenames
+    }
+
+    /// display names for the whole list in browser order, only rebuilt when the list or its
+    /// order changes
+    fn ordered_filenames(&mut self) -> Arc<Vec<String>> {
+        if let Some(cached) = &self.ordered_filenames {
+            return cached.clone();
+        }
+        let filenames: Arc<Vec<String>> = Arc::new(
+            self.ordered_files()
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+        );
+        self.ordered_filenames = Some(filenames.clone());
+        filenames
+    }
+
+    /// the browser's list or its order has changed, so what's been worked out from it is stale
+    fn list_changed(&mut self) {
+        self.page_filenames = None;
+        self.ordered_filenames = None;
+        self.file_positions = None;
+    }
+
+    /// where `filepath` is in the browser's list and how long the list is, `None` if it's not in
+    /// there
+    fn list_position(&mut self, filepath: &str) -> (Option<usize>, usize) {
+        if self.file_positions.is_none() {
+            self.file_positions = Some(browser_context::positions(&self.ordered_files()));
+        }
+        match &self.file_positions {
+            Some(positions) => (positions.get(filepath).copied(), positions.len()),
+            None => (None, 0),
+        }
+    }
+
+    /// if a search or filter's leaving anything out of the browser
+    fn is_filtered(&self) -> bool {
+        self.search_query.is_some() || self.hide_uploaded || self.format_filter.is_active()
+    }
+
+    /// show everything in the workdir again, keeping `filepath` selected in the browser
+    fn clear_filters(&mut self, filepath: &str) {
+        self.search_box.clear();
+        // it's been filtered already, the browser doesn't need to do it again
+        self.search_box_last = Some(String::new());
+        self.hide_uploaded = false;
+        self.format_filter = FormatFilter::default();
+        self.filter_files_list();
+        let files = self.ordered_files();
+        let per_page = self.per_page;
+        self.browser_context
+            .select_file(&files, Path::new(filepath), per_page);
+    }
+
+    /// the files list in the order the browser should show it
+    pub fn ordered_files(&self) -> Vec<PathBuf> {
+        match self.sort_field {
+            SortField::Custom if !self.custom_order.is_empty() => {
+                let mut positions: HashMap<&str, usize> = HashMap::new();
+                for (index, filepath) in self.custom_order.iter().enumerate() {
+                    positions.entry(filepath.as_str()).or_insert(index);
+                }
+                self.files_list
+                    .iter()
+                    .cloned()
+                    // anything we don't know about goes on the end, in name order
+                    .sorted_by_cached_key(|filepath| {
+                        positions
+                            .get(filepath.display().to_string().as_str())
+                            .copied()
+                            .unwrap_or(usize::MAX)
+                    })
+                    .collect()
+            }
+            _ => self.files_list.clone(),
+        }
+    }
+
+    /// switch how the browser's sorted, loading any saved custom order
+    fn set_sort_field(&mut self, sort_field: SortField) {
+        self.sort_field = sort_field;
+        self.list_changed();
+        if sort_field == SortField::Custom && self.custom_order.is_empty() {
+            match custom_order::load(&custom_order_path(), &self.workdir) {
+                Ok(filenames) => {
+                    let workdir = PathBuf::from(shellexpand::tilde(&self.workdir).as_ref());
+                    self.custom_order = filenames
+                        .iter()
+                        .map(|filename| workdir.join(filename).display().to_string())
+                        .collect();
+                }
+                Err(err) => debug!("No saved custom order: {:?}", err),
+            }
+        }
+        self.browser_new_page();
+    }
+
+    /// swap two files in the custom order, starting it from the current order if needed
+    fn swap_custom_order(&mut self, first: &str, second: &str) {
+        if self.custom_order.is_empty() {
+            self.custom_order = self
+                .ordered_files()
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+        }
+        for filepath in [first, second] {
+            if !self.custom_order.iter().any(|f| f == filepath) {
+                self.custom_order.push(filepath.to_string());
+            }
+        }
+        let first_index = self.custom_order.iter().position(|f| f == first);
+        let second_index = self.custom_order.iter().position(|f| f == second);
+        if let (Some(first_index), Some(second_index)) = (first_index, second_index) {
+            debug!("Swapping {} and {} in custom order", first, second);
+            self.custom_order.swap(first_index, second_index);
+            self.list_changed();
+        }
+    }
+
+    /// work out if a press on a thumbnail has turned into a drag, and handle the drop
+    fn handle_thumbnail_drag(&mut self, ctx: &egui::Context, thumbnail_rects: &[(String, Rect)]) {
+        let (pointer_pos, pointer_down, released) = ctx.input(|i| {
+            (
+                i.pointer.interact_pos(),
+                i.pointer.primary_down(),
+                i.pointer.primary_released(),
+            )
+        });
+
+        let Some(drag_state) = self.drag_state.as_mut() else {
+            return;
+        };
+
+        if let Some(pos) = pointer_pos {
+            if pointer_down && pos.distance(drag_state.origin) > DRAG_THRESHOLD {
+                drag_state.dragging = true;
+            }
+            if drag_state.dragging {
+                let filepath = drag_state.filepath.clone();
+                let thumbnail_size = self.thumbnail_size;
+                egui::Area::new("thumbnail_drag")
+                    .fixed_pos(pos)
+                    .order(egui::Order::Tooltip)
+                    .show(ctx, |ui| match self.browser_images.peek(&filepath) {
+                        Some(thumbnail) => {
+                            let size =
+                                fit_size(thumbnail.texture.size_vec2(), thumbnail_size / 2.0);
+                            ui.image((thumbnail.texture.id(), size));
+                        }
+                        None => {
+                            ui.label(&filepath);
+                        }
+                    });
+            }
+        }
+
+        if released || !pointer_down {
+            let drag_state = self.drag_state.take();
+            if let (Some(drag_state), Some(pos)) = (drag_state, pointer_pos) {
+                if drag_state.dragging {
+                    if let Some((target, _)) = thumbnail_rects.iter().find(|(filepath, rect)| {
+                        rect.contains(pos) && filepath != &drag_state.filepath
+                    }) {
+                        self.swap_custom_order(&drag_state.filepath, target);
+                    }
+                }
+            }
+        }
+    }
+
+    /// hand a big workdir to the background to read, see [dir_scan]
+    fn start_dir_scan(&mut self, path: PathBuf, mtime: Option<SystemTime>, started_at: SystemTime) {
+        self.cancel_dir_scan();
+        self.scan_generation += 1;
+        let stream = self.listed_workdir.as_deref() != Some(self.workdir.as_str());
+        if stream {
+            // what's there is another directory's
+            self.workdir_files.clear();
+            self.listed_workdir = Some(self.workdir.clone());
+        }
+        info!(
+            "{} is too big to read here, scanning it in the background",
+            self.workdir
+        );
+        self.dir_scan = Some(DirScan {
+            generation: self.scan_generation,
+            workdir: self.workdir.clone(),
+            stream,
+            found: vec![],
+            mtime,
+            started_at,
+        });
+        self.sendmessage(AppMsg::ScanDirectory {
+            path,
+            generation: self.scan_generation,
+        });
+    }
+
+    /// stop reading a directory that's not wanted any more
+    fn cancel_dir_scan(&mut self) {
+        if let Some(scan) = self.dir_scan.take() {
+            debug!("Cancelling scan {} of {}", scan.generation, scan.workdir);
+            self.sendmessage(AppMsg::CancelScan(scan.generation));
+        }
+    }
+
+    /// some of a [AppMsg::ScanDirectory], anything from an older scan's ignored
+    fn directory_listing(
+        &mut self,
+        ctx: &Context,
+        entries: Vec<PathBuf>,
+        generation: u64,
+        done: bool,
+    ) {
+        let Some(scan) = self
+            .dir_scan
+            .as_mut()
+            .filter(|scan| scan.generation == generation)
+        else {
+            trace!(
+                "Ignoring {} files from old scan {}",
+                entries.len(),
+                generation
+            );
+            return;
+        };
+        let stream = scan.stream;
+        let first = stream && !entries.is_empty() && self.workdir_files.is_empty();
+        if stream {
+            self.workdir_files.extend(entries);
+            dir_scan::sort_listing(&mut self.workdir_files);
+        } else {
+            scan.found.extend(entries);
+        }
+
+        if done {
+            let Some(scan) = self.dir_scan.take() else {
+                return;
+            };
+            let mut files = match scan.stream {
+                true => std::mem::take(&mut self.workdir_files),
+                false => scan.found,
+            };
+            dir_scan::sort_listing(&mut files);
+            info!("Finished scanning {}, {} files", scan.workdir, files.len());
+            self.set_workdir_files(files, listing_mtime(scan.mtime, scan.started_at));
+            self.start_update(ctx);
+        } else if first {
+            // the first page can be shown while the rest's still coming
+            self.start_update(ctx);
+        } else if stream {
+            self.filter_files_list();
+        }
+    }
+
+    /// the workdir's been read, `listed_mtime` is what [MemeTool::files_list_mtime] becomes
+    fn set_workdir_files(&mut self, files: Vec<PathBuf>, listed_mtime: Option<SystemTime>) {
+        self.workdir_files = files;
+        self.listed_workdir = Some(self.workdir.clone());
+        self.files_list_mtime = listed_mtime;
+        // the stats don't care about searching, so they only need redoing when this does
+        self.dir_stats = None;
+        self.dir_stats_requested = false;
+        self.dir_stats_progress = None;
+    }
+
+    /// when the workdir last had something added, removed or renamed in it
+    fn workdir_mtime(&self) -> Option<SystemTime> {
+        std::fs::metadata(shellexpand::tilde(&self.workdir).as_ref())
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    fn update_files_list(&mut self) {
+        let mtime = self.workdir_mtime();
+        let scanning = self
+            .dir_scan
+            .as_ref()
+            .map(|scan| scan.workdir == self.workdir)
+            .unwrap_or(false);
+        if scanning {
+            trace!("Still scanning {}", self.workdir);
+        } else if mtime.is_some() && mtime == self.files_list_mtime {
+            trace!("{} hasn't changed, not reading it again", self.workdir);
+        } else {
+            let read_at = SystemTime::now();
+            let resolved = PathBuf::from(shellexpand::tilde(&self.workdir).as_ref());
+            match dir_scan::read_dir_limited(&resolved, dir_scan::SYNC_SCAN_MAX_ENTRIES) {
+                Some(files) => {
+                    self.cancel_dir_scan();
+                    self.set_workdir_files(files, listing_mtime(mtime, read_at));
+                }
+                None => self.start_dir_scan(resolved, mtime, read_at),
+            }
+        }
+        self.filter_files_list();
+    }
+
+    /// work out what the browser shows from [MemeTool::workdir_files]
+    fn filter_files_list(&mut self) {
+        // searching and hiding uploads can change without the directory changing
+        self.files_list = self.workdir_files.clone();
+        self.list_changed();
+        self.auto_tag_by_directory();
+
+        // clear out the cached files that have been deleted or changed, the thumbnails are
+        // done by [MemeTool::clear_stale_cache]
+        self.integrity.prune_stale();
+        self.color_analyses.prune_stale();
+        self.image_issues.prune_stale();
+        self.formats.prune_stale();
+
+        // after we've cleaned up the cache filter based on search
+        self.search_query = SearchQuery::new(&self.search_box, self.search_case_sensitive);
+        if let Some(query) = &self.search_query {
+            let tags = &self.tags;
+            self.files_list.retain(|filepath| {
+                let filename = filepath
+                    .file_name()
+                    .expect("Failed to parse filename from OsStr to String")
+                    .to_string_lossy(); // if you're doing bad things with file paths then too bad
+                query.matches_tagged(&filename, &tags.tags(&filepath.display().to_string()))
+            });
+        }
+
+        if self.hide_uploaded {
+            let history = &self.upload_history;
+            self.files_list
+                .retain(|filepath| !history.contains(&filepath.display().to_string()));
+        }
+
+        // anything that's not been sniffed yet goes by its extension until it has been
+        if self.format_filter.is_active() {
+            let (formats, filter) = (&self.formats, &self.format_filter);
+            self.files_list
+                .retain(|filepath| filter.shows(formats.get_or_guess(filepath)));
+        }
+    }
+
+    /// the tag [Configuration::auto_tag_by_directory] gives the workdir's files, if it's on
+    fn directory_tag(&self) -> Option<String> {
+        let enabled = self
+            .configuration
+            .as_ref()
+            .map(|config| config.auto_tag_by_directory)
+            .unwrap_or(false);
+        if enabled {
+            tags::directory_tag(&self.workdir)
+        } else {
+            None
+        }
+    }
+
+    /// tag everything in the workdir with its name, if that's turned on
+    fn auto_tag_by_directory(&mut self) {
+        let Some(tag) = self.directory_tag() else {
+            return;
+        };
+        let mut added = 0;
+        for filepath in &self.workdir_files {
+            if self.tags.add(&filepath.display().to_string(), &tag) {
+                added += 1;
+            }
+        }
+        if added > 0 {
+            debug!("Tagged {} files with {}", added, tag);
+            if let Err(err) = self.tags.save(&tags_path()) {
+                error!("Failed to save tags: {:?}", err);
+            }
+        }
+    }
+
+    /// ask the backend what's really in the files on this page, or all of them when they're
+    /// being filtered on, it's only a few bytes of each
+    fn sniff_formats(&mut self) {
+        let filepaths: Vec<String> = if self.format_filter.is_active() {
+            self.workdir_files
+                .iter()
+                .map(|filepath| filepath.display().to_string())
+                .collect()
+        } else {
+            self.get_page()
+                .iter()
+                .map(|filepath| filepath.display().to_string())
+                .collect()
+        };
+        let filepaths = self.formats.start(filepaths.iter().map(String::as_str));
+        if !filepaths.is_empty() {
+            self.sendmessage(AppMsg::SniffFormats(filepaths));
+        }
+    }
+
+    /// how big `filepath` is, from [MemeTool::dimensions_cache] if it's been asked before.
+    /// `None` if it can't be read.
+    fn image_dimensions(&mut self, filepath: &str) -> Option<(u32, u32)> {
+        if let Some(dimensions) = self.dimensions_cache.get(filepath) {
+            return Some(*dimensions);
+        }
+        match image_utils::load_image_dimensions_fast(Path::new(filepath)) {
+            Ok(dimensions) => {
+                self.dimensions_cache
+                    .insert(filepath.to_string(), dimensions);
+                Some(dimensions)
+            }
+            Err(err) => {
+                debug!("Couldn't read how big {} is: {}", filepath, err);
+                None
+            }
+        }
+    }
+
+    /// throw away thumbnails for files that have been deleted or changed, and if the cache is
+    /// getting full, the ones that aren't on the current page. Other directories can stay
+    /// otherwise, so going back to one is quick.
+    fn clear_stale_cache(&mut self) {
+        // the page is what's about to be shown, and it's a stat each, so that's all that's checked
+        let page: HashSet<String> = self
+            .get_page()
+            .iter()
+            .map(|filepath| filepath.display().to_string())
+            .collect();
+        let mut stale = self
+            .browser_images
+            .prune_stale_among(page.iter().map(String::as_str));
+        // anything in the workdir that's not in the listing has gone, no need to stat it. A
+        // listing that's still being scanned isn't all there yet.
+        let listed_whole = self.dir_scan.is_none()
+            && self.listed_workdir.as_deref() == Some(self.workdir.as_str());
+        if listed_whole {
+            let workdir = PathBuf::from(shellexpand::tilde(&self.workdir).as_ref());
+            let listed: HashSet<&Path> = self.workdir_files.iter().map(PathBuf::as_path).collect();
+            stale += self.browser_images.retain(|filepath| {
+                let path = Path::new(filepath);
+                path.parent() != Some(workdir.as_path()) || listed.contains(path)
+            });
+        }
+        let under_pressure = self.browser_images.used_bytes()
+            > self.browser_images.budget_bytes() / 100 * thumbnail_cache::CACHE_PRESSURE_PERCENT;
+        // there aren't pages to keep when it's all one scroll, the LRU does it
+        let off_page = if under_pressure && !self.infinite_scroll {
+            self.browser_images
+                .retain(|filepath| page.contains(filepath))
+        } else {
+            0
+        };
+        debug!(
+            "Evicted {} stale and {} off-page thumbnails, {} left using {} bytes",
+            stale,
+            off_page,
+            self.browser_images.len(),
+            self.browser_images.used_bytes()
+        );
+    }
+
+    /// build a threaded promisey thing to update images in the backend.
+    fn start_update(&mut self, ctx: &egui::Context) {
+        self.update_files_list();
+        self.clear_stale_cache();
+        // anything could've changed, and they're quick to read again
+        self.dimensions_cache.clear();
+        // along with the thumbnails, so it's all one trip to the backend's queue
+        self.sniff_formats();
+
+        debug!("Starting update in thread...");
+        self.lazy_thumbnails.clear();
+
+        if self.infinite_scroll {
+            // the scroll view asks for thumbnails as they come into view
+            ctx.request_repaint_after(Duration::from_millis(100));
+            return;
+        }
+
+        let current_page = self.browser_context.current_page;
+        let page = self.get_page();
+        let eager = browser_context::eager_count(page.len(), self.grid_columns, self.eager_rows());
+        let mut requested = vec![];
+        let mut skipped_cached = 0;
+        for (index, filepath) in page.iter().enumerate() {
+            let image = ImageData::new(filepath);
+            let filepath = image.filepath.clone();
+            if self.browser_images.contains(&filepath) {
+                trace!("Already have a thumbnail for: {}", filepath);
+                skipped_cached += 1;
+                continue;
+            }
+            if index < eager {
+                debug!("Sending message for: {}", filepath);
+                self.sendmessage(AppMsg::LoadImage(
+                    self.with_thumbnail_settings(image.to_thumbnail_request(current_page)),
+                ));
+            } else {
+                // asked for once it's drawn, or once the eager ones are in
+                self.lazy_thumbnails.push(filepath.clone());
+            }
+            requested.push(filepath);
+        }
+        let finished = self.thumb_load.start(requested, skipped_cached);
+        self.thumb_load_finished(finished);
+        self.thumb_load.watch_first_row(
+            page.iter()
+                .take(self.grid_columns)
+                .map(|filepath| filepath.display().to_string()),
+        );
+        ctx.request_repaint_after(Duration::from_millis(100));
+    }
+
+    /// log a round of thumbnails once they're all in
+    fn thumb_load_finished(&mut self, stats: Option<ThumbLoadStats>) {
+        if let Some(stats) = stats {
+            info!(
+                "Thumbnails for page {}: {} loaded, {} failed, {} cached of {} in {} ms, first row in {} ms",
+                self.browser_context.current_page + 1,
+                stats.loaded,
+                stats.failed,
+                stats.skipped_cached,
+                stats.total,
+                stats.duration_ms,
+                stats
+                    .first_row_ms
+                    .map(|ms| ms.to_string())
+                    .unwrap_or_else(|| "?".to_string())
+            );
+            self.last_load_stats = Some(stats);
+        }
+    }
+
+    /// `request` with everything the current settings want. New [ThumbImageMsg] fields that come
+    /// from the config get set here.
+    fn with_thumbnail_settings(&self, request: ThumbImageMsg) -> ThumbImageMsg {
+        request
+            .with_size(self.thumbnail_size)
+            .with_animate_gif(self.animate_gif_thumbnails())
+            .with_collect_stats(self.collect_load_stats())
+            .with_tab(self.tabs.active().id)
+    }
+
+    /// how many rows of thumbnails to ask for as soon as a page opens, see
+    /// [Configuration::eager_rows]
+    fn eager_rows(&self) -> Option<usize> {
+        self.configuration
+            .as_ref()
+            .and_then(|config| config.eager_rows)
+    }
+
+    /// ask for a thumbnail that was held back by [Configuration::eager_rows], now it's on screen.
+    /// The backend works through its queue in order, so it'll jump ahead of the rest of the
+    /// held back ones.
+    fn request_lazy_thumbnail(&mut self, filepath: &str) {
+        if let Some(index) = self.lazy_thumbnails.iter().position(|f| f == filepath) {
+            let filepath = self.lazy_thumbnails.remove(index);
+            trace!("{} is on screen, asking for it", filepath);
+            let request =
+                ImageData::new(filepath).to_thumbnail_request(self.browser_context.current_page);
+            self.sendmessage(AppMsg::LoadImage(self.with_thumbnail_settings(request)));
+        }
+    }
+
+    /// once the eager thumbnails are in, ask for everything that was held back
+    fn flush_lazy_thumbnails(&mut self) {
+        if self.lazy_thumbnails.is_empty() || self.tasks.outstanding(TaskPurpose::Thumbnail) > 0 {
+            return;
+        }
+        debug!(
+            "Asking for the other {} thumbnails on the page",
+            self.lazy_thumbnails.len()
+        );
+        let current_page = self.browser_context.current_page;
+        for filepath in std::mem::take(&mut self.lazy_thumbnails) {
+            let request = ImageData::new(filepath).to_thumbnail_request(current_page);
+            self.sendmessage(AppMsg::LoadImage(self.with_thumbnail_settings(request)));
+        }
+    }
+
+    /// whether GIF thumbnails get every frame decoded, or just the first
+    fn animate_gif_thumbnails(&self) -> bool {
+        self.configuration
+            .as_ref()
+            .map(|config| config.animate_gif_thumbnails)
+            .unwrap_or(false)
+    }
+
+    /// whether thumbnails are timed for [MemeTool::page_load_stats]
+    fn collect_load_stats(&self) -> bool {
+        !self
+            .configuration
+            .as_ref()
+            .map(|config| config.disable_load_stats)
+            .unwrap_or(false)
+    }
+
+    fn check_needs_update(&mut self, ctx: &egui::Context) {
+        if let Some(last_box) = self.search_box_last.clone() {
+            if last_box != self.search_box {
+                debug!("Search box changed to '{}', updating.", self.search_box);
+                // it's a different list now, so start from the top
+                self.browser_context = BrowserContext::default();
+                self.pending_scroll_offset = Some(0.0);
+                self.start_update(ctx);
+            }
+        } else if self.search_box_last.is_none() {
+            debug!("Search box changed to '{}', updating.", self.search_box);
+            self.start_update(ctx);
+        } else {
+            match (&self.last_checked_dir, &self.last_checked_page) {
+                (Some(dir), Some(page)) => {
+                    if dir != &self.workdir || page != &self.browser_context.current_page {
+                        self.start_update(ctx)
+                    } else {
+                        trace!("no update needed {} == {}", dir, self.workdir);
+                    }
+                }
+                (None, None) => {
+                    debug!("last_checked is None, starting update");
+                    self.start_update(ctx);
+                }
+                _ => {}
+            };
+        };
+        self.search_box_last = Some(self.search_box.clone());
+        self.last_checked_dir = Some(self.workdir.clone());
+        self.last_checked_page = Some(self.browser_context.current_page);
+    }
+
+    /// move the browser selection by `offset` places within the current page, in the order
+    /// [MemeTool::get_page] has them
+    pub fn move_selection(&mut self, offset: isize) {
+        let page: Vec<String> = self
+            .visible_files()
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        if page.is_empty() {
+            return;
+        }
+        let current = self
+            .browser_context
+            .selected_file
+            .as_ref()
+            .and_then(|selected| page.iter().position(|f| f == selected));
+        let new_index = match current {
+            Some(index) => (index as isize + offset).clamp(0, page.len() as isize - 1) as usize,
+            None => 0,
+        };
+        self.browser_context.selected_file = Some(page[new_index].clone());
+
+        // keep the selection on screen
+        let row = new_index / self.grid_columns;
+        if self.infinite_scroll && !self.infinite_visible_rows.contains(&row) {
+            self.pending_scroll_offset = Some(row as f32 * self.infinite_row_stride());
+        }
+    }
+
+    /// the arrow keys move the selection rather than the page
+    fn selection_keys(&self) -> bool {
+        self.split_view || self.infinite_scroll
+    }
+
+    /// everything the browser's currently showing, one page or the whole list
+    fn visible_files(&self) -> Vec<PathBuf> {
+        if self.infinite_scroll {
+            self.ordered_files()
+        } else {
+            self.get_page()
+        }
+    }
+
+    /// height of a row in the infinite scroll view, including spacing
+    fn infinite_row_stride(&self) -> f32 {
+        self.thumbnail_size.y + GRID_SPACING.y
+    }
+
+    /// work out how many thumbnails fit across the window, and maybe how many fit on a page
+    fn update_grid_layout(&mut self, available: Vec2) {
+        let columns = (available.x + GRID_SPACING.x) / (self.thumbnail_size.x + GRID_SPACING.x);
+        self.grid_columns = (columns.floor() as usize).max(1);
+        if self.scale_per_page {
+            let rows = (available.y + GRID_SPACING.y) / (self.thumbnail_size.y + GRID_SPACING.y);
+            let per_page = self.grid_columns * (rows.floor() as usize).max(1);
+            if per_page != self.per_page {
+                debug!("Changing per_page from {} to {}", self.per_page, per_page);
+                // stay near the first image on the current page
+                self.browser_context.current_page =
+                    (self.browser_context.current_page * self.per_page) / per_page;
+                self.per_page = per_page;
+                self.browser_new_page();
+            }
+        }
+    }
+
+    /// set a new thumbnail width and throw away the old thumbnails so they're regenerated
+    fn set_thumbnail_width(&mut self, width: f32) {
+        debug!("Setting thumbnail width to {}", width);
+        self.thumbnail_size = thumbnail_size_for_width(width);
+        self.browser_images.clear();
+        self.requested_thumbnails.clear();
+        if let Some(config) = self.configuration.as_mut() {
+            config.thumbnail_width = Some(width);
+            if let Err(err) = config.save() {
+                error!("Failed to save thumbnail width: {:?}", err);
+            }
+        }
+        self.browser_new_page();
+    }
+
+    /// switch between paged and infinite scroll, trying to stay in roughly the same place
+    fn toggle_infinite_scroll(&mut self) {
+        self.infinite_scroll = !self.infinite_scroll;
+        if self.infinite_scroll {
+            let row = (self.browser_context.current_page * self.per_page) / self.grid_columns;
+            self.pending_scroll_offset = Some(row as f32 * self.infinite_row_stride());
+        } else {
+            self.browser_context.current_page =
+                (self.infinite_visible_rows.start * self.grid_columns) / self.per_page;
+            self.requested_thumbnails.clear();
+        }
+        if let Some(config) = self.configuration.as_mut() {
+            config.infinite_scroll = self.infinite_scroll;
+            if let Err(err) = config.save() {
+                error!("Failed to save infinite scroll setting: {:?}", err);
+            }
+        }
+        self.browser_new_page();
+    }
+
+    /// ask for thumbnails near the viewport and throw away ones that are a long way off
+    fn update_infinite_thumbnails(&mut self, filenames: &[String], visible_rows: &Range<usize>) {
+        let columns = self.grid_columns;
+        let row_of = |index: usize| index / columns;
+        let load_rows = visible_rows
+            .start
+            .saturating_sub(INFINITE_SCROLL_PRELOAD_ROWS)
+            ..visible_rows.end + INFINITE_SCROLL_PRELOAD_ROWS;
+        let keep_rows = visible_rows.start.saturating_sub(INFINITE_SCROLL_KEEP_ROWS)
+            ..visible_rows.end + INFINITE_SCROLL_KEEP_ROWS;
+
+        for (index, filename) in filenames.iter().enumerate() {
+            let row = row_of(index);
+            if load_rows.contains(&row) {
+                if !self.requested_thumbnails.contains(filename)
+                    && !self.browser_images.contains(filename)
+                {
+                    self.requested_thumbnails.insert(filename.clone());
+                    let request = ImageData::new(filename)
+                        .to_thumbnail_request(self.browser_context.current_page);
+                    self.sendmessage(AppMsg::LoadImage(self.with_thumbnail_settings(request)));
+                }
+            } else if !keep_rows.contains(&row) && self.requested_thumbnails.remove(filename) {
+                // the thumbnail cache decides when to actually throw it away
+                trace!("Forgetting request for {}", filename);
+            }
+        }
+    }
+
+    /// the whole file list in a virtualised scroll view
+    fn show_infinite_grid(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        thumbnail_rects: &mut Vec<(String, Rect)>,
+    ) -> usize {
+        let filenames = self.ordered_filenames();
+        let columns = self.grid_columns;
+        let total_rows = (filenames.len() + columns - 1) / columns;
+        let mut loaded_images = 0;
+
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .max_height(ui.available_height() - 40.0);
+        if let Some(offset) = self.pending_scroll_offset.take() {
+            scroll_area = scroll_area.vertical_scroll_offset(offset);
+        }
+
+        let thumbnail_size = self.thumbnail_size;
+        let output = scroll_area.show_rows(ui, thumbnail_size.y, total_rows, |ui, row_range| {
+            ui.spacing_mut().item_spacing = *GRID_SPACING;
+            for row in row_range.clone() {
+                ui.horizontal(|ui| {
+                    ui.set_min_height(thumbnail_size.y);
+                    filenames
+                        .iter()
+                        .skip(row * columns)
+                        .take(columns)
+                        .for_each(|filename| {
+                            let (imageresponse, loaded) = self.show_thumbnail(ui, ctx, filename);
+                            if loaded {
+                                loaded_images += 1;
+                            }
+                            thumbnail_rects.push((filename.clone(), imageresponse.rect));
+                        });
+                });
+            }
+            self.update_infinite_thumbnails(&filenames, &row_range);
+            self.infinite_visible_rows = row_range;
+        });
+        self.browser_context.scroll_offset = output.state.offset.y;
+        loaded_images
+    }
+
+    /// whether the browser's page uses [image_utils::compute_thumbnail_grid_layout] rather than
+    /// fixed size cells
+    fn justified_layout(&self) -> bool {
+        self.configuration
+            .as_ref()
+            .map(|config| config.justified_layout)
+            .unwrap_or(false)
+    }
+
+    /// the current page in rows of thumbnails the same height, each as wide as its image needs
+    fn show_justified_grid(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        thumbnail_rects: &mut Vec<(String, Rect)>,
+    ) -> usize {
+        let filenames = self.page_filenames();
+        // anything that's not loaded yet gets a square, and moves over once it is
+        let sizes: Vec<(u32, u32)> = filenames
+            .iter()
+            .map(|filename| match self.browser_images.get(filename) {
+                Some(thumbnail) => {
+                    let [width, height] = thumbnail.texture.size();
+                    (width as u32, height as u32)
+                }
+                None => (0, 0),
+            })
+            .collect();
+        let rows = image_utils::compute_thumbnail_grid_layout(
+            &sizes,
+            ui.available_width(),
+            self.thumbnail_size.y,
+        );
+        let mut loaded_images = 0;
+        ui.spacing_mut().item_spacing = *GRID_SPACING;
+        for row in rows {
+            // they all share a height, so any of them will do
+            let Some((first, first_width)) = row.first().copied() else {
+                continue;
+            };
+            let (width, height) = sizes[first];
+            let row_height = first_width / image_utils::justified_aspect(width, height);
+            ui.horizontal(|ui| {
+                ui.set_min_height(row_height);
+                for (index, cell_width) in row {
+                    let filename = &filenames[index];
+                    // the widths fill the row, the spacing comes out of them
+                    let cell = vec2((cell_width - GRID_SPACING.x).max(1.0), row_height);
+                    let (imageresponse, loaded) = self.show_thumbnail_in(ui, ctx, filename, cell);
+                    if loaded {
+                        loaded_images += 1;
+                    }
+                    thumbnail_rects.push((filename.clone(), imageresponse.rect));
+                }
+            });
+        }
+        loaded_images
+    }
+
+    /// draw a single thumbnail and deal with clicks and drags on it, returns the response and
+    /// whether the thumbnail's loaded yet
+    fn show_thumbnail(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        filename: &str,
+    ) -> (egui::Response, bool) {
+        self.show_thumbnail_in(ui, ctx, filename, self.thumbnail_size)
+    }
+
+    /// a spinner with the filename under it, in place of a thumbnail that's still loading
+    fn paint_loading_placeholder(&self, ui: &mut egui::Ui, rect: Rect, filename: &str) {
+        ui.painter()
+            .rect_filled(rect, 4.0, ui.visuals().faint_bg_color);
+        let spinner_size = (rect.height() / 4.0).clamp(12.0, 32.0);
+        ui.put(
+            Rect::from_center_size(rect.center(), vec2(spinner_size, spinner_size)),
+            egui::Spinner::new().size(spinner_size),
+        );
+        // long names get cut off at the edge of the cell rather than spilling into the next one
+        ui.painter().with_clip_rect(rect.shrink(4.0)).text(
+            rect.center_bottom() - vec2(0.0, 8.0),
+            egui::Align2::CENTER_BOTTOM,
+            display_filename(Path::new(filename)),
+            egui::TextStyle::Small.resolve(ui.style()),
+            ui.visuals().weak_text_color(),
+        );
+    }
+
+    /// [MemeTool::show_thumbnail] in a `cell` that isn't the usual thumbnail size, for the
+    /// justified layout
+    fn show_thumbnail_in(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        filename: &str,
+        cell: Vec2,
+    ) -> (egui::Response, bool) {
+        let mut loaded = false;
+        let is_video = video::is_video(std::path::Path::new(filename));
+        // every thumbnail gets the whole cell whether it's loaded or not, so nothing moves
+        // around as they come in
+        let (rect, image) = ui.allocate_exact_size(cell, egui::Sense::hover());
+        let image = match self.browser_images.get(filename) {
+            Some(thumbnail) => {
+                loaded = true;
+                // the thumbnail might've been made at a different size, so work it out from
+                // what'll show, letterboxed in the middle of the cell
+                let size = fit_size(thumbnail.texture.size_vec2(), cell);
+                ui.painter().image(
+                    thumbnail.texture.id(),
+                    Rect::from_center_size(rect.center(), size),
+                    Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+                image
+            }
+            // it's never going to load, so say why instead of spinning forever
+            None if is_video && !video::ffmpeg_available() => {
+                loaded = true;
+                ui.painter()
+                    .rect_filled(rect, 4.0, ui.visuals().faint_bg_color);
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "▶",
+                    egui::FontId::proportional(32.0),
+                    ui.visuals().weak_text_color(),
+                );
+                image.on_hover_text(t!("video-needs-ffmpeg"))
+            }
+            None => {
+                if ui.is_rect_visible(rect) {
+                    self.request_lazy_thumbnail(filename);
+                }
+                self.paint_loading_placeholder(ui, rect, filename);
+                image
+            }
+        };
+        let imageresponse = image.interact(egui::Sense::click_and_drag());
+        let is_selected = self.browser_context.selected_file.as_deref() == Some(filename);
+        let label = thumbnail_label(
+            filename,
+            loaded,
+            self.upload_history.get(filename).is_some(),
+            self.integrity.corruption(filename).is_some(),
+        );
+        imageresponse.widget_info(|| {
+            egui::WidgetInfo::selected(egui::WidgetType::ImageButton, is_selected, &label)
+        });
+        // tabbing through the grid moves the selection along with it
+        if imageresponse.gained_focus() {
+            self.browser_context.selected_file = Some(filename.to_string());
+        }
+        if self.sort_field == SortField::Custom
+            && self.drag_state.is_none()
+            && imageresponse.is_pointer_button_down_on()
+        {
+            if let Some(origin) = ctx.input(|i| i.pointer.press_origin()) {
+                self.drag_state = Some(DragState {
+                    filepath: filename.to_string(),
+                    origin,
+                    dragging: false,
+                });
+            }
+        }
+        if is_selected || imageresponse.has_focus() {
+            self.paint_selection_outline(ui, imageresponse.rect);
+        }
+
+        // multi-select checkbox in the corner of the thumbnail
+        let mut checked = self.selected_images.contains(filename);
+        let checkbox = ui.put(
+            Rect::from_min_size(imageresponse.rect.min + vec2(4.0, 4.0), vec2(20.0, 20.0)),
+            egui::Checkbox::without_text(&mut checked),
+        );
+        checkbox.widget_info(|| {
+            egui::WidgetInfo::selected(
+                egui::WidgetType::Checkbox,
+                checked,
+                t!("thumbnail-select", name = label),
+            )
+        });
+        if checkbox.changed() {
+            self.toggle_selected(filename);
+        }
+        if checkbox.hovered() {
+            // don't open the editor when they're clicking the checkbox
+            return (imageresponse, loaded);
+        }
+
+        // so videos don't get mistaken for their first frame
+        if is_video && self.browser_images.contains(filename) {
+            let badge = Rect::from_center_size(
+                imageresponse.rect.right_bottom() + vec2(-14.0, -14.0),
+                vec2(20.0, 20.0),
+            );
+            ui.painter()
+                .circle_filled(badge.center(), 10.0, egui::Color32::from_black_alpha(160));
+            ui.painter().text(
+                badge.center(),
+                egui::Align2::CENTER_CENTER,
+                "▶",
+                egui::FontId::proportional(12.0),
+                egui::Color32::WHITE,
+            );
+        }
+
+        // mark things that are already in the bucket
+        if let Some(record) = self.upload_history.get(filename) {
+            let badge = Rect::from_center_size(
+                imageresponse.rect.right_top() + vec2(-12.0, 12.0),
+                vec2(12.0, 12.0),
+            );
+            ui.painter()
+                .circle_filled(badge.center(), 6.0, egui::Color32::from_rgb(40, 140, 220));
+            ui.interact(
+                badge,
+                egui::Id::new("upload_badge").with(filename),
+                egui::Sense::hover(),
+            )
+            .on_hover_text(t!(
+                "uploaded-as",
+                key = record.key,
+                age = dir_stats::format_age(record.uploaded_time())
+            ));
+        }
+
+        let upload_key = self
+            .upload_history
+            .get(filename)
+            .map(|record| record.key.clone());
+        let imageresponse = imageresponse.context_menu(|ui| {
+            if ui.button(t!("open-in-new-window")).clicked() {
+                self.open_editor_popout(filename);
+                ui.close_menu();
+            }
+            // things that are already in the bucket can be copied around in it
+            if let Some(src_key) = &upload_key {
+                if ui.button(t!("copy-in-s3-to-prefix")).clicked() {
+                    self.s3_copy_prompt = Some(S3CopyPrompt {
+                        src_key: src_key.clone(),
+                        dest_prefix: String::new(),
+                    });
+                    ui.close_menu();
+                }
+            }
+        });
+
+        // how big it is, and why it matched the search
+        let dimensions = match imageresponse.hovered() {
+            true => self.image_dimensions(filename),
+            false => None,
+        };
+        let ranges = self
+            .search_query
+            .as_ref()
+            .and_then(|query| query.match_ranges(&display_filename(Path::new(filename))));
+        let imageresponse = match (dimensions, ranges) {
+            (None, None) => imageresponse,
+            (dimensions, ranges) => imageresponse.on_hover_ui(|ui| {
+                if let Some(ranges) = ranges {
+                    ui.label(highlight_matches(
+                        ui,
+                        &display_filename(Path::new(filename)),
+                        &ranges,
+                    ));
+                }
+                if let Some((width, height)) = dimensions {
+                    ui.label(t!("dimensions", width = width, height = height));
+                }
+            }),
+        };
+
+        // things that might be wrong with it, next to where a corruption warning goes
+        if let Some(issues) = self
+            .image_issues
+            .peek(filename)
+            .filter(|issues| !issues.is_empty())
+        {
+            let (symbol, color) = issue_badge(ui, issues);
+            let summary = issues.iter().map(ImageIssue::describe).join("\n");
+            let badge = Rect::from_min_size(
+                imageresponse.rect.left_bottom() + vec2(28.0, -24.0),
+                vec2(20.0, 20.0),
+            );
+            ui.put(badge, egui::Label::new(RichText::new(symbol).color(color)))
+                .on_hover_text(summary);
+        }
+
+        if let Some(error) = self.integrity.corruption(filename) {
+            let warning = Rect::from_min_size(
+                imageresponse.rect.left_bottom() + vec2(4.0, -24.0),
+                vec2(20.0, 20.0),
+            );
+            ui.put(
+                warning,
+                egui::Label::new(RichText::new("⚠").color(ui.visuals().warn_fg_color)),
+            )
+            .on_hover_text(t!("file-corrupt", error = error));
+        }
+
+        let open_editor = if self.split_view {
+            if imageresponse.clicked() {
+                self.browser_context.selected_file = Some(filename.to_string());
+            }
+            imageresponse.double_clicked()
+        } else {
+            imageresponse.clicked()
+        };
+        if open_editor {
+            // reset the things
+            self.editor_image_cache = None;
+            self.editor_rename_target = String::new();
+            self.browser_context.selected_file = Some(filename.to_string());
+            self.push_state(AppState::Editor {
+                filepath: filename.to_string(),
+            });
+        };
+        (imageresponse, loaded)
+    }
+
+    /// the outline around the selected thumbnail, which can be made thicker and brighter
+    fn paint_selection_outline(&self, ui: &egui::Ui, rect: Rect) {
+        let high_contrast = self
+            .configuration
+            .as_ref()
+            .map(|config| config.high_contrast_selection)
+            .unwrap_or(false);
+        if high_contrast {
+            // black inside yellow stands out against light and dark images alike
+            ui.painter().rect_stroke(
+                rect.expand(4.0),
+                2.0,
+                egui::Stroke::new(4.0, egui::Color32::YELLOW),
+            );
+            ui.painter().rect_stroke(
+                rect.expand(1.0),
+                0.0,
+                egui::Stroke::new(2.0, egui::Color32::BLACK),
+            );
+        } else {
+            ui.painter()
+                .rect_stroke(rect.expand(2.0), 2.0, ui.visuals().selection.stroke);
+        }
+    }
+
+    /// add or remove a file from the multi-selection
+    fn toggle_selected(&mut self, filepath: &str) {
+        if !self.selected_images.remove(filepath) {
+            self.selected_images.insert(filepath.to_string());
+        }
+    }
+
+    /// select everything the browser's showing
+    fn select_all(&mut self) {
+        let files: Vec<String> = self
+            .visible_files()
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        self.selected_images.extend(files);
+    }
+
+    /// the multi-selection in the order the browser shows it, so batch operations happen in the
+    /// same order every time. Anything that's dropped out of the files list goes on the end.
+    fn get_selected_filepaths_sorted(&self) -> Vec<String> {
+        let mut filepaths: Vec<String> = self
+            .ordered_files()
+            .iter()
+            .map(|filepath| filepath.display().to_string())
+            .filter(|filepath| self.selected_images.contains(filepath))
+            .collect();
+        let known: HashSet<String> = filepaths.iter().cloned().collect();
+        filepaths.extend(
+            self.selected_images
+                .iter()
+                .filter(|filepath| !known.contains(*filepath))
+                .sorted()
+                .cloned(),
+        );
+        filepaths
+    }
+
+    /// how big the multi-selection is on disk, skipping anything that can't be read
+    fn get_selection_total_size(&self) -> u64 {
+        self.selected_images
+            .iter()
+            .filter_map(|filepath| std::fs::metadata(filepath).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    /// the action bar shown when there's a multi-selection
+    fn show_selection_bar(&mut self, ui: &mut egui::Ui) {
+        egui::TopBottomPanel::top("selection_bar").show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(t!("selected-count", count = self.selected_images.len()));
+                ui.add_space(15.0);
+                if ui.button(t!("delete-selected")).clicked() {
+                    self.batch_total_size = self.get_selection_total_size();
+                    let filepaths = self.get_selected_filepaths_sorted();
+                    self.push_state(AppState::BatchDeletePrompt(filepaths));
+                }
+                if ui.button(t!("upload-selected")).clicked() {
+                    let uploads = self
+                        .get_selected_filepaths_sorted()
+                        .into_iter()
+                        .map(AppMsg::UploadImage)
+                        .collect();
+                    self.sendmessage(AppMsg::Batch(uploads));
+                }
+                if ui.button(t!("contact-sheet")).clicked() {
+                    let filepaths = self.get_selected_filepaths_sorted();
+                    self.open_contact_sheet_prompt(filepaths);
+                }
+                if ui.button(t!("optimize-selected")).clicked() {
+                    self.open_batch_optimize();
+                }
+                if ui.button(t!("clear-selection")).clicked() {
+                    self.selected_images.clear();
+                }
+            });
+        });
+    }
+
+    /// the preview pane for the split view
+    fn show_preview_panel(&mut self, ui: &mut egui::Ui) {
+        if self.preview_requested != self.browser_context.selected_file {
+            self.preview_requested = self.browser_context.selected_file.clone();
+            // anything still loading for the last selection isn't wanted now
+            self.tasks.forget(TaskPurpose::Preview);
+            if let Some(filepath) = self.browser_context.selected_file.clone() {
+                self.sendmessage(AppMsg::LoadPreview {
+                    filepath,
+                    task: None,
+                });
+            }
+        }
+
+        egui::SidePanel::right("preview_panel")
+            .resizable(true)
+            .default_width(PREVIEW_SIZE.x / 2.0)
+            .show_inside(ui, |ui| {
+                let Some(selected) = &self.browser_context.selected_file else {
+                    ui.label(t!("preview-select-prompt"));
+                    return;
+                };
+                match &self.preview_image {
+                    Some((preview, texture)) if &preview.filepath == selected => {
+                        let size = fit_size(
+                            texture.size_vec2(),
+                            Vec2 {
+                                x: ui.available_width(),
+                                y: ui.available_height() * 0.8,
+                            },
+                        );
+                        ui.image((texture.id(), size));
+                        ui.label(selected);
+                        ui.label(t!(
+                            "image-size",
+                            width = preview.dimensions.0,
+                            height = preview.dimensions.1
+                        ));
+                        ui.label(t!(
+                            "file-size",
+                            size = humansize::format_size(preview.file_size, humansize::DECIMAL)
+                        ));
+                    }
+                    _ => {
+                        ui.spinner();
+                        ui.label(selected);
+                    }
+                }
+            });
+    }
+
+    /// flip the split view on or off, and remember it in the config
+    fn toggle_split_view(&mut self) {
+        self.split_view = !self.split_view;
+        if let Some(config) = self.configuration.as_mut() {
+            config.split_view = self.split_view;
+            if let Err(err) = config.save() {
+                error!("Failed to save split view setting: {:?}", err);
+            }
+        }
+    }
+
+    /// flip case sensitive searching and re-run the search
+    fn toggle_search_case_sensitive(&mut self, ctx: &egui::Context) {
+        self.search_case_sensitive = !self.search_case_sensitive;
+        if let Some(config) = self.configuration.as_mut() {
+            config.default_search_case_sensitive = self.search_case_sensitive;
+            if let Err(err) = config.save() {
+                error!("Failed to save search case setting: {:?}", err);
+            }
+        }
+        self.start_update(ctx);
+    }
+
+    /// stats about everything in the workdir, ignoring the search
+    fn directory_info_panel(&mut self, ui: &mut egui::Ui) {
+        if self.dir_stats.is_none() && !self.dir_stats_requested {
+            self.dir_stats_requested = true;
+            self.sendmessage(AppMsg::GetDirectoryStats(self.workdir.clone()));
+        }
+        let Some(stats) = &self.dir_stats else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                match self.dir_stats_progress {
+                    Some((done, total)) => {
+                        ui.label(t!("counting-files-progress", done = done, total = total))
+                    }
+                    None => ui.label(t!("counting-files")),
+                };
+            });
+            return;
+        };
+        let max_bytes = match &self.configuration {
+            Some(config) => config.web_max_bytes(),
+            None => Configuration::default().web_max_bytes(),
+        };
+        let mut open_file = None;
+        ui.group(|ui| {
+            Grid::new("directory_info")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label(t!("dir-image-files"));
+                    ui.label(stats.total_files.to_string());
+                    ui.end_row();
+
+                    ui.label(t!("dir-total-size"));
+                    ui.label(humansize::format_size(
+                        stats.total_bytes,
+                        humansize::DECIMAL,
+                    ));
+                    ui.end_row();
+
+                    ui.label(t!("dir-formats"));
+                    ui.label(stats.formats_summary());
+                    ui.end_row();
+
+                    if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+                        ui.label(t!("dir-oldest"));
+                        ui.label(dir_stats::format_age(oldest));
+                        ui.end_row();
+
+                        ui.label(t!("dir-newest"));
+                        ui.label(dir_stats::format_age(newest));
+                        ui.end_row();
+                    }
+
+                    ui.label(t!("dir-not-web-friendly"));
+                    ui.label(t!(
+                        "dir-over-size",
+                        count = stats.count_over(max_bytes),
+                        size = humansize::format_size(max_bytes, humansize::DECIMAL)
+                    ));
+                    ui.end_row();
+                });
+            let largest = stats.largest(dir_stats::LARGEST_FILES);
+            if !largest.is_empty() {
+                egui::CollapsingHeader::new(t!("dir-largest-files"))
+                    .id_source("dir_largest_files")
+                    .show(ui, |ui| {
+                        Grid::new("dir_largest_files_grid")
+                            .num_columns(2)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (filepath, size) in largest {
+                                    let name = filepath
+                                        .file_name()
+                                        .map(|name| name.to_string_lossy().to_string())
+                                        .unwrap_or_else(|| filepath.display().to_string());
+                                    if ui
+                                        .link(name)
+                                        .on_hover_text(t!("dir-open-in-editor"))
+                                        .clicked()
+                                    {
+                                        open_file = Some(filepath.display().to_string());
+                                    }
+                                    ui.label(humansize::format_size(*size, humansize::DECIMAL));
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            }
+        });
+        if let Some(filepath) = open_file {
+            self.open_from_dir_stats(filepath);
+        }
+    }
+
+    /// open one of the info panel's largest files in the editor, with the browser set up to show
+    /// it when they come back
+    fn open_from_dir_stats(&mut self, filepath: String) {
+        // it might be hidden by the search
+        if !self
+            .files_list
+            .iter()
+            .any(|listed| listed.display().to_string() == filepath)
+        {
+            self.search_box = String::new();
+        }
+        self.editor_image_cache = None;
+        self.editor_rename_target = String::new();
+        self.browser_context.selected_file = Some(filepath.clone());
+        self.push_state(AppState::Editor { filepath });
+    }
+
+    /// a checkbox for each format, going by what's in the files rather than their extensions
+    fn format_filter_controls(&mut self, ui: &mut egui::Ui, ctx: &Context) {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label(t!("formats")).on_hover_text(t!("formats-hint"));
+            for format in SniffedFormat::ALL {
+                let mut shown = self.format_filter.shows(format);
+                if ui.checkbox(&mut shown, format.label()).changed() {
+                    self.format_filter.set(format, shown);
+                    changed = true;
+                }
+            }
+        });
+        if changed {
+            // it's a different list now, so start from the top
+            self.browser_context = BrowserContext::default();
+            self.pending_scroll_offset = Some(0.0);
+            self.start_update(ctx);
+        }
+    }
+
+    /// what's shown instead of an empty grid when nothing matches the search
+    fn no_search_matches(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(40.0);
+            ui.label(RichText::new(t!("search-no-matches")).text_style(heading3()));
+            ui.label(t!(
+                "search-no-matches-hint",
+                search = self.search_box.trim()
+            ));
+            ui.add_space(15.0);
+            if ui.button(t!("clear-search")).clicked() {
+                // the list's redone once the search box has changed
+                self.search_box = String::new();
+            }
+        });
+    }
+
+    /// Keyboard focus follows the order things are added, so it's toolbar, grid then footer. The
+    /// selection bar's a panel so it has to be added first, and comes before all of them.
+    fn show_browser(&mut self, ui: &mut egui::Ui) {
+        let ctx = ui.ctx().clone();
+        // println!("starting show_browser repaint");
+        if !self.selected_images.is_empty() {
+            self.show_selection_bar(ui);
+        }
+        if self.split_view {
+            self.show_preview_panel(ui);
+        }
+        ui.scope(|ui| {
+            self.check_needs_update(&ctx);
+
+            self.tab_bar(ui);
+            self.scan_progress(ui);
+            ui.horizontal(|ui| {
+                let name_label = ui.label(
+                    RichText::new(t!("current-workdir"))
+                        .text_style(heading3())
+                        .strong(),
+                );
+                let workdir_editor = ui
+                    .text_edit_singleline(&mut self.workdir_input)
+                    .labelled_by(name_label.id);
+                // only change directory once they're done typing
+                if workdir_editor.lost_focus() && self.workdir_input != self.workdir {
+                    self.set_workdir(self.workdir_input.clone());
+                }
+                let dir_info = ui
+                    .selectable_label(self.show_dir_info, "ℹ")
+                    .on_hover_text(t!("directory-info"));
+                dir_info.widget_info(|| {
+                    egui::WidgetInfo::selected(
+                        egui::WidgetType::SelectableLabel,
+                        self.show_dir_info,
+                        t!("directory-info"),
+                    )
+                });
+                if dir_info.clicked() {
+                    self.show_dir_info = !self.show_dir_info;
+                }
+            });
+            if self.show_dir_info {
+                self.directory_info_panel(ui);
+            }
+
+            // search box
+            ui.horizontal(|ui| {
+                let search_label =
+                    ui.label(RichText::new(t!("search")).text_style(heading3()).strong());
+                ui.text_edit_singleline(&mut self.search_box)
+                    .labelled_by(search_label.id);
+                let match_case = ui
+                    .selectable_label(self.search_case_sensitive, "Aa")
+                    .on_hover_text(t!("match-case"));
+                match_case.widget_info(|| {
+                    egui::WidgetInfo::selected(
+                        egui::WidgetType::SelectableLabel,
+                        self.search_case_sensitive,
+                        t!("match-case"),
+                    )
+                });
+                if match_case.clicked() {
+                    self.toggle_search_case_sensitive(&ctx);
+                }
+                if ui.button(t!("reset")).clicked() {
+                    self.search_box = "".to_string();
+                }
+                // it's the list that's showing, so it catches up once the search's been applied
+                if self.search_query.is_some() {
+                    let matches = self.files_list.len();
+                    ui.label(if matches == 1 {
+                        t!("search-match")
+                    } else {
+                        t!("search-matches", count = matches)
+                    });
+                }
+            });
+            self.format_filter_controls(ui, &ctx);
+
+            // navigation bars
+            ui.add_space(15.0);
+            ui.horizontal(|ui| {
+                // pages are counted from one for people
+                let current_page = self.browser_context.current_page + 1;
+                if self.browser_context.current_page > 0 {
+                    let first_page = ui.button(t!("first-page"));
+                    first_page.widget_info(|| {
+                        egui::WidgetInfo::labeled(
+                            egui::WidgetType::Button,
+                            t!("first-page-accessible"),
+                        )
+                    });
+                    if first_page.clicked() {
+                        self.browser_first_page();
+                    };
+
+                    let prev_page = ui.button(t!("prev-page"));
+                    prev_page.widget_info(|| {
+                        egui::WidgetInfo::labeled(
+                            egui::WidgetType::Button,
+                            t!("prev-page-accessible", page = current_page - 1),
+                        )
+                    });
+                    if prev_page.clicked() {
+                        self.browser_prev_page();
+                    }
+                    ui.add_space(15.0);
+                }
+
+                let next_page = ui.button(t!("next-page"));
+                next_page.widget_info(|| {
+                    egui::WidgetInfo::labeled(
+                        egui::WidgetType::Button,
+                        t!("next-page-accessible", page = current_page + 1),
+                    )
+                });
+                if next_page.clicked() {
+                    self.browser_next_page();
+                }
+                ui.add_space(15.0);
+                if ui
+                    .selectable_label(self.split_view, t!("split-view"))
+                    .clicked()
+                {
+                    self.toggle_split_view();
+                }
+                if ui
+                    .selectable_label(self.infinite_scroll, t!("infinite-scroll"))
+                    .clicked()
+                {
+                    self.toggle_infinite_scroll();
+                }
+                if ui
+                    .selectable_label(self.hide_uploaded, t!("hide-uploaded"))
+                    .clicked()
+                {
+                    self.hide_uploaded = !self.hide_uploaded;
+                    self.start_update(&ctx);
+                }
+                ui.add_space(15.0);
+                let mut thumbnail_width = self.thumbnail_size.x;
+                let slider = ui.add(
+                    egui::Slider::new(
+                        &mut thumbnail_width,
+                        THUMBNAIL_WIDTH_MIN..=THUMBNAIL_WIDTH_MAX,
+                    )
+                    .text(t!("thumbnail-size")),
+                );
+                if slider.changed() {
+                    // rescale what we've got while the slider's moving
+                    self.thumbnail_size = thumbnail_size_for_width(thumbnail_width);
+                }
+                // only regenerate once they've let go
+                if slider.drag_released() || (slider.changed() && !slider.dragged()) {
+                    self.set_thumbnail_width(thumbnail_width);
+                }
+                ui.add_space(15.0);
+                let mut sort_field = self.sort_field;
+                egui::ComboBox::from_label(t!("sort"))
+                    .selected_text(sort_field.label())
+                    .show_ui(ui, |ui| {
+                        for field in [SortField::Name, SortField::Custom] {
+                            ui.selectable_value(&mut sort_field, field, field.label());
+                        }
+                    });
+                if sort_field != self.sort_field {
+                    self.set_sort_field(sort_field);
+                }
+                if self.sort_field == SortField::Custom {
+                    if ui.button(t!("save-order")).clicked() {
+                        if let Err(err) = custom_order::save(
+                            &custom_order_path(),
+                            &self.workdir,
+                            &self.custom_order,
+                        ) {
+                            self.push_error(
+                                t!("error-save-order", error = format!("{:?}", err)),
+                                None,
+                            );
+                        }
+                    }
+                    if ui.button(t!("reset-order")).clicked() {
+                        self.custom_order.clear();
+                        self.list_changed();
+                    }
+                }
+                #[cfg(debug_assertions)]
+                if ui.button(t!("refresh")).clicked() {
+                    debug!("Refresh clicked");
+                    self.search_box_last = None;
+                    self.sendmessage(AppMsg::NewAppState(AppState::Browser));
+                }
+            });
+            ui.add_space(15.0);
+
+            let mut loaded_images = 0;
+            let mut thumbnail_rects: Vec<(String, Rect)> = vec![];
+            // leave room for the status bar at the bottom
+            self.update_grid_layout(ui.available_size() - vec2(0.0, 40.0));
+            let grid_columns = self.grid_columns;
+
+            if self.files_list.is_empty() && self.search_query.is_some() {
+                self.no_search_matches(ui);
+            } else if self.infinite_scroll {
+                loaded_images = self.show_infinite_grid(ui, &ctx, &mut thumbnail_rects);
+            } else if self.justified_layout() {
+                loaded_images = self.show_justified_grid(ui, &ctx, &mut thumbnail_rects);
+            } else {
+                // exactly the order get_page has them in, so the cell at row r and column c is
+                // r * grid_columns + c in the list, which is what move_selection counts in
+                let filenames = self.page_filenames();
+                Grid::new("browser")
+                    .num_columns(10)
+                    .spacing(*GRID_SPACING) // grid spacing
+                    .show(ui, |ui| {
+                        for (index, filename) in filenames.iter().enumerate() {
+                            let (imageresponse, loaded) = self.show_thumbnail(ui, &ctx, filename);
+                            if loaded {
+                                loaded_images += 1;
+                            }
+                            thumbnail_rects.push((filename.clone(), imageresponse.rect));
+
+                            if (index + 1) % grid_columns.max(1) == 0 {
+                                ui.end_row();
+                            }
+                        }
+                    });
+            }
+
+            self.handle_thumbnail_drag(&ctx, &thumbnail_rects);
+
+            ui.add_space(15.0);
+
+            ui.horizontal(|ui| {
+                if ui.button(t!("configuration")).clicked() {
+                    self.push_state(AppState::Configuration);
+                }
+                if ui
+                    .button(t!("recently-deleted", count = self.trash.len()))
+                    .clicked()
+                {
+                    self.push_state(AppState::Trash);
+                }
+                if ui
+                    .add_enabled(
+                        !self.mosaic_exporting,
+                        egui::Button::new(t!("export-mosaic")),
+                    )
+                    .clicked()
+                {
+                    self.export_mosaic();
+                }
+                if self.mosaic_exporting {
+                    ui.spinner();
+                    ui.label(t!("mosaic-progress"));
+                }
+                if ui.button(t!("export-csv")).clicked() {
+                    self.export_csv();
+                }
+                if ui.button(t!("edit-from-s3")).clicked() {
+                    self.s3_edit_prompt = Some(String::new());
+                }
+                if ui.button(t!("export-contact-sheet")).clicked() {
+                    let filepaths = self
+                        .get_page()
+                        .iter()
+                        .map(|filepath| filepath.display().to_string())
+                        .collect();
+                    self.open_contact_sheet_prompt(filepaths);
+                }
+                if let Some((done, total)) = self.contact_sheet_progress {
+                    ui.spinner();
+                    ui.label(t!("contact-sheet-progress", done = done, total = total));
+                }
+                if ui.button(t!("batch-optimize")).clicked() {
+                    self.open_batch_optimize();
+                }
+                if ui.button(t!("select-all")).clicked() {
+                    self.select_all();
+                }
+
+                ui.label(t!("number-of-files", count = self.files_list.len()));
+                if let Some(last_checked) = &self.last_checked_dir {
+                    ui.label(t!("last-checked", path = last_checked));
+                };
+                if !self.infinite_scroll {
+                    ui.label(t!(
+                        "current-page",
+                        page = self.browser_context.current_page + 1
+                    ));
+                }
+                let page_len = self.page_filenames().len();
+                if !self.infinite_scroll && loaded_images != page_len {
+                    ui.label(t!(
+                        "loading-images",
+                        loaded = loaded_images,
+                        total = page_len
+                    ));
+                } else {
+                    self.page_load_stats_line(ui);
+                }
+                if cfg!(debug_assertions) {
+                    if let Some(stats) = &self.last_load_stats {
+                        ui.label(
+                            RichText::new(t!(
+                                "thumb-load-stats",
+                                loaded = stats.loaded,
+                                failed = stats.failed,
+                                cached = stats.skipped_cached,
+                                total = stats.total,
+                                millis = stats.duration_ms
+                            ))
+                            .weak(),
+                        );
+                    }
+                }
+            });
+        });
+        ctx.request_repaint_after(Duration::from_micros(100));
+    }
+
+    /// a row for each file the browser's showing, see [csv_export::write_image_list_csv]
+    fn export_image_list_to_csv(&self, dest: &Path) -> Result<usize, std::io::Error> {
+        let file = std::fs::File::create(dest)?;
+        csv_export::write_image_list_csv(&self.files_list, std::io::BufWriter::new(file))
+    }
+
+    /// ask where to save the CSV then write it
+    fn export_csv(&mut self) {
+        let Some(savepath) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("images.csv")
+            .save_file()
+        else {
+            debug!("User cancelled CSV export");
+            return;
+        };
+        match self.export_image_list_to_csv(&savepath) {
+            Ok(rows) => {
+                self.push_toast(t!("exported-csv", count = rows, path = savepath.display()))
+            }
+            Err(err) => self.push_error(t!("error-export-csv", error = err), None),
+        }
+    }
+
+    /// ask the user where to save a mosaic of the current file list, then have the backend make it
+    fn export_mosaic(&mut self) {
+        let Some(savepath) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_file_name("mosaic.png")
+            .save_file()
+        else {
+            debug!("User cancelled mosaic export");
+            return;
+        };
+
+        // decoding the full-size images takes a while, so it's done in the background
+        let (width, height) = *MOSAIC_SIZE;
+        self.mosaic_exporting = true;
+        self.sendmessage(AppMsg::ExportMosaic {
+            savepath,
+            filepaths: self.files_list.clone(),
+            width,
+            height,
+        });
+    }
+
+    /// pop up an error over whatever's showing, `next_state` is where to go once it's dismissed
+    fn push_error(&mut self, message: String, next_state: Option<Box<AppState>>) {
+        self.push_error_with_details(message, None, next_state)
+    }
+
+    /// [MemeTool::push_error], with `details` that can be copied from the error window
+    fn push_error_with_details(
+        &mut self,
+        message: String,
+        details: Option<String>,
+        next_state: Option<Box<AppState>>,
+    ) {
+        match &details {
+            Some(details) => error!("{} ({})", message, details),
+            None => error!("{}", message),
+        }
+        self.pending_errors.push(PendingError {
+            message,
+            details,
+            next_state,
+        });
+    }
+
+    /// close an error window, moving to its next state if it has one
+    fn dismiss_error(&mut self, index: usize) {
+        if index >= self.pending_errors.len() {
+            return;
+        }
+        let PendingError { next_state, .. } = self.pending_errors.remove(index);
+        if let Some(state) = next_state {
+            self.navigation.replace(*state);
+        }
+    }
+
+    /// "Page loaded in 1.8 s..." for the footer, opening up to show the rest
+    fn page_load_stats_line(&self, ui: &mut egui::Ui) {
+        let Some(stats) = self
+            .page_load_stats
+            .as_ref()
+            .filter(|stats| stats.page == self.browser_context.current_page)
+        else {
+            return;
+        };
+        let mut summary = t!(
+            "page-load-summary",
+            seconds = format!("{:.1}", stats.elapsed.as_secs_f32()),
+            size = humansize::format_size(stats.source_bytes, humansize::DECIMAL)
+        );
+        if let Some((filepath, decode_time)) = &stats.slowest {
+            let name = std::path::Path::new(filepath)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| filepath.clone());
+            summary = t!(
+                "page-load-slowest",
+                summary = summary,
+                name = name,
+                millis = decode_time.as_millis()
+            );
+        }
+        egui::CollapsingHeader::new(summary)
+            .id_source("page_load_stats")
+            .show(ui, |ui| {
+                ui.label(t!("page-load-files", count = stats.files));
+                ui.label(t!(
+                    "page-load-pixels",
+                    megapixels = format!("{:.1}", stats.pixels as f64 / 1_000_000.0)
+                ));
+                ui.label(t!(
+                    "page-load-average",
+                    millis = stats.average_decode_time().as_millis()
+                ));
+            });
+    }
+
+    /// ask how to lay out a contact sheet of `filepaths`, there has to be something to put on it
+    fn open_contact_sheet_prompt(&mut self, filepaths: Vec<String>) {
+        if filepaths.is_empty() {
+            self.push_error(t!("error-contact-sheet-empty"), None);
+            return;
+        }
+        self.contact_sheet_files = Some(filepaths);
+    }
+
+    /// the contact sheet's layout knobs, then where to save it
+    fn show_contact_sheet_prompt(&mut self, ctx: &egui::Context) {
+        let Some(filepaths) = self.contact_sheet_files.as_ref() else {
+            return;
+        };
+        let layout = &mut self.contact_sheet_layout;
+        let mut open = true;
+        let mut export = false;
+        let mut cancelled = false;
+        egui::Window::new(t!("contact-sheet-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_pos(ctx.screen_rect().center())
+            .show(ctx, |ui| {
+                ui.label(t!("contact-sheet-images", count = filepaths.len()));
+                Grid::new("contact_sheet_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 10.0])
+                    .show(ui, |ui| {
+                        ui.label(t!("contact-sheet-columns"));
+                        ui.add(egui::Slider::new(&mut layout.columns, 1..=10));
+                        ui.end_row();
+
+                        ui.label(t!("contact-sheet-cell-size"));
+                        ui.add(egui::Slider::new(&mut layout.cell_size, 64..=512).suffix("px"));
+                        ui.end_row();
+
+                        ui.label(t!("contact-sheet-background"));
+                        ui.color_edit_button_srgb(&mut layout.background);
+                        ui.end_row();
+                    });
+                ui.horizontal(|ui| {
+                    if ui.button(t!("export")).clicked() {
+                        export = true;
+                    }
+                    if ui.button(t!("cancel")).clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+        if export {
+            self.export_contact_sheet();
+        } else if cancelled || !open {
+            self.contact_sheet_files = None;
+        }
+    }
+
+    /// ask where to save the contact sheet, then hand it to the backend with whatever thumbnails
+    /// we've already got
+    fn export_contact_sheet(&mut self) {
+        let Some(filepaths) = self.contact_sheet_files.take() else {
+            return;
+        };
+        let Some(savepath) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .add_filter("JPEG", &["jpg", "jpeg"])
+            .set_file_name("contact-sheet.png")
+            .save_file()
+        else {
+            debug!("User cancelled contact sheet export");
+            // leave the dialog up so they can try again
+            self.contact_sheet_files = Some(filepaths);
+            return;
+        };
+
+        let tiles = filepaths
+            .into_iter()
+            .map(|filepath| {
+                let cached = self
+                    .browser_images
+                    .peek(&filepath)
+                    .map(|thumbnail| thumbnail.image.clone());
+                ContactSheetTile { filepath, cached }
+            })
+            .collect::<Vec<_>>();
+        self.contact_sheet_progress = Some((0, tiles.len()));
+        self.sendmessage(AppMsg::ExportContactSheet {
+            savepath,
+            layout: self.contact_sheet_layout.clone(),
+            tiles,
+        });
+    }
+
+    /// open the batch optimize window for the selection, or everything the search is showing if
+    /// nothing's selected
+    fn open_batch_optimize(&mut self) {
+        let selected = self.get_selected_filepaths_sorted();
+        let filtered: Vec<String> = self
+            .ordered_files()
+            .iter()
+            .map(|filepath| filepath.display().to_string())
+            .collect();
+        if selected.is_empty() && filtered.is_empty() {
+            self.push_error(t!("error-batch-optimize-empty"), None);
+            return;
+        }
+        self.batch_optimize = Some(BatchOptimize {
+            use_filtered: selected.is_empty(),
+            selected,
+            filtered,
+            action: OptimizeAction::Optimize,
+            stage: BatchOptimizeStage::Planning,
+        });
+    }
+
+    /// what'll happen to each file, then progress while it's going, then how it went
+    fn show_batch_optimize(&mut self, ctx: &egui::Context) {
+        let Some(batch) = self.batch_optimize.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut start = false;
+        let mut cancel_job = None;
+        let mut close = false;
+        egui::Window::new(t!("batch-optimize-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_pos(ctx.screen_rect().center())
+            .show(ctx, |ui| match &mut batch.stage {
+                BatchOptimizeStage::Planning => {
+                    ui.add_enabled_ui(!batch.selected.is_empty(), |ui| {
+                        ui.radio_value(
+                            &mut batch.use_filtered,
+                            false,
+                            t!("batch-optimize-selected", count = batch.selected.len()),
+                        );
+                    });
+                    ui.radio_value(
+                        &mut batch.use_filtered,
+                        true,
+                        t!("batch-optimize-filtered", count = batch.filtered.len()),
+                    );
+                    ui.separator();
+                    for action in OptimizeAction::ALL {
+                        // resizing's picked by kind, the size is set below
+                        let (chosen, label) = match action {
+                            OptimizeAction::Resize { .. } => (
+                                matches!(batch.action, OptimizeAction::Resize { .. }),
+                                t!("optimize-resize-to"),
+                            ),
+                            _ => (batch.action == action, action.label()),
+                        };
+                        if ui.radio(chosen, label).clicked() && !chosen {
+                            batch.action = action;
+                        }
+                    }
+                    if let OptimizeAction::Resize {
+                        max_width,
+                        max_height,
+                        quality,
+                    } = &mut batch.action
+                    {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(max_width)
+                                    .clamp_range(1..=65535)
+                                    .suffix(" px"),
+                            );
+                            ui.label("×");
+                            ui.add(
+                                egui::DragValue::new(max_height)
+                                    .clamp_range(1..=65535)
+                                    .suffix(" px"),
+                            );
+                            ui.label(t!("jpeg-quality"));
+                            ui.add(egui::DragValue::new(quality).clamp_range(1..=100));
+                        });
+                    }
+                    ui.separator();
+
+                    let action = batch.action;
+                    let filepaths = if batch.use_filtered {
+                        &batch.filtered
+                    } else {
+                        &batch.selected
+                    };
+                    let skipping = filepaths
+                        .iter()
+                        .filter(|filepath| action.skip_reason(Path::new(filepath)).is_some())
+                        .count();
+                    ui.label(t!(
+                        "batch-optimize-plan",
+                        count = filepaths.len() - skipping,
+                        skipped = skipping
+                    ));
+                    ui.label(RichText::new(t!("batch-optimize-plan-hint")).small());
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| {
+                            Grid::new("batch_optimize_plan")
+                                .num_columns(2)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for filepath in filepaths {
+                                        let path = Path::new(filepath);
+                                        ui.label(display_filename(path));
+                                        let plan = match (
+                                            action.skip_reason(path),
+                                            action.destination(path),
+                                        ) {
+                                            (Some(reason), _) => RichText::new(t!(
+                                                "batch-optimize-skip",
+                                                reason = reason
+                                            ))
+                                            .weak(),
+                                            (None, Some(destination)) => RichText::new(format!(
+                                                "→ {}",
+                                                display_filename(&destination)
+                                            )),
+                                            (None, None) => {
+                                                RichText::new(t!("batch-optimize-in-place"))
+                                            }
+                                        };
+                                        ui.label(plan);
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(
+                                !filepaths.is_empty(),
+                                egui::Button::new(t!("batch-optimize-start")),
+                            )
+                            .clicked()
+                        {
+                            start = true;
+                        }
+                        if ui.button(t!("cancel")).clicked() {
+                            close = true;
+                        }
+                    });
+                }
+                BatchOptimizeStage::Running {
+                    job,
+                    done,
+                    total,
+                    current,
+                    cancelling,
+                } => {
+                    ui.label(batch.action.label());
+                    ui.add(
+                        egui::ProgressBar::new(*done as f32 / (*total).max(1) as f32)
+                            .text(format!("{} / {}", done, total)),
+                    );
+                    if let Some(current) = current {
+                        ui.label(display_filename(Path::new(current)));
+                    }
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        if ui
+                            .add_enabled(!*cancelling, egui::Button::new(t!("cancel")))
+                            .clicked()
+                        {
+                            *cancelling = true;
+                            cancel_job = Some(*job);
+                        }
+                        if *cancelling {
+                            ui.label(t!("batch-optimize-cancelling"));
+                        }
+                    });
+                }
+                BatchOptimizeStage::Finished(report) => {
+                    let processed = report.items.len();
+                    ui.label(if report.cancelled {
+                        t!(
+                            "batch-optimize-cancelled",
+                            processed = processed,
+                            total = report.total
+                        )
+                    } else {
+                        t!("batch-optimize-finished", total = report.total)
+                    });
+                    ui.label(t!(
+                        "batch-optimize-saved",
+                        count = report.done(),
+                        size = humansize::format_size(
+                            report.bytes_saved().max(0) as u64,
+                            humansize::DECIMAL
+                        )
+                    ));
+                    let skipped = report.skipped();
+                    if !skipped.is_empty() {
+                        egui::CollapsingHeader::new(t!(
+                            "batch-optimize-skipped",
+                            count = skipped.len()
+                        ))
+                        .id_source("batch_optimize_skipped")
+                        .show(ui, |ui| {
+                            report_list(ui, "batch_optimize_skipped_list", &skipped)
+                        });
+                    }
+                    let failures = report.failures();
+                    if !failures.is_empty() {
+                        egui::CollapsingHeader::new(
+                            RichText::new(t!("batch-optimize-failed", count = failures.len()))
+                                .color(ui.visuals().error_fg_color),
+                        )
+                        .id_source("batch_optimize_failed")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            report_list(ui, "batch_optimize_failed_list", &failures)
+                        });
+                    }
+                    if ui.button(t!("close")).clicked() {
+                        close = true;
+                    }
+                }
+            });
+
+        if start {
+            let job = JobId::next();
+            let filepaths = batch.filepaths().to_vec();
+            batch.stage = BatchOptimizeStage::Running {
+                job,
+                done: 0,
+                total: filepaths.len(),
+                current: None,
+                cancelling: false,
+            };
+            let work = JobWork::Optimize(batch.action);
+            self.sendmessage(AppMsg::StartJob {
+                job,
+                work,
+                filepaths,
+            });
+        } else if close || !open {
+            // closing it while it's going stops it
+            if let BatchOptimizeStage::Running {
+                job,
+                cancelling: false,
+                ..
+            } = batch.stage
+            {
+                cancel_job = Some(job);
+            }
+            self.batch_optimize = None;
+        }
+        if let Some(job) = cancel_job {
+            self.sendmessage(AppMsg::CancelJob(job));
+        }
+    }
+
+    /// show the report, and forget everything we had for the files that changed
+    fn batch_optimize_done(&mut self, ctx: &egui::Context, job: JobId, report: JobReport) {
+        info!(
+            "{} finished, {} of {} done, {} bytes saved",
+            job,
+            report.done(),
+            report.total,
+            report.bytes_saved()
+        );
+        for (filepath, result) in &report.items {
+            if let ItemResult::Done { .. } = result {
+                self.browser_images.remove(filepath);
+                self.color_analyses.remove(filepath);
+                self.integrity.invalidate(filepath);
+            }
+        }
+        self.editor_image_cache = None;
+        self.start_update(ctx);
+        match self.batch_optimize.as_mut() {
+            Some(batch) if matches!(batch.stage, BatchOptimizeStage::Running { job: running, .. } if running == job) =>
+            {
+                batch.stage = BatchOptimizeStage::Finished(report);
+            }
+            // they closed the window, it's all in the log
+            _ => debug!("Nothing's showing {}, not showing its report", job),
+        }
+    }
+
+    /// ask where to copy an S3 object to, showing the new key before doing it
+    fn show_s3_copy_prompt(&mut self, ctx: &egui::Context) {
+        let Some(prompt) = self.s3_copy_prompt.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut copy = None;
+        let mut cancelled = false;
+        egui::Window::new(t!("s3-copy-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_pos(ctx.screen_rect().center())
+            .show(ctx, |ui| {
+                ui.label(t!("s3-copy-from", key = prompt.src_key));
+                ui.horizontal(|ui| {
+                    let prefix_label = ui.label(t!("s3-copy-to-prefix"));
+                    ui.text_edit_singleline(&mut prompt.dest_prefix)
+                        .labelled_by(prefix_label.id);
+                });
+                let dest_key = s3_upload::key_with_prefix(&prompt.src_key, &prompt.dest_prefix);
+                ui.label(t!("s3-copy-new-key", key = dest_key));
+                ui.horizontal(|ui| {
+                    // copying something onto itself doesn't do anything useful
+                    if ui
+                        .add_enabled(dest_key != prompt.src_key, egui::Button::new(t!("copy")))
+                        .clicked()
+                    {
+                        copy = Some((prompt.src_key.clone(), dest_key));
                     }
-                })
-                .collect();
+                    if ui.button(t!("cancel")).clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+        if let Some((src_key, dest_key)) = copy {
+            self.sendmessage(AppMsg::S3CopyObject { src_key, dest_key });
+            self.s3_copy_prompt = None;
+        } else if cancelled || !open {
+            self.s3_copy_prompt = None;
+        }
+    }
+
+    /// ask which object to download from the bucket and edit
+    fn show_s3_edit_prompt(&mut self, ctx: &egui::Context) {
+        let Some(key) = self.s3_edit_prompt.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut download = None;
+        let mut cancelled = false;
+        egui::Window::new(t!("s3-edit-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_pos(ctx.screen_rect().center())
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let key_label = ui.label(t!("s3-edit-key"));
+                    ui.text_edit_singleline(key).labelled_by(key_label.id);
+                });
+                ui.label(t!("s3-edit-hint"));
+                ui.horizontal(|ui| {
+                    let key = key.trim();
+                    if ui
+                        .add_enabled(!key.is_empty(), egui::Button::new(t!("s3-edit-download")))
+                        .clicked()
+                    {
+                        download = Some(key.to_string());
+                    }
+                    if ui.button(t!("cancel")).clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+        if let Some(key) = download {
+            let filepath = s3_upload::temp_path_for_key(&key).display().to_string();
+            self.s3_edits.insert(filepath, key.clone());
+            self.push_toast(t!("s3-edit-downloading", key = key));
+            self.sendmessage(AppMsg::DownloadAndEdit { key });
+            self.s3_edit_prompt = None;
+        } else if cancelled || !open {
+            self.s3_edit_prompt = None;
+        }
+    }
+
+    /// in the editor for something that came from the bucket, offer to put it back or throw the
+    /// download away
+    fn s3_edit_banner(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let Some(key) = self.s3_edits.get(filepath).cloned() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label(t!("s3-edit-from", key = key));
+            if ui.button(t!("s3-edit-upload")).clicked() {
+                self.show_update(filepath, key);
+            }
+            if ui
+                .button(t!("s3-edit-discard"))
+                .on_hover_text(t!("s3-edit-discard-hint"))
+                .clicked()
+            {
+                if let Err(err) = std::fs::remove_file(filepath) {
+                    warn!("Failed to remove {}: {:?}", filepath, err);
+                }
+                self.s3_edits.remove(filepath);
+                self.forget_file(filepath);
+                self.pop_state();
+            }
+        });
+    }
+
+    /// pop up `message` in the corner for [TOAST_DURATION]
+    fn push_toast(&mut self, message: String) {
+        info!("{}", message);
+        self.toast = Some((message, Instant::now() + TOAST_DURATION));
+        self.toast_link = None;
+    }
+
+    /// [MemeTool::push_toast] with a link to show `path` in the browser
+    fn push_toast_link(&mut self, message: String, path: PathBuf) {
+        self.push_toast(message);
+        self.toast_link = Some(path);
+    }
+
+    fn show_toast(&mut self, ctx: &egui::Context) {
+        let Some((message, until)) = &self.toast else {
+            return;
+        };
+        if *until <= Instant::now() {
+            self.toast = None;
+            self.toast_link = None;
+            return;
+        }
+        let until = *until;
+        let mut follow_link = false;
+        egui::Area::new("toast")
+            .anchor(egui::Align2::RIGHT_BOTTOM, vec2(-10.0, -40.0))
+            .interactable(self.toast_link.is_some())
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(message);
+                    if self.toast_link.is_some() && ui.link(t!("show-in-browser")).clicked() {
+                        follow_link = true;
+                    }
+                });
+            });
+        if follow_link {
+            self.toast = None;
+            if let Some(path) = self.toast_link.take() {
+                self.show_in_browser(&path);
+            }
+            return;
+        }
+        ctx.request_repaint_after(until.saturating_duration_since(Instant::now()));
+    }
+
+    /// draw a window for each error that hasn't been dismissed yet
+    fn show_pending_errors(&mut self, ctx: &egui::Context) {
+        let mut dismissed = vec![];
+        for (index, error) in self.pending_errors.iter().enumerate() {
+            let mut open = true;
+            let offset = 20.0 * index as f32;
+            egui::Window::new(t!("error-title"))
+                .id(egui::Id::new("error_window").with(index))
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .default_pos(ctx.screen_rect().center() + vec2(offset, offset))
+                .show(ctx, |ui| {
+                    ui.label(&error.message);
+                    ui.horizontal(|ui| {
+                        if ui.button(t!("continue")).clicked() {
+                            dismissed.push(index);
+                        }
+                        if let Some(details) = &error.details {
+                            if ui
+                                .button(t!("copy-details"))
+                                .on_hover_text(details)
+                                .clicked()
+                            {
+                                ui.output_mut(|output| output.copied_text = details.clone());
+                            }
+                        }
+                    });
+                });
+            if !open {
+                dismissed.push(index);
+            }
+        }
+        // work backwards so the indexes stay valid
+        dismissed.sort();
+        dismissed.dedup();
+        dismissed
+            .into_iter()
+            .rev()
+            .for_each(|index| self.dismiss_error(index));
+    }
+
+    fn set_new_app_state(&mut self, newappstate: AppState) {
+        self.sendmessage(AppMsg::NewAppState(newappstate))
+    }
+
+    fn show_editor(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let ctx = ui.ctx().clone();
+        trace!("Showing editor: {}", filepath);
+
+        if self.editor_rename_target.is_empty() {
+            self.editor_rename_target = filepath.to_string();
+        }
+        ui.scope(|ui| {
+            // "~/memes/cat.jpg" is fine, it's expanded before it's checked
+            let target_path = file_ops::expand_path(&self.editor_rename_target);
+            let target_problem = file_ops::rename_problem(Path::new(filepath), &target_path);
+
+            ui.horizontal(|ui| {
+                let file_label = ui.label(t!("file-path"));
+
+                let filename_editor = ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.editor_rename_target)
+                            .desired_width(ctx.available_rect().width() * 0.7),
+                    ) // 70% of the screen width
+                    .labelled_by(file_label.id);
+
+                self.editor_rename_has_focus = filename_editor.has_focus();
+                // escape drops focus from the text box before the key handler sees it
+                if filename_editor.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    self.cancel_rename_edit(filepath);
+                }
+
+                // if they've changed the filename in the box
+                if filepath != self.editor_rename_target {
+                    // the problem's part of the text box's name, so it's read out with it
+                    if let Some(problem) = target_problem {
+                        let problem = ui.label(problem);
+                        filename_editor.clone().labelled_by(problem.id);
+                    } else {
+                        filename_editor.ctx.input(|i| {
+                            if i.key_pressed(egui::Key::Enter)
+                                && filepath != self.editor_rename_target
+                            {
+                                self.set_new_app_state(AppState::RenameConfirm {
+                                    filepath: filepath.to_string(),
+                                    newfilepath: file_ops::expand_path(&self.editor_rename_target)
+                                        .display()
+                                        .to_string(),
+                                });
+                            }
+                        });
+
+                        // show the rename button
+                        if ui.button(t!("rename")).clicked() {
+                            info!("Clicked rename!");
+                            if filepath != self.editor_rename_target {
+                                self.push_state(AppState::RenameConfirm {
+                                    filepath: filepath.to_string(),
+                                    newfilepath: file_ops::expand_path(&self.editor_rename_target)
+                                        .display()
+                                        .to_string(),
+                                });
+                            }
+                        };
+                    }
+                    if ui.button(t!("cancel")).clicked() {
+                        debug!("Cancelled rename of {}", filepath);
+                        self.cancel_rename_edit(filepath);
+                    }
+                }
+
+                if filename_editor.changed() {
+                    debug!(
+                        "Typed into filename: {} => {}",
+                        filepath, self.editor_rename_target
+                    );
+                }
+            });
+            self.editor_position(ui, filepath);
+            ui.horizontal(|ui| {
+                if ui
+                    .button(RichText::new(t!("back")).text_style(heading3()))
+                    .clicked()
+                {
+                    self.pop_state();
+                };
+                ui.add_space(15.0);
+                if ui
+                    .button(RichText::new(t!("delete-image")).text_style(heading3()))
+                    .clicked()
+                {
+                    self.set_new_app_state(AppState::DeletePrompt(filepath.to_string()));
+                };
+
+                if ui
+                    .button(RichText::new(t!("open-in-new-window")).text_style(heading3()))
+                    .clicked()
+                {
+                    // the main window goes back to the browser, which is the point
+                    self.open_editor_popout(filepath);
+                    self.pop_state();
+                }
+
+                if ui
+                    .button(RichText::new(t!("duplicate")).text_style(heading3()))
+                    .on_hover_text(t!("duplicate-hint"))
+                    .clicked()
+                {
+                    self.copy_image_to_workdir(&ctx, filepath);
+                }
+
+                if ui
+                    .button(RichText::new(t!("upload-to-s3")).text_style(heading3()))
+                    .clicked()
+                {
+                    self.upload_corrupt_confirmed = false;
+                    self.set_new_app_state(AppState::UploadPrompt(filepath.to_string()));
+                }
+
+                // already uploaded, so it can be put over the top of what's there
+                if let Some(record) = self.upload_history.get(filepath) {
+                    let key = record.key.clone();
+                    if ui
+                        .button(RichText::new(t!("update-in-s3")).text_style(heading3()))
+                        .on_hover_text(t!("update-in-s3-hint", key = key))
+                        .clicked()
+                    {
+                        self.show_update(filepath, key);
+                    }
+                }
+            });
+            self.s3_edit_banner(ui, filepath);
+            self.integrity_banner(ui, filepath);
+            self.rotate_controls(ui, filepath);
+            self.date_rename_controls(ui, &ctx, filepath);
+            self.previous_version_controls(ui, &ctx, filepath);
+            ui.horizontal(|ui| {
+                ui.label(t!("original-path"));
+                ui.label(filepath);
+            });
+            self.folder_breadcrumb(ui, filepath);
+            self.format_controls(ui, &ctx, filepath);
+
+            if let Some((texture, _)) = &self.editor_image_cache {
+                ui.image((texture.id(), texture.size_vec2()));
+            } else if let Ok(image) = load_image_to_thumbnail(
+                &PathBuf::from(filepath),
+                Some(Vec2 {
+                    x: ui.available_width() * 0.9,
+                    y: ui.available_height() * 0.8,
+                }),
+            ) {
+                let texture = ctx.load_texture(
+                    format!("editor:{}", filepath),
+                    image,
+                    TextureOptions::default(),
+                );
+                ui.image((texture.id(), texture.size_vec2()));
+                let info = editor_image_info(filepath, &texture);
+                self.editor_image_cache = Some((texture, info));
+            }
+            if let Some((_, info)) = &self.editor_image_cache {
+                self.show_image_info(ui, info);
+            }
+            self.modified_time_controls(ui, &ctx, filepath);
+            self.show_image_issues(ui, filepath);
+            self.show_color_analysis(ui, filepath);
+        });
+    }
+
+    /// ask the backend to look for [ImageIssue]s in `filepath` if we don't know about it already
+    fn request_issue_detection(&mut self, filepath: &str) {
+        let disabled = self
+            .configuration
+            .as_ref()
+            .map(|config| config.disable_issue_detection)
+            .unwrap_or(false);
+        // there's no decoding videos in here, and URLs aren't files
+        if disabled
+            || video::is_video(Path::new(filepath))
+            || !Path::new(filepath).is_file()
+            || self.image_issues.contains(filepath)
+            || self.image_issues_requested.contains(filepath)
+        {
+            return;
+        }
+        self.image_issues_requested.insert(filepath.to_string());
+        self.sendmessage(AppMsg::DetectIssues(filepath.to_string()));
+    }
+
+    /// anything [image_utils::detect_image_issues] found, folded away until it's wanted
+    fn show_image_issues(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        self.request_issue_detection(filepath);
+        let Some(issues) = self
+            .image_issues
+            .peek(filepath)
+            .filter(|issues| !issues.is_empty())
+        else {
+            return;
+        };
+        egui::CollapsingHeader::new(t!("image-issues", count = issues.len()))
+            .id_source("image_issues")
+            .show(ui, |ui| {
+                for issue in issues {
+                    let (symbol, color) = issue_badge(ui, std::slice::from_ref(issue));
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(symbol).color(color));
+                        ui.label(issue.describe());
+                    });
+                }
+            });
+    }
+
+    /// a way back to how `filepath` was before it was last edited, while the backup's still in the
+    /// trash
+    fn previous_version_controls(&mut self, ui: &mut egui::Ui, ctx: &Context, filepath: &str) {
+        let Some(backup) = self.trash.latest_backup(filepath) else {
+            return;
+        };
+        let trash_path = backup.trash_path.clone();
+        let metadata = std::fs::metadata(&trash_path).ok();
+        let size = metadata
+            .as_ref()
+            .map(|metadata| humansize::format_size(metadata.len(), humansize::DECIMAL))
+            .unwrap_or_default();
+        let age = metadata
+            .and_then(|metadata| metadata.modified().ok())
+            .map(dir_stats::format_age)
+            .unwrap_or_else(|| dir_stats::format_age(backup.deleted_time()));
+        let mut restore = false;
+        ui.horizontal(|ui| {
+            restore = ui
+                .add_enabled(
+                    self.image_action_pending.is_none(),
+                    egui::Button::new(t!("restore-previous-version")),
+                )
+                .on_hover_text(t!("restore-previous-version-hint"))
+                .clicked();
+            ui.label(t!("previous-version", size = size, age = age));
+        });
+        if restore {
+            match self.trash.restore_backup(&trash_path) {
+                Ok(_) => {
+                    info!("Put {} back to how it was before it was edited", filepath);
+                    self.save_trash();
+                    self.reload_current_image(ctx, filepath);
+                }
+                Err(err) => self.push_error(t!("error-restore", error = err), None),
+            }
+        }
+    }
+
+    /// what's really in the file and what its extension says, with a way to fix the extension
+    /// when they disagree
+    fn format_controls(&mut self, ui: &mut egui::Ui, ctx: &Context, filepath: &str) {
+        let path = Path::new(filepath);
+        if video::is_video(path) {
+            return;
+        }
+        let Some(sniffed) = self.formats.get(filepath) else {
+            let filepaths = self.formats.start([filepath]);
+            if !filepaths.is_empty() {
+                self.sendmessage(AppMsg::SniffFormats(filepaths));
+            }
+            return;
+        };
+        let extension = path
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_else(|| t!("format-no-extension"));
+        let claimed = SniffedFormat::from_extension(path);
+        let mut fix = None;
+        ui.horizontal(|ui| {
+            ui.label(t!(
+                "file-format",
+                format = sniffed.label(),
+                extension = extension
+            ));
+            if claimed == sniffed {
+                return;
+            }
+            ui.label(
+                RichText::new(t!("format-mismatch", format = claimed.label()))
+                    .color(ui.visuals().warn_fg_color),
+            );
+            if let Some(correct) = sniffed.extension() {
+                if ui
+                    .button(t!("fix-extension"))
+                    .on_hover_text(t!("fix-extension-hint", extension = correct))
+                    .clicked()
+                {
+                    fix = Some(path.with_extension(correct));
+                }
+            }
+        });
+        if let Some(target) = fix {
+            self.do_rename(ctx, filepath, &target.display().to_string());
+        }
+    }
+
+    /// the folders `filepath` is in, clicking its folder shows it in the browser and clicking
+    /// one further up just switches to that folder
+    fn folder_breadcrumb(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        // it could've come from the command line as a relative path
+        let path = std::fs::canonicalize(filepath).unwrap_or_else(|_| PathBuf::from(filepath));
+        let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        else {
+            return;
+        };
+        let mut clicked = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label(t!("in-folder"));
+            let folders: Vec<&Path> = parent.ancestors().collect();
+            for (index, folder) in folders.iter().rev().enumerate() {
+                if index > 0 {
+                    ui.label("›");
+                }
+                let name = folder
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| folder.display().to_string());
+                let hint = if *folder == parent {
+                    t!("show-in-browser-hint")
+                } else {
+                    folder.display().to_string()
+                };
+                if ui.link(name).on_hover_text(hint).clicked() {
+                    clicked = Some(folder.to_path_buf());
+                }
+            }
+        });
+        match clicked {
+            Some(folder) if folder == parent => self.show_in_browser(&path),
+            Some(folder) => self.set_workdir(folder.display().to_string()),
+            None => {}
+        }
+    }
+
+    /// switch the browser to the folder `path` is in, on the page it's on and with it selected
+    fn show_in_browser(&mut self, path: &Path) {
+        let (Some(parent), Some(filename)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+        let workdir = std::fs::canonicalize(shellexpand::tilde(&self.workdir).as_ref()).ok();
+        if workdir.as_deref() != Some(parent) {
+            self.set_workdir(parent.display().to_string());
+        }
+        // the listing's paths start with the workdir however it was typed in
+        let listed = PathBuf::from(shellexpand::tilde(&self.workdir).as_ref()).join(filename);
+        self.update_files_list();
+        if !self.files_list.contains(&listed) {
+            // it's hidden by the search
+            self.search_box = String::new();
+        }
+        if !self.jump_to_file(&listed.display().to_string()) {
+            self.browser_new_page();
+        }
+    }
+
+    /// go to the page `filepath` is on in the browser and select it, false if it's not in the
+    /// browser's list
+    fn jump_to_file(&mut self, filepath: &str) -> bool {
+        self.update_files_list();
+        let files = self.ordered_files();
+        let Some(index) = files
+            .iter()
+            .position(|f| f.display().to_string() == filepath)
+        else {
+            debug!("{} isn't in the browser, not jumping to it", filepath);
+            return false;
+        };
+        self.browser_context
+            .select_file(&files, Path::new(filepath), self.per_page);
+        if self.infinite_scroll {
+            let row = index / self.grid_columns.max(1);
+            self.pending_scroll_offset = Some(row as f32 * self.infinite_row_stride());
+        }
+        self.browser_new_page();
+        true
+    }
+
+    /// "Set modified date…", for putting files that have been saved again back where they were
+    /// in date order
+    fn modified_time_controls(&mut self, ui: &mut egui::Ui, ctx: &Context, filepath: &str) {
+        let Some((_, info)) = &self.editor_image_cache else {
+            return;
+        };
+        let (modified, taken) = (info.modified, info.taken);
+        // it's for a different file if they've moved on without closing it
+        if self
+            .modified_edit
+            .as_ref()
+            .map(|edit| edit.filepath != filepath)
+            .unwrap_or(false)
+        {
+            self.modified_edit = None;
+        }
+        if self.modified_edit.is_none() {
+            if ui.button(t!("set-modified")).clicked() {
+                self.modified_edit = Some(ModifiedEdit {
+                    filepath: filepath.to_string(),
+                    time: file_ops::LocalTimeEdit::from_time(
+                        modified.unwrap_or_else(SystemTime::now),
+                    ),
+                });
+            }
+            return;
+        }
+        let Some(edit) = self.modified_edit.as_mut() else {
+            return;
+        };
+        let mut apply = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            ui.add(
+                egui_extras::DatePickerButton::new(&mut edit.time.date).id_source("set_modified"),
+            );
+            ui.add(egui::DragValue::new(&mut edit.time.hour).clamp_range(0..=23));
+            ui.label(":");
+            ui.add(egui::DragValue::new(&mut edit.time.minute).clamp_range(0..=59));
+            ui.label(":");
+            ui.add(egui::DragValue::new(&mut edit.time.second).clamp_range(0..=59));
+            if let Some(from_exif) =
+                taken.and_then(|taken| file_ops::LocalTimeEdit::from_exif(&taken))
+            {
+                if ui
+                    .button(t!("set-modified-taken"))
+                    .on_hover_text(t!("set-modified-taken-hint"))
+                    .clicked()
+                {
+                    edit.time = from_exif;
+                }
+            }
+            apply = ui.button(t!("apply")).clicked();
+            cancel = ui.button(t!("cancel")).clicked();
+        });
+        if cancel {
+            self.modified_edit = None;
+        } else if apply {
+            let result = match edit.time.to_time() {
+                Some(time) => {
+                    file_ops::set_modified(Path::new(filepath), time).map_err(|err| err.to_string())
+                }
+                None => Err(t!("error-set-modified-invalid")),
+            };
+            match result {
+                Ok(()) => {
+                    info!("Set the modified time of {}", filepath);
+                    self.modified_edit = None;
+                    self.reload_current_image(ctx, filepath);
+                }
+                Err(err) => {
+                    self.push_error(t!("error-set-modified", path = filepath, error = err), None)
+                }
+            }
+        }
+    }
+
+    /// rename the file after when it was taken, going by its EXIF data
+    fn date_rename_controls(&mut self, ui: &mut egui::Ui, ctx: &Context, filepath: &str) {
+        let Some((_, info)) = &self.editor_image_cache else {
+            return;
+        };
+        let path = Path::new(filepath);
+        let target = info
+            .taken
+            .map(|taken| image_utils::date_rename_path(path, &taken));
+        let renameable = matches!(&target, Some(Some(target)) if target != path);
+        ui.horizontal(|ui| {
+            let apply = ui
+                .add_enabled(renameable, egui::Button::new(t!("rename-to-date")))
+                .on_hover_text(t!("rename-to-date-hint"));
+            match &target {
+                Some(Some(target)) if target == path => {
+                    ui.label(t!("rename-to-date-already"));
+                }
+                Some(Some(target)) => {
+                    let filename = target
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    ui.label(t!("rename-to-date-preview", filename = filename));
+                }
+                Some(None) => {
+                    ui.label(t!("error-no-free-name", path = filepath));
+                }
+                None => {
+                    ui.label(t!("rename-to-date-no-exif"));
+                }
+            }
+            if apply.clicked() {
+                self.rename_with_exif_date(ctx, filepath);
+            }
+        });
+    }
+
+    /// rename the image in the editor after when it was taken, going by its EXIF data
+    pub fn rename_with_exif_date(&mut self, ctx: &Context, filepath: &str) {
+        let Some(taken) = self
+            .editor_image_cache
+            .as_ref()
+            .and_then(|(_, info)| info.taken)
+        else {
+            self.push_error(t!("rename-to-date-no-exif"), None);
+            return;
+        };
+        let path = Path::new(filepath);
+        match image_utils::date_rename_path(path, &taken) {
+            Some(target) if target == path => {
+                debug!("{} is already named after when it was taken", filepath);
+            }
+            Some(target) => {
+                let newfilename = target.display().to_string();
+                self.do_rename(ctx, filepath, &newfilename);
+            }
+            None => self.push_error(t!("error-no-free-name", path = filepath), None),
+        }
+    }
+
+    /// the editor's rotate buttons, JPEGs get lossless ones as well, HEIFs get converted and videos
+    /// get handed to something that can play them
+    fn rotate_controls(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let busy = self.image_action_pending.is_some();
+        ui.horizontal(|ui| {
+            let mut action = None;
+            let mut open_error = None;
+            ui.add_enabled_ui(!busy, |ui| {
+                // there's no playback or editing of videos in here
+                if video::is_video(std::path::Path::new(filepath)) {
+                    if ui
+                        .button(t!("open-externally"))
+                        .on_hover_text(t!("open-externally-hint"))
+                        .clicked()
+                    {
+                        open_error = video::open_externally(std::path::Path::new(filepath)).err();
+                    }
+                    return;
+                }
+                // nothing can write HEIF, so the way to change one is to make a JPEG of it
+                if image_utils::is_heif(std::path::Path::new(filepath)) {
+                    if ui
+                        .button(t!("convert-to-jpeg"))
+                        .on_hover_text(t!("convert-to-jpeg-hint"))
+                        .clicked()
+                    {
+                        action = Some(ImageAction::ConvertToJpeg);
+                    }
+                    return;
+                }
+                ui.label(t!("rotate"));
+                let lossy_hint = t!("rotate-lossy-hint");
+                if ui
+                    .button(t!("rotate-left"))
+                    .on_hover_text(&lossy_hint)
+                    .clicked()
+                {
+                    action = Some(ImageAction::Rotate { degrees: 270 });
+                }
+                if ui
+                    .button(t!("rotate-right"))
+                    .on_hover_text(&lossy_hint)
+                    .clicked()
+                {
+                    action = Some(ImageAction::Rotate { degrees: 90 });
+                }
+                if image_utils::is_jpeg(std::path::Path::new(filepath)) {
+                    ui.add_space(15.0);
+                    let lossless_hint = t!("rotate-lossless-hint");
+                    if ui
+                        .button(t!("rotate-lossless-left"))
+                        .on_hover_text(&lossless_hint)
+                        .clicked()
+                    {
+                        action = Some(ImageAction::LosslessRotate { degrees: 270 });
+                    }
+                    if ui
+                        .button(t!("rotate-lossless-right"))
+                        .on_hover_text(&lossless_hint)
+                        .clicked()
+                    {
+                        action = Some(ImageAction::LosslessRotate { degrees: 90 });
+                    }
+                    ui.label(RichText::new(t!("rotate-lossless-note")).small());
+                }
+                ui.add_space(15.0);
+                if ui
+                    .selectable_label(self.border_prompt.is_some(), t!("border"))
+                    .on_hover_text(t!("border-hint"))
+                    .clicked()
+                {
+                    self.border_prompt = match self.border_prompt {
+                        Some(_) => None,
+                        None => Some(BorderPrompt::default()),
+                    };
+                }
+                if ui
+                    .selectable_label(self.sharpen_prompt.is_some(), t!("sharpen"))
+                    .on_hover_text(t!("sharpen-hint"))
+                    .clicked()
+                {
+                    // put back the original image, they'd fight over the preview otherwise
+                    self.editor_image_cache = None;
+                    self.sepia_prompt = None;
+                    self.sharpen_prompt = match self.sharpen_prompt {
+                        Some(_) => None,
+                        None => Some(SharpenPrompt::default()),
+                    };
+                }
+                if ui
+                    .selectable_label(self.sepia_prompt.is_some(), t!("sepia"))
+                    .on_hover_text(t!("sepia-hint"))
+                    .clicked()
+                {
+                    self.editor_image_cache = None;
+                    self.sharpen_prompt = None;
+                    self.sepia_prompt = match self.sepia_prompt {
+                        Some(_) => None,
+                        None => Some(SepiaPrompt::default()),
+                    };
+                }
+            });
+            if busy {
+                ui.spinner();
+            }
+            if let Some(error) = open_error {
+                self.push_error(
+                    t!("error-open-externally", path = filepath, error = error),
+                    None,
+                );
+            }
+            if let Some(action) = action {
+                self.apply_image_action(filepath, action);
+            }
+        });
+        self.border_controls(ui, filepath);
+        self.sharpen_controls(ui, filepath);
+        self.sepia_controls(ui, filepath);
+    }
+
+    /// how wide and what colour the border's going to be, once "Border"'s been clicked
+    fn border_controls(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let busy = self.image_action_pending.is_some();
+        let Some(prompt) = self.border_prompt.as_mut() else {
+            return;
+        };
+        let mut apply = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            ui.label(t!("border-width"));
+            ui.add(
+                egui::DragValue::new(&mut prompt.pixels)
+                    .clamp_range(1..=50)
+                    .suffix("px"),
+            );
+            ui.label(t!("border-colour"));
+            egui::color_picker::color_edit_button_rgb(ui, &mut prompt.color);
+            apply = ui
+                .add_enabled(!busy, egui::Button::new(t!("apply")))
+                .clicked();
+            cancel = ui.button(t!("cancel")).clicked();
+        });
+        if apply {
+            let action = ImageAction::AddBorder {
+                pixels: prompt.pixels,
+                color: prompt.color.map(|channel| (channel * 255.0).round() as u8),
+            };
+            self.border_prompt = None;
+            self.apply_image_action(filepath, action);
+        } else if cancel {
+            self.border_prompt = None;
         }
     }
 
-    /// build a threaded promisey thing to update images in the backend.
-    fn start_update(&mut self, ctx: &egui::Context) {
-        // TODO: maybe set an upper bound on the cache?
-        self.update_files_list();
+    /// how much to sharpen by once "Sharpen"'s been clicked, the editor's image is swapped for a
+    /// sharpened one whenever they're changed
+    fn sharpen_controls(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let busy = self.image_action_pending.is_some();
+        let Some(prompt) = self.sharpen_prompt.as_mut() else {
+            return;
+        };
+        let mut apply = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            ui.label(t!("sharpen-radius"));
+            ui.add(egui::Slider::new(&mut prompt.sigma, 0.1..=10.0).suffix("px"));
+            ui.label(t!("sharpen-amount"));
+            ui.add(egui::Slider::new(&mut prompt.amount, 0.0..=3.0));
+            ui.label(t!("sharpen-threshold"))
+                .on_hover_text(t!("sharpen-threshold-hint"));
+            ui.add(egui::Slider::new(&mut prompt.threshold, 0..=64));
+            apply = ui
+                .add_enabled(!busy, egui::Button::new(t!("apply")))
+                .clicked();
+            cancel = ui.button(t!("cancel")).clicked();
+        });
+        if apply {
+            let action = ImageAction::Sharpen {
+                sigma: prompt.sigma,
+                amount: prompt.amount,
+                threshold: prompt.threshold,
+            };
+            self.sharpen_prompt = None;
+            self.apply_image_action(filepath, action);
+            return;
+        } else if cancel {
+            self.sharpen_prompt = None;
+            self.editor_image_cache = None;
+            return;
+        }
 
-        debug!("Starting update in thread...");
+        // it's the first frame of a new file, the preview can wait for the next one
+        let Some((texture, info)) = self.editor_image_cache.as_mut() else {
+            return;
+        };
+        let settings = (texture.id(), prompt.sigma, prompt.amount, prompt.threshold);
+        if prompt.previewed == Some(settings) {
+            return;
+        }
+        if prompt.unsharpened.as_ref().map(|(path, _)| path.as_str()) != Some(filepath) {
+            let Some(image) = editor_shown_image(filepath, texture) else {
+                return;
+            };
+            prompt.unsharpened = Some((filepath.to_string(), image));
+        }
+        let Some((_, unsharpened)) = &prompt.unsharpened else {
+            return;
+        };
+        let mut preview = unsharpened.clone();
+        // it's been shrunk to fit, so the blur has to be as well to look the same
+        let scale = (preview.width() as f32 / info.width.max(1) as f32).min(1.0);
+        image_utils::apply_unsharp_mask(
+            &mut preview,
+            prompt.sigma * scale,
+            prompt.amount,
+            prompt.threshold,
+        );
+        set_editor_preview(texture, &preview);
+        prompt.previewed = Some(settings);
+    }
+
+    /// "Apply" and "Revert preview" once "Sepia"'s been clicked, the editor shows it tinted until
+    /// one of them is
+    fn sepia_controls(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let busy = self.image_action_pending.is_some();
+        let Some(prompt) = self.sepia_prompt.as_mut() else {
+            return;
+        };
+        let mut apply = false;
+        let mut revert = false;
+        ui.horizontal(|ui| {
+            apply = ui
+                .add_enabled(!busy, egui::Button::new(t!("apply")))
+                .clicked();
+            revert = ui.button(t!("revert-preview")).clicked();
+        });
+        if apply {
+            self.sepia_prompt = None;
+            self.apply_image_action(filepath, ImageAction::Sepia);
+            return;
+        } else if revert {
+            self.sepia_prompt = None;
+            self.editor_image_cache = None;
+            return;
+        }
+
+        // it's the first frame of a new file, the preview can wait for the next one
+        let Some((texture, _)) = self.editor_image_cache.as_mut() else {
+            return;
+        };
+        if prompt.previewed == Some(texture.id()) {
+            return;
+        }
+        if prompt.original.as_ref().map(|(path, _)| path.as_str()) != Some(filepath) {
+            let Some(image) = editor_shown_image(filepath, texture) else {
+                return;
+            };
+            prompt.original = Some((filepath.to_string(), image));
+        }
+        let Some((_, original)) = &prompt.original else {
+            return;
+        };
+        set_editor_preview(texture, &image_utils::apply_sepia(original));
+        prompt.previewed = Some(texture.id());
+    }
+
+    /// `filepath`'s different on disk now, so everything we had for it is out of date
+    fn reload_current_image(&mut self, ctx: &Context, filepath: &str) {
+        self.editor_image_cache = None;
+        if let Some(popout) = self.editor_popout.as_mut() {
+            if popout.filepath == filepath {
+                popout.image_cache = None;
+            }
+        }
+        self.browser_images.remove(filepath);
+        self.color_analyses.remove(filepath);
+        self.integrity.invalidate(filepath);
+        self.formats.invalidate(filepath);
+        self.image_issues.remove(filepath);
+        self.dimensions_cache.remove(filepath);
+        self.start_update(ctx);
+    }
+
+    /// ask the backend to do `action` to `filepath`, the editor's controls wait until it's done.
+    /// Anything that saves over the file keeps a backup in the trash first.
+    fn apply_image_action(&mut self, filepath: &str, action: ImageAction) {
+        if action.overwrites() {
+            match self.trash.keep_backup(filepath, &trash_dir()) {
+                Ok(entry) => {
+                    debug!("Backed up {} to {}", filepath, entry.trash_path);
+                    self.save_trash();
+                }
+                Err(err) => {
+                    // it's not getting changed without a way back
+                    self.push_error(t!("error-backup", path = filepath, error = err), None);
+                    return;
+                }
+            }
+        }
+        self.image_action_pending = Some(filepath.to_string());
+        self.sendmessage(AppMsg::ApplyImageAction {
+            filepath: filepath.to_string(),
+            action,
+        });
+    }
+
+    /// dominant colours and a histogram, only worked out once the section's opened
+    fn show_color_analysis(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let disabled = self
+            .configuration
+            .as_ref()
+            .map(|config| config.disable_color_analysis)
+            .unwrap_or(false);
+        if disabled {
+            return;
+        }
+        egui::CollapsingHeader::new(t!("colours"))
+            .id_source("editor_colours")
+            .show(ui, |ui| {
+                let Some(analysis) = self.color_analyses.get(filepath) else {
+                    if self.color_analysis_requested.insert(filepath.to_string()) {
+                        self.sendmessage(AppMsg::AnalyzeColors(filepath.to_string()));
+                    }
+                    ui.spinner();
+                    return;
+                };
+                let analysis = match analysis {
+                    Ok(analysis) => analysis,
+                    Err(err) => {
+                        ui.label(t!("colours-failed", error = err));
+                        return;
+                    }
+                };
+                ui.horizontal(|ui| {
+                    for (color, share) in analysis.dominant.iter() {
+                        let hex = color_analysis::hex_code(*color);
+                        ui.vertical(|ui| {
+                            let (rect, swatch) =
+                                ui.allocate_exact_size(vec2(48.0, 32.0), egui::Sense::click());
+                            ui.painter().rect_filled(rect, 2.0, *color);
+                            if swatch
+                                .on_hover_text(t!(
+                                    "colour-share",
+                                    percent = format!("{:.0}", share * 100.0)
+                                ))
+                                .clicked()
+                            {
+                                ui.output_mut(|output| output.copied_text = hex.clone());
+                            }
+                            ui.label(RichText::new(hex).small().monospace());
+                        });
+                    }
+                });
+                ui.label(RichText::new(t!("colour-copy-hint")).small());
 
-        let current_page = self.current_page;
+                // one row of bars per channel
+                let channel_colors = [
+                    egui::Color32::from_rgb(220, 60, 60),
+                    egui::Color32::from_rgb(60, 180, 60),
+                    egui::Color32::from_rgb(60, 100, 220),
+                ];
+                for (bins, channel_color) in analysis.histogram.iter().zip(channel_colors) {
+                    let (rect, _) = ui.allocate_exact_size(vec2(240.0, 24.0), egui::Sense::hover());
+                    let tallest = bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+                    let bar_width = rect.width() / bins.len() as f32;
+                    for (index, count) in bins.iter().enumerate() {
+                        let height = rect.height() * (*count as f32 / tallest);
+                        let bar = Rect::from_min_max(
+                            Pos2::new(
+                                rect.left() + bar_width * index as f32,
+                                rect.bottom() - height,
+                            ),
+                            Pos2::new(rect.left() + bar_width * (index + 1) as f32, rect.bottom()),
+                        );
+                        ui.painter().rect_filled(bar, 0.0, channel_color);
+                    }
+                }
+            });
+    }
 
-        self.get_page().into_iter().for_each(|filepath| {
-            debug!("Sending message for: {}", filepath.display());
-            self.sendmessage(AppMsg::LoadImage(ThumbImageMsg {
-                filepath: filepath.display().to_string(),
-                page: current_page,
-                image: None,
-            }));
+    /// the editor's metadata block, with a warning if it's too big for the web
+    fn show_image_info(&self, ui: &mut egui::Ui, info: &ImageInfo) {
+        let (ratio_x, ratio_y) = info.aspect_ratio();
+        let mut rows = vec![
+            (
+                t!("info-image-size"),
+                format!("{}x{}", info.width, info.height),
+            ),
+            (t!("aspect-ratio"), format!("{ratio_x}:{ratio_y}")),
+            (t!("megapixels"), format!("{:.2} MP", info.megapixels())),
+            (
+                t!("info-file-size"),
+                match info.file_size {
+                    Some(size) => humansize::format_size(size, humansize::DECIMAL),
+                    None => t!("unknown-size"),
+                },
+            ),
+        ];
+        if let Some(format) = info.format {
+            rows.push((t!("file-type"), format.label()));
+            rows.push((
+                t!("content-type"),
+                format
+                    .mime_type()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| t!("unknown")),
+            ));
+        }
+        if let Some(created) = info.created {
+            rows.push((t!("file-created"), dir_stats::format_timestamp(created)));
+        }
+        if let Some(modified) = info.modified {
+            rows.push((t!("file-modified"), dir_stats::format_timestamp(modified)));
+        }
+        if let Some(duration) = info.duration {
+            rows.push((t!("video-duration"), video::format_duration(duration)));
+        }
+        if let Some(bits_per_pixel) = info.bits_per_pixel() {
+            rows.push((t!("bits-per-pixel"), format!("{:.2}", bits_per_pixel)));
+        }
+        // a term and its description on each row
+        Grid::new("image_info").num_columns(2).show(ui, |ui| {
+            for (term, description) in rows {
+                ui.label(RichText::new(term).weak());
+                ui.label(description);
+                ui.end_row();
+            }
         });
-        ctx.request_repaint_after(Duration::from_millis(100));
+
+        let (max_dimension, max_bytes) = match &self.configuration {
+            Some(config) => (config.web_max_dimension(), config.web_max_bytes()),
+            None => {
+                let config = Configuration::default();
+                (config.web_max_dimension(), config.web_max_bytes())
+            }
+        };
+        let reasons = info.oversized_reasons(max_dimension, max_bytes);
+        if !reasons.is_empty() {
+            ui.label(
+                RichText::new(t!("not-web-friendly", reasons = reasons.join(", ")))
+                    .small()
+                    .color(ui.visuals().warn_fg_color),
+            );
+        }
     }
 
-    fn check_needs_update(&mut self, ctx: &egui::Context) {
-        if let Some(last_box) = self.search_box_last.clone() {
-            if last_box != self.search_box {
-                debug!("Search box changed to '{}', updating.", self.search_box);
-                self.start_update(ctx);
+    /// warn before uploading something that's too big for the web, like the editor does
+    fn upload_size_warning(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let Some((width, height)) = self.image_dimensions(filepath) else {
+            return;
+        };
+        let (max_dimension, max_bytes) = match &self.configuration {
+            Some(config) => (config.web_max_dimension(), config.web_max_bytes()),
+            None => {
+                let config = Configuration::default();
+                (config.web_max_dimension(), config.web_max_bytes())
             }
-        } else if self.search_box_last.is_none() {
-            debug!("Search box changed to '{}', updating.", self.search_box);
-            self.start_update(ctx);
-        } else {
-            match (&self.last_checked_dir, &self.last_checked_page) {
-                (Some(dir), Some(page)) => {
-                    if dir != &self.workdir || page != &self.current_page {
-                        self.start_update(ctx)
-                    } else {
-                        trace!("no update needed {} == {}", dir, self.workdir);
+        };
+        let file_size = std::fs::metadata(filepath)
+            .ok()
+            .map(|metadata| metadata.len());
+        let reasons = ImageInfo::new(width, height, 0)
+            .with_file_size(file_size)
+            .oversized_reasons(max_dimension, max_bytes);
+        if !reasons.is_empty() {
+            ui.label(
+                RichText::new(t!("not-web-friendly", reasons = reasons.join(", ")))
+                    .color(ui.visuals().warn_fg_color),
+            );
+        }
+    }
+
+    /// warn about `filepath` if it doesn't decode, asking for a check if we don't know yet
+    fn integrity_banner(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        self.request_integrity_check(filepath);
+        let Some(error) = self.integrity.corruption(filepath).map(str::to_owned) else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(t!("file-corrupt", error = error)).color(ui.visuals().warn_fg_color),
+            );
+            if ui.button(t!("recheck")).clicked() {
+                self.recheck_integrity(filepath);
+            }
+        });
+    }
+
+    /// ask the backend to decode `filepath`, unless we already know or have asked
+    fn request_integrity_check(&mut self, filepath: &str) {
+        if self.integrity.needs_check(filepath) {
+            self.integrity.start(filepath);
+            self.sendmessage(AppMsg::CheckIntegrity(filepath.to_string()));
+        }
+    }
+
+    /// forget the verdict for `filepath` and check it again
+    fn recheck_integrity(&mut self, filepath: &str) {
+        self.integrity.invalidate(filepath);
+        self.request_integrity_check(filepath);
+    }
+
+    /// throw away whatever's been typed in the editor's filename box, staying in the editor
+    fn cancel_rename_edit(&mut self, filepath: &str) {
+        self.editor_rename_target = filepath.to_string();
+        self.editor_rename_has_focus = false;
+    }
+
+    fn show_rename_confirm(&mut self, ui: &mut egui::Ui, filepath: &str, newfilename: &str) {
+        let ctx = ui.ctx().clone();
+        ui.scope(|ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(t!("confirm-rename"));
+            });
+            ui.horizontal(|ui| {
+                ui.add_space(2.0);
+                ui.label(filepath);
+            });
+            ui.horizontal(|ui| {
+                ui.add_space(2.0);
+                ui.label(newfilename);
+            });
+            if file_ops::is_case_only_rename(Path::new(filepath), Path::new(newfilename)) {
+                ui.horizontal(|ui| {
+                    ui.add_space(2.0);
+                    ui.label(t!("rename-case-only"));
+                });
+            }
+            let moving = file_ops::moved_between(Path::new(filepath), Path::new(newfilename));
+            if let Some((from_dir, to_dir)) = &moving {
+                self.rename_move_warning(ui, from_dir, to_dir, newfilename);
+            }
+            let confirmed =
+                moving.is_none() || self.rename_move_confirmed.as_deref() == Some(newfilename);
+            if let Some((src_key, dest_key)) = self.s3_rename_keys(filepath, newfilename) {
+                ui.horizontal(|ui| {
+                    ui.add_space(2.0);
+                    ui.checkbox(&mut self.rename_in_s3, t!("rename-in-s3"))
+                        .on_hover_text(t!("rename-in-s3-hint", from = src_key, to = dest_key));
+                });
+            }
+            if let Some(moving) = &self.rename_move {
+                ui.label(t!("rename-copying"));
+                ui.add(
+                    egui::ProgressBar::new(moving.done as f32 / moving.total.max(1) as f32).text(
+                        format!(
+                            "{} / {}",
+                            humansize::format_size(moving.done, humansize::DECIMAL),
+                            humansize::format_size(moving.total, humansize::DECIMAL)
+                        ),
+                    ),
+                );
+                ctx.request_repaint_after(Duration::from_millis(100));
+                return;
+            }
+            ui.horizontal(|ui| {
+                let confirm = ui.add_enabled(
+                    confirmed,
+                    egui::Button::new(
+                        RichText::new(t!("confirm")).text_style(egui::TextStyle::Heading),
+                    ),
+                );
+
+                let cancel =
+                    ui.button(RichText::new(t!("cancel")).text_style(egui::TextStyle::Heading));
+                focus_if_unfocused(&cancel);
+
+                if confirm.clicked() {
+                    // rename the file
+                    self.do_rename(&ctx, filepath, newfilename);
+                }
+
+                if cancel.clicked() {
+                    self.pop_state();
+                }
+            });
+        });
+    }
+    /// spell out that a rename's moving the file to another directory, with the directories that
+    /// differ picked out, and a box to tick to say that's what they meant
+    fn rename_move_warning(
+        &mut self,
+        ui: &mut egui::Ui,
+        from_dir: &Path,
+        to_dir: &Path,
+        newfilename: &str,
+    ) {
+        ui.add_space(10.0);
+        ui.label(
+            RichText::new(t!(
+                "rename-moves",
+                from = file_ops::home_relative(from_dir),
+                to = file_ops::home_relative(to_dir)
+            ))
+            .strong()
+            .color(egui::Color32::YELLOW),
+        );
+        let (common, from_rest, to_rest) = file_ops::split_common_dirs(from_dir, to_dir);
+        let common = match common.as_os_str().is_empty() {
+            true => String::new(),
+            false => format!(
+                "{}{}",
+                file_ops::home_relative(&common),
+                std::path::MAIN_SEPARATOR
+            ),
+        };
+        for (label, rest, color) in [
+            (t!("rename-move-from"), from_rest, egui::Color32::LIGHT_RED),
+            (t!("rename-move-to"), to_rest, egui::Color32::LIGHT_GREEN),
+        ] {
+            ui.horizontal(|ui| {
+                ui.add_space(2.0);
+                ui.spacing_mut().item_spacing.x = 0.0;
+                ui.label(format!("{label} "));
+                ui.label(RichText::new(&common).monospace());
+                ui.label(
+                    RichText::new(rest.display().to_string())
+                        .monospace()
+                        .strong()
+                        .color(color),
+                );
+            });
+        }
+        let mut confirmed = self.rename_move_confirmed.as_deref() == Some(newfilename);
+        ui.horizontal(|ui| {
+            ui.add_space(2.0);
+            if ui
+                .checkbox(&mut confirmed, t!("rename-move-confirm"))
+                .changed()
+            {
+                self.rename_move_confirmed = confirmed.then(|| newfilename.to_string());
+            }
+        });
+        ui.add_space(10.0);
+    }
+
+    fn show_delete_prompt(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let ctx = ui.ctx().clone();
+        ui.scope(|ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(t!("confirm-delete"));
+            });
+            ui.horizontal(|ui| {
+                ui.add_space(2.0);
+                ui.label(filepath);
+            });
+
+            ui.horizontal(|ui| {
+                let confirm = ui.button(t!("confirm"));
+
+                let cancel = ui.button(t!("cancel"));
+                focus_if_unfocused(&cancel);
+
+                if confirm.clicked() {
+                    let from_editor = matches!(
+                        self.navigation.previous(),
+                        Some(AppState::Editor { filepath: current }) if current == filepath
+                    );
+                    let next = self.neighbour_in_list(filepath);
+                    match self.delete_file(&ctx, filepath) {
+                        Ok(_) if from_editor => self.auto_advance_after_delete(next),
+                        Ok(_) => self.pop_state(),
+                        Err(err) => {
+                            self.push_error(
+                                t!("error-delete", error = err),
+                                Some(Box::new(AppState::Editor {
+                                    filepath: filepath.to_string(),
+                                })),
+                            );
+                        }
                     }
                 }
-                (None, None) => {
-                    debug!("last_checked is None, starting update");
-                    self.start_update(ctx);
+
+                if cancel.clicked() {
+                    self.pop_state();
                 }
-                _ => {}
+            });
+        });
+    }
+    /// move `filepath` to the trash and forget it everywhere it's remembered
+    fn delete_file(&mut self, ctx: &Context, filepath: &str) -> Result<(), String> {
+        self.trash_file(filepath)?;
+        let files = self.ordered_files();
+        let per_page = self.per_page;
+        self.browser_context
+            .select_after_removal(&files, filepath, per_page);
+        self.browser_images.remove(filepath);
+        // the browser image list will be wrong at this point, so tell it to cache
+        self.start_update(ctx);
+        self.forget_file(filepath);
+        Ok(())
+    }
+
+    /// if the editor should move on to the next file after moving or uploading one
+    fn advance_after_action(&self) -> bool {
+        self.configuration
+            .as_ref()
+            .map(|config| config.advance_after_action)
+            .unwrap_or(false)
+    }
+
+    /// the file in the editor's just been deleted, so open `next` in its place, or go back to the
+    /// browser if that's turned off or there's nothing left
+    fn auto_advance_after_delete(&mut self, next: Option<String>) {
+        let advance = self
+            .configuration
+            .as_ref()
+            .map(|config| config.auto_advance_on_delete)
+            .unwrap_or(true);
+        if advance {
+            self.advance_to(next);
+        } else {
+            self.pop_state();
+        }
+    }
+
+    /// the next file after `filepath` in the browser's list that's still there. This needs
+    /// working out before the files list catches up with `filepath` going away.
+    fn next_in_list(&self, filepath: &str) -> Option<String> {
+        browser_context::next_remaining(&self.ordered_files(), filepath, |path| path.is_file())
+            .map(|next| next.display().to_string())
+    }
+
+    /// [MemeTool::next_in_list], or the one before `filepath` if it's the last
+    fn neighbour_in_list(&self, filepath: &str) -> Option<String> {
+        browser_context::neighbour_remaining(&self.ordered_files(), filepath, |path| path.is_file())
+            .map(|next| next.display().to_string())
+    }
+
+    /// "file 3 of 500" in the editor, with buttons to step through the browser's list
+    fn editor_position(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let (position, count) = self.list_position(filepath);
+        let mut step_to = None;
+        ui.horizontal(|ui| {
+            let Some(position) = position else {
+                ui.label(RichText::new(t!("not-in-current-view")).weak());
+                if self.is_filtered() && ui.button(t!("clear-filters")).clicked() {
+                    self.clear_filters(filepath);
+                }
+                return;
             };
-        };
-        self.search_box_last = Some(self.search_box.clone());
-        self.last_checked_dir = Some(self.workdir.clone());
-        self.last_checked_page = Some(self.current_page);
+            if ui
+                .add_enabled(position > 0, egui::Button::new("◀"))
+                .on_hover_text(t!("previous-file"))
+                .clicked()
+            {
+                step_to = Some(position - 1);
+            }
+            let position = position + 1;
+            ui.label(if self.is_filtered() {
+                t!(
+                    "editor-position-filtered",
+                    position = position,
+                    count = count
+                )
+            } else {
+                t!("editor-position", position = position, count = count)
+            });
+            if ui
+                .add_enabled(position < count, egui::Button::new("▶"))
+                .on_hover_text(t!("next-file"))
+                .clicked()
+            {
+                step_to = Some(position);
+            }
+        });
+        if let Some(index) = step_to {
+            let next = self
+                .ordered_files()
+                .get(index)
+                .map(|path| path.display().to_string());
+            if next.is_some() {
+                self.advance_to(next);
+            }
+        }
     }
 
-    fn show_browser(&mut self, ctx: egui::Context) {
-        // println!("starting show_browser repaint");
-        egui::CentralPanel::default().show(&ctx, |ui| {
-            self.check_needs_update(&ctx);
+    /// open `next` in the editor in place of the file that was there, or go back to the browser
+    /// if there's nothing left
+    fn advance_to(&mut self, next: Option<String>) {
+        match next {
+            Some(filepath) => {
+                debug!("Advancing to {}", filepath);
+                self.editor_rename_target = String::new();
+                self.editor_image_cache = None;
+                let files = self.ordered_files();
+                let per_page = self.per_page;
+                self.browser_context
+                    .select_file(&files, Path::new(&filepath), per_page);
+                self.navigation.replace(AppState::Editor { filepath });
+            }
+            None => {
+                debug!("Nothing left to advance to, going back to the browser");
+                self.pop_state();
+            }
+        }
+    }
 
-            // ui.horizontal(|ui| {
-            //     let name_label = ui.label(
-            //         RichText::new("Current workdir: ")
-            //             .text_style(heading3())
-            //             .strong(),
-            //     );
-            //     ui.text_edit_singleline(&mut self.workdir)
-            //         .labelled_by(name_label.id);
-            // });
+    /// move `filepath` into the trash and write down where it went
+    fn trash_file(&mut self, filepath: &str) -> Result<(), String> {
+        let entry = self.trash.trash(filepath, &trash_dir())?;
+        info!("Deleted {} to {}", filepath, entry.trash_path);
+        self.save_trash();
+        Ok(())
+    }
+
+    fn save_trash(&mut self) {
+        if let Err(err) = self.trash.save(&trash_journal_path()) {
+            self.push_error(t!("error-save-trash", error = format!("{:?}", err)), None);
+        }
+    }
+
+    /// get the backend to remove anything that's been in the trash longer than
+    /// `retention_days`, done once at startup
+    fn purge_expired_trash(&mut self, retention_days: u32) {
+        let expired = self.trash.take_expired(retention_days, SystemTime::now());
+        if expired.is_empty() {
+            return;
+        }
+        info!(
+            "Purging {} files that have been in the trash more than {} days",
+            expired.len(),
+            retention_days
+        );
+        self.save_trash();
+        self.sendmessage(AppMsg::PurgeTrash(
+            expired
+                .into_iter()
+                .map(|entry| PathBuf::from(entry.trash_path))
+                .collect(),
+        ));
+    }
+
+    /// put something back where it came from, or `destination` if that's been taken
+    fn restore_from_trash(&mut self, ctx: &Context, trash_path: &str, destination: &Path) {
+        match self.trash.restore(trash_path, destination) {
+            Ok(entry) => {
+                info!(
+                    "Restored {} to {}",
+                    entry.original_path,
+                    destination.display()
+                );
+                self.trash_restore = None;
+                self.save_trash();
+                self.start_update(ctx);
+            }
+            Err(err) => self.push_error(t!("error-restore", error = err), None),
+        }
+    }
+
+    fn purge_from_trash(&mut self, trash_path: &str) {
+        match self.trash.purge(trash_path) {
+            Ok(entry) => {
+                info!("Purged {} from the trash", entry.original_path);
+                self.save_trash();
+            }
+            Err(err) => self.push_error(t!("error-purge", error = err), None),
+        }
+    }
+
+    /// pasting an image in the browser offers to save it into the workdir
+    fn check_clipboard_paste(&mut self, ctx: &Context) {
+        if !matches!(self.navigation.current(), AppState::Browser) {
+            return;
+        }
+        // egui only sends Paste when there's text on the clipboard, so look for the key as well
+        let pasted = ctx.input(|input| {
+            input
+                .events
+                .iter()
+                .any(|event| matches!(event, egui::Event::Paste(_)))
+                || (input.modifiers.command && input.key_pressed(Key::V))
+        });
+        if !pasted {
+            return;
+        }
+        match clipboard::read_image() {
+            Ok(Some(image_data)) => {
+                debug!("Pasted {:?}", image_data);
+                self.clipboard_filename = clipboard::default_filename();
+                self.clipboard_preview = None;
+                self.push_state(AppState::ImportFromClipboard { image_data });
+            }
+            Ok(None) => debug!("Nothing on the clipboard is an image"),
+            Err(err) => self.push_error(t!("error-clipboard", error = err), None),
+        }
+    }
+
+    fn show_import_clipboard(&mut self, ui: &mut egui::Ui, image_data: &ClipboardImage) {
+        let ctx = ui.ctx().clone();
+        let texture = self
+            .clipboard_preview
+            .get_or_insert_with(|| {
+                ctx.load_texture(
+                    "clipboard_import",
+                    image_data.color_image(),
+                    TextureOptions::default(),
+                )
+            })
+            .clone();
+        ui.scope(|ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(t!("import-clipboard"));
+                ui.label(t!(
+                    "import-clipboard-size",
+                    width = image_data.width,
+                    height = image_data.height
+                ));
+            });
+
+            let target = clipboard::import_path(&self.workdir, &self.clipboard_filename);
+            let on_conflict = self.on_import_conflict();
+            let exists = target.exists();
+            let problem = match self.clipboard_filename.trim().is_empty() {
+                true => Some(t!("import-clipboard-no-filename")),
+                // what happens to it is up to the config, or them
+                false if exists => None,
+                false => file_ops::rename_target_problem(&target),
+            };
 
-            // search box
             ui.horizontal(|ui| {
-                let search_label =
-                    ui.label(RichText::new("Search:").text_style(heading3()).strong());
-                ui.text_edit_singleline(&mut self.search_box)
-                    .labelled_by(search_label.id);
-                if ui.button("Reset").clicked() {
-                    self.search_box = "".to_string();
+                let filename_label = ui.label(t!("filename"));
+                let filename = ui
+                    .text_edit_singleline(&mut self.clipboard_filename)
+                    .labelled_by(filename_label.id);
+                focus_if_unfocused(&filename);
+                if let Some(problem) = &problem {
+                    ui.label(problem);
+                } else if exists {
+                    ui.label(t!("file-exists"));
                 }
             });
+            ui.label(t!("import-clipboard-target", path = target.display()));
 
-            // navigation bars
-            ui.add_space(15.0);
+            let mut resolution = None;
             ui.horizontal(|ui| {
-                if self.current_page > 0 {
-                    if ui.button("First Page").clicked() {
-                        self.browser_first_page();
-                    };
-
-                    if ui.button("Prev Page").clicked() {
-                        self.browser_prev_page();
+                if exists && on_conflict == ImportConflict::Ask {
+                    if let Some(choice) = import_conflict_buttons(ui) {
+                        resolution = Some(import::resolve(&target, choice));
+                    }
+                } else {
+                    let save = ui.add_enabled(problem.is_none(), egui::Button::new(t!("save")));
+                    let enter = problem.is_none() && ui.input(|i| i.key_pressed(Key::Enter));
+                    if save.clicked() || enter {
+                        resolution = Some(import::resolve(&target, on_conflict));
                     }
-                    ui.add_space(15.0);
                 }
-
-                if ui.button("Next Page").clicked() {
-                    self.browser_next_page();
+                if ui.button(t!("cancel")).clicked() {
+                    self.clipboard_preview = None;
+                    self.pop_state();
                 }
-                #[cfg(debug_assertions)]
-                if ui.button("Refresh").clicked() {
-                    debug!("Refresh clicked");
-                    self.search_box_last = None;
-                    self.sendmessage(AppMsg::NewAppState(AppState::Browser));
+            });
+            if let Some(resolution) = resolution {
+                match import::import_with(resolution, |path| image_data.save(path)) {
+                    ImportOutcome::Failed(err) => self.push_error(
+                        t!(
+                            "error-import-clipboard",
+                            path = target.display(),
+                            error = err
+                        ),
+                        None,
+                    ),
+                    outcome => {
+                        let mut summary = ImportSummary::default();
+                        summary.record(outcome);
+                        self.clipboard_preview = None;
+                        self.pop_state();
+                        self.finish_import(&ctx, summary);
+                    }
                 }
+            }
+
+            let size = fit_size(texture.size_vec2(), ui.available_size());
+            ui.vertical_centered(|ui| {
+                ui.image((texture.id(), size));
             });
-            ui.add_space(15.0);
+        });
+    }
 
-            let mut loaded_images = 0;
+    /// what to do when an import's in the way of something, going by the config
+    fn on_import_conflict(&self) -> ImportConflict {
+        self.configuration
+            .as_ref()
+            .map(|config| config.on_import_conflict)
+            .unwrap_or_default()
+    }
 
-            Grid::new("browser")
-                .num_columns(10)
-                .spacing(*GRID_SPACING) // grid spacing
-                .show(ui, |ui| {
-                    let mut col = 0;
-                    let filenames: Vec<String> = self
-                        .get_page()
-                        .iter()
-                        .map(|p| p.display().to_string())
-                        .collect();
+    /// images dropped on the browser get copied into the workdir
+    fn check_dropped_files(&mut self, ctx: &Context) {
+        if !matches!(self.navigation.current(), AppState::Browser) {
+            return;
+        }
+        let dropped: Vec<PathBuf> = ctx.input(|input| {
+            input
+                .raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        if dropped.is_empty() {
+            return;
+        }
+        let queue = self.import_queue.get_or_insert_with(ImportQueue::default);
+        for path in dropped {
+            if dir_scan::is_listable(&path) {
+                queue.pending.push_back(path);
+            } else {
+                debug!("Not importing {}, it's not an image", path.display());
+                queue.summary.record(ImportOutcome::Skipped);
+            }
+        }
+    }
 
-                    filenames.into_iter().sorted().for_each(|filename| {
-                        let image = match self.browser_images.get(&filename) {
-                            Some(i) => {
-                                loaded_images += 1;
-                                let img = i.image.clone().unwrap();
-                                let space = ((THUMBNAIL_SIZE.x - img.width() as f32) / 2.0) + 1.0;
-                                ui.add_space(space);
-                                img.as_ref().show_max_size(ui, *THUMBNAIL_SIZE)
-                            }
-                            None => {
-                                ui.add_space((THUMBNAIL_SIZE.x - THUMBNAIL_SIZE.y) / 2.0);
-                                ui.image(
-                                    self.loading_image.,
-                                    // vec2(THUMBNAIL_SIZE.y, THUMBNAIL_SIZE.y),
-                                )
-                            }
-                        };
-                        let imageresponse = image.interact(egui::Sense::click());
-                        if imageresponse.clicked() {
-                            // reset the things
-                            self.editor_image_cache = None;
-                            self.editor_rename_target = String::new();
-                            self.app_state = AppState::Editor { filepath: filename };
-                        };
+    /// copy in what's been dropped, stopping to ask about any that are in the way of something if
+    /// that's what the config says to do
+    fn process_import_queue(&mut self, ctx: &Context) {
+        let on_conflict = self.on_import_conflict();
+        let workdir = PathBuf::from(shellexpand::tilde(&self.workdir).as_ref());
+        let Some(queue) = self.import_queue.as_mut() else {
+            return;
+        };
+        let mut asking = None;
+        while let Some(source) = queue.pending.front().cloned() {
+            let Some(target) = import::import_target(&workdir, &source) else {
+                queue.pending.pop_front();
+                queue.summary.record(ImportOutcome::Skipped);
+                continue;
+            };
+            if file_ops::is_same_file(&source, &target) {
+                debug!("{} is already in the workdir", source.display());
+                queue.pending.pop_front();
+                queue.summary.record(ImportOutcome::Skipped);
+                continue;
+            }
+            let resolution = import::resolve(&target, queue.apply_to_all.unwrap_or(on_conflict));
+            if resolution == Resolution::Ask {
+                asking = Some(target);
+                break;
+            }
+            queue.pending.pop_front();
+            queue
+                .summary
+                .record(import::import_file(&source, resolution));
+        }
 
-                        col += 1;
-                        if col > 4 {
-                            col = 0;
-                            ui.end_row();
-                        }
-                    });
+        let Some(target) = asking else {
+            let summary = std::mem::take(&mut queue.summary);
+            self.import_queue = None;
+            self.finish_import(ctx, summary);
+            return;
+        };
+        let mut choice = None;
+        egui::Window::new(t!("import-conflict-title"))
+            .collapsible(false)
+            .resizable(false)
+            .default_pos(ctx.screen_rect().center())
+            .show(ctx, |ui| {
+                ui.label(t!("import-conflict", path = target.display()));
+                ui.horizontal(|ui| {
+                    choice = import_conflict_buttons(ui);
                 });
+                if queue.pending.len() > 1 {
+                    ui.checkbox(
+                        &mut queue.apply_to_all_ticked,
+                        t!(
+                            "import-conflict-apply-to-all",
+                            count = queue.pending.len() - 1
+                        ),
+                    );
+                }
+            });
+        if let Some(choice) = choice {
+            if queue.apply_to_all_ticked {
+                queue.apply_to_all = Some(choice);
+            }
+            let source = queue.pending.pop_front().expect("it was just asked about");
+            queue.summary.record(import::import_file(
+                &source,
+                import::resolve(&target, choice),
+            ));
+        }
+    }
 
-            ui.add_space(15.0);
+    /// say how an import went, and show what's come in
+    fn finish_import(&mut self, ctx: &Context, summary: ImportSummary) {
+        if summary.total() == 0 {
+            return;
+        }
+        let message = t!(
+            "import-summary",
+            imported = summary.imported,
+            renamed = summary.renamed,
+            skipped = summary.skipped,
+            failed = summary.failed
+        );
+        match summary.last_path {
+            Some(path) => self.push_toast_link(message, path),
+            None => self.push_toast(message),
+        }
+        self.start_update(ctx);
+    }
 
+    /// everything that's been deleted through memetool, newest first
+    fn show_trash(&mut self, ui: &mut egui::Ui) {
+        let ctx = ui.ctx().clone();
+        let retention_days = self
+            .configuration
+            .as_ref()
+            .map(|config| config.trash_retention_days())
+            .unwrap_or(config::DEFAULT_TRASH_RETENTION_DAYS);
+        let mut restore = None;
+        let mut purge = None;
+        ui.scope(|ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(
+                    RichText::new(t!("recently-deleted", count = self.trash.len()))
+                        .text_style(heading3()),
+                );
+            });
             ui.horizontal(|ui| {
-                if ui.button("Configuration").clicked() {
-                    self.app_state = AppState::Configuration;
+                if ui.button(t!("back")).clicked() {
+                    self.trash_restore = None;
+                    self.pop_state();
                 }
-
-                ui.label(format!("Number of files: {}", self.files_list.len()));
-                if let Some(last_checked) = &self.last_checked_dir {
-                    ui.label(format!("Last Checked: {}", last_checked));
-                };
-                ui.label(format!("Current page: {}", self.current_page + 1));
-                if loaded_images != self.get_page().len() {
-                    ui.label(format!(
-                        "Loading images... {}/{}",
-                        loaded_images,
-                        self.get_page().len()
-                    ));
-                };
+                ui.label(if retention_days == 0 {
+                    t!("trash-retention-forever")
+                } else {
+                    t!("trash-retention-hint", days = retention_days)
+                });
             });
+            ui.add_space(10.0);
+            if self.trash.is_empty() {
+                ui.label(t!("trash-empty"));
+                return;
+            }
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    Grid::new("trash_grid")
+                        .striped(true)
+                        .num_columns(3)
+                        .spacing([10.0, 6.0])
+                        .show(ui, |ui| {
+                            for entry in self.trash.entries().iter().rev() {
+                                let name = display_filename(Path::new(&entry.original_path));
+                                // the file's still there, this is how it was before an edit
+                                let name = if entry.backup {
+                                    t!("trash-backup", name = name)
+                                } else {
+                                    name
+                                };
+                                ui.label(name).on_hover_text(&entry.original_path);
+                                ui.label(dir_stats::format_age(entry.deleted_time()));
+                                ui.horizontal(|ui| {
+                                    if entry.available() {
+                                        if ui.button(t!("restore")).clicked() {
+                                            restore = Some(entry.clone());
+                                        }
+                                    } else {
+                                        // something else emptied it out of the trash directory
+                                        ui.label(RichText::new(t!("trash-unavailable")).weak())
+                                            .on_hover_text(t!("trash-unavailable-hint"));
+                                    }
+                                    if ui.button(t!("purge")).clicked() {
+                                        purge = Some(entry.trash_path.clone());
+                                    }
+                                });
+                                ui.end_row();
+                            }
+                        });
+                });
         });
-        ctx.request_repaint_after(Duration::from_micros(100));
+
+        if let Some(entry) = restore {
+            match entry.restore_path() {
+                Some(destination) if destination == Path::new(&entry.original_path) => {
+                    self.restore_from_trash(&ctx, &entry.trash_path, &destination);
+                }
+                Some(destination) => {
+                    self.trash_restore = Some(TrashRestore {
+                        trash_path: entry.trash_path,
+                        destination: destination.display().to_string(),
+                    });
+                }
+                // they'll have to pick somewhere themselves
+                None => {
+                    self.trash_restore = Some(TrashRestore {
+                        destination: entry.original_path.clone(),
+                        trash_path: entry.trash_path,
+                    });
+                }
+            }
+        }
+        if let Some(trash_path) = purge {
+            self.purge_from_trash(&trash_path);
+        }
+        self.show_trash_restore_prompt(&ctx);
+        self.check_dropped_files(&ctx);
+        self.process_import_queue(&ctx);
     }
 
-    fn show_error(
-        &mut self,
-        ctx: egui::Context,
-        message: String,
-        next_state: Option<Box<AppState>>,
-    ) {
-        egui::CentralPanel::default().show(&ctx, |ui| {
-            ui.label(message);
-            if ui.button("Continue").clicked() {
-                if let Some(state) = next_state {
-                    self.app_state = *state;
-                } else {
-                    self.app_state = AppState::Browser;
+    /// the original path's taken, so ask where to put it instead, starting with a suffixed name
+    fn show_trash_restore_prompt(&mut self, ctx: &Context) {
+        let Some(prompt) = self.trash_restore.as_mut() else {
+            return;
+        };
+        let Some(entry) = self.trash.get(&prompt.trash_path) else {
+            self.trash_restore = None;
+            return;
+        };
+        let mut open = true;
+        let mut restore = false;
+        let mut cancel = false;
+        egui::Window::new(t!("restore"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_pos(ctx.screen_rect().center())
+            .show(ctx, |ui| {
+                ui.label(t!("trash-restore-occupied", path = entry.original_path));
+                let destination_label = ui.label(t!("restore-to"));
+                ui.add(egui::TextEdit::singleline(&mut prompt.destination).desired_width(400.0))
+                    .labelled_by(destination_label.id);
+                let taken = Path::new(&prompt.destination).exists();
+                if taken {
+                    ui.label(RichText::new(t!("file-exists")).color(ui.visuals().error_fg_color));
                 }
-            };
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !taken && !prompt.destination.is_empty(),
+                            egui::Button::new(t!("restore")),
+                        )
+                        .clicked()
+                    {
+                        restore = true;
+                    }
+                    if ui.button(t!("cancel")).clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if restore {
+            let trash_path = prompt.trash_path.clone();
+            let destination = PathBuf::from(&prompt.destination);
+            self.restore_from_trash(ctx, &trash_path, &destination);
+        } else if cancel || !open {
+            self.trash_restore = None;
+        }
+    }
+
+    /// pop `filepath` out into its own window, replacing whatever was popped out before
+    fn open_editor_popout(&mut self, filepath: &str) {
+        debug!("Opening {} in a new window", filepath);
+        self.editor_popout = Some(EditorPopout {
+            filepath: filepath.to_string(),
+            image_cache: None,
+            confirm_delete: false,
         });
     }
 
-    fn set_new_app_state(&mut self, newappstate: AppState) {
-        self.sendmessage(AppMsg::NewAppState(newappstate))
+    /// The popped-out editor, drawn in its own viewport, or as a window inside the main one if the
+    /// backend can't do more than one
+    fn show_editor_popout(&mut self, ctx: &Context) {
+        let Some(filepath) = self
+            .editor_popout
+            .as_ref()
+            .map(|popout| popout.filepath.clone())
+        else {
+            return;
+        };
+        let title = t!("popout-title", path = filepath);
+        let mut close = false;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("editor_popout"),
+            egui::ViewportBuilder::default()
+                .with_title(&title)
+                .with_inner_size([800.0, 600.0]),
+            |ctx, class| {
+                if class == egui::ViewportClass::Embedded {
+                    egui::Window::new(&title).show(ctx, |ui| {
+                        close = self.editor_popout_ui(ui, &filepath);
+                    });
+                } else {
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        close = self.editor_popout_ui(ui, &filepath);
+                    });
+                    if ctx.input(|input| input.viewport().close_requested()) {
+                        close = true;
+                    }
+                }
+            },
+        );
+        if close {
+            debug!("Closing the editor window for {}", filepath);
+            self.editor_popout = None;
+        }
     }
 
-    fn show_editor(&mut self, ctx: egui::Context, filepath: &str) {
-        trace!("Showing editor: {}", filepath);
+    /// what's in the popped-out editor, returns true if it should be closed
+    fn editor_popout_ui(&mut self, ui: &mut egui::Ui, filepath: &str) -> bool {
+        let ctx = ui.ctx().clone();
+        let mut close = false;
 
-        if self.editor_rename_target.is_empty() {
-            self.editor_rename_target = filepath.to_string();
-        }
-        egui::CentralPanel::default().show(&ctx, |ui| {
-            let target_path = PathBuf::from(&self.editor_rename_target);
-            let target_path_parent_exists = match target_path.parent() {
-                None => false,
-                Some(parent_path) => parent_path.exists(),
-            };
+        // only the focused window acts on Delete, so the browser's selection is left alone
+        let (delete_pressed, escape_pressed) = ctx.input(|input| {
+            (
+                input.focused && input.key_released(Key::Delete),
+                input.focused && input.key_released(Key::Escape),
+            )
+        });
+        let confirm_delete = match self.editor_popout.as_mut() {
+            Some(popout) => {
+                if delete_pressed && !ctx.wants_keyboard_input() {
+                    popout.confirm_delete = true;
+                } else if escape_pressed {
+                    popout.confirm_delete = false;
+                }
+                popout.confirm_delete
+            }
+            None => return true,
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(t!("file-path"));
+            ui.label(filepath);
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button(RichText::new(t!("open-in-main-window")).text_style(heading3()))
+                .clicked()
+            {
+                self.editor_image_cache = None;
+                self.editor_rename_target = String::new();
+                self.push_state(AppState::Editor {
+                    filepath: filepath.to_string(),
+                });
+                close = true;
+            }
+            ui.add_space(15.0);
+            if ui
+                .button(RichText::new(t!("delete-image")).text_style(heading3()))
+                .clicked()
+            {
+                if let Some(popout) = self.editor_popout.as_mut() {
+                    popout.confirm_delete = true;
+                }
+            }
+            if ui
+                .button(RichText::new(t!("upload-to-s3")).text_style(heading3()))
+                .clicked()
+            {
+                self.upload_corrupt_confirmed = false;
+                self.push_state(AppState::UploadPrompt(filepath.to_string()));
+            }
+            if ui
+                .button(RichText::new(t!("close")).text_style(heading3()))
+                .clicked()
+            {
+                close = true;
+            }
+        });
 
+        if confirm_delete {
             ui.horizontal(|ui| {
-                let file_label = ui.label("File Path:");
+                ui.label(
+                    RichText::new(t!("popout-delete-confirm")).color(ui.visuals().warn_fg_color),
+                );
+                if ui.button(t!("confirm")).clicked() {
+                    // closes the window too, see [MemeTool::forget_file]
+                    if let Err(err) = self.delete_file(&ctx, filepath) {
+                        self.push_error(t!("error-delete", error = err), None);
+                    }
+                }
+                let cancel = ui.button(t!("cancel"));
+                focus_if_unfocused(&cancel);
+                if cancel.clicked() {
+                    if let Some(popout) = self.editor_popout.as_mut() {
+                        popout.confirm_delete = false;
+                    }
+                }
+            });
+        }
 
-                let filename_editor = ui
-                    .add(
-                        egui::TextEdit::singleline(&mut self.editor_rename_target)
-                            .desired_width(ctx.available_rect().width() * 0.7),
-                    ) // 70% of the screen width
-                    .labelled_by(file_label.id);
+        self.integrity_banner(ui, filepath);
 
-                self.editor_rename_has_focus = filename_editor.has_focus();
+        let image_size = Vec2 {
+            x: ui.available_width() * 0.9,
+            y: ui.available_height() * 0.8,
+        };
+        let Some(popout) = self.editor_popout.as_mut() else {
+            return true;
+        };
+        if popout.image_cache.is_none() {
+            if let Ok(image) = load_image_to_thumbnail(&PathBuf::from(filepath), Some(image_size)) {
+                let texture = ctx.load_texture(
+                    format!("editor_popout:{}", filepath),
+                    image,
+                    TextureOptions::default(),
+                );
+                let info = editor_image_info(filepath, &texture);
+                popout.image_cache = Some((texture, info));
+            }
+        }
+        if let Some((texture, info)) = popout.image_cache.clone() {
+            ui.image((texture.id(), texture.size_vec2()));
+            self.show_image_info(ui, &info);
+        }
+        close
+    }
 
-                // if they've changed the filename in the box
-                if filepath != self.editor_rename_target {
-                    if target_path.exists() {
-                        ui.label("File already exists!");
-                    } else if !target_path_parent_exists {
-                        ui.label("Parent path doesn't exist!");
-                    } else {
-                        filename_editor.ctx.input(|i| {
-                            if i.key_pressed(egui::Key::Enter)
-                                && filepath != self.editor_rename_target
-                            {
-                                self.set_new_app_state(AppState::RenameConfirm {
-                                    filepath: filepath.to_string(),
-                                    newfilepath: self.editor_rename_target.clone(),
-                                });
-                            }
+    fn show_batch_delete_prompt(&mut self, ui: &mut egui::Ui, filepaths: &[String]) {
+        let ctx = ui.ctx().clone();
+        ui.scope(|ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(t!("confirm-delete-many", count = filepaths.len()));
+                ui.label(t!(
+                    "selection-total-size",
+                    size = humansize::format_size(self.batch_total_size, humansize::DECIMAL)
+                ));
+            });
+            egui::ScrollArea::vertical()
+                .max_height(ui.available_height() - 40.0)
+                .show(ui, |ui| {
+                    filepaths.iter().for_each(|filepath| {
+                        ui.horizontal(|ui| {
+                            ui.add_space(2.0);
+                            ui.label(filepath);
                         });
+                    });
+                });
 
-                        // show the rename button
-                        if ui.button("Rename").clicked() {
-                            info!("Clicked rename!");
-                            if filepath != self.editor_rename_target {
-                                self.app_state = AppState::RenameConfirm {
-                                    filepath: filepath.to_string(),
-                                    newfilepath: self.editor_rename_target.clone(),
-                                };
+            ui.horizontal(|ui| {
+                let confirm = ui.button(t!("confirm"));
+
+                let cancel = ui.button(t!("cancel"));
+                focus_if_unfocused(&cancel);
+
+                if confirm.clicked() {
+                    let mut failures = vec![];
+                    for filepath in filepaths.iter() {
+                        match self.trash_file(filepath) {
+                            Ok(_) => {
+                                self.selected_images.remove(filepath);
+                                self.forget_file(filepath);
                             }
-                        };
+                            Err(err) => failures.push(format!("{}: {}", filepath, err)),
+                        }
+                    }
+                    // the browser image list will be wrong at this point, so tell it to cache
+                    self.start_update(&ctx);
+                    self.pop_state();
+                    if !failures.is_empty() {
+                        self.push_error(
+                            format!("{}\n{}", t!("error-delete-many"), failures.join("\n")),
+                            None,
+                        );
                     }
                 }
 
-                if filename_editor.changed() {
-                    debug!(
-                        "Typed into filename: {} => {}",
-                        filepath, self.editor_rename_target
-                    );
+                if cancel.clicked() {
+                    self.pop_state();
                 }
             });
+        });
+    }
+
+    fn show_upload_prompt(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        ui.scope(|ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(t!("confirm-upload"));
+            });
+            ui.horizontal(|ui| {
+                ui.add_space(2.0);
+                ui.label(filepath);
+            });
+            self.integrity_banner(ui, filepath);
+            self.upload_size_warning(ui, filepath);
+            // corrupt files need an extra tick before they'll go up
+            let corrupt = self.integrity.corruption(filepath).is_some();
+            if corrupt {
+                ui.checkbox(
+                    &mut self.upload_corrupt_confirmed,
+                    t!("upload-corrupt-anyway"),
+                );
+            }
+            let can_upload = !corrupt || self.upload_corrupt_confirmed;
+
             ui.horizontal(|ui| {
                 if ui
-                    .button(RichText::new("Back").text_style(heading3()))
-                    .clicked()
-                {
-                    self.set_new_app_state(AppState::Browser);
-                };
-                ui.add_space(15.0);
-                if ui
-                    .button(RichText::new("Delete Image").text_style(heading3()))
+                    .add_enabled(
+                        can_upload,
+                        egui::Button::new(RichText::new(t!("confirm")).text_style(heading3())),
+                    )
                     .clicked()
                 {
-                    self.set_new_app_state(AppState::DeletePrompt(filepath.to_string()));
-                };
+                    // rename the file
+                    debug!("Sending upload message for: {}", filepath);
+                    self.sendmessage(AppMsg::UploadImage(filepath.to_string()));
+                    self.upload_status = Some(UploadStatus::new());
+                    self.navigation
+                        .replace(AppState::Uploading(filepath.to_string()));
+                }
 
                 if ui
-                    .button(RichText::new("Upload to S3").text_style(heading3()))
+                    .button(RichText::new(t!("cancel")).text_style(heading3()))
                     .clicked()
                 {
-                    self.set_new_app_state(AppState::UploadPrompt(filepath.to_string()));
+                    self.pop_state();
                 }
             });
-            ui.horizontal(|ui| {
-                ui.label("Original Path: ");
-                ui.label(filepath);
-            });
-
-            let mut image_width = 0;
-            let mut image_height = 0;
-
-            if let Some(image) = &self.editor_image_cache {
-                image_height = image.height();
-                image_width = image.width();
-                image.show(ui);
-            } else if let Ok(image) = load_image_to_thumbnail(
-                &PathBuf::from(filepath),
-                Some(Vec2 {
-                    x: ui.available_width() * 0.9,
-                    y: ui.available_height() * 0.8,
-                }),
-            ) {
-                image_height = image.height();
-                image_width = image.width();
-                image.show(ui);
-                self.editor_image_cache = Some(image);
-            }
-            ui.label(format!("Image Size: {}x{}", image_width, image_height));
-
-            // show filepath size on disk
-            if let Ok(metadata) = std::fs::metadata(filepath) {
-                ui.label(format!(
-                    "File Size: {}",
-                    humansize::format_size(metadata.len(), humansize::DECIMAL)
-                ));
-            }
         });
     }
 
-    fn show_rename_confirm(&mut self, ctx: egui::Context, filepath: String, newfilename: String) {
-        egui::CentralPanel::default().show(&ctx, |ui| {
+    /// like [MemeTool::show_upload_prompt], but it's replacing what's in the bucket so it shows
+    /// what's being replaced
+    fn show_update_prompt(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let Some(key) = self.s3_edits.get(filepath).cloned().or_else(|| {
+            self.upload_history
+                .get(filepath)
+                .map(|record| record.key.clone())
+        }) else {
+            // nothing to update, it's never been uploaded
+            self.pop_state();
+            return;
+        };
+        ui.scope(|ui| {
             ui.vertical_centered(|ui| {
-                ui.heading("Please confirm rename");
-            });
-            ui.horizontal(|ui| {
-                ui.add_space(2.0);
-                ui.label(&filepath);
+                ui.heading(t!("confirm-update"));
             });
             ui.horizontal(|ui| {
                 ui.add_space(2.0);
-                ui.label(&newfilename);
+                ui.label(filepath);
             });
-            ui.horizontal(|ui| {
-                let confirm =
-                    ui.button(RichText::new("Confirm").text_style(egui::TextStyle::Heading));
+            ui.label(t!("update-key", key = key));
 
-                let cancel =
-                    ui.button(RichText::new("Cancel").text_style(egui::TextStyle::Heading));
+            let local_size = std::fs::metadata(filepath)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            ui.label(t!(
+                "update-local-size",
+                size = humansize::format_size(local_size, humansize::DECIMAL)
+            ));
+            match &self.remote_size {
+                Some((checked, size)) if checked == filepath => match size {
+                    Ok(Some(size)) => {
+                        ui.label(t!(
+                            "update-remote-size",
+                            size = humansize::format_size(*size, humansize::DECIMAL)
+                        ));
+                    }
+                    Ok(None) => {
+                        ui.label(t!("update-not-in-bucket"));
+                    }
+                    Err(error) => {
+                        ui.label(t!("update-remote-size-unknown", error = error));
+                    }
+                },
+                _ => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(t!("checking"));
+                    });
+                }
+            }
 
-                if confirm.clicked() {
-                    // rename the file
-                    self.do_rename(&ctx, &filepath, &newfilename);
+            self.integrity_banner(ui, filepath);
+            let corrupt = self.integrity.corruption(filepath).is_some();
+            if corrupt {
+                ui.checkbox(
+                    &mut self.upload_corrupt_confirmed,
+                    t!("upload-corrupt-anyway"),
+                );
+            }
+            let can_upload = !corrupt || self.upload_corrupt_confirmed;
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        can_upload,
+                        egui::Button::new(RichText::new(t!("confirm")).text_style(heading3())),
+                    )
+                    .clicked()
+                {
+                    debug!("Sending update message for: {}", filepath);
+                    self.sendmessage(AppMsg::UpdateImage {
+                        filepath: filepath.to_string(),
+                        key: key.clone(),
+                    });
+                    self.upload_status = Some(UploadStatus::new());
+                    self.navigation
+                        .replace(AppState::Uploading(filepath.to_string()));
                 }
 
+                let cancel = ui.button(RichText::new(t!("cancel")).text_style(heading3()));
+                // overwriting's the risky choice, so it's not the default
+                focus_if_unfocused(&cancel);
                 if cancel.clicked() {
-                    self.app_state = AppState::Editor {
-                        filepath: filepath.clone(),
-                    };
+                    self.pop_state();
                 }
             });
         });
     }
-    fn show_delete_prompt(&mut self, ctx: egui::Context, filepath: String) {
-        egui::CentralPanel::default().show(&ctx, |ui| {
+
+    /// ask whether to put `filepath` over the top of `key` in the bucket
+    fn show_update(&mut self, filepath: &str, key: String) {
+        self.upload_corrupt_confirmed = false;
+        self.remote_size = None;
+        self.sendmessage(AppMsg::CheckRemoteSize {
+            filepath: filepath.to_string(),
+            key,
+        });
+        self.push_state(AppState::UpdatePrompt(filepath.to_string()));
+    }
+
+    fn show_uploading(&mut self, ui: &mut egui::Ui, filepath: &str) {
+        let ctx = ui.ctx().clone();
+        let status = self
+            .upload_status
+            .get_or_insert_with(UploadStatus::new)
+            .clone();
+        let stall_threshold = match &self.configuration {
+            Some(config) => config.upload_stall_threshold(),
+            None => Duration::from_secs(config::DEFAULT_UPLOAD_STALL_SECS),
+        };
+
+        ui.scope(|ui| {
             ui.vertical_centered(|ui| {
-                ui.heading("Please confirm deletion");
+                ui.heading(t!("uploading"));
             });
             ui.horizontal(|ui| {
                 ui.add_space(2.0);
-                ui.label(&filepath);
+                ui.label(filepath);
             });
-
             ui.horizontal(|ui| {
-                let confirm = ui.button("Confirm");
-
-                let cancel = ui.button("Cancel");
+                ui.spinner();
+                ui.label(t!(
+                    "upload-elapsed",
+                    seconds = status.started.elapsed().as_secs()
+                ));
+            });
+            if let Some(fraction) = status.fraction() {
+                ui.add(egui::ProgressBar::new(fraction).show_percentage());
+            }
+            if let Some(part) = status.part {
+                ui.label(t!("upload-part", part = part.part, parts = part.parts));
+            }
+            if let Some(remaining) = status.remaining() {
+                ui.label(t!("upload-remaining", seconds = remaining.as_secs()));
+            }
+            let sent = humansize::format_size(status.bytes_sent, humansize::DECIMAL);
+            match status.total_bytes {
+                Some(total) => ui.label(t!(
+                    "upload-sent-of",
+                    sent = sent,
+                    total = humansize::format_size(total, humansize::DECIMAL)
+                )),
+                None => ui.label(t!("upload-sent", sent = sent)),
+            };
+            ui.label(t!(
+                "upload-rate",
+                rate = humansize::format_size(status.rate() as u64, humansize::DECIMAL)
+            ));
+            if let Some(message) = &status.message {
+                ui.label(t!("upload-failed-retrying", message = message));
+            }
 
-                if confirm.clicked() {
-                    // rename the file
-                    match std::fs::remove_file(&filepath) {
-                        Ok(_) => {
-                            info!("Deleted {}", filepath);
-                            // the browser image list will be wrong at this point, so tell it to cache
-                            self.start_update(&ctx);
-                            self.app_state = AppState::Browser;
-                        }
-                        Err(err) => {
-                            self.app_state = AppState::ShowError {
-                                message: format!("Failed to delete file: {:?}", err),
-                                next_state: Some(Box::new(AppState::Editor {
-                                    filepath: filepath.clone(),
-                                })),
-                            };
-                        }
-                    }
+            if status.last_progress.elapsed() > stall_threshold {
+                ui.label(
+                    RichText::new(t!(
+                        "upload-stalled",
+                        seconds = status.last_progress.elapsed().as_secs()
+                    ))
+                    .color(egui::Color32::YELLOW),
+                );
+                if ui.button(t!("cancel")).clicked() {
+                    self.upload_status = None;
+                    self.pop_state();
                 }
+            }
+        });
+        // keep the spinner and timers moving
+        ctx.request_repaint_after(Duration::from_millis(100));
+    }
 
-                if cancel.clicked() {
-                    self.app_state = AppState::Editor { filepath };
-                }
-            });
+    /// start the slideshow from whatever's selected in the browser, or the first file
+    fn start_slideshow(&mut self) {
+        let files = self.ordered_files();
+        if files.is_empty() {
+            return;
+        }
+        let index = self
+            .browser_context
+            .selected_file
+            .as_ref()
+            .and_then(|selected| {
+                files
+                    .iter()
+                    .position(|filepath| filepath.display().to_string() == *selected)
+            })
+            .unwrap_or(0);
+        debug!("Starting the slideshow at {}", index);
+        self.slideshow_paused = false;
+        self.slideshow_advanced_at = Instant::now();
+        self.push_state(AppState::Slideshow {
+            index,
+            interval_ms: SLIDESHOW_INTERVAL_MS,
         });
     }
-    fn show_upload_prompt(&mut self, ctx: egui::Context, filepath: String) {
-        egui::CentralPanel::default().show(&ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.heading("Confirm upload...");
-            });
+
+    /// move the slideshow `step` images along, wrapping around at either end
+    fn slideshow_advance(&mut self, step: isize) {
+        if let AppState::Slideshow { index, interval_ms } = *self.navigation.current() {
+            let index = slideshow_index(index, step, self.ordered_files().len());
+            // it's the same slideshow, so going back leaves it rather than stepping through it
+            self.navigation
+                .replace(AppState::Slideshow { index, interval_ms });
+            self.slideshow_advanced_at = Instant::now();
+        }
+    }
+
+    /// one image at a time, as big as it'll go
+    fn show_slideshow(&mut self, ui: &mut egui::Ui, index: usize, interval_ms: u32) {
+        let ctx = ui.ctx().clone();
+        let files = self.ordered_files();
+        if files.is_empty() {
+            self.pop_state();
+            return;
+        }
+        let filepath = files[index % files.len()].display().to_string();
+
+        let interval = Duration::from_millis(interval_ms as u64);
+        if !self.slideshow_paused {
+            let elapsed = self.slideshow_advanced_at.elapsed();
+            if elapsed >= interval {
+                self.slideshow_advance(1);
+            } else {
+                ctx.request_repaint_after(interval - elapsed);
+            }
+        }
+
+        ui.scope(|ui| {
             ui.horizontal(|ui| {
-                ui.add_space(2.0);
+                if ui.button(t!("slideshow-previous")).clicked() {
+                    self.slideshow_advance(-1);
+                }
+                let pause = if self.slideshow_paused {
+                    t!("slideshow-resume")
+                } else {
+                    t!("slideshow-pause")
+                };
+                if ui.button(pause).clicked() {
+                    self.slideshow_paused = !self.slideshow_paused;
+                    self.slideshow_advanced_at = Instant::now();
+                }
+                if ui.button(t!("slideshow-next")).clicked() {
+                    self.slideshow_advance(1);
+                }
+                ui.add_space(15.0);
+                ui.label(t!(
+                    "slideshow-position",
+                    position = index % files.len() + 1,
+                    total = files.len()
+                ));
                 ui.label(&filepath);
             });
 
-            ui.horizontal(|ui| {
-                if ui
-                    .button(RichText::new("Confirm").text_style(heading3()))
-                    .clicked()
-                {
-                    // rename the file
-                    debug!("Sending upload message for: {}", filepath);
-                    let target_filepath = filepath.clone();
-                    self.sendmessage(AppMsg::UploadImage(target_filepath));
+            let loaded = self
+                .slideshow_image
+                .as_ref()
+                .map(|(cached, _)| *cached == filepath)
+                .unwrap_or(false);
+            if !loaded {
+                self.slideshow_image = match load_image_to_thumbnail(
+                    &PathBuf::from(&filepath),
+                    Some(ui.available_size()),
+                ) {
+                    Ok(image) => Some((
+                        filepath.clone(),
+                        ctx.load_texture(
+                            format!("slideshow:{}", filepath),
+                            image,
+                            TextureOptions::default(),
+                        ),
+                    )),
+                    Err(err) => {
+                        warn!("Slideshow couldn't load {}: {}", filepath, err);
+                        None
+                    }
+                };
+            }
+            ui.centered_and_justified(|ui| match &self.slideshow_image {
+                Some((_, texture)) => {
+                    ui.image((texture.id(), texture.size_vec2()));
                 }
-
-                if ui
-                    .button(RichText::new("Cancel").text_style(heading3()))
-                    .clicked()
-                {
-                    self.set_new_app_state(AppState::Editor { filepath });
+                None => {
+                    ui.label(t!("slideshow-failed", path = filepath));
                 }
             });
         });
     }
 
-    fn show_uploading(&mut self, ctx: Context, filepath: String) {
-        egui::CentralPanel::default().show(&ctx, |ui| {
+    /// keyboard shortcut help
+    fn show_help(&mut self, ui: &mut egui::Ui) {
+        ui.scope(|ui| {
             ui.vertical_centered(|ui| {
-                ui.heading("Uploading...");
-            });
-            ui.horizontal(|ui| {
-                ui.add_space(2.0);
-                ui.label(filepath);
+                ui.heading(t!("help-title"));
             });
+            ui.add_space(15.0);
+            Grid::new("help_grid")
+                .striped(true)
+                .min_col_width(100.0)
+                .spacing([10.0, 10.0])
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label(
+                        RichText::new(t!("help-action"))
+                            .text_style(heading3())
+                            .strong(),
+                    );
+                    ui.label(
+                        RichText::new(t!("help-key"))
+                            .text_style(heading3())
+                            .strong(),
+                    );
+                    ui.end_row();
+                    KEYBINDINGS.iter().for_each(|(action, key)| {
+                        ui.label(t!(*action));
+                        ui.label(*key);
+                        ui.end_row();
+                    });
+                });
+            ui.add_space(15.0);
+            if ui.button(t!("back")).clicked() {
+                self.close_help();
+            }
         });
     }
 
     /// config UI
-    fn show_config(&mut self, ctx: Context) {
+    fn show_config(&mut self, ui: &mut egui::Ui) {
+        let ctx = ui.ctx().clone();
         // load config file
         if self.configuration.is_none() {
             self.configuration = match Configuration::try_new() {
                 Ok(val) => Some(val),
                 Err(err) => {
-                    self.app_state = AppState::ShowError {
-                        message: format!("Failed to load configuration: {:?}", err),
-                        next_state: Some(Box::new(AppState::Browser)),
-                    };
+                    // go back first, otherwise we'd try to load it again next frame
+                    self.pop_state();
+                    self.push_error(t!("error-load-config", error = format!("{:?}", err)), None);
                     return;
                 }
             }
@@ -849,33 +7294,34 @@ impl MemeTool {
             endpoint_url = config.to_owned();
         };
 
-        egui::CentralPanel::default().show(&ctx, |ui| {
+        ui.scope(|ui| {
             ui.vertical_centered(|ui| {
-                ui.heading(RichText::new("Configuration").text_style(heading3()));
+                ui.heading(RichText::new(t!("configuration")).text_style(heading3()));
             });
+            if !Configuration::file_exists() {
+                ui.label(
+                    RichText::new(t!("default-configuration")).color(ui.visuals().warn_fg_color),
+                );
+            }
             ui.horizontal(|ui| {
                 // TODO: need to save config here
-                if ui.button("Back").clicked() {
-                    self.app_state = AppState::Browser;
-                    if let Some(config) = self.configuration.as_mut() {
-                        if let Err(err) = config.save() {
-                            self.app_state = AppState::ShowError {
-                                message: format!("Failed to save configuration: {:?}", err),
-                                next_state: Some(Box::new(AppState::Browser)),
-                            };
-                        }
-                    }
+                if ui.button(t!("back")).clicked() {
+                    self.pop_state();
+                    self.save_configuration();
+                    // check again next time, they might've changed something
+                    self.bucket_status = None;
                 }
             });
 
-            ui.heading("S3 Configuration");
+            ui.heading(t!("s3-configuration"));
+            self.bucket_status_row(ui);
             Grid::new("config_grid")
                 .striped(true)
                 .min_col_width(100.0)
                 .spacing([10.0, 10.0])
                 .num_columns(2)
                 .show(ui, |ui| {
-                    let s3_access_key_id_label = ui.label("S3 Access Key ID");
+                    let s3_access_key_id_label = ui.label(t!("s3-access-key-id"));
                     ui.add(
                         egui::TextEdit::singleline(
                             &mut self.configuration.as_mut().unwrap().s3_access_key_id,
@@ -885,7 +7331,7 @@ impl MemeTool {
                     .labelled_by(s3_access_key_id_label.id);
                     ui.end_row();
 
-                    let s3_secret_access_key_label = ui.label("S3 Secret");
+                    let s3_secret_access_key_label = ui.label(t!("s3-secret"));
                     ui.add(
                         egui::TextEdit::singleline(
                             &mut self.configuration.as_mut().unwrap().s3_secret_access_key,
@@ -896,7 +7342,7 @@ impl MemeTool {
                     .labelled_by(s3_secret_access_key_label.id);
                     ui.end_row();
 
-                    let bucket_label = ui.label("S3 Bucket");
+                    let bucket_label = ui.label(t!("s3-bucket"));
                     ui.add(
                         egui::TextEdit::singleline(
                             &mut self.configuration.as_mut().unwrap().s3_bucket,
@@ -906,7 +7352,7 @@ impl MemeTool {
                     .labelled_by(bucket_label.id);
                     ui.end_row();
 
-                    let region_label = ui.label("S3 Region");
+                    let region_label = ui.label(t!("s3-region"));
                     ui.add(
                         egui::TextEdit::singleline(
                             &mut self.configuration.as_mut().unwrap().s3_region,
@@ -916,7 +7362,7 @@ impl MemeTool {
                     .labelled_by(region_label.id);
                     ui.end_row();
 
-                    let endpoint_label = ui.label("S3 Endpoint");
+                    let endpoint_label = ui.label(t!("s3-endpoint"));
                     let endpoint = ui
                         .add(
                             egui::TextEdit::singleline(&mut endpoint_url)
@@ -929,30 +7375,599 @@ impl MemeTool {
                             Some(endpoint_url.clone());
                     }
                     ui.end_row();
+
+                    let config = self.configuration.as_mut().unwrap();
+                    let destination_label = ui.label(t!("upload-destination"));
+                    egui::ComboBox::from_id_source("upload_destination")
+                        .selected_text(match config.upload_destination {
+                            UploadDestination::S3 => t!("upload-destination-s3"),
+                            UploadDestination::Http => t!("upload-destination-http"),
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut config.upload_destination,
+                                UploadDestination::S3,
+                                t!("upload-destination-s3"),
+                            );
+                            ui.selectable_value(
+                                &mut config.upload_destination,
+                                UploadDestination::Http,
+                                t!("upload-destination-http"),
+                            );
+                        })
+                        .response
+                        .labelled_by(destination_label.id);
+                    ui.end_row();
+
+                    if config.upload_destination == UploadDestination::Http {
+                        let url_label = ui.label(t!("http-upload-url"));
+                        let mut url = config.http_upload_url.clone().unwrap_or_default();
+                        if ui
+                            .add(
+                                egui::TextEdit::singleline(&mut url)
+                                    .hint_text("https://example.com/memes")
+                                    .desired_width(ctx.available_rect().width() * 0.7),
+                            )
+                            .labelled_by(url_label.id)
+                            .changed()
+                        {
+                            config.http_upload_url = Some(url);
+                        }
+                        ui.end_row();
+
+                        let token_label = ui.label(t!("http-upload-token"));
+                        let mut token = config.http_upload_token.clone().unwrap_or_default();
+                        if ui
+                            .add(
+                                egui::TextEdit::singleline(&mut token)
+                                    .password(true)
+                                    .desired_width(ctx.available_rect().width() * 0.7),
+                            )
+                            .labelled_by(token_label.id)
+                            .changed()
+                        {
+                            config.http_upload_token = Some(token);
+                        }
+                        ui.end_row();
+                    }
+
+                    let conflict_label = ui.label(t!("on-import-conflict"));
+                    egui::ComboBox::from_id_source("on_import_conflict")
+                        .selected_text(import_conflict_label(config.on_import_conflict))
+                        .show_ui(ui, |ui| {
+                            for choice in [
+                                ImportConflict::Ask,
+                                ImportConflict::Rename,
+                                ImportConflict::Overwrite,
+                                ImportConflict::Skip,
+                            ] {
+                                ui.selectable_value(
+                                    &mut config.on_import_conflict,
+                                    choice,
+                                    import_conflict_label(choice),
+                                );
+                            }
+                        })
+                        .response
+                        .labelled_by(conflict_label.id)
+                        .on_hover_text(t!("on-import-conflict-hint"));
+                    ui.end_row();
+
+                    ui.label(t!("thumbnail-layout"));
+                    ui.checkbox(
+                        &mut self.configuration.as_mut().unwrap().justified_layout,
+                        t!("justified-layout"),
+                    )
+                    .on_hover_text(t!("justified-layout-hint"));
+                    ui.end_row();
+
+                    ui.label(t!("selection-outline"));
+                    ui.checkbox(
+                        &mut self.configuration.as_mut().unwrap().high_contrast_selection,
+                        t!("high-contrast"),
+                    )
+                    .on_hover_text(t!("high-contrast-hint"));
+                    ui.end_row();
+
+                    ui.label(t!("colour-analysis"));
+                    ui.checkbox(
+                        &mut self.configuration.as_mut().unwrap().disable_color_analysis,
+                        t!("disable-colour-analysis"),
+                    );
+                    ui.end_row();
+
+                    ui.label(t!("advance-after-action"));
+                    ui.checkbox(
+                        &mut self.configuration.as_mut().unwrap().advance_after_action,
+                        t!("advance-after-action-hint"),
+                    );
+                    ui.end_row();
+
+                    ui.label(t!("advance-after-delete"));
+                    ui.checkbox(
+                        &mut self.configuration.as_mut().unwrap().auto_advance_on_delete,
+                        t!("advance-after-delete-hint"),
+                    );
+                    ui.end_row();
+
+                    ui.label(t!("auto-tag"));
+                    ui.horizontal(|ui| {
+                        let config = self.configuration.as_mut().unwrap();
+                        if ui
+                            .checkbox(
+                                &mut config.auto_tag_by_directory,
+                                t!("auto-tag-by-directory"),
+                            )
+                            .changed()
+                        {
+                            self.auto_tag_preview = None;
+                        }
+                        if ui.button(t!("auto-tag-dry-run")).clicked() {
+                            let filepaths: Vec<String> = self
+                                .workdir_files
+                                .iter()
+                                .map(|filepath| filepath.display().to_string())
+                                .collect();
+                            self.auto_tag_preview = tags::directory_tag(&self.workdir).map(|tag| {
+                                let count = self
+                                    .tags
+                                    .count_missing(filepaths.iter().map(String::as_str), &tag);
+                                (tag, count)
+                            });
+                        }
+                        match &self.auto_tag_preview {
+                            Some((tag, 1)) => {
+                                ui.label(t!("auto-tag-preview-one", tag = tag));
+                            }
+                            Some((tag, count)) => {
+                                ui.label(t!("auto-tag-preview", count = count, tag = tag));
+                            }
+                            None => {}
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label(t!("issue-detection"));
+                    ui.checkbox(
+                        &mut self.configuration.as_mut().unwrap().disable_issue_detection,
+                        t!("disable-issue-detection"),
+                    );
+                    ui.end_row();
+
+                    ui.label(t!("upload-keys"));
+                    ui.checkbox(
+                        &mut self.configuration.as_mut().unwrap().hash_upload_keys,
+                        t!("hash-upload-keys"),
+                    )
+                    .on_hover_text(t!("hash-upload-keys-hint"));
+                    ui.end_row();
+
+                    let retention_label = ui.label(t!("trash-retention"));
+                    let config = self.configuration.as_mut().unwrap();
+                    let mut retention_days = config.trash_retention_days();
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut retention_days)
+                                .clamp_range(0..=3650)
+                                .suffix(format!(" {}", t!("trash-retention-days"))),
+                        )
+                        .on_hover_text(t!("trash-retention-setting-hint"))
+                        .labelled_by(retention_label.id)
+                        .changed()
+                    {
+                        config.trash_retention_days = Some(retention_days);
+                    }
+                    ui.end_row();
+
+                    let eager_label = ui.label(t!("eager-rows"));
+                    let config = self.configuration.as_mut().unwrap();
+                    let mut eager_rows = config.eager_rows.unwrap_or(0);
+                    if ui
+                        .add(egui::DragValue::new(&mut eager_rows).clamp_range(0..=20))
+                        .on_hover_text(t!("eager-rows-hint"))
+                        .labelled_by(eager_label.id)
+                        .changed()
+                    {
+                        config.eager_rows = Some(eager_rows).filter(|rows| *rows > 0);
+                    }
+                    ui.end_row();
+
+                    let language_label = ui.label(t!("language"));
+                    self.language_picker(ui).labelled_by(language_label.id);
+                    ui.end_row();
                 });
         });
     }
 
-    fn do_rename(&mut self, ctx: &Context, filepath: &str, newfilename: &str) {
-        match std::fs::rename(filepath, newfilename) {
-            Ok(_) => {
-                debug!("Renamed {} to {}", filepath, newfilename);
-                self.start_update(ctx);
-                self.app_state = AppState::Editor {
-                    filepath: newfilename.to_string(),
+    /// write the config to disk, complaining if it doesn't work
+    fn save_configuration(&mut self) {
+        if let Some(config) = self.configuration.as_mut() {
+            if let Err(err) = config.save() {
+                self.push_error(t!("error-save-config", error = format!("{:?}", err)), None);
+            }
+        }
+    }
+
+    /// pick which translation to use, switching to it straight away
+    fn language_picker(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let Some(config) = self.configuration.as_mut() else {
+            return ui.label("");
+        };
+        let mut locale = config.locale.clone();
+        let selected = match &locale {
+            Some(code) => i18n::available_locales()
+                .find(|(available, _)| available == code)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| code.clone()),
+            None => t!("language-system"),
+        };
+        let response = egui::ComboBox::from_id_source("config_language")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut locale, None, t!("language-system"));
+                for (code, name) in i18n::available_locales() {
+                    ui.selectable_value(&mut locale, Some(code.to_string()), name);
+                }
+            })
+            .response;
+        if locale != config.locale {
+            i18n::set_locale(locale.as_deref());
+            config.locale = locale;
+        }
+        response
+    }
+
+    /// whether the bucket's reachable, checking when the config screen's first shown
+    fn bucket_status_row(&mut self, ui: &mut egui::Ui) {
+        if self.bucket_status.is_none() && !self.bucket_check_pending {
+            self.bucket_check_pending = true;
+            self.sendmessage(AppMsg::CheckBucket);
+        }
+        ui.horizontal(|ui| {
+            ui.label(t!("bucket"));
+            match &self.bucket_status {
+                None => {
+                    ui.spinner();
+                    ui.label(t!("checking"));
                 }
+                Some(Ok(())) => {
+                    ui.label(t!("bucket-accessible"));
+                }
+                Some(Err(err)) => {
+                    ui.label(
+                        RichText::new(t!("bucket-not-accessible", error = err))
+                            .color(ui.visuals().error_fg_color),
+                    );
+                }
+            }
+            // the backend reads the config from disk, so save it before checking
+            if ui
+                .add_enabled(!self.bucket_check_pending, egui::Button::new(t!("recheck")))
+                .clicked()
+            {
+                self.save_configuration();
+                self.bucket_status = None;
+            }
+        });
+    }
+
+    /// the S3 key `filepath` was uploaded as and what it'd be called after renaming it to
+    /// `newfilepath`, if it was uploaded under its filename
+    fn s3_rename_keys(&self, filepath: &str, newfilepath: &str) -> Option<(String, String)> {
+        let src_key = self.upload_history.get(filepath)?.key.clone();
+        let dest_key = s3_upload::renamed_key(&src_key, filepath, newfilepath)?;
+        Some((src_key, dest_key))
+    }
+
+    fn do_rename(&mut self, ctx: &Context, filepath: &str, newfilename: &str) {
+        let target = file_ops::expand_path(newfilename);
+        // it could've changed since the editor checked it
+        if let Some(problem) = file_ops::rename_problem(Path::new(filepath), &target) {
+            self.push_error(
+                t!("error-rename", error = problem),
+                Some(Box::new(AppState::Editor {
+                    filepath: filepath.to_string(),
+                })),
+            );
+            return;
+        }
+        let newfilename = target.display().to_string();
+        let result = match file_ops::is_case_only_rename(Path::new(filepath), &target) {
+            true => file_ops::rename_case_only(Path::new(filepath), &target),
+            false => std::fs::rename(filepath, &target),
+        };
+        match result {
+            Ok(_) => self.renamed(ctx, filepath, &newfilename),
+            Err(err) if file_ops::is_cross_device(&err) => {
+                info!(
+                    "{} is on another filesystem, copying {} there instead",
+                    newfilename, filepath
+                );
+                let total = std::fs::metadata(filepath)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0);
+                self.rename_move = Some(RenameMove {
+                    from: filepath.to_string(),
+                    done: 0,
+                    total,
+                });
+                self.sendmessage(AppMsg::MoveFile {
+                    from: filepath.to_string(),
+                    to: newfilename,
+                });
             }
             Err(err) => {
-                self.app_state = AppState::ShowError {
-                    message: format!("Failed to rename file: {:?}", err),
-                    next_state: Some(Box::new(AppState::Editor {
+                self.push_error(
+                    t!("error-rename", error = format!("{:?}", err)),
+                    Some(Box::new(AppState::Editor {
                         filepath: filepath.to_string(),
                     })),
+                );
+            }
+        }
+    }
+
+    /// `filepath`'s now at `newfilename`, so catch everything else up and show it in the editor
+    fn renamed(&mut self, ctx: &Context, filepath: &str, newfilename: &str) {
+        debug!("Renamed {} to {}", filepath, newfilename);
+        // a rename that stays put is still the same file, moving it somewhere else is filing it
+        let moved = Path::new(filepath).parent() != Path::new(newfilename).parent();
+        self.rename_move_confirmed = None;
+        let next = self.next_in_list(filepath);
+        if self.rename_in_s3 {
+            if let Some((src_key, dest_key)) = self.s3_rename_keys(filepath, newfilename) {
+                self.sendmessage(AppMsg::S3RenameObject {
+                    old_filepath: filepath.to_string(),
+                    filepath: newfilename.to_string(),
+                    src_key,
+                    dest_key,
+                });
+            }
+        }
+        self.tags.move_file(filepath, newfilename);
+        if let Err(err) = self.tags.save(&tags_path()) {
+            error!("Failed to save tags: {:?}", err);
+        }
+        self.start_update(ctx);
+        self.forget_file(filepath);
+        self.browser_images.remove(filepath);
+        // the browser keys everything on the path, so a change of case is a different file to it
+        if self.browser_context.selected_file.as_deref() == Some(filepath) {
+            self.browser_context.selected_file = Some(newfilename.to_string());
+        }
+        if moved {
+            let to_dir = Path::new(newfilename).parent().unwrap_or(Path::new(""));
+            self.push_toast_link(
+                t!("renamed-moved-to", path = file_ops::home_relative(to_dir)),
+                PathBuf::from(newfilename),
+            );
+        }
+        if moved && self.advance_after_action() {
+            self.advance_to(next);
+        } else {
+            self.navigation.replace(AppState::Editor {
+                filepath: newfilename.to_string(),
+            })
+        }
+    }
+
+    /// copy `filepath` next to itself and open the copy in the editor, back goes to the original
+    fn copy_image_to_workdir(&mut self, ctx: &Context, filepath: &str) {
+        let Some(new_path) = image_utils::duplicate_path_for(std::path::Path::new(filepath)) else {
+            self.push_error(t!("error-no-free-name", path = filepath), None);
+            return;
+        };
+        match std::fs::copy(filepath, &new_path) {
+            Ok(_) => {
+                info!("Duplicated {} as {}", filepath, new_path.display());
+                self.start_update(ctx);
+                self.editor_rename_target = String::new();
+                self.editor_image_cache = None;
+                let filepath = new_path.display().to_string();
+                self.browser_context.selected_file = Some(filepath.clone());
+                self.push_state(AppState::Editor { filepath });
+            }
+            Err(err) => {
+                self.push_error(
+                    t!(
+                        "error-duplicate",
+                        path = new_path.display(),
+                        error = format!("{:?}", err)
+                    ),
+                    None,
+                );
+            }
+        }
+    }
+
+    /// directories go in the browser and files go in the editor, then the window's raised since
+    /// they've probably just double-clicked something in a file manager
+    pub fn open_path(&mut self, ctx: &egui::Context, path: PathBuf) {
+        info!("Opening {}", path.display());
+        if path.is_dir() {
+            self.set_workdir(path.display().to_string());
+        } else if path.is_file() {
+            self.open_file_in_editor(&path);
+        } else {
+            self.push_error(t!("error-open-path", path = path.display()), None);
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    }
+
+    /// show `path` in the editor, with the directory it's in as the workdir
+    fn open_file_in_editor(&mut self, path: &Path) {
+        if let Some(parent) = path.parent() {
+            let parent = parent.display().to_string();
+            if parent != self.workdir {
+                self.set_workdir(parent);
+            }
+        }
+        let filepath = path.display().to_string();
+        self.editor_image_cache = None;
+        self.editor_rename_target = String::new();
+        self.browser_context.selected_file = Some(filepath.clone());
+        self.push_state(AppState::Editor { filepath });
+    }
+
+    /// show the active tab, picking up where it left off
+    fn load_tab(&mut self) {
+        let tab = self.tabs.active().clone();
+        debug!("Showing {} for {}", tab.id, tab.workdir);
+        self.workdir = tab.workdir.clone();
+        self.workdir_input = tab.workdir;
+        self.search_box = tab.search;
+        self.pending_scroll_offset = Some(tab.context.scroll_offset);
+        self.browser_context = tab.context;
+        self.custom_order.clear();
+        self.list_changed();
+        self.selected_images.clear();
+        self.requested_thumbnails.clear();
+        self.page_load_stats = None;
+        self.files_list_mtime = None;
+        self.navigation.clear_history();
+        self.browser_new_page();
+    }
+
+    /// put what the browser's showing back into the active tab, before leaving it or saving
+    fn stash_tab(&mut self) {
+        let tab = self.tabs.active_mut();
+        tab.workdir = self.workdir.clone();
+        tab.search = self.search_box.clone();
+        tab.context = self.browser_context.clone();
+    }
+
+    /// remember the tabs for next time
+    fn save_tabs(&mut self) {
+        self.stash_tab();
+        if let Some(config) = self.configuration.as_mut() {
+            config.tabs = self.tabs.tabs().to_vec();
+            config.active_tab = self.tabs.active_index();
+            if let Err(err) = config.save() {
+                error!("Failed to save tabs: {:?}", err);
+            }
+        }
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.tabs.active_index() {
+            return;
+        }
+        self.stash_tab();
+        if self.tabs.switch(index) {
+            self.load_tab();
+            self.save_tabs();
+        }
+    }
+
+    /// ask for a directory and open it in a new tab
+    fn new_tab(&mut self) {
+        let start = PathBuf::from(shellexpand::tilde(&self.workdir).as_ref());
+        let Some(folder) = rfd::FileDialog::new().set_directory(start).pick_folder() else {
+            return;
+        };
+        self.stash_tab();
+        self.tabs.open(WorkdirTab::new(folder.display()));
+        self.load_tab();
+        self.save_tabs();
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        let was_active = index == self.tabs.active_index();
+        if was_active {
+            self.stash_tab();
+        }
+        match self.tabs.close(index) {
+            Some(closed) => {
+                debug!("Closed {} for {}", closed.id, closed.workdir);
+                if was_active {
+                    self.load_tab();
+                }
+                self.save_tabs();
+            }
+            None => debug!("Not closing the last tab"),
+        }
+    }
+
+    /// a spinner while a big workdir's being read in the background
+    fn scan_progress(&self, ui: &mut egui::Ui) {
+        let Some(scan) = self
+            .dir_scan
+            .as_ref()
+            .filter(|scan| scan.workdir == self.workdir)
+        else {
+            return;
+        };
+        let found = match scan.stream {
+            true => self.workdir_files.len(),
+            false => scan.found.len(),
+        };
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(t!("scanning-directory", count = found));
+        });
+        ui.ctx().request_repaint_after(Duration::from_millis(100));
+    }
+
+    /// the row of tabs above the browser
+    fn tab_bar(&mut self, ui: &mut egui::Ui) {
+        let mut switch_to = None;
+        let mut close = None;
+        ui.horizontal(|ui| {
+            let can_close = self.tabs.len() > 1;
+            for (index, tab) in self.tabs.tabs().iter().enumerate() {
+                let active = index == self.tabs.active_index();
+                if ui
+                    .selectable_label(active, tab.label())
+                    .on_hover_text(&tab.workdir)
+                    .clicked()
+                {
+                    switch_to = Some(index);
                 }
+                if can_close
+                    && ui
+                        .small_button("×")
+                        .on_hover_text(t!("close-tab"))
+                        .clicked()
+                {
+                    close = Some(index);
+                }
+                ui.separator();
             }
+            if ui.small_button("+").on_hover_text(t!("new-tab")).clicked() {
+                self.new_tab();
+            }
+        });
+        if let Some(index) = close {
+            self.close_tab(index);
+        } else if let Some(index) = switch_to {
+            self.switch_tab(index);
         }
     }
 
+    /// switch the browser to a different directory
+    pub fn set_workdir(&mut self, path: String) {
+        let resolvedpath = PathBuf::from(shellexpand::tilde(&path).as_ref());
+        if !resolvedpath.is_dir() {
+            self.workdir_input = self.workdir.clone();
+            self.push_error(t!("error-not-a-directory", path = path), None);
+            return;
+        }
+        info!("Changing workdir to {}", path);
+        self.workdir = path.clone();
+        self.workdir_input = path;
+        self.browser_context = BrowserContext::default();
+        self.pending_scroll_offset = Some(0.0);
+        self.custom_order.clear();
+        self.list_changed();
+        self.selected_images.clear();
+        self.page_load_stats = None;
+        self.files_list_mtime = None;
+        debug!("Workdir changed, clearing navigation history");
+        self.navigation.clear_history();
+        self.browser_new_page();
+        self.save_tabs();
+    }
+
     /// force-update the browser view
     fn browser_new_page(&mut self) {
         self.search_box_last = None;
@@ -963,8 +7978,8 @@ impl MemeTool {
     /// take you to the previous page
     fn browser_prev_page(&mut self) {
         debug!("Prev page clicked");
-        if self.current_page > 0 {
-            self.current_page -= 1;
+        if self.browser_context.current_page > 0 {
+            self.browser_context.current_page -= 1;
         }
         self.browser_new_page();
     }
@@ -972,13 +7987,13 @@ impl MemeTool {
     /// take you to the next page
     fn browser_next_page(&mut self) {
         debug!("Next page clicked");
-        if self.current_page < (self.files_list.len() / self.per_page) {
-            self.current_page += 1;
+        if self.browser_context.current_page < (self.files_list.len() / self.per_page) {
+            self.browser_context.current_page += 1;
         } else {
-            if self.current_page * self.per_page > self.files_list.len() {
+            if self.browser_context.current_page * self.per_page > self.files_list.len() {
                 error!(
                     "Current page={} Per page={} Files list len={}",
-                    self.current_page,
+                    self.browser_context.current_page,
                     self.per_page,
                     self.files_list.len()
                 );
@@ -991,17 +8006,46 @@ impl MemeTool {
     /// take you to the first page
     fn browser_first_page(&mut self) {
         debug!("First page clicked");
-        self.current_page = 0;
+        self.browser_context.current_page = 0;
+        if self.infinite_scroll {
+            self.pending_scroll_offset = Some(0.0);
+        }
         self.browser_new_page();
     }
 
-    /// send a message using the internal broadcast channel
-    fn sendmessage(&mut self, msg: AppMsg) {
-        let tx = self.background_tx.clone();
-        tokio::spawn(async move {
-            if let Err(err) = tx.send(msg).await {
-                error!("Failed to send background message: {}", err.to_string());
-            };
+    /// take you to the last page
+    fn browser_last_page(&mut self) {
+        debug!("Last page requested");
+        let files = self.files_list.len();
+        self.browser_context.current_page = files.saturating_sub(1) / self.per_page.max(1);
+        if self.infinite_scroll {
+            let rows = (files + self.grid_columns - 1) / self.grid_columns;
+            self.pending_scroll_offset = Some(rows as f32 * self.infinite_row_stride());
+        }
+        self.browser_new_page();
+    }
+
+    /// send a message to the background task, tracking it if it'll get a response. If the
+    /// channel's full it waits in [MemeTool::outbox] behind anything else that's waiting.
+    fn sendmessage(&mut self, mut msg: AppMsg) {
+        msg.track(&mut self.tasks);
+        self.outbox.push(msg);
+        self.outbox.flush(&self.background_tx);
+    }
+
+    /// a bar along the bottom while messages are stuck waiting for the background
+    fn backpressure_bar(&self, ui: &mut egui::Ui) {
+        if !self.outbox.is_backed_up() {
+            return;
+        }
+        egui::TopBottomPanel::bottom("backpressure_bar").show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(
+                    RichText::new(t!("channel-full", count = self.outbox.pending()))
+                        .color(ui.visuals().warn_fg_color),
+                );
+            });
         });
     }
 }
@@ -0,0 +1,199 @@
+//! What thumbnails cost to make, added up per page for the browser's footer
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// If nothing's been loaded for this long, the next thumbnail's the start of a new page load even
+/// if it's the same page, eg after a refresh
+pub const PAGE_STATS_IDLE: Duration = Duration::from_secs(2);
+
+/// What decoding one file for a thumbnail took
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+    /// How big the file was
+    pub source_bytes: u64,
+    /// How many pixels the thumbnail has
+    pub pixels: u64,
+    /// Decoding, rotating and resizing, but not reading the file
+    pub decode_time: Duration,
+}
+
+/// Everything loaded for a page so far
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PageLoadStats {
+    pub page: usize,
+    pub files: usize,
+    /// From the first thumbnail being started to the last one being done
+    pub elapsed: Duration,
+    pub source_bytes: u64,
+    pub pixels: u64,
+    /// Added up across all the files, they're decoded one at a time
+    pub decode_time: Duration,
+    /// The file that took longest to decode, and how long it took
+    pub slowest: Option<(String, Duration)>,
+}
+
+impl PageLoadStats {
+    /// how long each file took to decode, on average
+    pub fn average_decode_time(&self) -> Duration {
+        match u32::try_from(self.files) {
+            Ok(files) if files > 0 => self.decode_time / files,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+/// Adds up [DecodeStats] as thumbnails come through the background, starting again when the page
+/// changes or things have gone quiet for [PAGE_STATS_IDLE]
+#[derive(Debug, Default)]
+pub struct PageStatsCollector {
+    stats: PageLoadStats,
+    started: Option<Instant>,
+    last_finished: Option<Instant>,
+}
+
+impl PageStatsCollector {
+    /// add a thumbnail for `page` that was started at `started`, returning the page's totals
+    pub fn record(
+        &mut self,
+        page: usize,
+        filepath: &str,
+        started: Instant,
+        decode: DecodeStats,
+    ) -> PageLoadStats {
+        let idle = self
+            .last_finished
+            .map(|finished| started.saturating_duration_since(finished) > PAGE_STATS_IDLE)
+            .unwrap_or(true);
+        if idle || page != self.stats.page {
+            *self = Self {
+                stats: PageLoadStats {
+                    page,
+                    ..Default::default()
+                },
+                started: Some(started),
+                ..Default::default()
+            };
+        }
+        let now = Instant::now();
+        self.last_finished = Some(now);
+
+        let stats = &mut self.stats;
+        stats.files += 1;
+        stats.source_bytes += decode.source_bytes;
+        stats.pixels += decode.pixels;
+        stats.decode_time += decode.decode_time;
+        stats.elapsed = now.saturating_duration_since(self.started.unwrap_or(started));
+        let slower = stats
+            .slowest
+            .as_ref()
+            .map(|(_, slowest)| decode.decode_time > *slowest)
+            .unwrap_or(true);
+        if slower {
+            stats.slowest = Some((filepath.to_string(), decode.decode_time));
+        }
+        stats.clone()
+    }
+}
+
+/// How one round of asking for a page's thumbnails went, a round starts each time the browser
+/// updates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThumbLoadStats {
+    /// Everything on the page
+    pub total: usize,
+    pub loaded: usize,
+    pub failed: usize,
+    /// Already had a thumbnail, so it wasn't asked for
+    pub skipped_cached: usize,
+    /// From asking for the first one to the last one coming back
+    pub duration_ms: u64,
+    /// From asking for the first one to the top row being done, if it was being watched
+    pub first_row_ms: Option<u64>,
+}
+
+impl ThumbLoadStats {
+    pub fn is_complete(&self) -> bool {
+        self.loaded + self.failed + self.skipped_cached == self.total
+    }
+}
+
+/// Counts the thumbnails coming back for the latest round, starting another round drops
+/// whatever was still outstanding from the last one
+#[derive(Debug, Default)]
+pub struct ThumbLoadTracker {
+    stats: ThumbLoadStats,
+    started: Option<Instant>,
+    pending: HashSet<String>,
+    /// What's still to come back from the top row
+    first_row: HashSet<String>,
+}
+
+impl ThumbLoadTracker {
+    /// a new round asking for `requested`, returns the stats straight away if there's nothing to
+    /// wait for
+    pub fn start(
+        &mut self,
+        requested: impl IntoIterator<Item = String>,
+        skipped_cached: usize,
+    ) -> Option<ThumbLoadStats> {
+        self.pending = requested.into_iter().collect();
+        self.stats = ThumbLoadStats {
+            total: self.pending.len() + skipped_cached,
+            skipped_cached,
+            ..Default::default()
+        };
+        self.started = Some(Instant::now());
+        self.first_row.clear();
+        self.finish_if_complete()
+    }
+
+    /// time how long the thumbnails in the top row of this round take, any that weren't asked for
+    /// are already there
+    pub fn watch_first_row(&mut self, first_row: impl IntoIterator<Item = String>) {
+        self.first_row = first_row
+            .into_iter()
+            .filter(|filepath| self.pending.contains(filepath))
+            .collect();
+        if self.first_row.is_empty() {
+            self.stats.first_row_ms = Some(0);
+        }
+    }
+
+    /// `filepath`'s thumbnail arrived, returns the stats if that was the last one
+    pub fn loaded(&mut self, filepath: &str) -> Option<ThumbLoadStats> {
+        if !self.pending.remove(filepath) {
+            return None;
+        }
+        self.first_row_done(filepath);
+        self.stats.loaded += 1;
+        self.finish_if_complete()
+    }
+
+    /// `filepath` couldn't be made into a thumbnail, returns the stats if that was the last one
+    pub fn failed(&mut self, filepath: &str) -> Option<ThumbLoadStats> {
+        if !self.pending.remove(filepath) {
+            return None;
+        }
+        self.first_row_done(filepath);
+        self.stats.failed += 1;
+        self.finish_if_complete()
+    }
+
+    fn first_row_done(&mut self, filepath: &str) {
+        if self.first_row.remove(filepath) && self.first_row.is_empty() {
+            if let Some(started) = self.started {
+                self.stats.first_row_ms = Some(started.elapsed().as_millis() as u64);
+            }
+        }
+    }
+
+    fn finish_if_complete(&mut self) -> Option<ThumbLoadStats> {
+        if !self.stats.is_complete() {
+            return None;
+        }
+        let started = self.started.take()?;
+        self.stats.duration_ms = started.elapsed().as_millis() as u64;
+        Some(self.stats)
+    }
+}
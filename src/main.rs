@@ -4,6 +4,9 @@ use eframe::egui;
 use eframe::epaint::Vec2;
 use memetool::{THUMBNAIL_SIZE, GRID_X, GRID_Y};
 use memetool::background::background;
+use memetool::config::Configuration;
+use memetool::AppMsg;
+use log::error;
 use tokio::runtime::Runtime;
 
 
@@ -11,13 +14,36 @@ fn main() -> Result<(), eframe::Error> {
     if std::env::var("RUST_LOG").is_err() {
         std::env::set_var("RUST_LOG", "INFO");
     }
+    pretty_env_logger::init();
+
+    // memetool <path>, eg when it's the "open with" for images
+    let open_path = std::env::args_os().nth(1).map(std::path::PathBuf::from);
+    if let Some(path) = &open_path {
+        match memetool::single_instance::forward_to_running(path) {
+            // the running one's got it, nothing more to do
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            Err(err) => error!("Failed to send {} to the running memetool: {:?}", path.display(), err),
+        }
+    }
 
     let rt = Runtime::new().expect("Unable to create Runtime");
     // Enter the runtime so that `tokio::spawn` is available immediately.
     let _enter = rt.enter();
 
-    let (foreground_tx, foreground_rx) = tokio::sync::mpsc::channel(100);
-    let (background_tx, background_rx) = tokio::sync::mpsc::channel(100);
+    // if it's not there yet, start with the defaults and the configuration screen can save them
+    let configuration = Configuration::try_new_or_default();
+
+    let (foreground_tx, foreground_rx) = tokio::sync::mpsc::channel(configuration.channel_capacity());
+    let (background_tx, background_rx) = tokio::sync::mpsc::channel(configuration.channel_capacity());
+
+    // images open straight in the editor when the app starts, see MemeTool::handle_external_file_arg
+    if let Some(path) = open_path.filter(|path| memetool::external_file_state(path).is_none()) {
+        // the frontend picks this up along with everything else from the background
+        if let Err(err) = foreground_tx.try_send(AppMsg::OpenPath(path)) {
+            error!("Failed to open the path from the command line: {:?}", err);
+        }
+    }
 
     // Execute the runtime in its own thread.
     rt.spawn(background(background_rx, foreground_tx));
@@ -72,6 +98,13 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "memetool",
         options,
-        Box::new(|cc| Box::new(memetool::MemeTool::new(cc, foreground_rx, background_tx))),
+        Box::new(|cc| {
+            Box::new(memetool::MemeTool::new_with_config(
+                cc,
+                foreground_rx,
+                background_tx,
+                Some(configuration),
+            ))
+        }),
     )
 }
@@ -0,0 +1,110 @@
+//! Which screen the app's on and how it got there
+
+use std::sync::Arc;
+
+use crate::{AppState, MAX_HISTORY_DEPTH};
+
+/// The current [AppState] plus the history behind it.
+///
+/// Each frame draws from a [Navigation::frame_state] snapshot, so a state change made while a
+/// screen's being drawn takes effect on the next frame rather than halfway through this one.
+/// States are kept behind an [Arc] so taking that snapshot doesn't copy any paths.
+#[derive(Debug)]
+pub struct Navigation {
+    current: Arc<AppState>,
+    history: Vec<Arc<AppState>>,
+    /// Where to go when the help screen's closed
+    previous_state_for_help: Option<AppState>,
+}
+
+impl Default for Navigation {
+    fn default() -> Self {
+        Self::new(AppState::Browser)
+    }
+}
+
+impl Navigation {
+    pub fn new(state: AppState) -> Self {
+        Self {
+            current: Arc::new(state),
+            history: vec![],
+            previous_state_for_help: None,
+        }
+    }
+
+    /// the state right now, including any changes made this frame
+    pub fn current(&self) -> &AppState {
+        &self.current
+    }
+
+    /// the state to draw this frame, it won't change if the state does while it's being drawn
+    pub fn frame_state(&self) -> Arc<AppState> {
+        self.current.clone()
+    }
+
+    /// where the back button would go, if there's anywhere
+    pub fn previous(&self) -> Option<&AppState> {
+        self.history.last().map(|state| state.as_ref())
+    }
+
+    /// how many states the back button can go through
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// move to a new state without remembering the current one
+    pub fn replace(&mut self, state: AppState) {
+        self.current = Arc::new(state);
+    }
+
+    /// move to a new state, remembering the current one so we can come back to it
+    pub fn push(&mut self, state: AppState) {
+        if *self.current == state {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.current, Arc::new(state));
+        self.history.push(previous);
+        if self.history.len() > MAX_HISTORY_DEPTH {
+            self.history.remove(0);
+        }
+    }
+
+    /// go back to the last state, or the browser if there's nothing to go back to
+    pub fn pop(&mut self) -> &AppState {
+        self.current = self
+            .history
+            .pop()
+            .unwrap_or_else(|| Arc::new(AppState::Browser));
+        &self.current
+    }
+
+    /// switch to the help screen, remembering where we came from
+    pub fn show_help(&mut self) {
+        if let AppState::Help = *self.current {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.current, Arc::new(AppState::Help));
+        // copied if this frame's still drawing it, that's once per trip to help
+        self.previous_state_for_help =
+            Some(Arc::try_unwrap(previous).unwrap_or_else(|state| (*state).clone()));
+    }
+
+    /// leave the help screen and go back to whatever was showing before
+    pub fn close_help(&mut self) {
+        self.current = Arc::new(
+            self.previous_state_for_help
+                .take()
+                .unwrap_or(AppState::Browser),
+        );
+    }
+
+    /// drop any history pointing at a file that's been moved or deleted
+    pub fn forget_file(&mut self, filepath: &str) {
+        self.history
+            .retain(|state| state.filepath() != Some(filepath));
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
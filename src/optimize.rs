@@ -0,0 +1,269 @@
+//! Making images smaller on disk, one file at a time for [crate::jobs]
+//!
+//! Everything's encoded in memory first so nothing's written if it'd make the file bigger.
+//! Re-encoding drops EXIF, so the orientation's applied to the pixels before saving.
+
+use std::path::{Path, PathBuf};
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{DynamicImage, ImageEncoder, ImageFormat};
+use log::*;
+
+use crate::image_utils::{apply_exif_orientation, decode_image, exif_orientation};
+
+/// What JPEGs are saved at
+pub const JPEG_QUALITY: u8 = 85;
+/// The box [OptimizeAction::Resize] fits images into to start with
+pub const RESIZE_MAX_SIDE: u32 = 1600;
+
+/// What a batch optimize does to each file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptimizeAction {
+    /// Save JPEGs and PNGs again with better compression, over the top of the original
+    Optimize,
+    /// Save a JPEG at [JPEG_QUALITY] next to the original, see [crate::image_utils::jpeg_path_for]
+    ConvertToJpeg,
+    /// Shrink anything that doesn't fit in `max_width` by `max_height`, over the top of the
+    /// original. JPEGs are saved at `quality`.
+    Resize {
+        max_width: u32,
+        max_height: u32,
+        quality: u8,
+    },
+}
+
+impl OptimizeAction {
+    pub const ALL: [OptimizeAction; 3] = [
+        OptimizeAction::Optimize,
+        OptimizeAction::ConvertToJpeg,
+        OptimizeAction::Resize {
+            max_width: RESIZE_MAX_SIDE,
+            max_height: RESIZE_MAX_SIDE,
+            quality: JPEG_QUALITY,
+        },
+    ];
+
+    /// for the batch optimize window
+    pub fn label(&self) -> String {
+        match self {
+            OptimizeAction::Optimize => crate::t!("optimize-in-place"),
+            OptimizeAction::ConvertToJpeg => {
+                crate::t!("optimize-convert-jpeg", quality = JPEG_QUALITY)
+            }
+            OptimizeAction::Resize {
+                max_width,
+                max_height,
+                ..
+            } => crate::t!("optimize-resize", width = max_width, height = max_height),
+        }
+    }
+
+    /// where the result's saved, `None` if it goes over the original
+    pub fn destination(&self, path: &Path) -> Option<PathBuf> {
+        match self {
+            OptimizeAction::ConvertToJpeg => Some(crate::image_utils::jpeg_path_for(path)),
+            OptimizeAction::Optimize | OptimizeAction::Resize { .. } => None,
+        }
+    }
+
+    /// why `path` won't be touched, going by its name, or `None` if it's worth a try. The
+    /// batch optimize window shows this before anything's done.
+    pub fn skip_reason(&self, path: &Path) -> Option<String> {
+        let format = ImageFormat::from_path(path).ok();
+        match self {
+            OptimizeAction::ConvertToJpeg if format == Some(ImageFormat::Jpeg) => {
+                Some(crate::t!("optimize-skip-already-jpeg"))
+            }
+            OptimizeAction::ConvertToJpeg => None,
+            OptimizeAction::Optimize | OptimizeAction::Resize { .. } => match format {
+                Some(ImageFormat::Jpeg | ImageFormat::Png) => None,
+                _ => Some(crate::t!("optimize-skip-format")),
+            },
+        }
+    }
+}
+
+/// How one file went
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptimizeOutcome {
+    /// Saved, and how big it was before and after
+    Saved {
+        destination: PathBuf,
+        bytes_before: u64,
+        bytes_after: u64,
+    },
+    /// Left alone, and why
+    Skipped(String),
+}
+
+/// do `action` to `path`, blocking while it decodes and encodes
+pub fn optimize_file(path: &Path, action: OptimizeAction) -> Result<OptimizeOutcome, String> {
+    puffin::profile_function!(path.display().to_string());
+    if let Some(reason) = action.skip_reason(path) {
+        return Ok(OptimizeOutcome::Skipped(reason));
+    }
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let bytes_before = data.len() as u64;
+    let image = decode_image(path, &data)?;
+    let image = match exif_orientation(&data) {
+        Some(orientation) => apply_exif_orientation(image, orientation),
+        None => image,
+    };
+
+    let (image, format, quality) = match action {
+        OptimizeAction::Optimize => (image, same_format(path)?, JPEG_QUALITY),
+        OptimizeAction::ConvertToJpeg => (image, ImageFormat::Jpeg, JPEG_QUALITY),
+        OptimizeAction::Resize {
+            max_width,
+            max_height,
+            quality,
+        } => {
+            if image.width() <= max_width && image.height() <= max_height {
+                return Ok(OptimizeOutcome::Skipped(crate::t!(
+                    "optimize-skip-small-enough",
+                    width = image.width(),
+                    height = image.height()
+                )));
+            }
+            // keeps the aspect ratio, fitting inside the box
+            let resized =
+                image.resize(max_width, max_height, image::imageops::FilterType::Lanczos3);
+            (resized, same_format(path)?, quality)
+        }
+    };
+
+    let encoded = encode_with_quality(&image, format, quality)?;
+    let bytes_after = encoded.len() as u64;
+    if bytes_after >= bytes_before {
+        return Ok(OptimizeOutcome::Skipped(crate::t!(
+            "optimize-skip-would-grow",
+            before = humansize::format_size(bytes_before, humansize::DECIMAL),
+            after = humansize::format_size(bytes_after, humansize::DECIMAL)
+        )));
+    }
+
+    let destination = action
+        .destination(path)
+        .unwrap_or_else(|| path.to_path_buf());
+    if destination != path && destination.exists() {
+        return Err(format!("{} already exists", destination.display()));
+    }
+    write_replacing(&destination, &encoded)?;
+    info!(
+        "{:?} {} to {}, {} => {} bytes",
+        action,
+        path.display(),
+        destination.display(),
+        bytes_before,
+        bytes_after
+    );
+    Ok(OptimizeOutcome::Saved {
+        destination,
+        bytes_before,
+        bytes_after,
+    })
+}
+
+/// shrink each of `files` to fit in `max_width` by `max_height`, one at a time off the async
+/// workers, with one result for each. Ones that are already small enough count as done. This is
+/// for when there's no need for a [crate::jobs] job's progress or cancelling.
+pub async fn batch_resize(
+    files: &[PathBuf],
+    max_width: u32,
+    max_height: u32,
+    quality: u8,
+) -> Vec<Result<(), String>> {
+    let action = OptimizeAction::Resize {
+        max_width,
+        max_height,
+        quality,
+    };
+    let mut results = Vec::with_capacity(files.len());
+    for path in files {
+        let path = path.clone();
+        let result = match tokio::task::spawn_blocking(move || optimize_file(&path, action)).await {
+            Ok(outcome) => outcome.map(|_| ()),
+            Err(err) => Err(format!("{:?}", err)),
+        };
+        results.push(result);
+    }
+    results
+}
+
+/// the format `path` is already in, if we can write it
+fn same_format(path: &Path) -> Result<ImageFormat, String> {
+    match ImageFormat::from_path(path) {
+        Ok(format @ (ImageFormat::Jpeg | ImageFormat::Png)) => Ok(format),
+        _ => Err(crate::t!("optimize-skip-format")),
+    }
+}
+
+/// JPEGs at [JPEG_QUALITY] without transparency, PNGs with the best compression
+pub fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, String> {
+    encode_with_quality(image, format, JPEG_QUALITY)
+}
+
+/// like [encode], with JPEGs at `quality` out of 100
+pub fn encode_with_quality(
+    image: &DynamicImage,
+    format: ImageFormat,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let mut encoded = vec![];
+    match format {
+        ImageFormat::Jpeg => {
+            let rgb = image.to_rgb8();
+            JpegEncoder::new_with_quality(&mut encoded, quality.clamp(1, 100))
+                .write_image(
+                    rgb.as_raw(),
+                    rgb.width(),
+                    rgb.height(),
+                    image::ColorType::Rgb8,
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        ImageFormat::Png => {
+            let encoder = PngEncoder::new_with_quality(
+                &mut encoded,
+                CompressionType::Best,
+                FilterType::Adaptive,
+            );
+            if image.color().has_alpha() {
+                let rgba = image.to_rgba8();
+                encoder.write_image(
+                    rgba.as_raw(),
+                    rgba.width(),
+                    rgba.height(),
+                    image::ColorType::Rgba8,
+                )
+            } else {
+                let rgb = image.to_rgb8();
+                encoder.write_image(
+                    rgb.as_raw(),
+                    rgb.width(),
+                    rgb.height(),
+                    image::ColorType::Rgb8,
+                )
+            }
+            .map_err(|e| e.to_string())?;
+        }
+        other => return Err(format!("Can't save {:?} images", other)),
+    }
+    Ok(encoded)
+}
+
+/// write to a temporary file next to `path` then move it over, so a failure part way through
+/// doesn't leave half an image
+fn write_replacing(path: &Path, data: &[u8]) -> Result<(), String> {
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("{} doesn't have a filename", path.display()))?;
+    let temp = path.with_file_name(format!(".{filename}.memetool-tmp"));
+    std::fs::write(&temp, data).map_err(|e| e.to_string())?;
+    std::fs::rename(&temp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        e.to_string()
+    })
+}
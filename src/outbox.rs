@@ -0,0 +1,67 @@
+//! Messages on their way to the background task. They're queued up and sent in order each frame,
+//! so when the channel's full they wait here where they can be counted, rather than getting lost
+//! or overtaking each other.
+
+use std::collections::VecDeque;
+
+use log::*;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::Sender;
+
+use crate::AppMsg;
+
+/// What's waiting to go to the background, oldest first
+#[derive(Debug, Default)]
+pub struct Outbox {
+    queue: VecDeque<AppMsg>,
+    /// if the channel was full the last time it was flushed
+    backed_up: bool,
+}
+
+impl Outbox {
+    pub fn push(&mut self, msg: AppMsg) {
+        self.queue.push_back(msg);
+    }
+
+    /// send as much as `tx` has room for, in the order it was pushed, returns how many went
+    pub fn flush(&mut self, tx: &Sender<AppMsg>) -> usize {
+        let mut sent = 0;
+        while let Some(msg) = self.queue.pop_front() {
+            match tx.try_send(msg) {
+                Ok(()) => sent += 1,
+                Err(TrySendError::Full(msg)) => {
+                    self.queue.push_front(msg);
+                    if !self.backed_up {
+                        warn!(
+                            "Background channel's full, {} messages waiting",
+                            self.queue.len()
+                        );
+                    }
+                    self.backed_up = true;
+                    return sent;
+                }
+                Err(TrySendError::Closed(msg)) => {
+                    error!(
+                        "Background task's gone, dropping {:?} and {} more",
+                        msg,
+                        self.queue.len()
+                    );
+                    self.queue.clear();
+                    break;
+                }
+            }
+        }
+        self.backed_up = false;
+        sent
+    }
+
+    /// how many are still waiting
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// if the channel was full last time, so there's a backlog worth mentioning
+    pub fn is_backed_up(&self) -> bool {
+        self.backed_up
+    }
+}
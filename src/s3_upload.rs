@@ -1,28 +1,435 @@
 //! S3 things
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
 use anyhow::Result;
+use async_trait::async_trait;
+use aws_sdk_s3::config::http::HttpResponse;
 use aws_sdk_s3::config::Credentials;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
 use aws_sdk_s3::{Client, Config};
 use aws_types::region::Region;
 use log::*;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
 
 use crate::config::Configuration;
+use crate::upload_backend::{UploadBackend, UploadBackendFactory};
+
+/// How much of a file to hash at a time
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+/// Files bigger than this go up in parts this big, so there's progress to show along the way. S3
+/// wants parts to be at least 5MiB, except the last one.
+pub const MULTIPART_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// How far a multipart upload's got, after each part's done
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UploadPart {
+    /// Counting from 1, like S3 does
+    pub part: u32,
+    pub parts: u32,
+    pub bytes_sent: u64,
+}
+
+/// Told about each part as it finishes uploading
+pub type PartCallback = dyn Fn(UploadPart) + Send + Sync;
+
+/// how many parts `total_bytes` goes up in, one for anything that's not big enough to split
+pub fn part_count(total_bytes: u64, part_size: u64) -> u32 {
+    if part_size == 0 || total_bytes <= part_size {
+        return 1;
+    }
+    ((total_bytes + part_size - 1) / part_size) as u32
+}
+
+/// What kind of trouble talking to S3 was, for telling the user something more useful than a
+/// Debug dump of the SDK's error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum S3ErrorKind {
+    /// The access key's unknown or the secret doesn't match it
+    InvalidCredentials,
+    /// The credentials are fine but aren't allowed to do this
+    AccessDenied,
+    NoSuchBucket,
+    /// Couldn't connect at all, the network's down or the endpoint's wrong
+    NetworkUnreachable,
+    /// Connected, but the TLS handshake failed, usually a certificate problem
+    Tls,
+    Timeout,
+    /// S3 asked us to slow down, or is having a bad day
+    Throttled,
+    Other,
+}
+
+impl S3ErrorKind {
+    /// work out the kind from the error code S3 sent back and the HTTP status, either of which
+    /// might be missing. HEAD requests don't get a body, so there's only the status to go on.
+    pub fn from_parts(code: Option<&str>, status: Option<u16>) -> Self {
+        match code {
+            Some(
+                "InvalidAccessKeyId" | "SignatureDoesNotMatch" | "ExpiredToken" | "InvalidToken",
+            ) => return S3ErrorKind::InvalidCredentials,
+            Some("AccessDenied" | "AllAccessDisabled") => return S3ErrorKind::AccessDenied,
+            Some("NoSuchBucket") => return S3ErrorKind::NoSuchBucket,
+            Some("SlowDown" | "Throttling" | "ServiceUnavailable") => {
+                return S3ErrorKind::Throttled
+            }
+            Some("RequestTimeout") => return S3ErrorKind::Timeout,
+            _ => {}
+        }
+        match status {
+            Some(401) => S3ErrorKind::InvalidCredentials,
+            Some(403) => S3ErrorKind::AccessDenied,
+            // a missing key has its own code, so a bare 404 is the bucket
+            Some(404) if code != Some("NoSuchKey") => S3ErrorKind::NoSuchBucket,
+            Some(status) if is_retryable_status(status) => S3ErrorKind::Throttled,
+            _ => S3ErrorKind::Other,
+        }
+    }
+
+    /// classify whatever the SDK gave back from a request
+    pub fn from_sdk_error<E: ProvideErrorMetadata>(error: &SdkError<E, HttpResponse>) -> Self {
+        match error {
+            SdkError::TimeoutError(_) => S3ErrorKind::Timeout,
+            SdkError::DispatchFailure(failure) => {
+                let tls = failure
+                    .as_connector_error()
+                    .map(|error| mentions_tls(error))
+                    .unwrap_or(false);
+                if tls {
+                    S3ErrorKind::Tls
+                } else if failure.is_timeout() {
+                    S3ErrorKind::Timeout
+                } else if failure.is_io() {
+                    S3ErrorKind::NetworkUnreachable
+                } else {
+                    S3ErrorKind::Other
+                }
+            }
+            SdkError::ServiceError(_) => S3ErrorKind::from_parts(
+                error.code(),
+                error
+                    .raw_response()
+                    .map(|response| response.status().as_u16()),
+            ),
+            _ => S3ErrorKind::Other,
+        }
+    }
+
+    /// something to show the user, without the SDK's internals
+    pub fn user_message(&self) -> String {
+        match self {
+            S3ErrorKind::InvalidCredentials => crate::t!("s3-error-invalid-credentials"),
+            S3ErrorKind::AccessDenied => crate::t!("s3-error-access-denied"),
+            S3ErrorKind::NoSuchBucket => crate::t!("s3-error-no-such-bucket"),
+            S3ErrorKind::NetworkUnreachable => crate::t!("s3-error-network"),
+            S3ErrorKind::Tls => crate::t!("s3-error-tls"),
+            S3ErrorKind::Timeout => crate::t!("s3-error-timeout"),
+            S3ErrorKind::Throttled => crate::t!("s3-error-throttled"),
+            S3ErrorKind::Other => crate::t!("s3-error-other"),
+        }
+    }
+}
+
+/// whether anything in the chain of `error`'s sources looks like a certificate or TLS problem,
+/// the connectors don't have a kind for it
+fn mentions_tls(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        let text = error.to_string().to_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"]
+            .iter()
+            .any(|word| text.contains(word))
+        {
+            return true;
+        }
+        current = error.source();
+    }
+    false
+}
+
+/// Something going wrong in S3, with what to tell the user and the whole story for bug reports
+#[derive(Clone, Debug)]
+pub struct S3Error {
+    pub kind: S3ErrorKind,
+    /// everything the SDK said, for the logs and the "Copy details" button
+    pub details: String,
+}
+
+impl S3Error {
+    pub fn new(kind: S3ErrorKind, details: impl Into<String>) -> Self {
+        S3Error {
+            kind,
+            details: details.into(),
+        }
+    }
+
+    /// for when there's nothing to classify
+    pub fn other(details: impl Into<String>) -> Self {
+        S3Error::new(S3ErrorKind::Other, details)
+    }
+
+    /// classify `error`, prefixing the details with what we were doing
+    pub fn from_sdk_error<E>(context: &str, error: &SdkError<E, HttpResponse>) -> Self
+    where
+        E: ProvideErrorMetadata + std::fmt::Debug,
+    {
+        S3Error::new(
+            S3ErrorKind::from_sdk_error(error),
+            format!("{}: {:?}", context, error),
+        )
+    }
+}
+
+impl std::fmt::Display for S3Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind.user_message())
+    }
+}
+
+impl std::error::Error for S3Error {}
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub enum S3Result {
-    DeleteFailure(String),
-    // DownloadFailure(String),
+    DeleteFailure(S3Error),
+    DownloadFailure(S3Error),
     FileOpenFail(String),
-    HeadError(String),
+    HeadError(S3Error),
     Success,
-    UploadFailure(String),
+    UploadFailure(S3Error),
+    /// An upload failed for something that might not happen next time, like a timeout or throttling
+    RetryableUploadFailure(S3Error),
     FileNotFound,
+    /// The bucket's missing or we're not allowed to use it
+    BucketNotAccessible(S3Error),
+    CopyFailure(S3Error),
+}
+
+impl S3Result {
+    /// worth trying again, rather than something that'll fail the same way every time
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, S3Result::RetryableUploadFailure(_))
+    }
+
+    /// the [S3Error] behind it, if it came from S3
+    pub fn s3_error(&self) -> Option<&S3Error> {
+        match self {
+            S3Result::DeleteFailure(error)
+            | S3Result::DownloadFailure(error)
+            | S3Result::HeadError(error)
+            | S3Result::UploadFailure(error)
+            | S3Result::RetryableUploadFailure(error)
+            | S3Result::BucketNotAccessible(error)
+            | S3Result::CopyFailure(error) => Some(error),
+            S3Result::FileOpenFail(_) | S3Result::Success | S3Result::FileNotFound => None,
+        }
+    }
+
+    /// what went wrong, in words that make sense without knowing how S3 works
+    pub fn user_message(&self) -> String {
+        match self {
+            S3Result::FileOpenFail(message) => message.clone(),
+            S3Result::Success => "Success".to_string(),
+            S3Result::FileNotFound => crate::t!("s3-error-not-found"),
+            _ => self
+                .s3_error()
+                .map(|error| error.kind.user_message())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// everything there is to know about it, for the logs or a bug report
+    pub fn details(&self) -> String {
+        match self.s3_error() {
+            Some(error) => error.details.clone(),
+            None => format!("{:?}", self),
+        }
+    }
+}
+
+impl std::fmt::Display for S3Result {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.user_message())
+    }
+}
+
+impl std::error::Error for S3Result {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.s3_error()
+            .map(|error| error as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// An [UploadBackendFactory] that makes an [S3Client] from the config file
+pub fn s3_backend_factory() -> UploadBackendFactory {
+    Arc::new(|| Ok(Box::new(S3Client::try_new()?) as Box<dyn UploadBackend>))
 }
 
 pub struct S3Client {
     client: Client,
     bucket: String,
+    /// The endpoint, with the bucket and key on the end it's where an object can be fetched
+    base_url: String,
+    hash_keys: bool,
+    max_retries: u8,
+}
+
+#[async_trait]
+impl UploadBackend for S3Client {
+    async fn head_object(&self, key: &str) -> Result<String, S3Result> {
+        S3Client::head_object(self, key).await
+    }
+
+    async fn put_object(&self, key: &str, filename: &str) -> Result<String, S3Result> {
+        S3Client::put_object(self, key, filename).await
+    }
+
+    async fn put_object_parts(
+        &self,
+        key: &str,
+        filename: &str,
+        on_part: &PartCallback,
+    ) -> Result<String, S3Result> {
+        S3Client::put_object_parts(self, key, filename, on_part).await
+    }
+
+    async fn check_bucket_accessible(&self) -> Result<(), S3Result> {
+        S3Client::check_bucket_accessible(self).await
+    }
+
+    async fn copy_object(&self, src_key: &str, dest_key: &str) -> Result<String, S3Result> {
+        S3Client::copy_object(self, src_key, dest_key).await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<String, S3Result> {
+        S3Client::delete_object(self, key).await
+    }
+
+    async fn object_size(&self, key: &str) -> Result<u64, S3Result> {
+        S3Client::object_size(self, key).await
+    }
+
+    async fn get_object(&self, key: &str, dest: &Path) -> Result<(), S3Result> {
+        S3Client::get_object(self, key, dest).await
+    }
+
+    async fn rename_object(&self, src_key: &str, dest_key: &str) -> Result<(), S3Result> {
+        S3Client::rename_object(self, src_key, dest_key).await
+    }
+
+    fn url_for_key(&self, key: &str) -> Option<String> {
+        Some(format!("{}/{}/{}", self.base_url, self.bucket, key))
+    }
+
+    fn hash_keys(&self) -> bool {
+        self.hash_keys
+    }
+
+    fn max_retries(&self) -> u8 {
+        self.max_retries
+    }
+}
+
+/// where [UploadBackend::download_to_temp] puts `key`, anything that'd make it a path is flattened
+/// out of the name
+pub fn temp_path_for_key(key: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("memetool-{}", key.replace(['/', '\\'], "_")))
+}
+
+/// The S3 key to upload `filepath` as, either its filename or [content_hash_key]
+pub async fn upload_key(filepath: &str, hash_keys: bool) -> std::io::Result<String> {
+    if hash_keys {
+        content_hash_key(filepath).await
+    } else {
+        // it's safe to use unwrap here because split always returns at least one item
+        #[allow(clippy::unwrap_used)]
+        Ok(filepath.split('/').last().unwrap().to_string())
+    }
+}
+
+/// what `key` should become when the file it was uploaded from is renamed from `old_filepath` to
+/// `new_filepath`. Only keys that were named after the file get renamed, so content hashes and
+/// anything renamed by hand are left alone.
+pub fn renamed_key(key: &str, old_filepath: &str, new_filepath: &str) -> Option<String> {
+    let old_name = std::path::Path::new(old_filepath).file_name()?.to_str()?;
+    let new_name = std::path::Path::new(new_filepath).file_name()?.to_str()?;
+    let (prefix, name) = match key.rsplit_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+    if name != old_name || old_name == new_name {
+        return None;
+    }
+    Some(match prefix {
+        Some(prefix) => format!("{}/{}", prefix, new_name),
+        None => new_name.to_string(),
+    })
+}
+
+/// where `src_key` ends up when it's copied to `dest_prefix`, eg `uploads/cat.jpg` to
+/// `archive/2024/` is `archive/2024/cat.jpg`
+pub fn key_with_prefix(src_key: &str, dest_prefix: &str) -> String {
+    // it's safe to use unwrap here because split always returns at least one item
+    #[allow(clippy::unwrap_used)]
+    let filename = src_key.split('/').last().unwrap();
+    let dest_prefix = dest_prefix.trim_matches('/');
+    if dest_prefix.is_empty() {
+        filename.to_string()
+    } else {
+        format!("{}/{}", dest_prefix, filename)
+    }
+}
+
+/// throttling and server-side hiccups are worth retrying, things like 403 Forbidden or a missing
+/// bucket aren't going to fix themselves
+pub fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 429 | 500 | 502 | 503 | 504)
+}
+
+/// percent-encode a `bucket/key` for the CopySource header, leaving the slashes alone
+fn encode_copy_source(source: &str) -> String {
+    source
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// SHA-256 of the file's contents plus its extension, eg `ab12...ef.png`
+pub async fn content_hash_key(filepath: &str) -> std::io::Result<String> {
+    let hash = content_hash(filepath).await?;
+    match std::path::Path::new(filepath).extension() {
+        Some(ext) => Ok(format!("{}.{}", hash, ext.to_string_lossy().to_lowercase())),
+        None => Ok(hash),
+    }
+}
+
+/// SHA-256 of the file's contents as hex. Reads the file in chunks so big files don't end up in
+/// memory.
+pub async fn content_hash(filepath: &str) -> std::io::Result<String> {
+    let mut file = tokio::fs::File::open(filepath).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    let hash: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    Ok(hash)
 }
 
 impl S3Client {
@@ -48,9 +455,11 @@ impl S3Client {
             .credentials_provider(creds)
             .force_path_style(true)
             .region(Region::new(config.s3_region));
+        let mut base_url = format!("https://s3.{}.amazonaws.com", config.s3_region);
         // set the endpoint if we need to
         if let Some(endpoint_uri) = config.s3_endpoint {
             info!("Setting s3 endpoint: {} ", endpoint_uri);
+            base_url = endpoint_uri.trim_end_matches('/').to_string();
             client_config = client_config.endpoint_url(endpoint_uri);
         };
         let client = Client::from_conf(client_config.build());
@@ -59,9 +468,137 @@ impl S3Client {
         Self {
             client,
             bucket: config.s3_bucket,
+            base_url,
+            hash_keys: config.hash_upload_keys,
+            max_retries: config.s3_max_retries,
+        }
+    }
+
+    /// HEAD the bucket, which fails if it doesn't exist or the credentials can't see it
+    pub async fn check_bucket_accessible(&self) -> Result<(), S3Result> {
+        match self.client.head_bucket().bucket(&self.bucket).send().await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(S3Result::BucketNotAccessible(S3Error::from_sdk_error(
+                &self.bucket,
+                &error,
+            ))),
+        }
+    }
+
+    /// server-side copy of `src_key` to `dest_key` in the same bucket
+    pub async fn copy_object(&self, src_key: &str, dest_key: &str) -> Result<String, S3Result> {
+        debug!("copy_object: {} => {}", src_key, dest_key);
+        let copy = self
+            .client
+            .copy_object()
+            .copy_source(encode_copy_source(&format!("{}/{}", self.bucket, src_key)))
+            .key(dest_key)
+            .bucket(&self.bucket)
+            .send()
+            .await;
+
+        match copy {
+            Ok(response) => Ok(format!("{:?}", response)),
+            Err(error) => Err(S3Result::CopyFailure(S3Error::from_sdk_error(
+                &format!("Failed to copy {} to {}", src_key, dest_key),
+                &error,
+            ))),
+        }
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<String, S3Result> {
+        debug!("delete_object: {}", key);
+        let delete = self
+            .client
+            .delete_object()
+            .key(key)
+            .bucket(&self.bucket)
+            .send()
+            .await;
+
+        match delete {
+            Ok(response) => Ok(format!("{:?}", response)),
+            Err(error) => Err(S3Result::DeleteFailure(S3Error::from_sdk_error(
+                &format!("Failed to delete {}", key),
+                &error,
+            ))),
         }
     }
 
+    /// HEAD `key` for its size, without the Debug dump [S3Client::head_object] gives back
+    pub async fn object_size(&self, key: &str) -> Result<u64, S3Result> {
+        debug!("object_size: {}", key);
+        let head = self
+            .client
+            .head_object()
+            .key(key)
+            .bucket(&self.bucket)
+            .send()
+            .await;
+
+        match head {
+            Ok(response) => Ok(response.content_length().unwrap_or_default().max(0) as u64),
+            Err(SdkError::ServiceError(service_error)) if service_error.err().is_not_found() => {
+                Err(S3Result::FileNotFound)
+            }
+            Err(error) => Err(S3Result::HeadError(S3Error::from_sdk_error(key, &error))),
+        }
+    }
+
+    pub async fn get_object(&self, key: &str, dest: &Path) -> Result<(), S3Result> {
+        debug!("get_object: {} => {}", key, dest.display());
+        let get = self
+            .client
+            .get_object()
+            .key(key)
+            .bucket(&self.bucket)
+            .send()
+            .await;
+
+        let response = match get {
+            Ok(response) => response,
+            Err(SdkError::ServiceError(service_error)) if service_error.err().is_no_such_key() => {
+                return Err(S3Result::FileNotFound)
+            }
+            Err(error) => {
+                return Err(S3Result::DownloadFailure(S3Error::from_sdk_error(
+                    &format!("Failed to download {}", key),
+                    &error,
+                )))
+            }
+        };
+        let contents = response.body.collect().await.map_err(|error| {
+            S3Result::DownloadFailure(S3Error::other(format!(
+                "Failed to download {}: {:?}",
+                key, error
+            )))
+        })?;
+        tokio::fs::write(dest, contents.into_bytes())
+            .await
+            .map_err(|error| {
+                S3Result::FileOpenFail(format!("Failed to write {}: {:?}", dest.display(), error))
+            })
+    }
+
+    /// move `src_key` to `dest_key`, see [UploadBackend::rename_object]
+    pub async fn rename_object(&self, src_key: &str, dest_key: &str) -> Result<(), S3Result> {
+        self.copy_object(src_key, dest_key).await?;
+        self.delete_object(src_key).await?;
+        info!("Renamed {} to {} in S3", src_key, dest_key);
+        Ok(())
+    }
+
+    /// copy `src_key` into `dest_prefix`, keeping its filename. Returns the new key.
+    pub async fn copy_object_to_prefix(
+        &self,
+        src_key: &str,
+        dest_prefix: &str,
+    ) -> Result<String, S3Result> {
+        let dest_key = key_with_prefix(src_key, dest_prefix);
+        self.copy_object(src_key, &dest_key).await?;
+        Ok(dest_key)
+    }
+
     pub async fn head_object(&self, key: &str) -> Result<String, S3Result> {
         eprintln!("head_object: {}", key);
         let head = self
@@ -75,39 +612,13 @@ impl S3Client {
         match head {
             // TODO Reduced struct for nicer data
             Ok(response) => Ok(format!("{:?}", response)),
-            Err(error) => {
-                match error {
-                    aws_sdk_s3::error::SdkError::ConstructionFailure(err) => Err(
-                        S3Result::HeadError(format!("ConstructionFailure: {:?}", err)),
-                    ),
-                    aws_sdk_s3::error::SdkError::TimeoutError(err) => {
-                        Err(S3Result::HeadError(format!("TimeoutError: {:?}", err)))
-                    }
-                    aws_sdk_s3::error::SdkError::DispatchFailure(err) => {
-                        Err(S3Result::HeadError(format!("DispatchFailure: {:?}", err)))
-                    }
-                    aws_sdk_s3::error::SdkError::ResponseError(err) => {
-                        Err(S3Result::HeadError(format!("ResponseError: {:?}", err)))
-                    }
-                    aws_sdk_s3::error::SdkError::ServiceError(service_error) => {
-                        match service_error.into_err() {
-                            aws_sdk_s3::operation::head_object::HeadObjectError::NotFound(_) => {
-                                Err(S3Result::FileNotFound)
-                            }
-                            // aws_sdk_s3::operation::head_object::HeadObjectError::Unhandled(err) => {
-                            //     Err(S3Result::HeadError(format!("ResponseError: {:?}", err)))
-                            // }
-                            _ => todo!(),
-                        }
-                    }
-                    _ => Err(S3Result::HeadError("Generic Error".to_string())),
-                }
-                // println!("Error doing head: {:?}", error);
-                // Err(S3Result::HeadError(format!(
-                //     "Failed head_object() file: {:?}",
-                //     error
-                // )))
+            Err(SdkError::ServiceError(service_error)) if service_error.err().is_not_found() => {
+                Err(S3Result::FileNotFound)
             }
+            Err(error) => Err(S3Result::HeadError(S3Error::from_sdk_error(
+                &format!("Failed head_object() {}", key),
+                &error,
+            ))),
         }
     }
     pub async fn put_object(&self, key: &str, filename: &str) -> Result<String, S3Result> {
@@ -133,10 +644,150 @@ impl S3Client {
 
         match upload {
             Ok(response) => Ok(format!("{:?}", response)),
-            Err(error) => Err(S3Result::UploadFailure(format!(
-                "Failed to upload file: {:?}",
-                error
-            ))),
+            Err(error) => Err(upload_failure("Failed to upload file", &error)),
+        }
+    }
+
+    /// [S3Client::put_object], but anything bigger than [MULTIPART_PART_SIZE] goes up as a
+    /// multipart upload. If a part fails the whole upload's aborted, so retrying starts again.
+    pub async fn put_object_parts(
+        &self,
+        key: &str,
+        filename: &str,
+        on_part: &PartCallback,
+    ) -> Result<String, S3Result> {
+        let total_bytes = tokio::fs::metadata(filename)
+            .await
+            .map_err(|error| S3Result::FileOpenFail(format!("Failed to open file: {:?}", error)))?
+            .len();
+        let parts = part_count(total_bytes, MULTIPART_PART_SIZE);
+        if parts == 1 {
+            return self.put_object(key, filename).await;
+        }
+        debug!(
+            "put_object_parts: {} => {} in {} parts",
+            filename, key, parts
+        );
+
+        let created = self
+            .client
+            .create_multipart_upload()
+            .key(key)
+            .bucket(&self.bucket)
+            .send()
+            .await
+            .map_err(|error| upload_failure("Failed to start multipart upload", &error))?;
+        let upload_id = created.upload_id().unwrap_or_default().to_string();
+
+        match self
+            .upload_parts(key, filename, &upload_id, parts, on_part)
+            .await
+        {
+            Ok(completed) => self
+                .client
+                .complete_multipart_upload()
+                .key(key)
+                .bucket(&self.bucket)
+                .upload_id(&upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(completed))
+                        .build(),
+                )
+                .send()
+                .await
+                .map(|response| format!("{:?}", response))
+                .map_err(|error| upload_failure("Failed to finish multipart upload", &error)),
+            Err(err) => {
+                // otherwise the parts sit in the bucket, costing money
+                if let Err(error) = self
+                    .client
+                    .abort_multipart_upload()
+                    .key(key)
+                    .bucket(&self.bucket)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await
+                {
+                    warn!("Failed to abort multipart upload of {}: {:?}", key, error);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// send each part of `filename`, returning what S3 needs to put them back together
+    async fn upload_parts(
+        &self,
+        key: &str,
+        filename: &str,
+        upload_id: &str,
+        parts: u32,
+        on_part: &PartCallback,
+    ) -> Result<Vec<CompletedPart>, S3Result> {
+        let mut file = tokio::fs::File::open(filename)
+            .await
+            .map_err(|error| S3Result::FileOpenFail(format!("Failed to open file: {:?}", error)))?;
+        let mut completed = Vec::with_capacity(parts as usize);
+        let mut bytes_sent = 0;
+        for part in 1..=parts {
+            let mut buffer = Vec::with_capacity(MULTIPART_PART_SIZE as usize);
+            (&mut file)
+                .take(MULTIPART_PART_SIZE)
+                .read_to_end(&mut buffer)
+                .await
+                .map_err(|error| {
+                    S3Result::FileOpenFail(format!("Failed to read file: {:?}", error))
+                })?;
+            let length = buffer.len() as u64;
+            let uploaded = self
+                .client
+                .upload_part()
+                .key(key)
+                .bucket(&self.bucket)
+                .upload_id(upload_id)
+                .part_number(part as i32)
+                .body(ByteStream::from(buffer))
+                .send()
+                .await
+                .map_err(|error| {
+                    upload_failure(&format!("Failed to upload part {part}"), &error)
+                })?;
+            completed.push(
+                CompletedPart::builder()
+                    .set_e_tag(uploaded.e_tag().map(str::to_string))
+                    .part_number(part as i32)
+                    .build(),
+            );
+            bytes_sent += length;
+            on_part(UploadPart {
+                part,
+                parts,
+                bytes_sent,
+            });
+        }
+        Ok(completed)
+    }
+}
+
+/// sort an upload error into one that's worth retrying or not
+fn upload_failure<E>(context: &str, error: &SdkError<E, HttpResponse>) -> S3Result
+where
+    E: ProvideErrorMetadata + std::fmt::Debug,
+{
+    let status = error
+        .raw_response()
+        .map(|response| response.status().as_u16());
+    let retryable = match error {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => {
+            true
         }
+        _ => status.map(is_retryable_status).unwrap_or(false),
+    };
+    let error = S3Error::from_sdk_error(context, error);
+    if retryable {
+        S3Result::RetryableUploadFailure(error)
+    } else {
+        S3Result::UploadFailure(error)
     }
 }
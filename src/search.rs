@@ -0,0 +1,95 @@
+//! The browser's search, which filenames match and where, so what's highlighted is always what
+//! the list was filtered on
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// What's in the search box, split on spaces into terms that all have to be in a filename
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchQuery {
+    terms: Vec<String>,
+    case_sensitive: bool,
+}
+
+impl SearchQuery {
+    /// `None` if there's nothing to search for
+    pub fn new(input: &str, case_sensitive: bool) -> Option<Self> {
+        let terms: Vec<String> = input
+            .split(' ')
+            .filter(|term| !term.is_empty())
+            .map(|term| fold_case(term, case_sensitive))
+            .collect();
+        if terms.is_empty() {
+            None
+        } else {
+            Some(Self {
+                terms,
+                case_sensitive,
+            })
+        }
+    }
+
+    pub fn matches(&self, filename: &str) -> bool {
+        self.match_ranges(filename).is_some()
+    }
+
+    /// like [SearchQuery::matches], but a term that's the whole of one of the file's `tags`
+    /// counts as well as one in the filename
+    pub fn matches_tagged(&self, filename: &str, tags: &[&str]) -> bool {
+        let haystack = fold_case(filename, self.case_sensitive);
+        let tags: BTreeSet<String> = tags
+            .iter()
+            .map(|tag| fold_case(tag, self.case_sensitive))
+            .collect();
+        self.terms
+            .iter()
+            .all(|term| haystack.contains(term.as_str()) || tags.contains(term))
+    }
+
+    /// where the terms are in `filename` as byte ranges, sorted and with overlaps joined up, or
+    /// `None` if any of them aren't there
+    pub fn match_ranges(&self, filename: &str) -> Option<Vec<Range<usize>>> {
+        // lowercasing can change how long a character is, so each byte of what's searched
+        // remembers which character in the filename it came from
+        let mut haystack = String::with_capacity(filename.len());
+        let mut origins: Vec<Range<usize>> = Vec::with_capacity(filename.len());
+        for (start, c) in filename.char_indices() {
+            let origin = start..start + c.len_utf8();
+            let folded = fold_case(&c.to_string(), self.case_sensitive);
+            origins.extend(std::iter::repeat(origin).take(folded.len()));
+            haystack.push_str(&folded);
+        }
+
+        let mut ranges = vec![];
+        for term in &self.terms {
+            let before = ranges.len();
+            ranges.extend(
+                haystack.match_indices(term.as_str()).map(|(start, found)| {
+                    origins[start].start..origins[start + found.len() - 1].end
+                }),
+            );
+            if ranges.len() == before {
+                return None;
+            }
+        }
+        ranges.sort_by_key(|range| range.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        Some(merged)
+    }
+}
+
+/// a character at a time, so the terms and filenames get lowercased the same way
+fn fold_case(text: &str, case_sensitive: bool) -> String {
+    if case_sensitive {
+        text.to_string()
+    } else {
+        text.chars().flat_map(char::to_lowercase).collect()
+    }
+}
@@ -0,0 +1,160 @@
+//! Only running one memetool at a time. Opening a file when one's already running hands the path
+//! over to it through a local socket (a named pipe on Windows), instead of starting another window
+//! and runtime.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use log::*;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+
+use crate::AppMsg;
+
+/// Where the running instance listens, one per user
+pub fn socket_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        PathBuf::from(r"\\.\pipe\memetool")
+    }
+    #[cfg(not(windows))]
+    {
+        let user = std::env::var("USER").unwrap_or_else(|_| "memetool".to_string());
+        std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join(format!("memetool-{}.sock", user))
+    }
+}
+
+/// [forward_to] the usual [socket_path]
+pub fn forward_to_running(open: &Path) -> std::io::Result<bool> {
+    forward_to(&socket_path(), open)
+}
+
+/// Send `open` to the instance listening on `socket`. Returns false if there isn't one, in which
+/// case this one should start up as normal.
+pub fn forward_to(socket: &Path, open: &Path) -> std::io::Result<bool> {
+    // the running one's probably somewhere else, so relative paths won't mean the same thing
+    let open = std::fs::canonicalize(open).unwrap_or_else(|_| open.to_path_buf());
+    let mut stream = match connect(socket) {
+        Ok(stream) => stream,
+        Err(err) => {
+            debug!(
+                "Nothing listening on {}, starting up: {:?}",
+                socket.display(),
+                err
+            );
+            return Ok(false);
+        }
+    };
+    writeln!(stream, "{}", open.display())?;
+    stream.flush()?;
+    info!("Sent {} to the running instance", open.display());
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn connect(socket: &Path) -> std::io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(socket)
+}
+
+#[cfg(windows)]
+fn connect(socket: &Path) -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new().write(true).open(socket)
+}
+
+/// [listen_at] the usual [socket_path]
+pub async fn listen(tx: mpsc::Sender<AppMsg>) {
+    listen_at(socket_path(), tx).await
+}
+
+/// Pass paths from other instances to the frontend as [AppMsg::OpenPath], until this is dropped
+pub async fn listen_at(socket: PathBuf, tx: mpsc::Sender<AppMsg>) {
+    if let Err(err) = accept_loop(&socket, &tx).await {
+        error!(
+            "Couldn't listen for other instances on {}: {:?}",
+            socket.display(),
+            err
+        );
+    }
+}
+
+/// Removes the socket file when the listener goes away, so the next one to start doesn't try
+/// talking to it
+#[cfg(unix)]
+struct SocketGuard(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketGuard {
+    fn drop(&mut self) {
+        debug!("Removing {}", self.0.display());
+        if let Err(err) = std::fs::remove_file(&self.0) {
+            warn!("Failed to remove {}: {:?}", self.0.display(), err);
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn accept_loop(socket: &Path, tx: &mpsc::Sender<AppMsg>) -> std::io::Result<()> {
+    if socket.exists() {
+        // only opening a file checks for a running instance first, so this one might just have
+        // been started on its own while another's going. That one keeps the socket.
+        if connect(socket).is_ok() {
+            warn!(
+                "Another memetool's listening on {}, not listening for other instances",
+                socket.display()
+            );
+            return Ok(());
+        }
+        // nothing answered on it, so it's left over from a crash
+        warn!("Removing stale socket {}", socket.display());
+        std::fs::remove_file(socket)?;
+    }
+    let listener = tokio::net::UnixListener::bind(socket)?;
+    let _guard = SocketGuard(socket.to_path_buf());
+    info!("Listening for other instances on {}", socket.display());
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(read_paths(stream, tx.clone()));
+    }
+}
+
+#[cfg(windows)]
+async fn accept_loop(socket: &Path, tx: &mpsc::Sender<AppMsg>) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    // pipes go away by themselves once nobody's got them open
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(socket)?;
+    info!("Listening for other instances on {}", socket.display());
+    loop {
+        server.connect().await?;
+        let connected = server;
+        server = ServerOptions::new().create(socket)?;
+        tokio::spawn(read_paths(connected, tx.clone()));
+    }
+}
+
+/// each line's a path to open
+async fn read_paths<S: AsyncRead + Unpin>(stream: S, tx: mpsc::Sender<AppMsg>) {
+    let mut lines = BufReader::new(stream).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) if line.trim().is_empty() => {}
+            Ok(Some(line)) => {
+                debug!("Another instance asked us to open {}", line);
+                if let Err(err) = tx.send(AppMsg::OpenPath(PathBuf::from(line))).await {
+                    error!("Failed to send OpenPath to the frontend: {}", err);
+                    return;
+                }
+            }
+            Ok(None) => return,
+            Err(err) => {
+                warn!("Failed to read from another instance: {:?}", err);
+                return;
+            }
+        }
+    }
+}
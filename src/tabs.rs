@@ -0,0 +1,137 @@
+//! The browser's tabs, each one a workdir that remembers its page, search and selection. Only
+//! the active tab's shown, so switching stashes what the browser's doing into the tab it's
+//! leaving and picks up where the other one left off.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::browser_context::BrowserContext;
+
+/// Identifies a tab while memetool's running, requests carry it so their responses can tell
+/// which tab they were for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TabId(pub u64);
+
+impl TabId {
+    /// a new one, they only ever go up
+    pub fn next() -> Self {
+        static NEXT_TAB: AtomicU64 = AtomicU64::new(1);
+        TabId(NEXT_TAB.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl std::fmt::Display for TabId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tab#{}", self.0)
+    }
+}
+
+/// One tab, this is also what's saved in the config
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct WorkdirTab {
+    /// Handed out again each time memetool starts
+    #[serde(skip, default = "TabId::next")]
+    pub id: TabId,
+    pub workdir: String,
+    #[serde(default)]
+    pub search: String,
+    #[serde(default)]
+    pub context: BrowserContext,
+}
+
+impl WorkdirTab {
+    pub fn new(workdir: impl ToString) -> Self {
+        Self {
+            id: TabId::next(),
+            workdir: workdir.to_string(),
+            search: String::new(),
+            context: BrowserContext::default(),
+        }
+    }
+
+    /// what goes on the tab, the last part of the workdir
+    pub fn label(&self) -> String {
+        let workdir = shellexpand::tilde(&self.workdir);
+        Path::new(workdir.as_ref())
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.workdir.clone())
+    }
+}
+
+/// All the tabs, there's always at least one
+#[derive(Debug)]
+pub struct Tabs {
+    tabs: Vec<WorkdirTab>,
+    active: usize,
+}
+
+impl Tabs {
+    /// the tabs from last time, or one for `workdir` if there weren't any
+    pub fn new(tabs: Vec<WorkdirTab>, active: usize, workdir: &str) -> Self {
+        if tabs.is_empty() {
+            return Self {
+                tabs: vec![WorkdirTab::new(workdir)],
+                active: 0,
+            };
+        }
+        let active = active.min(tabs.len() - 1);
+        Self { tabs, active }
+    }
+
+    pub fn tabs(&self) -> &[WorkdirTab] {
+        &self.tabs
+    }
+
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn active(&self) -> &WorkdirTab {
+        &self.tabs[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut WorkdirTab {
+        &mut self.tabs[self.active]
+    }
+
+    /// add `tab` after the active one and switch to it, returns where it went
+    pub fn open(&mut self, tab: WorkdirTab) -> usize {
+        self.active += 1;
+        self.tabs.insert(self.active, tab);
+        self.active
+    }
+
+    /// returns false if there's no tab at `index`
+    pub fn switch(&mut self, index: usize) -> bool {
+        if index < self.tabs.len() {
+            self.active = index;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// close the tab at `index`, the last one can't be closed. If it was the active one, the
+    /// one after it takes over, or the one before if it was at the end.
+    pub fn close(&mut self, index: usize) -> Option<WorkdirTab> {
+        if self.tabs.len() <= 1 || index >= self.tabs.len() {
+            return None;
+        }
+        let closed = self.tabs.remove(index);
+        if index < self.active || self.active == self.tabs.len() {
+            self.active -= 1;
+        }
+        Some(closed)
+    }
+}
@@ -0,0 +1,96 @@
+//! Tags on files, kept in their own file so they don't depend on what the file's called. Moving a
+//! file in memetool takes its tags with it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::Context;
+
+pub const TAGS_PATH: &str = "~/.config/memetool-tags.json";
+
+/// The tag [crate::config::Configuration::auto_tag_by_directory] gives files in `workdir`, the
+/// last part of its path. `None` for the root, which doesn't have one.
+pub fn directory_tag(workdir: &str) -> Option<String> {
+    let workdir = shellexpand::tilde(workdir);
+    Path::new(workdir.as_ref())
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Each file's tags, by file path
+#[derive(Debug, Default)]
+pub struct TagStore {
+    tags: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl TagStore {
+    /// load the tags from `path`, a missing file is no tags
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read tags {}", path.display()))?;
+        let tags = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse tags {}", path.display()))?;
+        Ok(Self { tags })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.tags)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write tags {}", path.display()))
+    }
+
+    /// returns false if `filepath` already had `tag`
+    pub fn add(&mut self, filepath: &str, tag: &str) -> bool {
+        self.tags
+            .entry(filepath.to_string())
+            .or_default()
+            .insert(tag.to_string())
+    }
+
+    pub fn has_tag(&self, filepath: &str, tag: &str) -> bool {
+        self.tags
+            .get(filepath)
+            .map(|tags| tags.contains(tag))
+            .unwrap_or(false)
+    }
+
+    /// `filepath`'s tags in alphabetical order, empty if it hasn't got any
+    pub fn tags(&self, filepath: &str) -> Vec<&str> {
+        self.tags
+            .get(filepath)
+            .map(|tags| tags.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// how many of `filepaths` don't have `tag` yet, what adding it to all of them would change
+    pub fn count_missing<'a>(
+        &self,
+        filepaths: impl IntoIterator<Item = &'a str>,
+        tag: &str,
+    ) -> usize {
+        filepaths
+            .into_iter()
+            .filter(|filepath| !self.has_tag(filepath, tag))
+            .count()
+    }
+
+    /// `from`'s been renamed or moved to `to`, so its tags go with it
+    pub fn move_file(&mut self, from: &str, to: &str) {
+        if let Some(tags) = self.tags.remove(from) {
+            self.tags.entry(to.to_string()).or_default().extend(tags);
+        }
+    }
+
+    /// how many files have tags
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
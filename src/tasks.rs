@@ -0,0 +1,67 @@
+//! Keeping track of what we've asked the background task to do
+
+use std::collections::HashMap;
+
+/// Identifies a request to the background task, the response carries the same one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(pub u64);
+
+impl std::fmt::Display for TaskId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "task#{}", self.0)
+    }
+}
+
+/// Which bit of the UI asked for the work
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskPurpose {
+    /// A thumbnail for the browser grid
+    Thumbnail,
+    /// The split view's preview pane
+    Preview,
+}
+
+/// The requests that haven't had a response yet. A response for a task that isn't in here is
+/// stale, either it's been answered already or it was forgotten about.
+#[derive(Debug, Default)]
+pub struct TaskTracker {
+    next_id: u64,
+    outstanding: HashMap<TaskId, TaskPurpose>,
+}
+
+impl TaskTracker {
+    /// hand out a new [TaskId], they only ever go up
+    pub fn start(&mut self, purpose: TaskPurpose) -> TaskId {
+        self.next_id += 1;
+        let task = TaskId(self.next_id);
+        self.outstanding.insert(task, purpose);
+        task
+    }
+
+    /// a response's arrived, returns what it was for or `None` if it's stale
+    pub fn finish(&mut self, task: TaskId) -> Option<TaskPurpose> {
+        self.outstanding.remove(&task)
+    }
+
+    pub fn is_outstanding(&self, task: TaskId) -> bool {
+        self.outstanding.contains_key(&task)
+    }
+
+    /// stop waiting on everything for `purpose`, their responses will be treated as stale
+    pub fn forget(&mut self, purpose: TaskPurpose) {
+        self.outstanding.retain(|_, p| *p != purpose);
+    }
+
+    /// how many tasks are still waiting for `purpose`
+    pub fn outstanding(&self, purpose: TaskPurpose) -> usize {
+        self.outstanding.values().filter(|p| **p == purpose).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.outstanding.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outstanding.is_empty()
+    }
+}
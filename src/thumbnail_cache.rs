@@ -0,0 +1,182 @@
+//! Thumbnail cache shared across directories
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use log::*;
+
+/// How much memory the thumbnail cache can use if the config doesn't say otherwise
+pub const DEFAULT_THUMBNAIL_CACHE_BYTES: usize = 256 * 1024 * 1024;
+/// Once the cache is this full, as a percentage of its budget, thumbnails that aren't on the
+/// current page get thrown away before the LRU has to
+pub const CACHE_PRESSURE_PERCENT: usize = 75;
+
+struct CacheEntry<T> {
+    value: T,
+    /// When the file was last modified when we made the thumbnail
+    modified: Option<SystemTime>,
+    size_bytes: usize,
+    /// Bumped every time the entry's used, the lowest gets evicted first
+    last_used: u64,
+}
+
+/// A least-recently-used cache of thumbnails, keyed by absolute path and checked against the file's
+/// modification time so edited files get regenerated.
+pub struct ThumbnailCache<T> {
+    entries: HashMap<String, CacheEntry<T>>,
+    budget_bytes: usize,
+    used_bytes: usize,
+    tick: u64,
+}
+
+impl<T> ThumbnailCache<T> {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            budget_bytes,
+            used_bytes: 0,
+            tick: 0,
+        }
+    }
+
+    /// the modification time of a file, if we can get it
+    pub fn modified_time(filepath: impl AsRef<Path>) -> Option<SystemTime> {
+        std::fs::metadata(filepath)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// add a thumbnail, evicting the least recently used ones if we're over budget
+    pub fn insert(
+        &mut self,
+        filepath: String,
+        modified: Option<SystemTime>,
+        value: T,
+        size_bytes: usize,
+    ) {
+        self.tick += 1;
+        if let Some(old) = self.entries.insert(
+            filepath,
+            CacheEntry {
+                value,
+                modified,
+                size_bytes,
+                last_used: self.tick,
+            },
+        ) {
+            self.used_bytes -= old.size_bytes;
+        }
+        self.used_bytes += size_bytes;
+        self.evict();
+    }
+
+    /// get a thumbnail, marking it as recently used
+    pub fn get(&mut self, filepath: &str) -> Option<&T> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(filepath).map(|entry| {
+            entry.last_used = tick;
+            &entry.value
+        })
+    }
+
+    /// get a thumbnail without counting it as a use
+    pub fn peek(&self, filepath: &str) -> Option<&T> {
+        self.entries.get(filepath).map(|entry| &entry.value)
+    }
+
+    pub fn contains(&self, filepath: &str) -> bool {
+        self.entries.contains_key(filepath)
+    }
+
+    pub fn remove(&mut self, filepath: &str) -> Option<T> {
+        self.entries.remove(filepath).map(|entry| {
+            self.used_bytes -= entry.size_bytes;
+            entry.value
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used_bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// how much of the budget's in use
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    pub fn budget_bytes(&self) -> usize {
+        self.budget_bytes
+    }
+
+    /// drop everything `keep` says no to, returns how many were removed
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) -> usize {
+        let unwanted: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|filepath| !keep(filepath))
+            .cloned()
+            .collect();
+        for filepath in unwanted.iter() {
+            self.remove(filepath);
+        }
+        unwanted.len()
+    }
+
+    /// drop anything whose file has gone away or changed since we made the thumbnail, returns how
+    /// many were removed. That's a stat for everything in the cache, so a refresh uses
+    /// [ThumbnailCache::prune_stale_among] instead.
+    pub fn prune_stale(&mut self) -> usize {
+        let filepaths: Vec<String> = self.entries.keys().cloned().collect();
+        self.prune_stale_among(filepaths.iter().map(String::as_str))
+    }
+
+    /// [ThumbnailCache::prune_stale] for just the cached ones out of `filepaths`
+    pub fn prune_stale_among<'a>(&mut self, filepaths: impl IntoIterator<Item = &'a str>) -> usize {
+        let stale: Vec<String> = filepaths
+            .into_iter()
+            .filter(|filepath| match self.entries.get(*filepath) {
+                Some(entry) => {
+                    let path = Path::new(filepath);
+                    !path.exists() || Self::modified_time(path) != entry.modified
+                }
+                None => false,
+            })
+            .map(str::to_owned)
+            .collect();
+        for filepath in stale.iter() {
+            info!("Removing {} from cached files", filepath);
+            self.remove(filepath);
+        }
+        stale.len()
+    }
+
+    /// throw away the least recently used entries until we're under budget, always keeping at
+    /// least one so a single huge thumbnail still shows up
+    fn evict(&mut self) {
+        while self.used_bytes > self.budget_bytes && self.entries.len() > 1 {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(filepath, _)| filepath.to_owned());
+            match oldest {
+                Some(filepath) => {
+                    trace!("Evicting {} from the thumbnail cache", filepath);
+                    self.remove(&filepath);
+                }
+                None => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Deleting moves files into memetool's own trash directory instead of removing them, with a
+//! journal of where each one came from so it can be put back. Editing a file keeps a backup of
+//! how it was in here too. Things are purged for good from the "Recently deleted" view, or once
+//! they're older than the retention setting.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use log::*;
+use serde::{Deserialize, Serialize};
+
+pub const TRASH_JOURNAL_PATH: &str = "~/.config/memetool-trash.json";
+/// Where deleted files are moved to
+pub const TRASH_DIR: &str = "~/.config/memetool-trash";
+
+/// A file memetool's moved to the trash
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TrashEntry {
+    /// Where it was before it was deleted
+    pub original_path: String,
+    /// Where it is now, this is also how an entry's picked out
+    pub trash_path: String,
+    /// Seconds since the epoch
+    pub deleted_at: u64,
+    /// It's a copy of how the file was before it was edited, the file itself's still there
+    #[serde(default)]
+    pub backup: bool,
+}
+
+impl TrashEntry {
+    pub fn deleted_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.deleted_at)
+    }
+
+    /// false if something else has removed it from the trash, so there's nothing to restore
+    pub fn available(&self) -> bool {
+        Path::new(&self.trash_path).is_file()
+    }
+
+    /// where restoring it should put it, the original path unless something's there now in
+    /// which case it gets a `_restored{n}` suffix. `None` if there's no free `n`.
+    pub fn restore_path(&self) -> Option<PathBuf> {
+        let original = PathBuf::from(&self.original_path);
+        if original.exists() {
+            crate::image_utils::suffixed_path_for(&original, "restored")
+        } else {
+            Some(original)
+        }
+    }
+}
+
+/// Everything in the trash, oldest first
+#[derive(Debug, Default)]
+pub struct TrashJournal {
+    entries: Vec<TrashEntry>,
+}
+
+impl TrashJournal {
+    /// load the journal from `path`, a missing file is an empty trash
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read trash journal {}", path.display()))?;
+        let entries = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse trash journal {}", path.display()))?;
+        Ok(Self { entries })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.entries)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write trash journal {}", path.display()))
+    }
+
+    pub fn entries(&self) -> &[TrashEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, trash_path: &str) -> Option<&TrashEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.trash_path == trash_path)
+    }
+
+    /// move `filepath` into `trash_dir`, remembering where it came from
+    pub fn trash(&mut self, filepath: &str, trash_dir: &Path) -> Result<TrashEntry, String> {
+        self.add(filepath, trash_dir, false)
+    }
+
+    /// copy `filepath` into `trash_dir` before it's edited, so it can be put back with
+    /// [TrashJournal::restore_backup]
+    pub fn keep_backup(&mut self, filepath: &str, trash_dir: &Path) -> Result<TrashEntry, String> {
+        self.add(filepath, trash_dir, true)
+    }
+
+    /// the newest backup of `filepath` that's still in the trash
+    pub fn latest_backup(&self, filepath: &str) -> Option<&TrashEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.backup && entry.original_path == filepath && entry.available())
+    }
+
+    /// put a backup back over the file it was taken from
+    pub fn restore_backup(&mut self, trash_path: &str) -> Result<TrashEntry, String> {
+        let index = self.index_of(trash_path)?;
+        let entry = &self.entries[index];
+        if !entry.backup {
+            return Err(format!("{} isn't a backup", trash_path));
+        }
+        if !entry.available() {
+            return Err(format!("{} isn't in the trash any more", trash_path));
+        }
+        replace_file(Path::new(trash_path), Path::new(&entry.original_path))
+            .map_err(|e| e.to_string())?;
+        Ok(self.entries.remove(index))
+    }
+
+    fn add(
+        &mut self,
+        filepath: &str,
+        trash_dir: &Path,
+        backup: bool,
+    ) -> Result<TrashEntry, String> {
+        let original = PathBuf::from(filepath);
+        if !original.is_file() {
+            return Err(format!("{} isn't a file", filepath));
+        }
+        let filename = original
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| format!("{} doesn't have a filename", filepath))?;
+        std::fs::create_dir_all(trash_dir).map_err(|e| e.to_string())?;
+        let deleted_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        // the same name can be deleted more than once in a second
+        let trash_path = crate::image_utils::first_free_path(
+            (0..).map(|n| trash_dir.join(format!("{deleted_at}-{n}-{filename}"))),
+        )
+        .ok_or_else(|| format!("Couldn't find a free name in the trash for {}", filepath))?;
+        if backup {
+            std::fs::copy(&original, &trash_path).map_err(|e| e.to_string())?;
+            debug!("Copied {} to {}", original.display(), trash_path.display());
+        } else {
+            move_file(&original, &trash_path).map_err(|e| e.to_string())?;
+            debug!("Moved {} to {}", original.display(), trash_path.display());
+        }
+        let entry = TrashEntry {
+            original_path: filepath.to_string(),
+            trash_path: trash_path.display().to_string(),
+            deleted_at,
+            backup,
+        };
+        self.entries.push(entry.clone());
+        Ok(entry)
+    }
+
+    /// move an entry back out of the trash to `destination`, which mustn't exist
+    pub fn restore(&mut self, trash_path: &str, destination: &Path) -> Result<TrashEntry, String> {
+        let index = self.index_of(trash_path)?;
+        if destination.exists() {
+            return Err(format!("{} already exists", destination.display()));
+        }
+        if !self.entries[index].available() {
+            return Err(format!("{} isn't in the trash any more", trash_path));
+        }
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        move_file(Path::new(trash_path), destination).map_err(|e| e.to_string())?;
+        Ok(self.entries.remove(index))
+    }
+
+    /// remove an entry and its file for good, it's fine if the file's already gone
+    pub fn purge(&mut self, trash_path: &str) -> Result<TrashEntry, String> {
+        let index = self.index_of(trash_path)?;
+        remove_trashed(Path::new(trash_path))?;
+        Ok(self.entries.remove(index))
+    }
+
+    /// take out every entry deleted more than `retention_days` before `now`, the files still
+    /// need removing with [remove_trashed]. Zero days keeps everything.
+    pub fn take_expired(&mut self, retention_days: u32, now: SystemTime) -> Vec<TrashEntry> {
+        if retention_days == 0 {
+            return vec![];
+        }
+        let retention = Duration::from_secs(u64::from(retention_days) * 86400);
+        let (expired, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| {
+                now.duration_since(entry.deleted_time())
+                    .map(|age| age > retention)
+                    .unwrap_or(false)
+            });
+        self.entries = kept;
+        expired
+    }
+
+    fn index_of(&self, trash_path: &str) -> Result<usize, String> {
+        self.entries
+            .iter()
+            .position(|entry| entry.trash_path == trash_path)
+            .ok_or_else(|| format!("{} isn't in the trash journal", trash_path))
+    }
+}
+
+/// delete a file from the trash, one that's already gone doesn't count as a failure
+pub fn remove_trashed(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+/// move `from` over the top of `to`, copying if it's going to a different filesystem
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+/// rename, or copy and delete if it's going to a different filesystem
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from).map_err(|err| {
+        // don't leave it in both places
+        let _ = std::fs::remove_file(to);
+        err
+    })
+}
@@ -0,0 +1,85 @@
+//! Where uploads go, so the background task doesn't need to care whether it's S3 or something else
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::config::{Configuration, UploadDestination, DEFAULT_S3_MAX_RETRIES};
+use crate::http_upload::HttpStore;
+use crate::s3_upload::{temp_path_for_key, PartCallback, S3Client, S3Result};
+
+/// Somewhere uploads can go, S3 or an HTTP server. Tests swap in something that's neither.
+#[async_trait]
+pub trait UploadBackend: Send + Sync {
+    async fn head_object(&self, key: &str) -> Result<String, S3Result>;
+    async fn put_object(&self, key: &str, filename: &str) -> Result<String, S3Result>;
+    /// upload in parts if it's big enough to be worth it, calling `on_part` as each one's done.
+    /// Stores that can't do that upload it in one go.
+    async fn put_object_parts(
+        &self,
+        key: &str,
+        filename: &str,
+        _on_part: &PartCallback,
+    ) -> Result<String, S3Result> {
+        self.put_object(key, filename).await
+    }
+    /// make sure the bucket's there and we can get to it, before trying to upload anything
+    async fn check_bucket_accessible(&self) -> Result<(), S3Result>;
+    /// copy an object to a new key without it leaving S3
+    async fn copy_object(&self, src_key: &str, dest_key: &str) -> Result<String, S3Result>;
+    async fn delete_object(&self, key: &str) -> Result<String, S3Result>;
+    /// how big `key` is in the bucket, [S3Result::FileNotFound] if it's not there
+    async fn object_size(&self, key: &str) -> Result<u64, S3Result>;
+    /// download `key` to `dest`, replacing anything that's there
+    async fn get_object(&self, key: &str, dest: &Path) -> Result<(), S3Result>;
+
+    /// download `key` to [temp_path_for_key] so it can be edited, returns where it went
+    async fn download_to_temp(&self, key: &str) -> Result<PathBuf, S3Result> {
+        let path = temp_path_for_key(key);
+        self.get_object(key, &path).await?;
+        Ok(path)
+    }
+
+    /// S3 can't rename things, so it's a copy and then a delete. If the delete fails the object's
+    /// under both keys.
+    async fn rename_object(&self, src_key: &str, dest_key: &str) -> Result<(), S3Result> {
+        self.copy_object(src_key, dest_key).await?;
+        self.delete_object(src_key).await?;
+        Ok(())
+    }
+
+    /// where `key` can be fetched from once it's uploaded, if there's a way to tell
+    fn url_for_key(&self, _key: &str) -> Option<String> {
+        None
+    }
+
+    /// name uploads after their contents instead of their filename
+    fn hash_keys(&self) -> bool {
+        false
+    }
+
+    /// how many times to retry a [S3Result::RetryableUploadFailure]
+    fn max_retries(&self) -> u8 {
+        DEFAULT_S3_MAX_RETRIES
+    }
+}
+
+/// Builds an [UploadBackend] for each upload, so config changes get picked up
+pub type UploadBackendFactory =
+    Arc<dyn Fn() -> anyhow::Result<Box<dyn UploadBackend>> + Send + Sync>;
+
+/// An [UploadBackendFactory] for wherever the config file says uploads go
+pub fn configured_backend_factory() -> UploadBackendFactory {
+    Arc::new(|| {
+        let config = Configuration::try_new()?;
+        backend_for(config)
+    })
+}
+
+/// the [UploadBackend] for `config`'s [UploadDestination]
+pub fn backend_for(config: Configuration) -> anyhow::Result<Box<dyn UploadBackend>> {
+    Ok(match config.upload_destination {
+        UploadDestination::S3 => Box::new(S3Client::from(config)),
+        UploadDestination::Http => Box::new(HttpStore::from_config(&config)?),
+    })
+}
@@ -0,0 +1,107 @@
+//! What's been uploaded, and what it was called in S3
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+pub const UPLOAD_HISTORY_PATH: &str = "~/.config/memetool-uploads.json";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct UploadRecord {
+    pub filepath: String,
+    /// The S3 key it was uploaded as
+    pub key: String,
+    /// Seconds since the epoch
+    pub uploaded_at: u64,
+    /// SHA-256 of what was uploaded, older records don't have one
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+impl UploadRecord {
+    /// a record of `filepath` being uploaded as `key` just now
+    pub fn now(filepath: impl ToString, key: impl ToString) -> Self {
+        Self {
+            filepath: filepath.to_string(),
+            key: key.to_string(),
+            uploaded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            checksum: None,
+        }
+    }
+
+    pub fn with_checksum(self, checksum: Option<String>) -> Self {
+        Self { checksum, ..self }
+    }
+
+    pub fn uploaded_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.uploaded_at)
+    }
+}
+
+/// Every upload in the order they happened, with a lookup by file path so the browser can check
+/// each thumbnail cheaply
+#[derive(Debug, Default)]
+pub struct UploadHistory {
+    records: Vec<UploadRecord>,
+    /// file path to the index of its latest record
+    by_filepath: HashMap<String, usize>,
+}
+
+impl UploadHistory {
+    /// load the history from `path`, a missing file is an empty history
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read upload history {}", path.display()))?;
+        let records: Vec<UploadRecord> = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse upload history {}", path.display()))?;
+        let mut history = Self::default();
+        records
+            .into_iter()
+            .for_each(|record| history.record(record));
+        Ok(history)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.records)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write upload history {}", path.display()))
+    }
+
+    pub fn record(&mut self, record: UploadRecord) {
+        self.by_filepath
+            .insert(record.filepath.clone(), self.records.len());
+        self.records.push(record);
+    }
+
+    /// the latest upload of `filepath`
+    pub fn get(&self, filepath: &str) -> Option<&UploadRecord> {
+        self.by_filepath
+            .get(filepath)
+            .and_then(|index| self.records.get(*index))
+    }
+
+    pub fn contains(&self, filepath: &str) -> bool {
+        self.by_filepath.contains_key(filepath)
+    }
+
+    pub fn records(&self) -> &[UploadRecord] {
+        &self.records
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
@@ -0,0 +1,141 @@
+//! Short video clips in the browser, with the `video` feature. There's no playback, ffmpeg's asked
+//! for the first frame to use as a thumbnail and ffprobe for how big and long the clip is. Both
+//! have to be installed and on the `PATH`, without them videos get a placeholder tile.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use log::*;
+
+/// What gets listed as a video, see [OK_EXTENSIONS](crate::OK_EXTENSIONS)
+pub const VIDEO_EXTENSIONS: [&str; 3] = ["mp4", "webm", "mov"];
+
+lazy_static! {
+    /// only ask once, it's not going to be installed while we're running
+    static ref FFMPEG_AVAILABLE: bool = {
+        let available = ["ffmpeg", "ffprobe"].iter().all(|program| {
+            Command::new(program)
+                .arg("-version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .map(|status| status.success())
+                .unwrap_or(false)
+        });
+        if !available {
+            warn!("Couldn't find ffmpeg and ffprobe, videos won't get thumbnails");
+        }
+        available
+    };
+}
+
+/// if `path` looks like a video, going by the extension
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| {
+            VIDEO_EXTENSIONS
+                .iter()
+                .any(|video| ext.eq_ignore_ascii_case(video))
+        })
+        .unwrap_or(false)
+}
+
+/// whether ffmpeg and ffprobe can be run
+pub fn ffmpeg_available() -> bool {
+    *FFMPEG_AVAILABLE
+}
+
+/// What ffprobe says about a clip
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VideoInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Streams don't always say how long they are
+    pub duration: Option<Duration>,
+}
+
+/// pick [VideoInfo] out of `ffprobe -of default=noprint_wrappers=1`, which is `key=value` lines
+pub fn parse_probe_output(output: &str) -> Option<VideoInfo> {
+    let mut width = None;
+    let mut height = None;
+    let mut duration = None;
+    for line in output.lines() {
+        match line.trim().split_once('=') {
+            Some(("width", value)) => width = value.parse().ok(),
+            Some(("height", value)) => height = value.parse().ok(),
+            // it's "N/A" when it doesn't know
+            Some(("duration", value)) => {
+                duration = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                    .map(Duration::from_secs_f64)
+            }
+            _ => {}
+        }
+    }
+    Some(VideoInfo {
+        width: width?,
+        height: height?,
+        duration,
+    })
+}
+
+/// ask ffprobe how big and long the first video stream in `path` is
+pub fn probe(path: &Path) -> Result<VideoInfo, String> {
+    puffin::profile_function!(path.display().to_string());
+    if !ffmpeg_available() {
+        return Err(crate::t!("video-needs-ffmpeg"));
+    }
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries"])
+        .arg("stream=width,height:format=duration")
+        .args(["-of", "default=noprint_wrappers=1"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    parse_probe_output(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| format!("{} doesn't have a video stream", path.display()))
+}
+
+/// get ffmpeg to decode the first frame of `path`, passed back to us as a PNG
+pub fn first_frame(path: &Path) -> Result<image::DynamicImage, String> {
+    puffin::profile_function!(path.display().to_string());
+    if !ffmpeg_available() {
+        return Err(crate::t!("video-needs-ffmpeg"));
+    }
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "-"])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    image::load_from_memory_with_format(&output.stdout, image::ImageFormat::Png)
+        .map_err(|e| e.to_string())
+}
+
+/// how long a clip is, eg "0:07" or "1:02:03"
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, (seconds / 60) % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// hand `path` to whatever the desktop plays videos with
+pub fn open_externally(path: &Path) -> Result<(), String> {
+    info!("Opening {} externally", path.display());
+    open::that(path).map_err(|e| e.to_string())
+}
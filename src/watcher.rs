@@ -0,0 +1,91 @@
+//! Keeping an eye on the workdir, so files that turn up or go away while we're open show up in
+//! the browser without hitting refresh
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use log::*;
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::dir_scan::is_listable;
+use crate::AppMsg;
+
+/// How long things have to settle before the browser's refreshed, so copying a pile of files in
+/// doesn't reload the page for every one
+pub const WATCHER_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatcherEventKind {
+    Created(PathBuf),
+    Removed(PathBuf),
+    Modified(PathBuf),
+}
+
+impl WatcherEventKind {
+    /// what `event` means for the browser, renames come out as a removal and a creation, and
+    /// anything that isn't an image's skipped
+    pub fn from_event(event: &notify::Event) -> Vec<Self> {
+        let kinds: Vec<Self> = match &event.kind {
+            EventKind::Create(_) => event.paths.iter().cloned().map(Self::Created).collect(),
+            EventKind::Remove(_) => event.paths.iter().cloned().map(Self::Removed).collect(),
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                event.paths.iter().cloned().map(Self::Removed).collect()
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                event.paths.iter().cloned().map(Self::Created).collect()
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => match event.paths.as_slice() {
+                [from, to] => vec![Self::Removed(from.clone()), Self::Created(to.clone())],
+                _ => event.paths.iter().cloned().map(Self::Modified).collect(),
+            },
+            // it's still got its name, so it's gone if it's not there any more
+            EventKind::Modify(ModifyKind::Name(_)) => event
+                .paths
+                .iter()
+                .cloned()
+                .map(|path| {
+                    if path.exists() {
+                        Self::Created(path)
+                    } else {
+                        Self::Removed(path)
+                    }
+                })
+                .collect(),
+            EventKind::Modify(_) => event.paths.iter().cloned().map(Self::Modified).collect(),
+            EventKind::Access(_) | EventKind::Any | EventKind::Other => vec![],
+        };
+        kinds
+            .into_iter()
+            .filter(|kind| is_listable(kind.path()))
+            .collect()
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Created(path) | Self::Removed(path) | Self::Modified(path) => path,
+        }
+    }
+}
+
+/// start watching `path`, events go to the frontend as [AppMsg::WatcherEvent] for as long as the
+/// watcher's kept around
+pub fn watch_dir(path: &Path, tx: mpsc::Sender<AppMsg>) -> notify::Result<RecommendedWatcher> {
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) => {
+                for kind in WatcherEventKind::from_event(&event) {
+                    trace!("Watcher saw {:?}", kind);
+                    // this runs on the watcher's own thread, so blocking's fine
+                    if let Err(err) = tx.blocking_send(AppMsg::WatcherEvent(kind)) {
+                        error!("Failed to send watcher event: {}", err);
+                    }
+                }
+            }
+            Err(err) => error!("Watcher error: {:?}", err),
+        })?;
+    watcher.watch(path, RecursiveMode::NonRecursive)?;
+    info!("Watching {}", path.display());
+    Ok(watcher)
+}
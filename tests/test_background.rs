@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use memetool::background::{process_batch, upload_backoff};
+use memetool::load_stats::PageStatsCollector;
+use memetool::s3_upload::s3_backend_factory;
+use memetool::{AppMsg, AppState};
+
+#[tokio::test]
+async fn test_process_batch_flattens_and_keeps_order() {
+    let (tx, _rx) = tokio::sync::mpsc::channel(10);
+    let batch = vec![
+        AppMsg::NewAppState(AppState::Browser),
+        AppMsg::Batch(vec![AppMsg::NewAppState(AppState::Help)]),
+        AppMsg::NewAppState(AppState::Configuration),
+    ];
+
+    let result = process_batch(
+        batch,
+        &tx,
+        &s3_backend_factory(),
+        &mut None,
+        &mut PageStatsCollector::default(),
+    )
+    .await;
+
+    match result {
+        AppMsg::BatchResult(results) => {
+            assert_eq!(results.len(), 3);
+            assert!(matches!(results[0], AppMsg::NewAppState(AppState::Browser)));
+            assert!(matches!(results[1], AppMsg::NewAppState(AppState::Help)));
+            assert!(matches!(
+                results[2],
+                AppMsg::NewAppState(AppState::Configuration)
+            ));
+        }
+        other => panic!("Expected BatchResult, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_upload_backoff() {
+    assert_eq!(upload_backoff(1), Duration::from_millis(500));
+    assert_eq!(upload_backoff(2), Duration::from_secs(1));
+    assert_eq!(upload_backoff(3), Duration::from_secs(2));
+    // capped
+    assert_eq!(upload_backoff(8), Duration::from_secs(30));
+    assert_eq!(upload_backoff(100), Duration::from_secs(30));
+}
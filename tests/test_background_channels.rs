@@ -0,0 +1,931 @@
+//! Runs [background_with_store] on its own and talks to it over the channels, the same way the UI does
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eframe::epaint::{vec2, Color32, ColorImage};
+use memetool::background::background_with_store;
+use memetool::image_utils::{ContactSheetLayout, CONTACT_SHEET_PADDING};
+use memetool::jobs::{JobId, JobWork};
+use memetool::optimize::OptimizeAction;
+use memetool::outbox::Outbox;
+use memetool::s3_upload::{temp_path_for_key, S3Error, S3ErrorKind, S3Result};
+use memetool::tasks::{TaskPurpose, TaskTracker};
+use memetool::upload_backend::{UploadBackend, UploadBackendFactory};
+use memetool::watcher::WatcherEventKind;
+use memetool::{AppMsg, AppState, ContactSheetTile, ImageData, ThumbImageMsg};
+use tokio::sync::mpsc;
+
+/// How the fake upload backend should behave
+#[derive(Clone, Copy)]
+enum Remote {
+    Exists,
+    NotFound,
+    UploadFails,
+    /// The bucket's not there at all
+    NoBucket,
+    /// The first upload attempt times out, the next one works
+    Flaky,
+}
+
+struct MockStore {
+    remote: Remote,
+    hash_keys: bool,
+    uploaded: Arc<Mutex<Vec<String>>>,
+    /// put_object calls so far
+    attempts: AtomicU32,
+}
+
+#[async_trait]
+impl UploadBackend for MockStore {
+    async fn head_object(&self, key: &str) -> Result<String, S3Result> {
+        match self.remote {
+            Remote::Exists => Ok(format!("{key} is already there")),
+            Remote::NotFound | Remote::UploadFails | Remote::NoBucket | Remote::Flaky => {
+                Err(S3Result::FileNotFound)
+            }
+        }
+    }
+
+    async fn put_object(&self, key: &str, _filename: &str) -> Result<String, S3Result> {
+        let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+        match self.remote {
+            Remote::UploadFails => Err(S3Result::UploadFailure(S3Error::other("nope"))),
+            Remote::Flaky if attempt == 0 => Err(S3Result::RetryableUploadFailure(S3Error::new(
+                S3ErrorKind::Timeout,
+                "timed out",
+            ))),
+            _ => {
+                self.uploaded.lock().unwrap().push(key.to_string());
+                Ok(key.to_string())
+            }
+        }
+    }
+
+    async fn check_bucket_accessible(&self) -> Result<(), S3Result> {
+        match self.remote {
+            Remote::NoBucket => Err(S3Result::BucketNotAccessible(S3Error::new(
+                S3ErrorKind::NoSuchBucket,
+                "memes",
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    async fn copy_object(&self, src_key: &str, dest_key: &str) -> Result<String, S3Result> {
+        match self.remote {
+            Remote::Exists => {
+                self.uploaded.lock().unwrap().push(dest_key.to_string());
+                Ok(format!("{src_key} copied"))
+            }
+            _ => Err(S3Result::CopyFailure(S3Error::other(format!(
+                "{src_key} isn't there"
+            )))),
+        }
+    }
+
+    async fn object_size(&self, _key: &str) -> Result<u64, S3Result> {
+        match self.remote {
+            Remote::Exists => Ok(1234),
+            _ => Err(S3Result::FileNotFound),
+        }
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<String, S3Result> {
+        match self.remote {
+            Remote::Exists => {
+                self.uploaded
+                    .lock()
+                    .unwrap()
+                    .retain(|uploaded| uploaded != key);
+                Ok(format!("{key} deleted"))
+            }
+            _ => Err(S3Result::DeleteFailure(S3Error::other(format!(
+                "{key} isn't there"
+            )))),
+        }
+    }
+
+    async fn get_object(&self, key: &str, dest: &Path) -> Result<(), S3Result> {
+        match self.remote {
+            Remote::Exists => {
+                std::fs::write(dest, format!("pretend {key}")).unwrap();
+                Ok(())
+            }
+            _ => Err(S3Result::FileNotFound),
+        }
+    }
+
+    fn hash_keys(&self) -> bool {
+        self.hash_keys
+    }
+}
+
+fn mock_factory(remote: Remote, uploaded: Arc<Mutex<Vec<String>>>) -> UploadBackendFactory {
+    mock_factory_with_keys(remote, false, uploaded)
+}
+
+fn mock_factory_with_keys(
+    remote: Remote,
+    hash_keys: bool,
+    uploaded: Arc<Mutex<Vec<String>>>,
+) -> UploadBackendFactory {
+    Arc::new(move || {
+        Ok(Box::new(MockStore {
+            remote,
+            hash_keys,
+            uploaded: uploaded.clone(),
+            attempts: AtomicU32::new(0),
+        }) as Box<dyn UploadBackend>)
+    })
+}
+
+/// start the background task, returning the UI's ends of the channels
+fn start(store: UploadBackendFactory) -> (mpsc::Sender<AppMsg>, mpsc::Receiver<AppMsg>) {
+    start_with_capacity(store, memetool::config::DEFAULT_CHANNEL_CAPACITY)
+}
+
+/// [start] with channels that only have room for `capacity` messages
+fn start_with_capacity(
+    store: UploadBackendFactory,
+    capacity: usize,
+) -> (mpsc::Sender<AppMsg>, mpsc::Receiver<AppMsg>) {
+    let (foreground_tx, foreground_rx) = mpsc::channel(capacity);
+    let (background_tx, background_rx) = mpsc::channel(capacity);
+    tokio::spawn(background_with_store(background_rx, foreground_tx, store));
+    (background_tx, foreground_rx)
+}
+
+async fn next_reply(rx: &mut mpsc::Receiver<AppMsg>) -> AppMsg {
+    tokio::time::timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("Timed out waiting for the background task")
+        .expect("Background task hung up")
+}
+
+fn write_fixture(dir: &Path, name: &str) -> PathBuf {
+    let filepath = dir.join(name);
+    image::RgbaImage::from_pixel(64, 32, image::Rgba([255, 0, 0, 255]))
+        .save(&filepath)
+        .expect("Failed to write fixture image");
+    filepath
+}
+
+#[tokio::test]
+async fn test_load_image_replies_with_thumbnail() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "red.png");
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    tx.send(AppMsg::LoadImage(ThumbImageMsg::request(
+        filepath.display(),
+        3,
+        Some(vec2(32.0, 32.0)),
+    )))
+    .await
+    .unwrap();
+
+    match next_reply(&mut rx).await {
+        AppMsg::ThumbImageResponse(response) => {
+            assert_eq!(response.filepath(), filepath.display().to_string());
+            assert_eq!(response.page(), 3);
+            let image = response.image().expect("Response should have an image");
+            // keeps the aspect ratio while fitting in the requested size
+            assert_eq!(image.size, [32, 16]);
+        }
+        other => panic!("Expected ThumbImageResponse, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_image_data_thumbnail_request() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "red.png");
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    let request = ImageData::new(&filepath).to_thumbnail_request(2);
+    assert_eq!(request.filepath(), filepath.display().to_string());
+    assert_eq!(request.page(), 2);
+    assert!(request.image().is_none());
+
+    tx.send(AppMsg::LoadImage(request.with_size(vec2(16.0, 16.0))))
+        .await
+        .unwrap();
+    match next_reply(&mut rx).await {
+        AppMsg::ThumbImageResponse(response) => {
+            assert_eq!(response.page(), 2);
+            assert_eq!(response.image().map(|image| image.size), Some([16, 8]));
+        }
+        other => panic!("Expected ThumbImageResponse, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_responses_carry_task_ids() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let first = write_fixture(tempdir.path(), "first.png");
+    let missing = tempdir.path().join("missing.png");
+    let mut tasks = TaskTracker::default();
+    let first_task = tasks.start(TaskPurpose::Thumbnail);
+    let missing_task = tasks.start(TaskPurpose::Thumbnail);
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    // both requests are for thumbnails, the task ids are the only way to tell the replies apart
+    tx.send(AppMsg::LoadImage(
+        ThumbImageMsg::request(missing.display(), 0, None).with_task(missing_task),
+    ))
+    .await
+    .unwrap();
+    tx.send(AppMsg::LoadImage(
+        ThumbImageMsg::request(first.display(), 0, None).with_task(first_task),
+    ))
+    .await
+    .unwrap();
+
+    match next_reply(&mut rx).await {
+        AppMsg::ImageLoadFailed { task, .. } => assert_eq!(task, Some(missing_task)),
+        other => panic!("Expected ImageLoadFailed, got {:?}", other),
+    }
+    match next_reply(&mut rx).await {
+        AppMsg::ThumbImageResponse(response) => assert_eq!(response.task(), Some(first_task)),
+        other => panic!("Expected ThumbImageResponse, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_load_image_failures() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let not_an_image = tempdir.path().join("notes.png");
+    std::fs::write(&not_an_image, "this isn't a png").unwrap();
+    let missing = tempdir.path().join("missing.png");
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    for filepath in [not_an_image, missing] {
+        tx.send(AppMsg::LoadImage(ThumbImageMsg::request(
+            filepath.display(),
+            0,
+            None,
+        )))
+        .await
+        .unwrap();
+
+        match next_reply(&mut rx).await {
+            AppMsg::ImageLoadFailed { filename, .. } => {
+                assert_eq!(filename, filepath.display().to_string())
+            }
+            other => panic!("Expected ImageLoadFailed, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_upload_new_file() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "upload.png");
+    let total = std::fs::metadata(&filepath).unwrap().len();
+    let uploaded = Arc::new(Mutex::new(vec![]));
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, uploaded.clone()));
+
+    tx.send(AppMsg::UploadImage(filepath.display().to_string()))
+        .await
+        .unwrap();
+
+    match next_reply(&mut rx).await {
+        AppMsg::UploadProgress {
+            bytes_sent,
+            total_bytes,
+            ..
+        } => assert_eq!((bytes_sent, total_bytes), (0, total)),
+        other => panic!("Expected UploadProgress, got {:?}", other),
+    }
+    match next_reply(&mut rx).await {
+        AppMsg::UploadProgress {
+            bytes_sent,
+            total_bytes,
+            ..
+        } => assert_eq!((bytes_sent, total_bytes), (total, total)),
+        other => panic!("Expected UploadProgress, got {:?}", other),
+    }
+    match next_reply(&mut rx).await {
+        AppMsg::UploadComplete {
+            filepath: completed,
+            key,
+            checksum,
+        } => {
+            assert_eq!(completed, filepath.display().to_string());
+            assert_eq!(key, "upload.png");
+            assert_eq!(checksum.map(|checksum| checksum.len()), Some(64));
+        }
+        other => panic!("Expected UploadComplete, got {:?}", other),
+    }
+    assert_eq!(*uploaded.lock().unwrap(), vec!["upload.png".to_string()]);
+}
+
+#[tokio::test]
+async fn test_upload_with_content_hash_key() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("Renamed Copy.PNG");
+    std::fs::write(&filepath, "hello").unwrap();
+    let uploaded = Arc::new(Mutex::new(vec![]));
+    let (tx, mut rx) = start(mock_factory_with_keys(
+        Remote::NotFound,
+        true,
+        uploaded.clone(),
+    ));
+
+    tx.send(AppMsg::UploadImage(filepath.display().to_string()))
+        .await
+        .unwrap();
+
+    let expected = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824.png";
+    loop {
+        match next_reply(&mut rx).await {
+            AppMsg::UploadProgress { .. } => continue,
+            AppMsg::UploadComplete { key, .. } => {
+                assert_eq!(key, expected);
+                break;
+            }
+            other => panic!("Expected UploadComplete, got {:?}", other),
+        }
+    }
+    assert_eq!(*uploaded.lock().unwrap(), vec![expected.to_string()]);
+}
+
+#[tokio::test]
+async fn test_upload_existing_file_is_aborted() {
+    let uploaded = Arc::new(Mutex::new(vec![]));
+    let (tx, mut rx) = start(mock_factory(Remote::Exists, uploaded.clone()));
+
+    tx.send(AppMsg::UploadImage("/tmp/memes/exists.png".to_string()))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::UploadAborted { .. }
+    ));
+    assert!(uploaded.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_upload_failure() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "fails.png");
+    let (tx, mut rx) = start(mock_factory(Remote::UploadFails, Default::default()));
+
+    tx.send(AppMsg::UploadImage(filepath.display().to_string()))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::UploadProgress { bytes_sent: 0, .. }
+    ));
+    match next_reply(&mut rx).await {
+        AppMsg::DetailedError { message, details } => {
+            assert_eq!(message, S3ErrorKind::Other.user_message());
+            assert_eq!(details, "nope");
+        }
+        other => panic!("Expected DetailedError, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_upload_without_store_is_aborted() {
+    let (tx, mut rx) = start(Arc::new(|| Err(anyhow::anyhow!("no config"))));
+
+    tx.send(AppMsg::UploadImage("/tmp/memes/nowhere.png".to_string()))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::UploadAborted { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_check_integrity() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "whole.png");
+    let contents = std::fs::read(&filepath).unwrap();
+    let truncated = tempdir.path().join("truncated.png");
+    std::fs::write(&truncated, &contents[..contents.len() / 2]).unwrap();
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    for (path, corrupt) in [(filepath, false), (truncated, true)] {
+        tx.send(AppMsg::CheckIntegrity(path.display().to_string()))
+            .await
+            .unwrap();
+
+        match next_reply(&mut rx).await {
+            AppMsg::IntegrityChecked {
+                filepath,
+                modified,
+                verdict,
+            } => {
+                assert_eq!(filepath, path.display().to_string());
+                assert!(modified.is_some());
+                assert_eq!(verdict.corruption().is_some(), corrupt);
+            }
+            other => panic!("Expected IntegrityChecked, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_upload_without_bucket_is_aborted() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "nobucket.png");
+    let uploaded = Arc::new(Mutex::new(vec![]));
+    let (tx, mut rx) = start(mock_factory(Remote::NoBucket, uploaded.clone()));
+
+    tx.send(AppMsg::UploadImage(filepath.display().to_string()))
+        .await
+        .unwrap();
+
+    match next_reply(&mut rx).await {
+        AppMsg::UploadAborted { message, details } => {
+            assert!(message.starts_with("Bucket not accessible"));
+            assert!(message.contains(&S3ErrorKind::NoSuchBucket.user_message()));
+            assert_eq!(details.as_deref(), Some("memes"));
+        }
+        other => panic!("Expected UploadAborted, got {:?}", other),
+    }
+    assert!(uploaded.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_check_bucket() {
+    for (remote, accessible) in [(Remote::NotFound, true), (Remote::NoBucket, false)] {
+        let (tx, mut rx) = start(mock_factory(remote, Default::default()));
+        tx.send(AppMsg::CheckBucket).await.unwrap();
+
+        match next_reply(&mut rx).await {
+            AppMsg::BucketStatus(status) => assert_eq!(status.is_ok(), accessible),
+            other => panic!("Expected BucketStatus, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_copy_object() {
+    let copied = Arc::new(Mutex::new(vec![]));
+    let (tx, mut rx) = start(mock_factory(Remote::Exists, copied.clone()));
+
+    tx.send(AppMsg::S3CopyObject {
+        src_key: "uploads/cat.jpg".to_string(),
+        dest_key: "archive/2024/cat.jpg".to_string(),
+    })
+    .await
+    .unwrap();
+
+    match next_reply(&mut rx).await {
+        AppMsg::S3ObjectCopied { src_key, dest_key } => {
+            assert_eq!(src_key, "uploads/cat.jpg");
+            assert_eq!(dest_key, "archive/2024/cat.jpg");
+        }
+        other => panic!("Expected S3ObjectCopied, got {:?}", other),
+    }
+    assert_eq!(
+        *copied.lock().unwrap(),
+        vec!["archive/2024/cat.jpg".to_string()]
+    );
+
+    // nothing to copy
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+    tx.send(AppMsg::S3CopyObject {
+        src_key: "uploads/dog.jpg".to_string(),
+        dest_key: "archive/dog.jpg".to_string(),
+    })
+    .await
+    .unwrap();
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::DetailedError { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_download_and_edit() {
+    let key = "uploads/test_download_and_edit.jpg";
+    let (tx, mut rx) = start(mock_factory(Remote::Exists, Default::default()));
+    tx.send(AppMsg::DownloadAndEdit {
+        key: key.to_string(),
+    })
+    .await
+    .unwrap();
+
+    let filepath = match next_reply(&mut rx).await {
+        AppMsg::NewAppState(AppState::Editor { filepath }) => filepath,
+        other => panic!("Expected the editor, got {:?}", other),
+    };
+    assert_eq!(PathBuf::from(&filepath), temp_path_for_key(key));
+    assert_eq!(
+        std::fs::read_to_string(&filepath).unwrap(),
+        format!("pretend {key}")
+    );
+    std::fs::remove_file(&filepath).unwrap();
+
+    // it's not in the bucket
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+    tx.send(AppMsg::DownloadAndEdit {
+        key: key.to_string(),
+    })
+    .await
+    .unwrap();
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::DetailedError { .. }
+    ));
+    assert!(!temp_path_for_key(key).exists());
+}
+
+#[tokio::test]
+async fn test_update_overwrites_existing_object() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "cropped.png");
+    let uploaded = Arc::new(Mutex::new(vec![]));
+    // a plain upload would give up here, because it's already there
+    let (tx, mut rx) = start(mock_factory(Remote::Exists, uploaded.clone()));
+
+    tx.send(AppMsg::CheckRemoteSize {
+        filepath: filepath.display().to_string(),
+        key: "cropped.png".to_string(),
+    })
+    .await
+    .unwrap();
+    match next_reply(&mut rx).await {
+        AppMsg::RemoteSize { size, .. } => assert_eq!(size, Ok(Some(1234))),
+        other => panic!("Expected RemoteSize, got {:?}", other),
+    }
+
+    tx.send(AppMsg::UpdateImage {
+        filepath: filepath.display().to_string(),
+        key: "cropped.png".to_string(),
+    })
+    .await
+    .unwrap();
+    loop {
+        match next_reply(&mut rx).await {
+            AppMsg::UploadProgress { .. } => {}
+            AppMsg::UploadComplete { key, checksum, .. } => {
+                assert_eq!(key, "cropped.png");
+                assert!(checksum.is_some());
+                break;
+            }
+            other => panic!("Expected UploadComplete, got {:?}", other),
+        }
+    }
+    assert_eq!(*uploaded.lock().unwrap(), vec!["cropped.png".to_string()]);
+
+    // it's gone from the bucket since it was uploaded
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+    tx.send(AppMsg::CheckRemoteSize {
+        filepath: filepath.display().to_string(),
+        key: "cropped.png".to_string(),
+    })
+    .await
+    .unwrap();
+    match next_reply(&mut rx).await {
+        AppMsg::RemoteSize { size, .. } => assert_eq!(size, Ok(None)),
+        other => panic!("Expected RemoteSize, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_rename_object() {
+    let uploaded = Arc::new(Mutex::new(vec!["dog.png".to_string()]));
+    let (tx, mut rx) = start(mock_factory(Remote::Exists, uploaded.clone()));
+
+    tx.send(AppMsg::S3RenameObject {
+        old_filepath: "/tmp/memes/dog.png".to_string(),
+        filepath: "/tmp/memes/good-dog.png".to_string(),
+        src_key: "dog.png".to_string(),
+        dest_key: "good-dog.png".to_string(),
+    })
+    .await
+    .unwrap();
+
+    match next_reply(&mut rx).await {
+        AppMsg::S3ObjectRenamed {
+            filepath,
+            dest_key,
+            result,
+            ..
+        } => {
+            assert_eq!(filepath, "/tmp/memes/good-dog.png");
+            assert_eq!(dest_key, "good-dog.png");
+            assert!(result.is_ok());
+        }
+        other => panic!("Expected S3ObjectRenamed, got {:?}", other),
+    }
+    assert_eq!(*uploaded.lock().unwrap(), vec!["good-dog.png".to_string()]);
+
+    // the copy fails, which the frontend gets told about
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+    tx.send(AppMsg::S3RenameObject {
+        old_filepath: "/tmp/memes/cat.png".to_string(),
+        filepath: "/tmp/memes/good-cat.png".to_string(),
+        src_key: "cat.png".to_string(),
+        dest_key: "good-cat.png".to_string(),
+    })
+    .await
+    .unwrap();
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::S3ObjectRenamed { result: Err(_), .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_upload_retries_transient_failures() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "flaky.png");
+    let uploaded = Arc::new(Mutex::new(vec![]));
+    let (tx, mut rx) = start(mock_factory(Remote::Flaky, uploaded.clone()));
+
+    tx.send(AppMsg::UploadImage(filepath.display().to_string()))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::UploadProgress { message: None, .. }
+    ));
+    match next_reply(&mut rx).await {
+        AppMsg::UploadProgress { message, .. } => {
+            assert_eq!(message, Some("retry 1/3".to_string()))
+        }
+        other => panic!("Expected UploadProgress, got {:?}", other),
+    }
+    loop {
+        match next_reply(&mut rx).await {
+            AppMsg::UploadProgress { .. } => continue,
+            AppMsg::UploadComplete { key, .. } => {
+                assert_eq!(key, "flaky.png");
+                break;
+            }
+            other => panic!("Expected UploadComplete, got {:?}", other),
+        }
+    }
+    assert_eq!(*uploaded.lock().unwrap(), vec!["flaky.png".to_string()]);
+}
+
+#[tokio::test]
+async fn test_export_contact_sheet() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let on_disk = write_fixture(tempdir.path(), "on_disk.png");
+    let savepath = tempdir.path().join("sheet.jpg");
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    let tiles = vec![
+        ContactSheetTile {
+            filepath: "/tmp/memes/already_loaded.png".to_string(),
+            cached: Some(Arc::new(ColorImage::new([10, 10], Color32::BLUE))),
+        },
+        ContactSheetTile {
+            filepath: on_disk.display().to_string(),
+            cached: None,
+        },
+        ContactSheetTile {
+            filepath: tempdir.path().join("missing.png").display().to_string(),
+            cached: None,
+        },
+    ];
+    tx.send(AppMsg::ExportContactSheet {
+        savepath: savepath.clone(),
+        layout: ContactSheetLayout::default(),
+        tiles,
+    })
+    .await
+    .unwrap();
+
+    for expected in 1..=3 {
+        match next_reply(&mut rx).await {
+            AppMsg::ContactSheetProgress { done, total } => {
+                assert_eq!((done, total), (expected, 3))
+            }
+            other => panic!("Expected ContactSheetProgress, got {:?}", other),
+        }
+    }
+    match next_reply(&mut rx).await {
+        AppMsg::ContactSheetDone(Ok(saved)) => assert_eq!(saved, savepath),
+        other => panic!("Expected ContactSheetDone, got {:?}", other),
+    }
+    // the missing file's left off, so it's two across
+    let (width, _) = image::image_dimensions(&savepath).unwrap();
+    assert_eq!(
+        width,
+        2 * (200 + CONTACT_SHEET_PADDING) + CONTACT_SHEET_PADDING
+    );
+}
+
+#[tokio::test]
+async fn test_export_mosaic() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let on_disk = write_fixture(tempdir.path(), "on_disk.png");
+    let savepath = tempdir.path().join("mosaic.png");
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    tx.send(AppMsg::ExportMosaic {
+        savepath: savepath.clone(),
+        filepaths: vec![on_disk],
+        width: 64,
+        height: 64,
+    })
+    .await
+    .unwrap();
+    match next_reply(&mut rx).await {
+        AppMsg::MosaicDone(Ok(saved)) => assert_eq!(saved, savepath),
+        other => panic!("Expected MosaicDone, got {:?}", other),
+    }
+    assert_eq!(image::image_dimensions(&savepath).unwrap(), (64, 64));
+
+    tx.send(AppMsg::ExportMosaic {
+        savepath,
+        filepaths: vec![],
+        width: 64,
+        height: 64,
+    })
+    .await
+    .unwrap();
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::MosaicDone(Err(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_watch_dir() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    tx.send(AppMsg::WatchDir(tempdir.path().to_path_buf()))
+        .await
+        .unwrap();
+    match next_reply(&mut rx).await {
+        AppMsg::Echo(_) => {}
+        other => panic!("Expected Echo, got {:?}", other),
+    }
+
+    let filepath = write_fixture(tempdir.path(), "new.png");
+    // writing it can show up as a few events, the creation's the one that matters
+    loop {
+        match next_reply(&mut rx).await {
+            AppMsg::WatcherEvent(WatcherEventKind::Created(path)) => {
+                assert_eq!(path.file_name(), filepath.file_name());
+                break;
+            }
+            AppMsg::WatcherEvent(_) => {}
+            other => panic!("Expected WatcherEvent, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_optimize_job() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let gif = write_fixture(tempdir.path(), "red.gif");
+    // noisy enough that a smaller one's a smaller file
+    let png = tempdir.path().join("gradient.png");
+    image::RgbImage::from_fn(64, 32, |x, y| image::Rgb([(x * 4) as u8, (y * 8) as u8, 0]))
+        .save(&png)
+        .unwrap();
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    let job = JobId::next();
+    tx.send(AppMsg::StartJob {
+        job,
+        work: JobWork::Optimize(OptimizeAction::Resize {
+            max_width: 16,
+            max_height: 16,
+            quality: 85,
+        }),
+        filepaths: vec![gif.display().to_string(), png.display().to_string()],
+    })
+    .await
+    .unwrap();
+    let mut progress = vec![];
+    let report = loop {
+        match next_reply(&mut rx).await {
+            AppMsg::Echo(_) => {}
+            AppMsg::JobProgress { done, .. } => progress.push(done),
+            AppMsg::JobDone {
+                job: done_job,
+                report,
+            } => {
+                assert_eq!(done_job, job);
+                break report;
+            }
+            other => panic!("Expected job messages, got {:?}", other),
+        }
+    };
+    assert_eq!(progress, vec![0, 1]);
+    assert_eq!(report.skipped().len(), 1);
+    assert_eq!(report.done(), 1);
+    assert_eq!(image::image_dimensions(&png).unwrap(), (16, 8));
+}
+
+#[tokio::test]
+async fn test_batch_with_frontend_only_messages() {
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    tx.send(AppMsg::Batch(vec![
+        AppMsg::Echo("hello".to_string()),
+        AppMsg::ThumbImageResponse(ThumbImageMsg::request("/tmp/memes/cat.jpg", 0, None)),
+    ]))
+    .await
+    .unwrap();
+    match next_reply(&mut rx).await {
+        AppMsg::BatchResult(results) => {
+            assert_eq!(results.len(), 2);
+            assert!(matches!(&results[0], AppMsg::Echo(msg) if msg == "hello"));
+            assert!(matches!(results[1], AppMsg::Error(_)));
+        }
+        other => panic!("Expected BatchResult, got {:?}", other),
+    }
+
+    // and it's still there to answer the next one
+    tx.send(AppMsg::Batch(vec![AppMsg::Echo("still here".to_string())]))
+        .await
+        .unwrap();
+    assert!(matches!(
+        next_reply(&mut rx).await,
+        AppMsg::BatchResult(results) if results.len() == 1
+    ));
+}
+
+#[tokio::test]
+async fn test_scan_directory() {
+    let tempdir = tempfile::tempdir().unwrap();
+    for name in ["a.png", "b.jpg", "notes.txt"] {
+        std::fs::write(tempdir.path().join(name), b"").unwrap();
+    }
+    let (tx, mut rx) = start(mock_factory(Remote::NotFound, Default::default()));
+
+    tx.send(AppMsg::ScanDirectory {
+        path: tempdir.path().to_path_buf(),
+        generation: 7,
+    })
+    .await
+    .unwrap();
+    let mut found = vec![];
+    loop {
+        match next_reply(&mut rx).await {
+            AppMsg::DirectoryListing {
+                entries,
+                generation,
+                done,
+            } => {
+                assert_eq!(generation, 7);
+                found.extend(entries);
+                if done {
+                    break;
+                }
+            }
+            other => panic!("Expected DirectoryListing, got {:?}", other),
+        }
+    }
+    found.sort();
+    assert_eq!(
+        found,
+        vec![tempdir.path().join("a.png"), tempdir.path().join("b.jpg")]
+    );
+}
+
+#[tokio::test]
+async fn test_outbox_loses_nothing_when_the_channel_is_full() {
+    const MESSAGES: usize = 300;
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_fixture(tempdir.path(), "red.png");
+    let (tx, mut rx) = start_with_capacity(mock_factory(Remote::NotFound, Default::default()), 4);
+
+    let mut outbox = Outbox::default();
+    for page in 0..MESSAGES {
+        outbox.push(AppMsg::LoadImage(ThumbImageMsg::request(
+            filepath.display(),
+            page,
+            Some(vec2(8.0, 8.0)),
+        )));
+    }
+    outbox.flush(&tx);
+    assert!(outbox.is_backed_up());
+    assert!(outbox.pending() > 0);
+
+    let mut pages = vec![];
+    while pages.len() < MESSAGES {
+        outbox.flush(&tx);
+        if let AppMsg::ThumbImageResponse(response) = next_reply(&mut rx).await {
+            assert!(response.image().is_some());
+            pages.push(response.page());
+        }
+    }
+    assert_eq!(outbox.pending(), 0);
+    assert!(!outbox.is_backed_up());
+    // the background does them one at a time, so they come back in the order they were sent
+    assert_eq!(pages, (0..MESSAGES).collect::<Vec<_>>());
+}
@@ -0,0 +1,47 @@
+use memetool::image_utils::{add_border, ImageAction};
+
+#[test]
+fn test_add_border() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("square.png");
+    image::RgbImage::from_pixel(10, 6, image::Rgb([0, 0, 255]))
+        .save(&filepath)
+        .unwrap();
+
+    add_border(&filepath, 3, [255, 255, 0]).unwrap();
+
+    let bordered = image::open(&filepath).unwrap().to_rgb8();
+    assert_eq!(bordered.dimensions(), (16, 12));
+    assert_eq!(bordered.get_pixel(0, 0), &image::Rgb([255, 255, 0]));
+    assert_eq!(bordered.get_pixel(15, 11), &image::Rgb([255, 255, 0]));
+    assert_eq!(bordered.get_pixel(3, 3), &image::Rgb([0, 0, 255]));
+    assert_eq!(bordered.get_pixel(12, 8), &image::Rgb([0, 0, 255]));
+}
+
+#[test]
+fn test_add_border_keeps_transparency() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("clear.png");
+    image::RgbaImage::new(4, 4).save(&filepath).unwrap();
+
+    ImageAction::AddBorder {
+        pixels: 2,
+        color: [0, 0, 0],
+    }
+    .apply(&filepath)
+    .unwrap();
+
+    let bordered = image::open(&filepath).unwrap().to_rgba8();
+    assert_eq!(bordered.dimensions(), (8, 8));
+    assert_eq!(bordered.get_pixel(0, 0), &image::Rgba([0, 0, 0, 255]));
+    // the inside's left alone rather than blended with the border colour
+    assert_eq!(bordered.get_pixel(4, 4), &image::Rgba([0, 0, 0, 0]));
+}
+
+#[test]
+fn test_add_border_needs_a_width() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("square.png");
+    image::RgbImage::new(4, 4).save(&filepath).unwrap();
+    assert!(add_border(&filepath, 0, [0, 0, 0]).is_err());
+}
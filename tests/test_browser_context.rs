@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use memetool::browser_context::{
+    eager_count, neighbour_remaining, next_remaining, page_of, page_slice, positions,
+    BrowserContext,
+};
+
+fn files(count: usize) -> Vec<PathBuf> {
+    (0..count)
+        .map(|index| PathBuf::from(format!("/tmp/memes/{index:02}.jpg")))
+        .collect()
+}
+
+#[test]
+fn test_delete_selects_next_file() {
+    let mut context = BrowserContext {
+        current_page: 0,
+        selected_file: Some("/tmp/memes/03.jpg".to_string()),
+        scroll_offset: 120.0,
+    };
+    context.select_after_removal(&files(10), "/tmp/memes/03.jpg", 5);
+    assert_eq!(context.selected_file, Some("/tmp/memes/04.jpg".to_string()));
+    assert_eq!(context.current_page, 0);
+    // the scroll position isn't touched
+    assert_eq!(context.scroll_offset, 120.0);
+}
+
+#[test]
+fn test_delete_last_file_selects_previous() {
+    let mut context = BrowserContext {
+        current_page: 1,
+        selected_file: Some("/tmp/memes/05.jpg".to_string()),
+        scroll_offset: 0.0,
+    };
+    context.select_after_removal(&files(6), "/tmp/memes/05.jpg", 5);
+    assert_eq!(context.selected_file, Some("/tmp/memes/04.jpg".to_string()));
+    // the last page is now empty, so we should be back on the first one
+    assert_eq!(context.current_page, 0);
+}
+
+#[test]
+fn test_delete_only_file() {
+    let mut context = BrowserContext {
+        current_page: 0,
+        selected_file: Some("/tmp/memes/00.jpg".to_string()),
+        scroll_offset: 0.0,
+    };
+    context.select_after_removal(&files(1), "/tmp/memes/00.jpg", 5);
+    assert_eq!(context.selected_file, None);
+    assert_eq!(context.current_page, 0);
+}
+
+#[test]
+fn test_delete_unknown_file_keeps_context() {
+    let mut context = BrowserContext {
+        current_page: 1,
+        selected_file: Some("/tmp/memes/07.jpg".to_string()),
+        scroll_offset: 50.0,
+    };
+    let before = context.clone();
+    context.select_after_removal(&files(10), "/tmp/elsewhere.jpg", 5);
+    assert_eq!(context, before);
+}
+
+#[test]
+fn test_page_of() {
+    let files = files(12);
+    assert_eq!(page_of(&files, Path::new("/tmp/memes/00.jpg"), 5), Some(0));
+    assert_eq!(page_of(&files, Path::new("/tmp/memes/04.jpg"), 5), Some(0));
+    assert_eq!(page_of(&files, Path::new("/tmp/memes/05.jpg"), 5), Some(1));
+    assert_eq!(page_of(&files, Path::new("/tmp/memes/11.jpg"), 5), Some(2));
+    assert_eq!(page_of(&files, Path::new("/tmp/other/00.jpg"), 5), None);
+    // a zero page size doesn't divide by zero
+    assert_eq!(page_of(&files, Path::new("/tmp/memes/11.jpg"), 0), Some(0));
+}
+
+#[test]
+fn test_select_file() {
+    let mut context = BrowserContext::default();
+    assert!(context.select_file(&files(12), Path::new("/tmp/memes/07.jpg"), 5));
+    assert_eq!(context.current_page, 1);
+    assert_eq!(context.selected_file, Some("/tmp/memes/07.jpg".to_string()));
+
+    // missing files don't change anything
+    assert!(!context.select_file(&files(12), Path::new("/tmp/memes/99.jpg"), 5));
+    assert_eq!(context.current_page, 1);
+    assert_eq!(context.selected_file, Some("/tmp/memes/07.jpg".to_string()));
+}
+
+#[test]
+fn test_next_remaining_skips_missing_files() {
+    let files = files(6);
+    let gone = [
+        Path::new("/tmp/memes/03.jpg"),
+        Path::new("/tmp/memes/04.jpg"),
+    ];
+    let exists = |path: &Path| !gone.contains(&path);
+    assert_eq!(
+        next_remaining(&files, "/tmp/memes/01.jpg", exists),
+        Some(PathBuf::from("/tmp/memes/02.jpg"))
+    );
+    assert_eq!(
+        next_remaining(&files, "/tmp/memes/02.jpg", exists),
+        Some(PathBuf::from("/tmp/memes/05.jpg"))
+    );
+    // the current file doesn't have to be there any more
+    assert_eq!(
+        next_remaining(&files, "/tmp/memes/03.jpg", exists),
+        Some(PathBuf::from("/tmp/memes/05.jpg"))
+    );
+}
+
+#[test]
+fn test_next_remaining_at_the_end() {
+    let files = files(3);
+    assert_eq!(next_remaining(&files, "/tmp/memes/02.jpg", |_| true), None);
+    assert_eq!(
+        next_remaining(&files, "/tmp/memes/00.jpg", |path| path
+            == Path::new("/tmp/memes/00.jpg")),
+        None
+    );
+    assert_eq!(next_remaining(&files, "/tmp/elsewhere.jpg", |_| true), None);
+}
+
+#[test]
+fn test_neighbour_remaining() {
+    let files = files(6);
+    let gone = [Path::new("/tmp/memes/04.jpg")];
+    let exists = |path: &Path| !gone.contains(&path);
+    // there's something after it
+    assert_eq!(
+        neighbour_remaining(&files, "/tmp/memes/01.jpg", exists),
+        Some(PathBuf::from("/tmp/memes/02.jpg"))
+    );
+    // it's the last one, so back up past the one that's gone
+    let gone = [
+        Path::new("/tmp/memes/05.jpg"),
+        Path::new("/tmp/memes/04.jpg"),
+    ];
+    let exists = |path: &Path| !gone.contains(&path);
+    assert_eq!(
+        neighbour_remaining(&files, "/tmp/memes/05.jpg", exists),
+        Some(PathBuf::from("/tmp/memes/03.jpg"))
+    );
+    // the only one
+    assert_eq!(
+        neighbour_remaining(&files[..1], "/tmp/memes/00.jpg", |_| false),
+        None
+    );
+    assert_eq!(
+        neighbour_remaining(&files, "/tmp/elsewhere.jpg", |_| true),
+        None
+    );
+}
+
+#[test]
+fn test_page_slice() {
+    // nothing at all
+    assert!(page_slice(&[], 0, 5).is_empty());
+
+    // it all fits on one page
+    let few = files(3);
+    assert_eq!(page_slice(&few, 0, 5), &few[..]);
+
+    // the last page only has what's left over
+    let files = files(12);
+    assert_eq!(page_slice(&files, 1, 5), &files[5..10]);
+    assert_eq!(page_slice(&files, 2, 5), &files[10..]);
+
+    // past the end
+    assert!(page_slice(&files, 3, 5).is_empty());
+    assert!(page_slice(&files, usize::MAX, 5).is_empty());
+
+    // a zero page size is one big page
+    assert_eq!(page_slice(&files, 0, 0), &files[..]);
+    assert!(page_slice(&files, 1, 0).is_empty());
+}
+
+#[test]
+fn test_eager_count() {
+    // unset or zero is everything
+    assert_eq!(eager_count(20, 5, None), 20);
+    assert_eq!(eager_count(20, 5, Some(0)), 20);
+    assert_eq!(eager_count(20, 5, Some(2)), 10);
+    // a short page is all eager
+    assert_eq!(eager_count(7, 5, Some(2)), 7);
+    // columns shouldn't be zero, but if they are it's one per row
+    assert_eq!(eager_count(20, 0, Some(2)), 2);
+}
+
+#[test]
+fn test_pages_keep_list_order() {
+    // not in name order, like a custom sort, the pages mustn't put it back into name order
+    let mut files = files(23);
+    files.reverse();
+    files.swap(3, 17);
+    for per_page in [1, 5, 7, 23, 50] {
+        let pages = (files.len() + per_page - 1) / per_page;
+        let joined: Vec<PathBuf> = (0..pages)
+            .flat_map(|page| page_slice(&files, page, per_page).to_vec())
+            .collect();
+        assert_eq!(joined, files, "per_page {per_page}");
+        for (index, file) in files.iter().enumerate() {
+            assert_eq!(page_of(&files, file, per_page), Some(index / per_page));
+        }
+    }
+}
+
+#[test]
+fn test_positions() {
+    let files = files(500);
+    let positions = positions(&files);
+    assert_eq!(positions.len(), 500);
+    assert_eq!(positions.get("/tmp/memes/00.jpg"), Some(&0));
+    assert_eq!(positions.get("/tmp/memes/126.jpg"), Some(&126));
+    assert_eq!(positions.get("/tmp/memes/499.jpg"), Some(&499));
+    // filtered out, or gone
+    assert_eq!(positions.get("/tmp/memes/500.jpg"), None);
+
+    assert!(memetool::browser_context::positions(&[]).is_empty());
+}
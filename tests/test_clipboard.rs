@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use memetool::clipboard::{default_filename, import_path, ClipboardImage};
+
+fn red_square() -> ClipboardImage {
+    let rgba = [255, 0, 0, 255].repeat(4 * 4);
+    ClipboardImage::new(4, 4, rgba).expect("Failed to make clipboard image")
+}
+
+#[test]
+fn test_clipboard_image_size_check() {
+    assert!(ClipboardImage::new(2, 2, vec![0; 16]).is_ok());
+    assert!(ClipboardImage::new(2, 2, vec![0; 15]).is_err());
+}
+
+#[test]
+fn test_import_path() {
+    assert_eq!(
+        import_path("/tmp/memes", "cat.jpg"),
+        PathBuf::from("/tmp/memes/cat.jpg")
+    );
+    assert_eq!(
+        import_path("/tmp/memes", " cat "),
+        PathBuf::from("/tmp/memes/cat.png")
+    );
+    assert!(default_filename().ends_with(".png"));
+}
+
+#[test]
+fn test_save_clipboard_image() {
+    let tempdir = tempfile::tempdir().expect("Failed to make temp dir");
+    let image = red_square();
+
+    let png = tempdir.path().join("pasted.png");
+    image.save(&png).expect("Failed to save png");
+    let loaded = image::open(&png).expect("Failed to open png").to_rgba8();
+    assert_eq!(loaded.dimensions(), (4, 4));
+    assert_eq!(loaded.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+    // JPEGs can't have an alpha channel
+    let jpeg = tempdir.path().join("pasted.jpg");
+    image.save(&jpeg).expect("Failed to save jpeg");
+    assert_eq!(
+        image::open(&jpeg).expect("Failed to open jpeg").color(),
+        image::ColorType::Rgb8
+    );
+}
+
+#[test]
+fn test_clipboard_image_debug_skips_pixels() {
+    assert_eq!(
+        format!("{:?}", red_square()),
+        "ClipboardImage { width: 4, height: 4, bytes: 64 }"
+    );
+}
@@ -0,0 +1,54 @@
+use eframe::epaint::{Color32, ColorImage};
+use memetool::color_analysis::{hex_code, ColorAnalysis, DOMINANT_COLORS, HISTOGRAM_BINS};
+
+#[test]
+fn test_dominant_colors() {
+    // three quarters red, a quarter blue, plus a see-through pixel that shouldn't count
+    let mut pixels = vec![Color32::from_rgb(250, 10, 10); 6];
+    pixels.extend([Color32::from_rgb(10, 10, 250); 2]);
+    pixels.push(Color32::TRANSPARENT);
+    let image = ColorImage {
+        size: [3, 3],
+        pixels,
+    };
+
+    let analysis = ColorAnalysis::from_image(&image);
+    assert_eq!(
+        analysis.dominant,
+        vec![
+            (Color32::from_rgb(250, 10, 10), 0.75),
+            (Color32::from_rgb(10, 10, 250), 0.25),
+        ]
+    );
+    // every counted pixel lands in one bin per channel
+    for channel in analysis.histogram.iter() {
+        assert_eq!(channel.iter().sum::<u32>(), 8);
+    }
+    assert_eq!(analysis.histogram[0][HISTOGRAM_BINS - 1], 6);
+    assert_eq!(analysis.histogram[2][0], 6);
+}
+
+#[test]
+fn test_similar_colors_share_a_bucket() {
+    let pixels = (0..64)
+        .map(|index| Color32::from_rgb(index, index, index))
+        .collect();
+    let image = ColorImage {
+        size: [8, 8],
+        pixels,
+    };
+
+    let analysis = ColorAnalysis::from_image(&image);
+    // 64 shades of grey fit in four buckets
+    assert_eq!(analysis.dominant.len(), 4);
+    assert!(analysis.dominant.len() <= DOMINANT_COLORS);
+    assert!(analysis
+        .dominant
+        .iter()
+        .all(|(_, share)| (*share - 0.25).abs() < f32::EPSILON));
+}
+
+#[test]
+fn test_hex_code() {
+    assert_eq!(hex_code(Color32::from_rgb(255, 136, 0)), "#FF8800");
+}
@@ -0,0 +1,186 @@
+use memetool::config::{Configuration, ImportConflict, UploadDestination};
+
+fn write_config(dir: &std::path::Path, name: &str, contents: &str) -> std::path::PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_merge_overrides_set_fields() {
+    let mut base = Configuration {
+        s3_bucket: "shared-bucket".to_string(),
+        s3_region: "ap-southeast-2".to_string(),
+        s3_endpoint: Some("https://minio.example.com".to_string()),
+        thumbnail_cache_mb: Some(512),
+        ..Configuration::defaults()
+    };
+    let user = Configuration {
+        s3_access_key_id: "mine".to_string(),
+        s3_region: "us-east-1".to_string(),
+        split_view: true,
+        ..Configuration::defaults()
+    };
+
+    base.merge(&user);
+
+    assert_eq!(base.s3_access_key_id, "mine");
+    assert_eq!(base.s3_region, "us-east-1");
+    assert!(base.split_view);
+    // things the user didn't set come from the base
+    assert_eq!(base.s3_bucket, "shared-bucket");
+    assert_eq!(base.thumbnail_cache_mb, Some(512));
+    // None doesn't clear a Some
+    assert_eq!(
+        base.s3_endpoint,
+        Some("https://minio.example.com".to_string())
+    );
+}
+
+#[test]
+fn test_load_layered() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let system = write_config(
+        tempdir.path(),
+        "system.json",
+        r#"{"s3_bucket": "shared-bucket", "s3_region": "ap-southeast-2"}"#,
+    );
+    let user = write_config(
+        tempdir.path(),
+        "user.json",
+        r#"{"s3_access_key_id": "mine", "s3_region": "us-east-1"}"#,
+    );
+
+    let config = Configuration::load_layered(&system, &user).unwrap();
+    assert_eq!(config.s3_bucket, "shared-bucket");
+    assert_eq!(config.s3_region, "us-east-1");
+    assert_eq!(config.s3_access_key_id, "mine");
+}
+
+#[test]
+fn test_load_layered_missing_files() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let system = write_config(tempdir.path(), "system.json", r#"{"s3_bucket": "shared"}"#);
+    let missing = tempdir.path().join("missing.json");
+
+    // just the system config is fine
+    let config = Configuration::load_layered(&system, &missing).unwrap();
+    assert_eq!(config.s3_bucket, "shared");
+
+    // but there has to be something
+    assert!(Configuration::load_layered(&missing, &missing).is_err());
+}
+
+#[test]
+fn test_s3_max_retries_default() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let unset = write_config(tempdir.path(), "unset.json", r#"{"s3_bucket": "shared"}"#);
+    let set = write_config(tempdir.path(), "set.json", r#"{"s3_max_retries": 0}"#);
+
+    assert_eq!(
+        Configuration::load(&unset).unwrap().s3_max_retries,
+        memetool::config::DEFAULT_S3_MAX_RETRIES
+    );
+    assert_eq!(Configuration::load(&set).unwrap().s3_max_retries, 0);
+}
+
+#[test]
+fn test_auto_advance_on_delete_default() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let unset = write_config(tempdir.path(), "unset.json", r#"{"s3_bucket": "shared"}"#);
+    let off = write_config(
+        tempdir.path(),
+        "off.json",
+        r#"{"auto_advance_on_delete": false}"#,
+    );
+
+    assert!(Configuration::load(&unset).unwrap().auto_advance_on_delete);
+    assert!(!Configuration::load(&off).unwrap().auto_advance_on_delete);
+}
+
+#[test]
+fn test_upload_destination() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let unset = write_config(tempdir.path(), "unset.json", r#"{"s3_bucket": "shared"}"#);
+    let http = write_config(
+        tempdir.path(),
+        "http.json",
+        r#"{"upload_destination": "http", "http_upload_url": "https://example.com/memes"}"#,
+    );
+
+    assert_eq!(
+        Configuration::load(&unset).unwrap().upload_destination,
+        UploadDestination::S3
+    );
+    let config = Configuration::load(&http).unwrap();
+    assert_eq!(config.upload_destination, UploadDestination::Http);
+    assert_eq!(
+        config.http_upload_url.as_deref(),
+        Some("https://example.com/memes")
+    );
+}
+
+#[test]
+fn test_on_import_conflict() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let unset = write_config(tempdir.path(), "unset.json", r#"{"s3_bucket": "shared"}"#);
+    let skip = write_config(
+        tempdir.path(),
+        "skip.json",
+        r#"{"on_import_conflict": "skip"}"#,
+    );
+
+    assert_eq!(
+        Configuration::load(&unset).unwrap().on_import_conflict,
+        ImportConflict::Ask
+    );
+    assert_eq!(
+        Configuration::load(&skip).unwrap().on_import_conflict,
+        ImportConflict::Skip
+    );
+}
+
+#[test]
+fn test_save_and_load_from_config_path() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("memetool.json");
+    std::env::set_var(memetool::config::CONFIG_PATH_ENV, &path);
+    assert_eq!(Configuration::config_path(), path);
+
+    // nothing's been saved yet
+    assert!(!Configuration::file_exists());
+    assert!(Configuration::try_new().is_err());
+    let defaults = Configuration::try_new_or_default();
+    assert!(defaults.s3_bucket.is_empty());
+    assert_eq!(
+        defaults.s3_max_retries,
+        memetool::config::DEFAULT_S3_MAX_RETRIES
+    );
+
+    let config = Configuration {
+        s3_bucket: "saved-bucket".to_string(),
+        split_view: true,
+        ..Configuration::defaults()
+    };
+    config.save().unwrap();
+    assert!(path.exists());
+
+    let loaded = Configuration::try_new().unwrap();
+    assert_eq!(loaded.s3_bucket, "saved-bucket");
+    assert!(loaded.split_view);
+    std::env::remove_var(memetool::config::CONFIG_PATH_ENV);
+}
+
+#[test]
+fn test_channel_capacity() {
+    assert_eq!(
+        Configuration::defaults().channel_capacity(),
+        memetool::config::DEFAULT_CHANNEL_CAPACITY
+    );
+    let config = Configuration {
+        channel_capacity: Some(0),
+        ..Configuration::defaults()
+    };
+    // tokio panics making a channel with no room
+    assert_eq!(config.channel_capacity(), 1);
+}
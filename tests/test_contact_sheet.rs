@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use eframe::epaint::{Color32, ColorImage};
+use memetool::image_utils::{
+    generate_contact_sheet, ContactSheetLayout, CONTACT_SHEET_CAPTION_HEIGHT, CONTACT_SHEET_PADDING,
+};
+
+fn tile(name: &str, width: usize, height: usize) -> (String, Arc<ColorImage>) {
+    (
+        name.to_string(),
+        Arc::new(ColorImage::new([width, height], Color32::RED)),
+    )
+}
+
+#[test]
+fn test_contact_sheet_size() {
+    let layout = ContactSheetLayout {
+        columns: 3,
+        cell_size: 100,
+        background: [255, 255, 255],
+    };
+    let tiles: Vec<_> = (0..5)
+        .map(|index| tile(&format!("meme{index}.png"), 50, 50))
+        .collect();
+
+    let sheet = generate_contact_sheet(&tiles, &layout);
+    // three across and two down
+    assert_eq!(
+        sheet.width(),
+        3 * (100 + CONTACT_SHEET_PADDING) + CONTACT_SHEET_PADDING
+    );
+    assert_eq!(
+        sheet.height(),
+        2 * (100 + CONTACT_SHEET_CAPTION_HEIGHT + CONTACT_SHEET_PADDING) + CONTACT_SHEET_PADDING
+    );
+    // the middle of the first cell has the image in it, the corner's background
+    let middle = CONTACT_SHEET_PADDING + 50;
+    assert_eq!(sheet.get_pixel(middle, middle).0, [255, 0, 0, 255]);
+    assert_eq!(sheet.get_pixel(0, 0).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn test_contact_sheet_shrinks_big_images() {
+    let layout = ContactSheetLayout {
+        columns: 4,
+        cell_size: 64,
+        ..Default::default()
+    };
+    // fewer images than columns doesn't leave empty columns
+    let tiles = vec![
+        tile("huge.png", 1000, 500),
+        tile("a name that's far too long to fit.png", 10, 10),
+    ];
+
+    let sheet = generate_contact_sheet(&tiles, &layout);
+    assert_eq!(
+        sheet.width(),
+        2 * (64 + CONTACT_SHEET_PADDING) + CONTACT_SHEET_PADDING
+    );
+    // shrunk to 64x32 and centered, so just above and below it is background
+    let x = CONTACT_SHEET_PADDING + 32;
+    assert_eq!(
+        sheet.get_pixel(x, CONTACT_SHEET_PADDING + 32).0,
+        [255, 0, 0, 255]
+    );
+    assert_eq!(
+        sheet.get_pixel(x, CONTACT_SHEET_PADDING + 10).0,
+        [32, 32, 32, 255]
+    );
+}
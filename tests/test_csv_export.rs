@@ -0,0 +1,41 @@
+use memetool::csv_export::{csv_escape, write_image_list_csv};
+
+#[test]
+fn test_csv_escape() {
+    assert_eq!(csv_escape("plain.png"), "plain.png");
+    assert_eq!(csv_escape("a,b.png"), "\"a,b.png\"");
+    assert_eq!(
+        csv_escape("say \"cheese\".jpg"),
+        "\"say \"\"cheese\"\".jpg\""
+    );
+    assert_eq!(csv_escape("two\nlines.gif"), "\"two\nlines.gif\"");
+}
+
+#[test]
+fn test_write_image_list_csv() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let image = tempdir.path().join("red, really.png");
+    image::RgbImage::from_pixel(20, 10, image::Rgb([255, 0, 0]))
+        .save(&image)
+        .unwrap();
+    let missing = tempdir.path().join("gone.jpg");
+
+    let mut out = vec![];
+    let rows = write_image_list_csv(&[image.clone(), missing], &mut out).unwrap();
+    assert_eq!(rows, 2);
+
+    let csv = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "filepath,filename,file_size_bytes,width,height,format,mtime"
+    );
+    let size = std::fs::metadata(&image).unwrap().len();
+    assert!(lines[1].starts_with(&format!(
+        "\"{}\",\"red, really.png\",{},20,10,png,",
+        image.display(),
+        size
+    )));
+    // what can't be read is left empty rather than failing the whole thing
+    assert!(lines[2].ends_with(",gone.jpg,,,,jpeg,"));
+}
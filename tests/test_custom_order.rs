@@ -0,0 +1,46 @@
+use memetool::custom_order::{load, save, workdir_key};
+
+#[test]
+fn test_orders_are_kept_per_workdir() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let orderpath = tempdir.path().join("memetool-order.json");
+    let memes = tempdir.path().join("memes");
+    let cats = tempdir.path().join("cats");
+
+    // nothing saved yet
+    assert!(load(&orderpath, &memes.display().to_string()).is_err());
+
+    save(
+        &orderpath,
+        &memes.display().to_string(),
+        &[
+            memes.join("b.jpg").display().to_string(),
+            memes.join("a.jpg").display().to_string(),
+        ],
+    )
+    .unwrap();
+    save(
+        &orderpath,
+        &cats.display().to_string(),
+        &[cats.join("tabby.png").display().to_string()],
+    )
+    .unwrap();
+
+    // a trailing slash is still the same workdir
+    assert_eq!(
+        load(&orderpath, &format!("{}/", memes.display())).unwrap(),
+        vec!["b.jpg".to_string(), "a.jpg".to_string()]
+    );
+    assert_eq!(
+        load(&orderpath, &cats.display().to_string()).unwrap(),
+        vec!["tabby.png".to_string()]
+    );
+    // and nothing's written next to the images
+    assert!(!memes.exists());
+}
+
+#[test]
+fn test_workdir_key() {
+    assert_eq!(workdir_key("/tmp/memes/"), workdir_key("/tmp/memes"));
+    assert_eq!(workdir_key("/tmp//memes"), "/tmp/memes");
+}
@@ -0,0 +1,102 @@
+use std::path::{Path, PathBuf};
+
+use memetool::dir_scan::{is_listable, read_dir_limited, scan_directory, sort_listing};
+
+/// touch each of `names` in `dir`
+fn make_files(dir: &Path, names: &[&str]) {
+    for name in names {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+}
+
+#[test]
+fn test_is_listable() {
+    assert!(is_listable(Path::new("/tmp/cat.jpg")));
+    assert!(is_listable(Path::new("/tmp/CAT.PNG")));
+    assert!(!is_listable(Path::new("/tmp/notes.txt")));
+    assert!(!is_listable(Path::new("/tmp/jpg")));
+}
+
+#[test]
+fn test_sort_listing() {
+    let mut files = vec![
+        PathBuf::from("/b/cat.jpg"),
+        PathBuf::from("/a/dog.jpg"),
+        PathBuf::from("/c/ant.png"),
+    ];
+    sort_listing(&mut files);
+    assert_eq!(
+        files,
+        vec![
+            PathBuf::from("/c/ant.png"),
+            PathBuf::from("/b/cat.jpg"),
+            PathBuf::from("/a/dog.jpg"),
+        ]
+    );
+}
+
+#[test]
+fn test_read_dir_limited() {
+    let tempdir = tempfile::tempdir().unwrap();
+    make_files(tempdir.path(), &["b.png", "a.jpg", "notes.txt"]);
+
+    let files = read_dir_limited(tempdir.path(), 10).unwrap();
+    assert_eq!(
+        files,
+        vec![tempdir.path().join("a.jpg"), tempdir.path().join("b.png")]
+    );
+
+    // the text file still counts towards the limit
+    assert!(read_dir_limited(tempdir.path(), 2).is_none());
+    assert!(read_dir_limited(tempdir.path(), 3).is_some());
+
+    assert_eq!(
+        read_dir_limited(&tempdir.path().join("missing"), 10),
+        Some(vec![])
+    );
+}
+
+#[test]
+fn test_scan_directory_chunks() {
+    let tempdir = tempfile::tempdir().unwrap();
+    make_files(
+        tempdir.path(),
+        &["1.jpg", "2.jpg", "3.jpg", "4.jpg", "5.jpg", "notes.txt"],
+    );
+
+    let mut chunks = vec![];
+    let found = scan_directory(tempdir.path(), 2, |chunk| {
+        chunks.push(chunk);
+        true
+    })
+    .unwrap();
+    assert_eq!(found, Some(5));
+    assert_eq!(
+        chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![2, 2, 1]
+    );
+
+    let mut all: Vec<PathBuf> = chunks.into_iter().flatten().collect();
+    sort_listing(&mut all);
+    assert_eq!(all, read_dir_limited(tempdir.path(), 10).unwrap());
+}
+
+#[test]
+fn test_scan_directory_stops() {
+    let tempdir = tempfile::tempdir().unwrap();
+    make_files(
+        tempdir.path(),
+        &["1.jpg", "2.jpg", "3.jpg", "4.jpg", "5.jpg"],
+    );
+
+    let mut calls = 0;
+    let found = scan_directory(tempdir.path(), 2, |_| {
+        calls += 1;
+        false
+    })
+    .unwrap();
+    assert_eq!(found, None);
+    assert_eq!(calls, 1);
+
+    assert!(scan_directory(&tempdir.path().join("missing"), 2, |_| true).is_err());
+}
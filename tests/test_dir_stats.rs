@@ -0,0 +1,106 @@
+use std::time::{Duration, SystemTime};
+
+use filetime::FileTime;
+use memetool::dir_stats::{format_age, DirStats, LARGEST_FILES};
+
+#[test]
+fn test_compute_dir_stats() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let now = SystemTime::now();
+    let files: Vec<_> = [("a.jpg", 10, 300), ("b.JPG", 20, 200), ("c.png", 30, 100)]
+        .iter()
+        .map(|(name, size, age_secs)| {
+            let filepath = tempdir.path().join(name);
+            std::fs::write(&filepath, vec![0u8; *size]).unwrap();
+            let modified = now - Duration::from_secs(*age_secs);
+            filetime::set_file_mtime(&filepath, FileTime::from_system_time(modified)).unwrap();
+            filepath
+        })
+        .collect();
+
+    let stats = DirStats::compute(&files);
+    assert_eq!(stats.total_files, 3);
+    assert_eq!(stats.total_bytes, 60);
+    assert_eq!(stats.formats_summary(), "2 JPG, 1 PNG");
+    assert!(stats.oldest.unwrap() < stats.newest.unwrap());
+    assert_eq!(
+        FileTime::from_system_time(stats.oldest.unwrap()),
+        FileTime::from_system_time(now - Duration::from_secs(300))
+    );
+}
+
+#[test]
+fn test_missing_files_are_counted() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let stats = DirStats::compute(&[tempdir.path().join("gone.gif")]);
+    assert_eq!(stats.total_files, 1);
+    assert_eq!(stats.total_bytes, 0);
+    assert_eq!(stats.oldest, None);
+}
+
+#[test]
+fn test_format_age() {
+    let now = SystemTime::now();
+    assert_eq!(format_age(now), "just now");
+    assert_eq!(
+        format_age(now - Duration::from_secs(3 * 3600 + 5)),
+        "3 hours ago"
+    );
+    assert_eq!(
+        format_age(now - Duration::from_secs(86400 + 5)),
+        "1 day ago"
+    );
+}
+
+#[test]
+fn test_for_directory_only_counts_images() {
+    let tempdir = tempfile::tempdir().unwrap();
+    for (name, size) in [("a.jpg", 10), ("b.PNG", 20), ("notes.txt", 500)] {
+        std::fs::write(tempdir.path().join(name), vec![0u8; size]).unwrap();
+    }
+    std::fs::create_dir(tempdir.path().join("folder.png")).unwrap();
+
+    let stats = DirStats::for_directory(tempdir.path(), &["jpg", "png"]).unwrap();
+    // folder.png isn't a file, so it's not counted
+    assert_eq!(stats.total_files, 2);
+    assert_eq!(stats.total_bytes, 30);
+    assert_eq!(stats.formats_summary(), "1 JPG, 1 PNG");
+}
+
+#[test]
+fn test_largest_files_and_count_over() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let files: Vec<_> = [("small.jpg", 10), ("big.png", 300), ("medium.gif", 50)]
+        .iter()
+        .map(|(name, size)| {
+            let filepath = tempdir.path().join(name);
+            std::fs::write(&filepath, vec![0u8; *size]).unwrap();
+            filepath
+        })
+        .collect();
+
+    let mut progress = vec![];
+    let stats = DirStats::compute_with_progress(&files, |done, total| progress.push((done, total)));
+    // there's always one at the end
+    assert_eq!(progress, vec![(3, 3)]);
+
+    let largest: Vec<_> = stats
+        .largest(2)
+        .iter()
+        .map(|(filepath, size)| {
+            (
+                filepath.file_name().unwrap().to_string_lossy().to_string(),
+                *size,
+            )
+        })
+        .collect();
+    assert_eq!(
+        largest,
+        vec![("big.png".to_string(), 300), ("medium.gif".to_string(), 50)]
+    );
+    assert_eq!(stats.largest(LARGEST_FILES).len(), 3);
+
+    assert_eq!(stats.count_over(49), 2);
+    assert_eq!(stats.count_over(50), 1);
+    assert_eq!(stats.count_over(1000), 0);
+}
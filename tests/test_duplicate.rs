@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use memetool::image_utils::{duplicate_path_for, first_free_path};
+
+#[test]
+fn test_duplicate_path_counts_up() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let original = tempdir.path().join("cat.large.png");
+    std::fs::write(&original, b"meow").unwrap();
+
+    let first = duplicate_path_for(&original).unwrap();
+    assert_eq!(first, tempdir.path().join("cat.large_copy1.png"));
+    std::fs::copy(&original, &first).unwrap();
+    std::fs::write(tempdir.path().join("cat.large_copy2.png"), b"taken").unwrap();
+    assert_eq!(
+        duplicate_path_for(&original),
+        Some(tempdir.path().join("cat.large_copy3.png"))
+    );
+}
+
+#[test]
+fn test_duplicate_path_without_extension() {
+    assert_eq!(
+        duplicate_path_for(Path::new("/nonexistent/README")),
+        Some(Path::new("/nonexistent/README_copy1").to_path_buf())
+    );
+}
+
+#[test]
+fn test_first_free_path_gives_up() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let taken = tempdir.path().join("taken.png");
+    std::fs::write(&taken, b"meow").unwrap();
+    // every candidate's taken, and there's no end to them
+    assert_eq!(first_free_path(std::iter::repeat(taken.clone())), None);
+    assert_eq!(
+        first_free_path([taken, tempdir.path().join("free.png")]),
+        Some(tempdir.path().join("free.png"))
+    );
+}
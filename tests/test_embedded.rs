@@ -0,0 +1,42 @@
+//! [MemeTool::ui] drawing into a panel of an app that isn't memetool
+
+use eframe::egui;
+use memetool::config::Configuration;
+use memetool::{AppMsg, MemeTool};
+
+#[tokio::test]
+async fn test_embedded_ui_runs_in_a_host_panel() {
+    let ctx = egui::Context::default();
+    let (background_tx, mut background_rx) = tokio::sync::mpsc::channel(100);
+    let (_foreground_tx, foreground_rx) = tokio::sync::mpsc::channel(100);
+    let mut app = MemeTool::with_context(
+        &ctx,
+        foreground_rx,
+        background_tx,
+        Some(Configuration::defaults()),
+    );
+
+    let mut host_clicks = 0;
+    let output = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::SidePanel::left("host_panel").show(ctx, |ui| {
+            if ui.button("Host button").clicked() {
+                host_clicks += 1;
+            }
+        });
+        egui::CentralPanel::default().show(ctx, |ui| app.ui(ui));
+    });
+    assert!(!output.shapes.is_empty());
+    assert_eq!(host_clicks, 0);
+
+    // the first frame starts watching the workdir, so it's talking to the background
+    let mut watching = false;
+    while let Ok(Some(msg)) =
+        tokio::time::timeout(std::time::Duration::from_secs(5), background_rx.recv()).await
+    {
+        if matches!(msg, AppMsg::WatchDir(_)) {
+            watching = true;
+            break;
+        }
+    }
+    assert!(watching);
+}
@@ -0,0 +1,110 @@
+use memetool::image_utils::{
+    apply_lossless_jpeg_rotation, date_rename_path, exif_info, read_exif, ExifDateTime,
+};
+
+/// a JPEG that's nothing but EXIF data saying it was taken at `taken`
+fn jpeg_taken_at(taken: &str) -> Vec<u8> {
+    let mut tiff = b"MM\0\x2A".to_vec();
+    tiff.extend_from_slice(&8u32.to_be_bytes());
+    // IFD0, pointing at the EXIF IFD
+    tiff.extend_from_slice(&1u16.to_be_bytes());
+    tiff.extend_from_slice(&0x8769u16.to_be_bytes());
+    tiff.extend_from_slice(&4u16.to_be_bytes());
+    tiff.extend_from_slice(&1u32.to_be_bytes());
+    tiff.extend_from_slice(&26u32.to_be_bytes());
+    tiff.extend_from_slice(&0u32.to_be_bytes());
+    // the EXIF IFD, with DateTimeOriginal after it
+    tiff.extend_from_slice(&1u16.to_be_bytes());
+    tiff.extend_from_slice(&0x9003u16.to_be_bytes());
+    tiff.extend_from_slice(&2u16.to_be_bytes());
+    tiff.extend_from_slice(&(taken.len() as u32 + 1).to_be_bytes());
+    tiff.extend_from_slice(&44u32.to_be_bytes());
+    tiff.extend_from_slice(&0u32.to_be_bytes());
+    tiff.extend_from_slice(taken.as_bytes());
+    tiff.push(0);
+
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+    jpeg.extend_from_slice(&(2 + 6 + tiff.len() as u16).to_be_bytes());
+    jpeg.extend_from_slice(b"Exif\0\0");
+    jpeg.extend_from_slice(&tiff);
+    jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    jpeg
+}
+
+fn taken() -> ExifDateTime {
+    ExifDateTime::parse("2023:07:04 15:30:09").unwrap()
+}
+
+#[test]
+fn test_parse_exif_date_time() {
+    let taken = taken();
+    assert_eq!((taken.year, taken.month, taken.day), (2023, 7, 4));
+    assert_eq!((taken.hour, taken.minute, taken.second), (15, 30, 9));
+    assert_eq!(taken.filename_stem(), "2023-07-04_153009");
+    assert_eq!(taken.to_string(), "2023-07-04 15:30:09");
+    // what cameras write when they don't know
+    assert_eq!(ExifDateTime::parse("0000:00:00 00:00:00"), None);
+    assert_eq!(ExifDateTime::parse("    :  :     :  :  "), None);
+    assert_eq!(ExifDateTime::parse("2023:07:04"), None);
+}
+
+#[test]
+fn test_exif_date_time_original() {
+    let exif = exif_info(&jpeg_taken_at("2023:07:04 15:30:09"));
+    assert_eq!(exif.date_time_original, Some(taken()));
+    assert_eq!(exif.orientation, None);
+
+    assert_eq!(exif_info(b"not a jpeg").date_time_original, None);
+    assert_eq!(
+        exif_info(&jpeg_taken_at("0000:00:00 00:00:00")).date_time_original,
+        None
+    );
+}
+
+#[test]
+fn test_read_exif() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("IMG_1234.JPG");
+    std::fs::write(&path, jpeg_taken_at("2023:07:04 15:30:09")).unwrap();
+    assert_eq!(read_exif(&path).unwrap().date_time_original, Some(taken()));
+    assert!(read_exif(&tempdir.path().join("missing.jpg")).is_err());
+}
+
+#[test]
+fn test_lossless_rotation_adds_an_orientation() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("IMG_1234.JPG");
+    std::fs::write(&path, jpeg_taken_at("2023:07:04 15:30:09")).unwrap();
+
+    apply_lossless_jpeg_rotation(&path, 90).unwrap();
+    let exif = read_exif(&path).unwrap();
+    assert_eq!(exif.orientation, Some(6));
+    // everything that was there already is still where it points
+    assert_eq!(exif.date_time_original, Some(taken()));
+
+    apply_lossless_jpeg_rotation(&path, 90).unwrap();
+    assert_eq!(read_exif(&path).unwrap().orientation, Some(3));
+}
+
+#[test]
+fn test_date_rename_path() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("IMG_1234.JPG");
+    std::fs::write(&path, b"photo").unwrap();
+    assert_eq!(
+        date_rename_path(&path, &taken()),
+        Some(tempdir.path().join("2023-07-04_153009.JPG"))
+    );
+
+    // two taken in the same second
+    std::fs::write(tempdir.path().join("2023-07-04_153009.JPG"), b"other").unwrap();
+    std::fs::write(tempdir.path().join("2023-07-04_153009_1.JPG"), b"other").unwrap();
+    assert_eq!(
+        date_rename_path(&path, &taken()),
+        Some(tempdir.path().join("2023-07-04_153009_2.JPG"))
+    );
+
+    // it's already got the name, so it stays put
+    let named = tempdir.path().join("2023-07-04_153009.JPG");
+    assert_eq!(date_rename_path(&named, &taken()), Some(named));
+}
@@ -0,0 +1,25 @@
+use memetool::{external_file_state, AppState};
+
+#[test]
+fn test_external_file_state() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let image = tempdir.path().join("cat.png");
+    image::RgbImage::new(4, 4).save(&image).unwrap();
+    let notes = tempdir.path().join("notes.txt");
+    std::fs::write(&notes, "not an image").unwrap();
+
+    let expected = std::fs::canonicalize(&image).unwrap();
+    assert_eq!(
+        external_file_state(&image),
+        Some(AppState::Editor {
+            filepath: expected.display().to_string()
+        })
+    );
+
+    assert_eq!(external_file_state(&notes), None);
+    assert_eq!(external_file_state(tempdir.path()), None);
+    assert_eq!(
+        external_file_state(&tempdir.path().join("missing.png")),
+        None
+    );
+}
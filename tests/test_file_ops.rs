@@ -0,0 +1,239 @@
+use memetool::file_ops::{
+    copy_then_delete, expand_path, home_relative, is_case_only_rename, is_cross_device,
+    is_same_file, moved_between, rename_case_only, rename_problem, rename_target_problem,
+    set_modified, split_common_dirs, windows_path_too_long, LocalTimeEdit, COPY_CHUNK_BYTES,
+    WINDOWS_MAX_PATH,
+};
+use memetool::image_utils::ExifDateTime;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn test_expand_path() {
+    let home = shellexpand::tilde("~").to_string();
+    assert_eq!(
+        expand_path("~/memes/cat.jpg"),
+        std::path::Path::new(&home).join("memes/cat.jpg")
+    );
+    assert_eq!(
+        expand_path(" /tmp/cat.jpg "),
+        std::path::Path::new("/tmp/cat.jpg")
+    );
+}
+
+#[test]
+fn test_windows_path_too_long() {
+    let short = r"C:\memes\cat.jpg";
+    assert!(!windows_path_too_long(short));
+    let long = format!(r"C:\{}.jpg", "a".repeat(WINDOWS_MAX_PATH));
+    assert!(windows_path_too_long(&long));
+    // verbatim paths can be much longer
+    assert!(!windows_path_too_long(&format!(r"\\?\{}", long)));
+}
+
+#[test]
+fn test_rename_target_problem() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let existing = tempdir.path().join("cat.jpg");
+    std::fs::write(&existing, b"meow").unwrap();
+    assert!(rename_target_problem(&existing).is_some());
+    assert!(rename_target_problem(&tempdir.path().join("missing/dog.jpg")).is_some());
+    assert!(rename_target_problem(&tempdir.path().join("dog.jpg")).is_none());
+    // a bare filename's in the current directory, which is there
+    assert!(rename_target_problem(std::path::Path::new("memetool-no-such-file.jpg")).is_none());
+}
+
+#[test]
+fn test_copy_then_delete() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let from = tempdir.path().join("big.bin");
+    let to = tempdir.path().join("moved.bin");
+    let contents: Vec<u8> = (0..COPY_CHUNK_BYTES * 2 + 10)
+        .map(|i| (i % 251) as u8)
+        .collect();
+    std::fs::write(&from, &contents).unwrap();
+
+    let mut progress = vec![];
+    copy_then_delete(&from, &to, |done, total| progress.push((done, total))).unwrap();
+    assert!(!from.exists());
+    assert_eq!(std::fs::read(&to).unwrap(), contents);
+    let total = contents.len() as u64;
+    assert_eq!(progress.len(), 3);
+    assert_eq!(progress.last(), Some(&(total, total)));
+}
+
+#[test]
+fn test_copy_then_delete_wont_overwrite() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let from = tempdir.path().join("cat.jpg");
+    let to = tempdir.path().join("dog.jpg");
+    std::fs::write(&from, b"meow").unwrap();
+    std::fs::write(&to, b"woof").unwrap();
+
+    assert!(copy_then_delete(&from, &to, |_, _| {}).is_err());
+    assert_eq!(std::fs::read(&from).unwrap(), b"meow");
+    assert_eq!(std::fs::read(&to).unwrap(), b"woof");
+}
+
+#[test]
+fn test_is_cross_device() {
+    let code = if cfg!(windows) { 17 } else { 18 };
+    assert!(is_cross_device(&std::io::Error::from_raw_os_error(code)));
+    assert!(!is_cross_device(&std::io::Error::from(
+        std::io::ErrorKind::NotFound
+    )));
+}
+
+#[test]
+fn test_is_same_file() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let cat = tempdir.path().join("cat.jpg");
+    let dog = tempdir.path().join("dog.jpg");
+    std::fs::write(&cat, b"meow").unwrap();
+    std::fs::write(&dog, b"woof").unwrap();
+    assert!(is_same_file(&cat, &cat));
+    assert!(!is_same_file(&cat, &dog));
+    assert!(!is_same_file(&cat, &tempdir.path().join("missing.jpg")));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hard_link_isnt_case_only() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let cat = tempdir.path().join("cat.jpg");
+    std::fs::write(&cat, b"meow").unwrap();
+    // a different name for the same file isn't a change of case
+    let link = tempdir.path().join("link.jpg");
+    std::fs::hard_link(&cat, &link).unwrap();
+    assert!(is_same_file(&cat, &link));
+    assert!(!is_case_only_rename(&cat, &link));
+}
+
+#[test]
+fn test_rename_case_only() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let from = tempdir.path().join("Meme.PNG");
+    let to = tempdir.path().join("meme.png");
+    std::fs::write(&from, b"not really a png").unwrap();
+    rename_case_only(&from, &to).unwrap();
+    let names: Vec<String> = std::fs::read_dir(tempdir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names, vec!["meme.png".to_string()]);
+    assert_eq!(std::fs::read(&to).unwrap(), b"not really a png");
+}
+
+#[cfg(any(target_os = "macos", windows))]
+#[test]
+fn test_case_only_rename_on_case_insensitive_filesystem() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let from = tempdir.path().join("Meme.PNG");
+    let to = tempdir.path().join("meme.png");
+    std::fs::write(&from, b"not really a png").unwrap();
+    // it looks like it's already there, but it's the same file
+    assert!(to.exists());
+    assert!(rename_target_problem(&to).is_some());
+    assert!(is_case_only_rename(&from, &to));
+    assert!(rename_problem(&from, &to).is_none());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_case_only_rename_on_case_sensitive_filesystem() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let from = tempdir.path().join("Meme.PNG");
+    let to = tempdir.path().join("meme.png");
+    std::fs::write(&from, b"one").unwrap();
+    assert!(!is_case_only_rename(&from, &to));
+    assert!(rename_problem(&from, &to).is_none());
+    // they're two different files here, so it'd be overwriting one
+    std::fs::write(&to, b"two").unwrap();
+    assert!(!is_case_only_rename(&from, &to));
+    assert!(rename_problem(&from, &to).is_some());
+}
+
+#[test]
+fn test_set_modified() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("restored.png");
+    std::fs::write(&path, b"not really a png").unwrap();
+    let modified = UNIX_EPOCH + Duration::from_secs(1_688_484_609);
+    set_modified(&path, modified).unwrap();
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().modified().unwrap(),
+        modified
+    );
+    assert!(set_modified(&tempdir.path().join("missing.png"), modified).is_err());
+}
+
+#[test]
+fn test_local_time_edit() {
+    let time = UNIX_EPOCH + Duration::from_secs(1_688_484_609);
+    let edit = LocalTimeEdit::from_time(time);
+    assert_eq!(edit.to_time(), Some(time));
+
+    let taken = ExifDateTime::parse("2023:07:04 15:30:09").unwrap();
+    let edit = LocalTimeEdit::from_exif(&taken).unwrap();
+    assert_eq!(edit.date.to_string(), "2023-07-04");
+    assert_eq!((edit.hour, edit.minute, edit.second), (15, 30, 9));
+    // the 31st of February parses, but it isn't a date
+    let taken = ExifDateTime::parse("2023:02:31 15:30:09").unwrap();
+    assert_eq!(LocalTimeEdit::from_exif(&taken), None);
+}
+
+#[test]
+fn test_moved_between() {
+    assert_eq!(
+        moved_between(Path::new("/memes/cat.jpg"), Path::new("/memes/dog.jpg")),
+        None
+    );
+    assert_eq!(
+        moved_between(Path::new("/memes/cat.jpg"), Path::new("/tmp/cat.jpg")),
+        Some((PathBuf::from("/memes"), PathBuf::from("/tmp")))
+    );
+
+    // different ways of writing the same directory aren't a move
+    let tempdir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(tempdir.path().join("sub")).unwrap();
+    let from = tempdir.path().join("cat.jpg");
+    let to = tempdir.path().join("sub").join("..").join("dog.jpg");
+    assert_eq!(moved_between(&from, &to), None);
+}
+
+#[test]
+fn test_split_common_dirs() {
+    assert_eq!(
+        split_common_dirs(
+            Path::new("/home/me/Downloads"),
+            Path::new("/home/me/memes/cats")
+        ),
+        (
+            PathBuf::from("/home/me"),
+            PathBuf::from("Downloads"),
+            PathBuf::from("memes/cats")
+        )
+    );
+    assert_eq!(
+        split_common_dirs(Path::new("/home/me/Downloads"), Path::new("/tmp")),
+        (
+            PathBuf::from("/"),
+            PathBuf::from("home/me/Downloads"),
+            PathBuf::from("tmp")
+        )
+    );
+}
+
+#[test]
+fn test_home_relative() {
+    let home = expand_path("~");
+    assert_eq!(home_relative(&home), "~");
+    assert_eq!(
+        home_relative(&home.join("Downloads")),
+        Path::new("~").join("Downloads").display().to_string()
+    );
+    assert_eq!(
+        home_relative(Path::new("/definitely/not/home")),
+        "/definitely/not/home"
+    );
+}
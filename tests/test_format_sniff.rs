@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use memetool::format_sniff::{sniff_file, FormatCache, FormatFilter, SniffedFormat};
+
+#[test]
+fn test_sniff_file_ignores_the_extension() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("really-a-jpeg.png");
+    image::RgbImage::from_pixel(4, 4, image::Rgb([255, 0, 0]))
+        .save_with_format(&filepath, image::ImageFormat::Jpeg)
+        .unwrap();
+    assert_eq!(sniff_file(&filepath).unwrap(), SniffedFormat::Jpeg);
+    assert_eq!(SniffedFormat::from_extension(&filepath), SniffedFormat::Png);
+}
+
+#[test]
+fn test_sniff_short_file() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("tiny.gif");
+    std::fs::write(&filepath, b"GIF").unwrap();
+    assert_eq!(sniff_file(&filepath).unwrap(), SniffedFormat::Gif);
+    std::fs::write(&filepath, b"").unwrap();
+    assert_eq!(sniff_file(&filepath).unwrap(), SniffedFormat::Other);
+    assert!(sniff_file(&tempdir.path().join("missing.png")).is_err());
+}
+
+#[test]
+fn test_from_extension() {
+    assert_eq!(
+        SniffedFormat::from_extension(Path::new("a.JPEG")),
+        SniffedFormat::Jpeg
+    );
+    assert_eq!(
+        SniffedFormat::from_extension(Path::new("a.webp")),
+        SniffedFormat::WebP
+    );
+    assert_eq!(
+        SniffedFormat::from_extension(Path::new("a.heic")),
+        SniffedFormat::Other
+    );
+    assert_eq!(
+        SniffedFormat::from_extension(Path::new("noextension")),
+        SniffedFormat::Other
+    );
+}
+
+#[test]
+fn test_format_cache() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("really-a-png.jpg");
+    std::fs::write(&filepath, b"\x89PNG\r\n\x1a\n").unwrap();
+    let key = filepath.display().to_string();
+
+    let mut cache = FormatCache::default();
+    assert_eq!(cache.get_or_guess(&filepath), SniffedFormat::Jpeg);
+    assert_eq!(cache.start([key.as_str()]), vec![key.clone()]);
+    // it's already been asked for
+    assert!(cache.start([key.as_str()]).is_empty());
+
+    let modified = std::fs::metadata(&filepath).unwrap().modified().ok();
+    cache.insert(key.clone(), modified, SniffedFormat::Png);
+    assert_eq!(cache.get_or_guess(&filepath), SniffedFormat::Png);
+    assert_eq!(cache.prune_stale(), 0);
+
+    std::fs::remove_file(&filepath).unwrap();
+    assert_eq!(cache.prune_stale(), 1);
+    assert!(cache.is_empty());
+    assert_eq!(cache.start([key.as_str()]), vec![key]);
+}
+
+#[test]
+fn test_format_filter() {
+    let mut filter = FormatFilter::default();
+    assert!(!filter.is_active());
+    assert!(SniffedFormat::ALL
+        .iter()
+        .all(|format| filter.shows(*format)));
+
+    filter.set(SniffedFormat::Gif, false);
+    assert!(filter.is_active());
+    assert!(!filter.shows(SniffedFormat::Gif));
+    assert!(filter.shows(SniffedFormat::Png));
+
+    filter.set(SniffedFormat::Gif, true);
+    assert!(!filter.is_active());
+}
+
+#[test]
+fn test_mime_type() {
+    assert_eq!(SniffedFormat::Png.mime_type(), Some("image/png"));
+    assert_eq!(SniffedFormat::Jpeg.mime_type(), Some("image/jpeg"));
+    assert_eq!(SniffedFormat::WebP.mime_type(), Some("image/webp"));
+    assert_eq!(SniffedFormat::Other.mime_type(), None);
+}
@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+use memetool::image_utils::generate_mosaic;
+
+#[test]
+fn test_generate_mosaic() {
+    let filepaths = vec![PathBuf::from("tests/testfile.jpg"); 5];
+    let mosaic = generate_mosaic(&filepaths, 256, 128).expect("Failed to generate mosaic");
+    assert_eq!(mosaic.width(), 256);
+    assert_eq!(mosaic.height(), 128);
+    // it's three tiles by two, so it's fitted in with a gap down the sides instead of stretched
+    assert_eq!(mosaic.to_rgba8().get_pixel(0, 64)[3], 0);
+    assert_eq!(mosaic.to_rgba8().get_pixel(255, 64)[3], 0);
+}
+
+#[test]
+fn test_generate_mosaic_no_images() {
+    assert!(generate_mosaic(&[], 256, 256).is_err());
+}
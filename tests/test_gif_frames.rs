@@ -0,0 +1,28 @@
+use image::codecs::gif::GifEncoder;
+use image::{Delay, Frame, Rgba, RgbaImage};
+use memetool::image_utils::extract_first_gif_frame;
+
+#[test]
+fn test_extract_first_gif_frame() {
+    let mut data = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut data);
+        let frames = [Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255])]
+            .into_iter()
+            .map(|pixel| {
+                Frame::from_parts(
+                    RgbaImage::from_pixel(8, 8, pixel),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(100, 1),
+                )
+            });
+        encoder.encode_frames(frames).unwrap();
+    }
+
+    let frame = extract_first_gif_frame(&data).unwrap().to_rgba8();
+    assert_eq!(frame.dimensions(), (8, 8));
+    assert_eq!(frame.get_pixel(4, 4), &Rgba([255, 0, 0, 255]));
+
+    assert!(extract_first_gif_frame(b"not a gif").is_err());
+}
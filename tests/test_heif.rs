@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use memetool::image_utils::{convert_to_jpeg, is_heif, jpeg_path_for};
+use memetool::OK_EXTENSIONS;
+
+#[test]
+fn test_is_heif() {
+    assert!(is_heif(Path::new("/tmp/memes/IMG_0001.HEIC")));
+    assert!(is_heif(Path::new("/tmp/memes/IMG_0001.heif")));
+    assert!(!is_heif(Path::new("/tmp/memes/cat.jpg")));
+    assert!(!is_heif(Path::new("/tmp/memes/heic")));
+}
+
+#[test]
+fn test_heif_extensions_follow_the_feature() {
+    assert_eq!(OK_EXTENSIONS.contains(&"heic"), cfg!(feature = "heif"));
+    assert_eq!(OK_EXTENSIONS.contains(&"heif"), cfg!(feature = "heif"));
+}
+
+#[test]
+fn test_convert_to_jpeg() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let source = tempdir.path().join("see-through.png");
+    image::RgbaImage::from_pixel(40, 20, image::Rgba([0, 128, 255, 100]))
+        .save(&source)
+        .unwrap();
+
+    let dest = convert_to_jpeg(&source).unwrap();
+    assert_eq!(dest, jpeg_path_for(&source));
+    assert_eq!(dest, tempdir.path().join("see-through.jpg"));
+    assert_eq!(image::image_dimensions(&dest).unwrap(), (40, 20));
+    // the original's left where it was
+    assert!(source.exists());
+
+    // it won't clobber what's there
+    assert!(convert_to_jpeg(&source).is_err());
+}
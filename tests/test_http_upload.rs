@@ -0,0 +1,217 @@
+//! [HttpStore] against a tiny in-process HTTP server that keeps what's PUT to it in memory
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use memetool::config::{Configuration, UploadDestination};
+use memetool::http_upload::{upload_failure, HttpStore};
+use memetool::s3_upload::{S3Error, S3ErrorKind, S3Result};
+use memetool::upload_backend::{backend_for, UploadBackend};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const TOKEN: &str = "sekrit";
+
+type Objects = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// start the server, returns its base URL and what's been uploaded to it
+async fn serve() -> (String, Objects) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}/memes", listener.local_addr().unwrap());
+    let objects: Objects = Default::default();
+    let served = objects.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle(stream, served.clone()));
+        }
+    });
+    (base_url, objects)
+}
+
+/// answer one request, then hang up
+async fn handle(mut stream: TcpStream, objects: Objects) {
+    let mut buffer = vec![];
+    let head_end = loop {
+        let mut chunk = [0u8; 4096];
+        let read = stream.read(&mut chunk).await.unwrap();
+        if read == 0 {
+            return;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap().split(' ');
+    let (method, path) = (request_line.next().unwrap(), request_line.next().unwrap());
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+        .collect();
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = buffer[head_end..].to_vec();
+    while body.len() < length {
+        let mut chunk = vec![0u8; length - body.len()];
+        let read = stream.read(&mut chunk).await.unwrap();
+        body.extend_from_slice(&chunk[..read]);
+    }
+
+    let authorised = headers.get("authorization") == Some(&format!("Bearer {TOKEN}"));
+    let key = path.trim_start_matches("/memes").trim_start_matches('/');
+    let mut objects = objects.lock().unwrap();
+    let (status, content): (&str, Vec<u8>) = match method {
+        _ if !authorised => ("401 Unauthorized", vec![]),
+        "PUT" if key.contains("busy") => ("503 Service Unavailable", vec![]),
+        "PUT" => {
+            objects.insert(key.to_string(), body);
+            ("201 Created", vec![])
+        }
+        "HEAD" if key.is_empty() => ("200 OK", vec![]),
+        "HEAD" | "GET" => match objects.get(key) {
+            Some(object) => ("200 OK", object.clone()),
+            None => ("404 Not Found", vec![]),
+        },
+        "DELETE" => match objects.remove(key) {
+            Some(_) => ("204 No Content", vec![]),
+            None => ("404 Not Found", vec![]),
+        },
+        "COPY" => {
+            let destination = headers["destination"].rsplit("/memes/").next().unwrap();
+            match objects.get(key).cloned() {
+                Some(object) => {
+                    objects.insert(destination.to_string(), object);
+                    ("201 Created", vec![])
+                }
+                None => ("404 Not Found", vec![]),
+            }
+        }
+        _ => ("405 Method Not Allowed", vec![]),
+    };
+    drop(objects);
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        content.len()
+    )
+    .into_bytes();
+    if method != "HEAD" {
+        response.extend(content);
+    }
+    stream.write_all(&response).await.unwrap();
+}
+
+fn write_upload(dir: &std::path::Path) -> String {
+    let filepath = dir.join("cat.jpg");
+    std::fs::write(&filepath, b"not really a cat").unwrap();
+    filepath.display().to_string()
+}
+
+/// what every [UploadBackend] should do, whatever's behind it
+async fn check_contract(store: &dyn UploadBackend, filepath: &str) {
+    store.check_bucket_accessible().await.unwrap();
+    assert!(matches!(
+        store.head_object("cat.jpg").await,
+        Err(S3Result::FileNotFound)
+    ));
+
+    store.put_object("cat.jpg", filepath).await.unwrap();
+    store.head_object("cat.jpg").await.unwrap();
+    assert_eq!(store.object_size("cat.jpg").await.unwrap(), 16);
+
+    let tempdir = tempfile::tempdir().unwrap();
+    let downloaded = tempdir.path().join("downloaded.jpg");
+    store.get_object("cat.jpg", &downloaded).await.unwrap();
+    assert_eq!(std::fs::read(&downloaded).unwrap(), b"not really a cat");
+
+    store
+        .rename_object("cat.jpg", "cats/cat.jpg")
+        .await
+        .unwrap();
+    assert!(matches!(
+        store.head_object("cat.jpg").await,
+        Err(S3Result::FileNotFound)
+    ));
+    assert_eq!(store.object_size("cats/cat.jpg").await.unwrap(), 16);
+
+    store.delete_object("cats/cat.jpg").await.unwrap();
+    assert!(matches!(
+        store.get_object("cats/cat.jpg", &downloaded).await,
+        Err(S3Result::FileNotFound)
+    ));
+}
+
+#[tokio::test]
+async fn test_http_store_contract() {
+    let (base_url, objects) = serve().await;
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_upload(tempdir.path());
+
+    let store = HttpStore::new(&format!("{base_url}/"), Some(TOKEN.to_string()));
+    check_contract(&store, &filepath).await;
+    assert!(objects.lock().unwrap().is_empty());
+
+    assert_eq!(
+        store.url_for_key("cats/cat.jpg"),
+        Some(format!("{base_url}/cats/cat.jpg"))
+    );
+}
+
+#[tokio::test]
+async fn test_http_store_failures() {
+    let (base_url, _) = serve().await;
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_upload(tempdir.path());
+
+    let store = HttpStore::new(&base_url, Some(TOKEN.to_string()));
+    match store.put_object("busy/cat.jpg", &filepath).await {
+        Err(err) => assert!(err.is_retryable(), "{:?}", err),
+        Ok(_) => panic!("The server's busy, that shouldn't have worked"),
+    }
+
+    let no_token = HttpStore::new(&base_url, None);
+    match no_token.check_bucket_accessible().await {
+        Err(S3Result::BucketNotAccessible(error)) => {
+            assert_eq!(error.kind, S3ErrorKind::InvalidCredentials)
+        }
+        other => panic!("Expected BucketNotAccessible, got {:?}", other),
+    }
+    match no_token.put_object("cat.jpg", &filepath).await {
+        Err(S3Result::UploadFailure(error)) => {
+            assert_eq!(error.kind, S3ErrorKind::InvalidCredentials)
+        }
+        other => panic!("Expected UploadFailure, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_upload_failure() {
+    assert!(upload_failure(Some(503), S3Error::other("busy")).is_retryable());
+    assert!(!upload_failure(Some(403), S3Error::other("no")).is_retryable());
+    assert!(upload_failure(None, S3Error::new(S3ErrorKind::Timeout, "slow")).is_retryable());
+    assert!(!upload_failure(None, S3Error::other("who knows")).is_retryable());
+}
+
+#[test]
+fn test_backend_for_destination() {
+    let config = Configuration {
+        upload_destination: UploadDestination::Http,
+        http_upload_url: Some("https://example.com/memes/".to_string()),
+        ..Configuration::defaults()
+    };
+    let store = backend_for(config).unwrap();
+    assert_eq!(
+        store.url_for_key("cat.jpg"),
+        Some("https://example.com/memes/cat.jpg".to_string())
+    );
+
+    // there's nowhere to send them
+    let config = Configuration {
+        upload_destination: UploadDestination::Http,
+        ..Configuration::defaults()
+    };
+    assert!(backend_for(config).is_err());
+}
@@ -0,0 +1,34 @@
+use memetool::i18n::{available_locales, resolve_locale, translate_in};
+
+#[test]
+fn test_translate_interpolates() {
+    assert_eq!(
+        translate_in("en", "number-of-files", &[("count", "3".to_string())]),
+        "Number of files: 3"
+    );
+    assert_eq!(
+        translate_in("de", "last-checked", &[("path", "/tmp/memes".to_string())]),
+        "Zuletzt geprüft: /tmp/memes"
+    );
+    assert_eq!(
+        translate_in("de", "confirm-delete-many", &[("count", "12".to_string())]),
+        "Bitte Löschen von 12 Dateien bestätigen"
+    );
+}
+
+#[test]
+fn test_translate_fallbacks() {
+    // not a locale we've got
+    assert_eq!(translate_in("xx", "cancel", &[]), "Cancel");
+    // nobody's got this one
+    assert_eq!(translate_in("de", "not-a-real-key", &[]), "not-a-real-key");
+}
+
+#[test]
+fn test_resolve_locale() {
+    assert_eq!(resolve_locale(Some("de_DE.UTF-8")), "de");
+    assert_eq!(resolve_locale(Some("de-AT")), "de");
+    assert_eq!(resolve_locale(Some("EN")), "en");
+    assert_eq!(resolve_locale(Some("tlh")), "en");
+    assert!(available_locales().any(|(code, _)| code == "de"));
+}
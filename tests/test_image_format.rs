@@ -0,0 +1,61 @@
+use image::ImageFormat;
+use memetool::image_utils::{
+    image_format_from_bytes, load_image_to_thumbnail_async, ImageLoadError,
+};
+
+fn header(start: &[u8]) -> [u8; 12] {
+    let mut header = [0u8; 12];
+    header[..start.len()].copy_from_slice(start);
+    header
+}
+
+#[test]
+fn test_image_format_from_bytes() {
+    assert_eq!(
+        image_format_from_bytes(&header(&[0xFF, 0xD8, 0xFF, 0xE0])),
+        Some(ImageFormat::Jpeg)
+    );
+    assert_eq!(
+        image_format_from_bytes(&header(b"\x89PNG\r\n\x1a\n")),
+        Some(ImageFormat::Png)
+    );
+    assert_eq!(
+        image_format_from_bytes(&header(b"GIF89a")),
+        Some(ImageFormat::Gif)
+    );
+    assert_eq!(
+        image_format_from_bytes(b"RIFF\x24\x00\x00\x00WEBP"),
+        Some(ImageFormat::WebP)
+    );
+    assert_eq!(
+        image_format_from_bytes(&header(b"BM")),
+        Some(ImageFormat::Bmp)
+    );
+    // a RIFF that's not a WebP, eg a .wav
+    assert_eq!(image_format_from_bytes(b"RIFF\x24\x00\x00\x00WAVE"), None);
+    assert_eq!(image_format_from_bytes(&header(b"hello world")), None);
+}
+
+#[tokio::test]
+async fn test_load_with_the_wrong_extension() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let png = tempdir.path().join("really-a-png.png");
+    image::RgbImage::from_pixel(40, 20, image::Rgb([255, 0, 0]))
+        .save(&png)
+        .unwrap();
+    let misnamed = tempdir.path().join("really-a-png.jpg");
+    std::fs::rename(&png, &misnamed).unwrap();
+
+    let image = load_image_to_thumbnail_async(&misnamed, None, true)
+        .await
+        .unwrap();
+    assert!(image.size[0] > image.size[1]);
+
+    let garbage = tempdir.path().join("not-an-image.jpg");
+    std::fs::write(&garbage, b"definitely not a jpeg").unwrap();
+    match load_image_to_thumbnail_async(&garbage, None, true).await {
+        Err(ImageLoadError::UnsupportedFormat(path)) => assert_eq!(path, garbage),
+        Err(other) => panic!("Expected UnsupportedFormat, got {:?}", other),
+        Ok(_) => panic!("That's not an image, it shouldn't have loaded"),
+    }
+}
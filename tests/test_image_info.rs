@@ -0,0 +1,71 @@
+use memetool::image_utils::{load_image_dimensions_fast, ImageInfo};
+
+#[test]
+fn test_aspect_ratio() {
+    assert_eq!(ImageInfo::new(1920, 1080, 0).aspect_ratio(), (16, 9));
+    assert_eq!(ImageInfo::new(800, 600, 0).aspect_ratio(), (4, 3));
+    assert_eq!(ImageInfo::new(500, 500, 0).aspect_ratio(), (1, 1));
+    // already as small as it goes
+    assert_eq!(ImageInfo::new(641, 480, 0).aspect_ratio(), (641, 480));
+    assert_eq!(ImageInfo::new(0, 0, 0).aspect_ratio(), (0, 0));
+}
+
+#[test]
+fn test_megapixels_and_bits_per_pixel() {
+    let info = ImageInfo::new(2000, 1000, 500_000);
+    assert_eq!(info.megapixels(), 2.0);
+    assert_eq!(info.bits_per_pixel(), Some(2.0));
+
+    assert_eq!(ImageInfo::new(0, 100, 1234).bits_per_pixel(), None);
+    assert_eq!(
+        ImageInfo::new(2000, 1000, 0)
+            .with_file_size(None)
+            .bits_per_pixel(),
+        None
+    );
+}
+
+#[test]
+fn test_oversized_reasons() {
+    assert!(ImageInfo::new(4000, 3000, 5_000_000)
+        .oversized_reasons(4000, 5_000_000)
+        .is_empty());
+
+    let reasons = ImageInfo::new(6000, 4500, 7_200_000).oversized_reasons(4000, 5_000_000);
+    assert_eq!(reasons.len(), 3);
+    assert_eq!(reasons[0], "6000px wide, over 4000px");
+    assert_eq!(reasons[1], "4500px tall, over 4000px");
+
+    // only the file size's a problem
+    let reasons = ImageInfo::new(640, 480, 7_200_000).oversized_reasons(4000, 5_000_000);
+    assert_eq!(reasons.len(), 1);
+
+    // not knowing how big the file is isn't a reason
+    assert!(ImageInfo::new(640, 480, 0)
+        .with_file_size(None)
+        .oversized_reasons(4000, 5_000_000)
+        .is_empty());
+}
+
+#[test]
+fn test_load_image_dimensions_fast_only_needs_the_header() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("wide.png");
+    image::RgbImage::from_pixel(120, 30, image::Rgb([200, 100, 50]))
+        .save(&filepath)
+        .unwrap();
+    assert_eq!(load_image_dimensions_fast(&filepath).unwrap(), (120, 30));
+
+    // the pixels are cut off just after they start, but the header's all it reads
+    let data = std::fs::read(&filepath).unwrap();
+    let pixels_start = data
+        .windows(4)
+        .position(|window| window == b"IDAT")
+        .unwrap();
+    let truncated = tempdir.path().join("truncated.png");
+    std::fs::write(&truncated, &data[..pixels_start + 8]).unwrap();
+    assert!(image::open(&truncated).is_err());
+    assert_eq!(load_image_dimensions_fast(&truncated).unwrap(), (120, 30));
+
+    assert!(load_image_dimensions_fast(&tempdir.path().join("missing.png")).is_err());
+}
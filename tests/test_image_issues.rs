@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use memetool::image_utils::{apply_lossless_jpeg_rotation, detect_image_issues, ImageIssue};
+
+/// 8 pixel squares of `dark` and `light`, which has plenty of sharp edges
+fn checkerboard(width: u32, height: u32, dark: u8, light: u8) -> image::GrayImage {
+    image::GrayImage::from_fn(width, height, |x, y| {
+        if (x / 8 + y / 8) % 2 == 0 {
+            image::Luma([dark])
+        } else {
+            image::Luma([light])
+        }
+    })
+}
+
+fn save(dir: &Path, name: &str, image: image::GrayImage) -> PathBuf {
+    let filepath = dir.join(name);
+    image.save(&filepath).unwrap();
+    filepath
+}
+
+#[test]
+fn test_no_issues() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = save(tempdir.path(), "fine.png", checkerboard(400, 400, 60, 190));
+    assert_eq!(detect_image_issues(&filepath), vec![]);
+}
+
+#[test]
+fn test_blurry() {
+    let tempdir = tempfile::tempdir().unwrap();
+    // a smooth gradient, there's nothing sharp in it
+    let gradient =
+        image::GrayImage::from_fn(400, 400, |x, _| image::Luma([50 + (x * 150 / 400) as u8]));
+    let filepath = save(tempdir.path(), "blurry.png", gradient);
+    let issues = detect_image_issues(&filepath);
+    assert!(matches!(
+        issues.as_slice(),
+        [ImageIssue::PossiblyBlurry { .. }]
+    ));
+}
+
+#[test]
+fn test_overexposed() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let mut image = checkerboard(400, 400, 60, 190);
+    for (x, _, pixel) in image.enumerate_pixels_mut() {
+        if x < 240 {
+            *pixel = image::Luma([255]);
+        }
+    }
+    let filepath = save(tempdir.path(), "bright.png", image);
+    let issues = detect_image_issues(&filepath);
+    let [ImageIssue::Overexposed { percent_blown }] = issues.as_slice() else {
+        panic!("Expected it to be overexposed, got {:?}", issues);
+    };
+    assert!((*percent_blown - 60.0).abs() < 1.0);
+}
+
+#[test]
+fn test_underexposed() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = save(tempdir.path(), "dark.png", checkerboard(400, 400, 5, 40));
+    assert_eq!(
+        detect_image_issues(&filepath),
+        vec![ImageIssue::Underexposed]
+    );
+}
+
+#[test]
+fn test_small_dimensions() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = save(tempdir.path(), "tiny.png", checkerboard(100, 50, 60, 190));
+    assert_eq!(
+        detect_image_issues(&filepath),
+        vec![ImageIssue::SmallDimensions {
+            width: 100,
+            height: 50
+        }]
+    );
+}
+
+#[test]
+fn test_corrupted_metadata() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("photo.jpg");
+    image::DynamicImage::ImageLuma8(checkerboard(400, 400, 60, 190))
+        .save(&filepath)
+        .unwrap();
+    // a valid EXIF segment's fine
+    apply_lossless_jpeg_rotation(&filepath, 90).unwrap();
+    assert!(!detect_image_issues(&filepath).contains(&ImageIssue::CorruptedMetadata));
+
+    // one that's not got a TIFF header isn't
+    let original = std::fs::read(&filepath).unwrap();
+    let garbage = b"Exif\0\0not a tiff header";
+    let mut corrupt = original[..2].to_vec();
+    corrupt.extend([0xFF, 0xE1]);
+    corrupt.extend(((garbage.len() + 2) as u16).to_be_bytes());
+    corrupt.extend(garbage);
+    corrupt.extend(&original[2..]);
+    std::fs::write(&filepath, corrupt).unwrap();
+    let issues = detect_image_issues(&filepath);
+    assert!(issues.contains(&ImageIssue::CorruptedMetadata));
+    assert!(issues.iter().any(ImageIssue::is_error));
+}
+
+#[test]
+fn test_unreadable_files_have_no_issues() {
+    let tempdir = tempfile::tempdir().unwrap();
+    assert_eq!(
+        detect_image_issues(&tempdir.path().join("missing.png")),
+        vec![]
+    );
+}
@@ -0,0 +1,140 @@
+use memetool::config::ImportConflict;
+use memetool::import::{
+    import_file, import_target, import_with, renamed_path_for, resolve, ImportOutcome,
+    ImportSummary, Resolution,
+};
+
+#[test]
+fn test_resolve_without_a_conflict() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let target = tempdir.path().join("cat.png");
+    for on_conflict in [
+        ImportConflict::Rename,
+        ImportConflict::Overwrite,
+        ImportConflict::Skip,
+        ImportConflict::Ask,
+    ] {
+        assert_eq!(
+            resolve(&target, on_conflict),
+            Resolution::Write {
+                path: target.clone(),
+                renamed: false
+            }
+        );
+    }
+}
+
+#[test]
+fn test_resolve_conflicts() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let target = tempdir.path().join("cat.png");
+    std::fs::write(&target, b"meow").unwrap();
+
+    assert_eq!(
+        resolve(&target, ImportConflict::Rename),
+        Resolution::Write {
+            path: tempdir.path().join("cat_1.png"),
+            renamed: true
+        }
+    );
+    assert_eq!(
+        resolve(&target, ImportConflict::Overwrite),
+        Resolution::Write {
+            path: target.clone(),
+            renamed: false
+        }
+    );
+    assert_eq!(resolve(&target, ImportConflict::Skip), Resolution::Skip);
+    assert_eq!(resolve(&target, ImportConflict::Ask), Resolution::Ask);
+}
+
+#[test]
+fn test_renamed_path_counts_up() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let target = tempdir.path().join("cat.png");
+    std::fs::write(&target, b"meow").unwrap();
+    std::fs::write(tempdir.path().join("cat_1.png"), b"meow").unwrap();
+    assert_eq!(
+        renamed_path_for(&target),
+        Some(tempdir.path().join("cat_2.png"))
+    );
+}
+
+#[test]
+fn test_import_files() {
+    let source_dir = tempfile::tempdir().unwrap();
+    let workdir = tempfile::tempdir().unwrap();
+    let source = source_dir.path().join("cat.png");
+    std::fs::write(&source, b"new cat").unwrap();
+    let target = import_target(workdir.path(), &source).unwrap();
+    assert_eq!(target, workdir.path().join("cat.png"));
+
+    let mut summary = ImportSummary::default();
+    summary.record(import_file(
+        &source,
+        resolve(&target, ImportConflict::Rename),
+    ));
+    assert_eq!(std::fs::read(&target).unwrap(), b"new cat");
+
+    std::fs::write(&source, b"newer cat").unwrap();
+    summary.record(import_file(
+        &source,
+        resolve(&target, ImportConflict::Rename),
+    ));
+    summary.record(import_file(&source, resolve(&target, ImportConflict::Skip)));
+    assert_eq!(std::fs::read(&target).unwrap(), b"new cat");
+    let renamed = workdir.path().join("cat_1.png");
+    assert_eq!(std::fs::read(&renamed).unwrap(), b"newer cat");
+
+    summary.record(import_file(
+        &source,
+        resolve(&target, ImportConflict::Overwrite),
+    ));
+    assert_eq!(std::fs::read(&target).unwrap(), b"newer cat");
+
+    summary.record(import_file(
+        &source_dir.path().join("missing.png"),
+        resolve(&workdir.path().join("missing.png"), ImportConflict::Ask),
+    ));
+
+    assert_eq!(
+        summary,
+        ImportSummary {
+            imported: 2,
+            renamed: 1,
+            skipped: 1,
+            failed: 1,
+            last_path: Some(target),
+        }
+    );
+    assert_eq!(summary.total(), 5);
+}
+
+#[test]
+fn test_import_over_itself_is_skipped() {
+    let workdir = tempfile::tempdir().unwrap();
+    let source = workdir.path().join("cat.png");
+    std::fs::write(&source, b"meow").unwrap();
+    let outcome = import_file(&source, resolve(&source, ImportConflict::Overwrite));
+    assert_eq!(outcome, ImportOutcome::Skipped);
+    assert_eq!(std::fs::read(&source).unwrap(), b"meow");
+}
+
+#[test]
+fn test_import_with_writes_where_its_resolved() {
+    let workdir = tempfile::tempdir().unwrap();
+    let target = workdir.path().join("pasted.png");
+    std::fs::write(&target, b"meow").unwrap();
+    let outcome = import_with(resolve(&target, ImportConflict::Rename), |path| {
+        std::fs::write(path, b"pasted").map_err(|e| e.to_string())
+    });
+    let renamed = workdir.path().join("pasted_1.png");
+    assert_eq!(outcome, ImportOutcome::Renamed(renamed.clone()));
+    assert_eq!(std::fs::read(renamed).unwrap(), b"pasted");
+
+    // asking hasn't been answered, so nothing's written
+    let outcome = import_with(Resolution::Ask, |_| {
+        panic!("It shouldn't have been written")
+    });
+    assert_eq!(outcome, ImportOutcome::Skipped);
+}
@@ -0,0 +1,76 @@
+use memetool::integrity::{check_file, IntegrityCache, Verdict};
+
+#[test]
+fn test_check_file() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let good = tempdir.path().join("good.png");
+    image::RgbaImage::from_pixel(64, 64, image::Rgba([0, 255, 0, 255]))
+        .save(&good)
+        .unwrap();
+    assert_eq!(check_file(&good), Verdict::Ok);
+
+    // a download that stopped halfway through
+    let contents = std::fs::read(&good).unwrap();
+    let truncated = tempdir.path().join("truncated.png");
+    std::fs::write(&truncated, &contents[..contents.len() / 2]).unwrap();
+    assert!(check_file(&truncated).corruption().is_some());
+
+    let missing = tempdir.path().join("missing.png");
+    assert!(matches!(check_file(&missing), Verdict::Unreadable(_)));
+}
+
+#[test]
+fn test_cache_checks_once() {
+    let mut cache = IntegrityCache::default();
+    assert!(cache.needs_check("/tmp/memes/cat.jpg"));
+
+    cache.start("/tmp/memes/cat.jpg");
+    assert!(cache.is_pending("/tmp/memes/cat.jpg"));
+    assert!(!cache.needs_check("/tmp/memes/cat.jpg"));
+
+    cache.insert(
+        "/tmp/memes/cat.jpg".to_string(),
+        None,
+        Verdict::Corrupt("unexpected EOF".to_string()),
+    );
+    assert!(!cache.is_pending("/tmp/memes/cat.jpg"));
+    assert!(!cache.needs_check("/tmp/memes/cat.jpg"));
+    assert_eq!(
+        cache.corruption("/tmp/memes/cat.jpg"),
+        Some("unexpected EOF")
+    );
+
+    // "Re-check"
+    cache.invalidate("/tmp/memes/cat.jpg");
+    assert!(cache.needs_check("/tmp/memes/cat.jpg"));
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_prune_stale() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let unchanged = tempdir.path().join("unchanged.png");
+    let changed = tempdir.path().join("changed.png");
+    std::fs::write(&unchanged, "not really a png").unwrap();
+    std::fs::write(&changed, "not really a png").unwrap();
+    let modified = |path: &std::path::Path| std::fs::metadata(path).unwrap().modified().ok();
+
+    let mut cache = IntegrityCache::default();
+    for path in [&unchanged, &changed] {
+        cache.insert(
+            path.display().to_string(),
+            modified(path),
+            Verdict::Corrupt("nope".to_string()),
+        );
+    }
+    cache.insert(
+        tempdir.path().join("gone.png").display().to_string(),
+        None,
+        Verdict::Ok,
+    );
+    filetime::set_file_mtime(&changed, filetime::FileTime::from_unix_time(0, 0)).unwrap();
+
+    assert_eq!(cache.prune_stale(), 2);
+    assert_eq!(cache.len(), 1);
+    assert!(cache.get(&unchanged.display().to_string()).is_some());
+}
@@ -0,0 +1,103 @@
+use std::sync::atomic::AtomicBool;
+
+use memetool::jobs::{run_job, ItemResult, JobId, JobRegistry, JobReport};
+use memetool::AppMsg;
+use tokio::sync::mpsc;
+
+#[test]
+fn test_report_totals() {
+    let report = JobReport {
+        items: vec![
+            (
+                "a.png".to_string(),
+                ItemResult::Done {
+                    bytes_before: 1000,
+                    bytes_after: 400,
+                },
+            ),
+            ("b.gif".to_string(), ItemResult::Skipped("gif".to_string())),
+            ("c.png".to_string(), ItemResult::Failed("nope".to_string())),
+            (
+                "d.jpg".to_string(),
+                ItemResult::Done {
+                    bytes_before: 500,
+                    bytes_after: 450,
+                },
+            ),
+        ],
+        total: 5,
+        cancelled: true,
+    };
+    assert_eq!(report.done(), 2);
+    assert_eq!(report.bytes_saved(), 650);
+    assert_eq!(report.skipped(), vec![("b.gif", "gif")]);
+    assert_eq!(report.failures(), vec![("c.png", "nope")]);
+}
+
+#[test]
+fn test_cancel_only_running_jobs() {
+    let mut registry = JobRegistry::default();
+    let job = JobId::next();
+    let flag = registry.register(job);
+    assert!(registry.cancel(job));
+    assert!(flag.load(std::sync::atomic::Ordering::Relaxed));
+    // already gone
+    assert!(!registry.cancel(job));
+    assert!(!registry.cancel(JobId::next()));
+}
+
+#[tokio::test]
+async fn test_run_job_reports_progress() {
+    let (tx, mut rx) = mpsc::channel(10);
+    let job = JobId::next();
+    let cancel = AtomicBool::new(false);
+    let filepaths = vec!["one".to_string(), "two".to_string()];
+    let done = run_job(job, filepaths, &tx, &cancel, |filepath| async move {
+        ItemResult::Skipped(filepath)
+    })
+    .await;
+
+    for expected in 0..2 {
+        match rx.recv().await.unwrap() {
+            AppMsg::JobProgress {
+                job: progress_job,
+                done,
+                total,
+                ..
+            } => {
+                assert_eq!(progress_job, job);
+                assert_eq!((done, total), (expected, 2));
+            }
+            other => panic!("Expected progress, got {:?}", other),
+        }
+    }
+    match done {
+        AppMsg::JobDone {
+            job: done_job,
+            report,
+        } => {
+            assert_eq!(done_job, job);
+            assert!(!report.cancelled);
+            assert_eq!(report.skipped(), vec![("one", "one"), ("two", "two")]);
+        }
+        other => panic!("Expected it to be done, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_cancelled_job_stops() {
+    let (tx, _rx) = mpsc::channel(10);
+    let cancel = AtomicBool::new(true);
+    let filepaths = vec!["one".to_string()];
+    let done = run_job(JobId::next(), filepaths, &tx, &cancel, |_| async {
+        panic!("Shouldn't have done anything")
+    })
+    .await;
+    match done {
+        AppMsg::JobDone { report, .. } => {
+            assert!(report.cancelled);
+            assert_eq!((report.items.len(), report.total), (0, 1));
+        }
+        other => panic!("Expected it to be done, got {:?}", other),
+    }
+}
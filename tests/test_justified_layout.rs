@@ -0,0 +1,60 @@
+use memetool::image_utils::{compute_thumbnail_grid_layout, justified_aspect};
+
+fn row_width(row: &[(usize, f32)]) -> f32 {
+    row.iter().map(|(_, width)| width).sum()
+}
+
+#[test]
+fn test_rows_fill_the_width() {
+    // landscape, portrait, square, panorama, portrait, landscape
+    let images = [
+        (400, 300),
+        (300, 400),
+        (500, 500),
+        (1200, 300),
+        (300, 450),
+        (800, 600),
+    ];
+    let rows = compute_thumbnail_grid_layout(&images, 800.0, 150.0);
+
+    // everything's there once, in order
+    let indexes: Vec<usize> = rows.iter().flatten().map(|(index, _)| *index).collect();
+    assert_eq!(indexes, (0..images.len()).collect::<Vec<_>>());
+
+    let (last, full) = rows.split_last().unwrap();
+    assert!(!full.is_empty());
+    for row in full {
+        assert!((row_width(row) - 800.0).abs() < 0.01, "{:?}", row);
+        // everything in a row's the same height
+        let heights: Vec<f32> = row
+            .iter()
+            .map(|(index, width)| width / justified_aspect(images[*index].0, images[*index].1))
+            .collect();
+        assert!(heights
+            .iter()
+            .all(|height| (height - heights[0]).abs() < 0.01));
+    }
+    assert!(row_width(last) <= 800.0 + 0.01);
+}
+
+#[test]
+fn test_wide_images_get_wide_cells() {
+    let rows = compute_thumbnail_grid_layout(&[(300, 600), (1200, 600)], 10_000.0, 100.0);
+    // nothing fills the row, so it stays at the target height
+    assert_eq!(rows, vec![vec![(0, 50.0), (1, 200.0)]]);
+}
+
+#[test]
+fn test_too_wide_for_a_row_on_its_own() {
+    let rows = compute_thumbnail_grid_layout(&[(4000, 100)], 500.0, 150.0);
+    assert_eq!(rows.len(), 1);
+    assert!((row_width(&rows[0]) - 500.0).abs() < 0.01);
+}
+
+#[test]
+fn test_unknown_sizes_are_square() {
+    assert_eq!(justified_aspect(0, 0), 1.0);
+    let rows = compute_thumbnail_grid_layout(&[(0, 0), (0, 0)], 1000.0, 100.0);
+    assert_eq!(rows, vec![vec![(0, 100.0), (1, 100.0)]]);
+    assert!(compute_thumbnail_grid_layout(&[], 1000.0, 100.0).is_empty());
+}
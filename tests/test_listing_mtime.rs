@@ -0,0 +1,18 @@
+use std::time::{Duration, SystemTime};
+
+use memetool::{listing_mtime, DIR_MTIME_GRANULARITY};
+
+#[test]
+fn test_listing_mtime() {
+    let now = SystemTime::now();
+    let a_while_ago = now - Duration::from_secs(60);
+    assert_eq!(listing_mtime(Some(a_while_ago), now), Some(a_while_ago));
+
+    // it changed too recently to be sure it hasn't changed again since
+    let just_now = now - DIR_MTIME_GRANULARITY / 2;
+    assert_eq!(listing_mtime(Some(just_now), now), None);
+    // clocks being odd
+    assert_eq!(listing_mtime(Some(now + Duration::from_secs(5)), now), None);
+    // no modified time from the filesystem
+    assert_eq!(listing_mtime(None, now), None);
+}
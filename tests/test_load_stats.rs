@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use memetool::load_stats::{DecodeStats, PageStatsCollector, ThumbLoadTracker, PAGE_STATS_IDLE};
+
+fn decode(source_bytes: u64, millis: u64) -> DecodeStats {
+    DecodeStats {
+        source_bytes,
+        pixels: 200 * 150,
+        decode_time: Duration::from_millis(millis),
+    }
+}
+
+#[test]
+fn test_page_stats_add_up() {
+    let mut collector = PageStatsCollector::default();
+    let started = Instant::now();
+    collector.record(0, "/tmp/memes/cat.jpg", started, decode(1_000, 20));
+    let stats = collector.record(0, "/tmp/memes/huge_scan.png", started, decode(9_000, 900));
+
+    assert_eq!(stats.page, 0);
+    assert_eq!(stats.files, 2);
+    assert_eq!(stats.source_bytes, 10_000);
+    assert_eq!(stats.pixels, 2 * 200 * 150);
+    assert_eq!(
+        stats.slowest,
+        Some((
+            "/tmp/memes/huge_scan.png".to_string(),
+            Duration::from_millis(900)
+        ))
+    );
+    assert_eq!(stats.average_decode_time(), Duration::from_millis(460));
+}
+
+#[test]
+fn test_page_stats_start_again() {
+    let mut collector = PageStatsCollector::default();
+    let started = Instant::now();
+    collector.record(0, "/tmp/memes/cat.jpg", started, decode(1_000, 20));
+
+    // a different page
+    let stats = collector.record(1, "/tmp/memes/dog.jpg", started, decode(2_000, 30));
+    assert_eq!(stats.page, 1);
+    assert_eq!(stats.files, 1);
+
+    // the same page, but long enough afterwards that it's been loaded again
+    let later = Instant::now() + PAGE_STATS_IDLE + Duration::from_secs(1);
+    let stats = collector.record(1, "/tmp/memes/dog.jpg", later, decode(2_000, 30));
+    assert_eq!(stats.files, 1);
+    assert_eq!(stats.source_bytes, 2_000);
+}
+
+#[test]
+fn test_thumb_load_round() {
+    let mut tracker = ThumbLoadTracker::default();
+    assert_eq!(
+        tracker.start(vec!["a.png".to_string(), "b.png".to_string()], 3),
+        None
+    );
+    assert_eq!(tracker.loaded("a.png"), None);
+    // not part of this round
+    assert_eq!(tracker.failed("elsewhere.png"), None);
+    let stats = tracker.failed("b.png").expect("The round should be done");
+    assert_eq!(
+        (
+            stats.total,
+            stats.loaded,
+            stats.failed,
+            stats.skipped_cached
+        ),
+        (5, 1, 1, 3)
+    );
+    assert!(stats.is_complete());
+    // it's only reported once
+    assert_eq!(tracker.loaded("a.png"), None);
+}
+
+#[test]
+fn test_thumb_load_all_cached() {
+    let mut tracker = ThumbLoadTracker::default();
+    let stats = tracker
+        .start(Vec::<String>::new(), 4)
+        .expect("Nothing to wait for");
+    assert_eq!((stats.total, stats.skipped_cached), (4, 4));
+}
+
+#[test]
+fn test_thumb_load_new_round_drops_old() {
+    let mut tracker = ThumbLoadTracker::default();
+    tracker.start(vec!["old.png".to_string()], 0);
+    tracker.start(vec!["new.png".to_string()], 0);
+    assert_eq!(tracker.loaded("old.png"), None);
+    assert!(tracker.loaded("new.png").is_some());
+}
+
+#[test]
+fn test_thumb_load_first_row() {
+    let mut tracker = ThumbLoadTracker::default();
+    tracker.start(
+        vec![
+            "a.png".to_string(),
+            "b.png".to_string(),
+            "c.png".to_string(),
+        ],
+        1,
+    );
+    // "cached.png" was already there, so it's not waited on
+    tracker.watch_first_row(vec!["a.png".to_string(), "cached.png".to_string()]);
+    tracker.loaded("b.png");
+    tracker.failed("a.png");
+    let stats = tracker.loaded("c.png").expect("The round should be done");
+    assert!(stats.first_row_ms.is_some());
+}
+
+#[test]
+fn test_thumb_load_first_row_all_cached() {
+    let mut tracker = ThumbLoadTracker::default();
+    tracker.start(vec!["b.png".to_string()], 1);
+    tracker.watch_first_row(vec!["cached.png".to_string()]);
+    let stats = tracker.loaded("b.png").unwrap();
+    assert_eq!(stats.first_row_ms, Some(0));
+}
@@ -0,0 +1,74 @@
+use memetool::image_utils::{
+    apply_exif_orientation, apply_lossless_jpeg_rotation, exif_orientation, rotate_image,
+    rotated_orientation,
+};
+
+/// a wide JPEG, red on the left and blue on the right
+fn write_jpeg(dir: &std::path::Path) -> std::path::PathBuf {
+    let filepath = dir.join("wide.jpg");
+    image::RgbImage::from_fn(64, 32, |x, _| {
+        if x < 32 {
+            image::Rgb([255, 0, 0])
+        } else {
+            image::Rgb([0, 0, 255])
+        }
+    })
+    .save(&filepath)
+    .unwrap();
+    filepath
+}
+
+#[test]
+fn test_rotated_orientation() {
+    assert_eq!(rotated_orientation(1, 90), Ok(6));
+    assert_eq!(rotated_orientation(6, 90), Ok(3));
+    assert_eq!(rotated_orientation(8, 90), Ok(1));
+    assert_eq!(rotated_orientation(1, 270), Ok(8));
+    assert_eq!(rotated_orientation(3, 180), Ok(1));
+    // mirrored ones stay mirrored
+    assert_eq!(rotated_orientation(2, 90), Ok(7));
+    assert_eq!(rotated_orientation(5, 90), Ok(2));
+    assert!(rotated_orientation(1, 45).is_err());
+}
+
+#[test]
+fn test_lossless_rotation_keeps_image_data() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_jpeg(tempdir.path());
+    let before = image::open(&filepath).unwrap().to_rgb8();
+    assert_eq!(exif_orientation(&std::fs::read(&filepath).unwrap()), None);
+
+    apply_lossless_jpeg_rotation(&filepath, 90).unwrap();
+    let contents = std::fs::read(&filepath).unwrap();
+    assert_eq!(exif_orientation(&contents), Some(6));
+    // the pixels as stored haven't been touched, only how they're shown
+    assert_eq!(
+        image::load_from_memory(&contents).unwrap().to_rgb8(),
+        before
+    );
+    let shown = apply_exif_orientation(image::load_from_memory(&contents).unwrap(), 6);
+    assert_eq!((shown.width(), shown.height()), (32, 64));
+
+    apply_lossless_jpeg_rotation(&filepath, 90).unwrap();
+    assert_eq!(
+        exif_orientation(&std::fs::read(&filepath).unwrap()),
+        Some(3)
+    );
+}
+
+#[test]
+fn test_lossless_rotation_needs_a_jpeg() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("image.png");
+    image::RgbImage::new(8, 8).save(&filepath).unwrap();
+    assert!(apply_lossless_jpeg_rotation(&filepath, 90).is_err());
+}
+
+#[test]
+fn test_rotate_image() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = write_jpeg(tempdir.path());
+    rotate_image(&filepath, 90).unwrap();
+    let rotated = image::open(&filepath).unwrap();
+    assert_eq!((rotated.width(), rotated.height()), (32, 64));
+}
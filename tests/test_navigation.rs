@@ -0,0 +1,87 @@
+use memetool::navigation::Navigation;
+use memetool::{AppState, MAX_HISTORY_DEPTH};
+
+fn editor(filepath: &str) -> AppState {
+    AppState::Editor {
+        filepath: filepath.to_string(),
+    }
+}
+
+#[test]
+fn test_frame_state_ignores_changes_made_during_the_frame() {
+    let mut navigation = Navigation::default();
+    let frame_state = navigation.frame_state();
+
+    navigation.push(editor("/tmp/memes/01.jpg"));
+
+    // this frame keeps drawing what it started with
+    assert_eq!(*frame_state, AppState::Browser);
+    // the next frame picks up the change
+    assert_eq!(*navigation.frame_state(), editor("/tmp/memes/01.jpg"));
+    assert_eq!(navigation.current(), &editor("/tmp/memes/01.jpg"));
+}
+
+#[test]
+fn test_push_and_pop() {
+    let mut navigation = Navigation::default();
+    navigation.push(editor("/tmp/memes/01.jpg"));
+    navigation.push(AppState::DeletePrompt("/tmp/memes/01.jpg".to_string()));
+    assert_eq!(navigation.history_len(), 2);
+
+    assert_eq!(navigation.pop(), &editor("/tmp/memes/01.jpg"));
+    assert_eq!(navigation.pop(), &AppState::Browser);
+    // nothing left, so it stays on the browser
+    assert_eq!(navigation.pop(), &AppState::Browser);
+}
+
+#[test]
+fn test_push_same_state_does_nothing() {
+    let mut navigation = Navigation::default();
+    navigation.push(editor("/tmp/memes/01.jpg"));
+    navigation.push(editor("/tmp/memes/01.jpg"));
+    assert_eq!(navigation.history_len(), 1);
+}
+
+#[test]
+fn test_replace_skips_history() {
+    let mut navigation = Navigation::default();
+    navigation.push(AppState::UploadPrompt("/tmp/memes/01.jpg".to_string()));
+    navigation.replace(AppState::Uploading("/tmp/memes/01.jpg".to_string()));
+    assert_eq!(navigation.history_len(), 1);
+    assert_eq!(navigation.pop(), &AppState::Browser);
+}
+
+#[test]
+fn test_history_is_capped() {
+    let mut navigation = Navigation::default();
+    for index in 0..(MAX_HISTORY_DEPTH + 10) {
+        navigation.push(editor(&format!("/tmp/memes/{index:02}.jpg")));
+    }
+    assert_eq!(navigation.history_len(), MAX_HISTORY_DEPTH);
+}
+
+#[test]
+fn test_help_returns_to_previous_state() {
+    let mut navigation = Navigation::default();
+    navigation.push(editor("/tmp/memes/01.jpg"));
+    navigation.show_help();
+    navigation.show_help();
+    assert_eq!(navigation.current(), &AppState::Help);
+
+    navigation.close_help();
+    assert_eq!(navigation.current(), &editor("/tmp/memes/01.jpg"));
+    // help doesn't go in the history
+    assert_eq!(navigation.history_len(), 1);
+}
+
+#[test]
+fn test_forget_file() {
+    let mut navigation = Navigation::default();
+    navigation.push(editor("/tmp/memes/01.jpg"));
+    navigation.push(editor("/tmp/memes/02.jpg"));
+    navigation.push(AppState::Configuration);
+
+    navigation.forget_file("/tmp/memes/02.jpg");
+    assert_eq!(navigation.history_len(), 2);
+    assert_eq!(navigation.pop(), &editor("/tmp/memes/01.jpg"));
+}
@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use memetool::optimize::{
+    batch_resize, optimize_file, OptimizeAction, OptimizeOutcome, JPEG_QUALITY, RESIZE_MAX_SIDE,
+};
+
+fn gradient(width: u32, height: u32) -> image::RgbImage {
+    image::RgbImage::from_fn(width, height, |x, y| {
+        image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8])
+    })
+}
+
+#[test]
+fn test_resize_shrinks_big_images() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("big.png");
+    gradient(3200, 400).save(&filepath).unwrap();
+    let size = std::fs::metadata(&filepath).unwrap().len();
+
+    let outcome = optimize_file(
+        &filepath,
+        OptimizeAction::Resize {
+            max_width: RESIZE_MAX_SIDE,
+            max_height: RESIZE_MAX_SIDE,
+            quality: JPEG_QUALITY,
+        },
+    )
+    .expect("Failed to resize");
+    match outcome {
+        OptimizeOutcome::Saved {
+            destination,
+            bytes_before,
+            bytes_after,
+        } => {
+            assert_eq!(destination, filepath);
+            assert_eq!(bytes_before, size);
+            assert!(bytes_after < bytes_before);
+            assert_eq!(std::fs::metadata(&filepath).unwrap().len(), bytes_after);
+        }
+        other => panic!("Expected it to be saved, got {:?}", other),
+    }
+    let resized = image::open(&filepath).unwrap();
+    assert_eq!((resized.width(), resized.height()), (RESIZE_MAX_SIDE, 200));
+}
+
+#[test]
+fn test_resize_skips_small_images() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("small.png");
+    gradient(100, 50).save(&filepath).unwrap();
+    let before = std::fs::read(&filepath).unwrap();
+
+    let outcome = optimize_file(
+        &filepath,
+        OptimizeAction::Resize {
+            max_width: 1600,
+            max_height: 1600,
+            quality: JPEG_QUALITY,
+        },
+    )
+    .unwrap();
+    assert!(matches!(outcome, OptimizeOutcome::Skipped(_)));
+    assert_eq!(std::fs::read(&filepath).unwrap(), before);
+}
+
+#[test]
+fn test_skips_when_it_would_grow() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("solid.png");
+    // a tiny flat PNG is a few bytes, any JPEG of it is hundreds
+    image::RgbImage::from_pixel(8, 8, image::Rgb([0, 0, 255]))
+        .save(&filepath)
+        .unwrap();
+
+    let outcome = optimize_file(&filepath, OptimizeAction::ConvertToJpeg).unwrap();
+    assert!(matches!(outcome, OptimizeOutcome::Skipped(_)));
+    assert!(!filepath.with_extension("jpg").exists());
+}
+
+#[test]
+fn test_convert_writes_next_to_the_original() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("photo.png");
+    gradient(400, 300).save(&filepath).unwrap();
+
+    match optimize_file(&filepath, OptimizeAction::ConvertToJpeg).unwrap() {
+        OptimizeOutcome::Saved { destination, .. } => {
+            assert_eq!(destination, tempdir.path().join("photo.jpg"));
+            assert_eq!(
+                image::ImageFormat::from_path(&destination).unwrap(),
+                image::ImageFormat::Jpeg
+            );
+            assert!(filepath.exists());
+        }
+        other => panic!("Expected it to be saved, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_skip_reasons() {
+    let gif = Path::new("animated.gif");
+    assert!(OptimizeAction::Optimize.skip_reason(gif).is_some());
+    assert!(OptimizeAction::ConvertToJpeg.skip_reason(gif).is_none());
+    let jpeg = Path::new("photo.JPG");
+    assert!(OptimizeAction::ConvertToJpeg.skip_reason(jpeg).is_some());
+    assert!(OptimizeAction::Optimize.skip_reason(jpeg).is_none());
+    // skipped before it's even opened
+    assert!(matches!(
+        optimize_file(Path::new("doesnt-exist.gif"), OptimizeAction::Optimize),
+        Ok(OptimizeOutcome::Skipped(_))
+    ));
+}
+
+#[test]
+fn test_resize_fits_in_box() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("tall.png");
+    gradient(400, 800).save(&filepath).unwrap();
+
+    // wide enough already, but too tall
+    let action = OptimizeAction::Resize {
+        max_width: 1000,
+        max_height: 200,
+        quality: JPEG_QUALITY,
+    };
+    assert!(matches!(
+        optimize_file(&filepath, action).unwrap(),
+        OptimizeOutcome::Saved { .. }
+    ));
+    assert_eq!(image::image_dimensions(&filepath).unwrap(), (100, 200));
+}
+
+#[tokio::test]
+async fn test_batch_resize() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let big = tempdir.path().join("big.jpg");
+    gradient(800, 400).save(&big).unwrap();
+    let small = tempdir.path().join("small.png");
+    gradient(40, 20).save(&small).unwrap();
+    let missing = tempdir.path().join("missing.png");
+
+    let results = batch_resize(&[big.clone(), small.clone(), missing], 200, 200, 50).await;
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    assert!(results[2].is_err());
+    assert_eq!(image::image_dimensions(&big).unwrap(), (200, 100));
+    assert_eq!(image::image_dimensions(&small).unwrap(), (40, 20));
+}
@@ -0,0 +1,111 @@
+//! The browser's pages and keyboard selection, driven through [MemeTool] itself
+
+use std::path::PathBuf;
+
+use eframe::egui;
+use memetool::config::Configuration;
+use memetool::{MemeTool, SortField};
+
+fn app() -> MemeTool {
+    let ctx = egui::Context::default();
+    let (background_tx, _background_rx) = tokio::sync::mpsc::channel(100);
+    let (_foreground_tx, foreground_rx) = tokio::sync::mpsc::channel(100);
+    MemeTool::with_context(
+        &ctx,
+        foreground_rx,
+        background_tx,
+        Some(Configuration::defaults()),
+    )
+}
+
+/// 23 files, in an order that isn't name order
+fn custom_order() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = (0..23)
+        .map(|index| PathBuf::from(format!("/tmp/memes/{index:02}.jpg")))
+        .collect();
+    files.reverse();
+    files.swap(3, 17);
+    files
+}
+
+#[tokio::test]
+async fn test_pages_join_up_into_the_list() {
+    let mut app = app();
+    let expected = custom_order();
+    app.files_list = expected.clone();
+    app.files_list.sort();
+    app.sort_field = SortField::Custom;
+    app.custom_order = expected.iter().map(|f| f.display().to_string()).collect();
+    assert_eq!(app.ordered_files(), expected);
+
+    for per_page in [1, 5, 7, 22] {
+        app.per_page = per_page;
+        let pages = (expected.len() + per_page - 1) / per_page;
+        let mut joined = vec![];
+        for page in 0..pages {
+            app.browser_context.current_page = page;
+            joined.extend(app.get_page());
+        }
+        assert_eq!(joined, expected, "per_page {per_page}");
+    }
+}
+
+#[tokio::test]
+async fn test_selection_steps_through_the_page_in_list_order() {
+    let mut app = app();
+    app.files_list = custom_order();
+    app.per_page = 7;
+
+    for page in 0..4 {
+        app.browser_context.current_page = page;
+        app.browser_context.selected_file = None;
+        let files: Vec<String> = app
+            .get_page()
+            .iter()
+            .map(|f| f.display().to_string())
+            .collect();
+
+        // nothing selected starts at the first one
+        app.move_selection(1);
+        for (index, file) in files.iter().enumerate() {
+            if index > 0 {
+                app.move_selection(1);
+            }
+            assert_eq!(
+                app.browser_context.selected_file.as_ref(),
+                Some(file),
+                "page {page} index {index}"
+            );
+        }
+        // and stops at the ends
+        app.move_selection(1);
+        assert_eq!(app.browser_context.selected_file.as_ref(), files.last());
+        app.move_selection(-(files.len() as isize));
+        assert_eq!(app.browser_context.selected_file.as_ref(), files.first());
+    }
+}
+
+#[tokio::test]
+async fn test_custom_order_with_gaps_and_repeats() {
+    let mut app = app();
+    app.files_list = ["a.jpg", "b.jpg", "c.jpg", "d.jpg"]
+        .iter()
+        .map(|f| PathBuf::from(format!("/tmp/memes/{f}")))
+        .collect();
+    app.sort_field = SortField::Custom;
+    // b's in there twice, the first one counts
+    app.custom_order = ["c.jpg", "b.jpg", "a.jpg", "b.jpg"]
+        .iter()
+        .map(|f| format!("/tmp/memes/{f}"))
+        .collect();
+    let ordered: Vec<String> = app
+        .ordered_files()
+        .iter()
+        .map(|f| f.display().to_string())
+        .collect();
+    // d's not in the custom order, so it goes on the end
+    assert_eq!(
+        ordered,
+        ["c.jpg", "b.jpg", "a.jpg", "d.jpg"].map(|f| format!("/tmp/memes/{f}"))
+    );
+}
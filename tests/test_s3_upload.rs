@@ -0,0 +1,239 @@
+use std::time::Duration;
+
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{ErrorMetadata, SdkError};
+use aws_sdk_s3::operation::head_bucket::HeadBucketError;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::http::StatusCode;
+use aws_smithy_types::body::SdkBody;
+use memetool::s3_upload::{
+    content_hash_key, is_retryable_status, key_with_prefix, part_count, renamed_key,
+    temp_path_for_key, upload_key, S3Error, S3ErrorKind, S3Result, MULTIPART_PART_SIZE,
+};
+use memetool::UploadStatus;
+
+#[tokio::test]
+async fn test_same_contents_same_key() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let first = tempdir.path().join("cat.jpg");
+    let second = tempdir.path().join("cat (1).JPG");
+    // bigger than one chunk, so the streaming gets exercised
+    let contents = vec![7u8; 200 * 1024];
+    std::fs::write(&first, &contents).unwrap();
+    std::fs::write(&second, &contents).unwrap();
+
+    let first_key = content_hash_key(&first.display().to_string())
+        .await
+        .unwrap();
+    let second_key = content_hash_key(&second.display().to_string())
+        .await
+        .unwrap();
+    assert_eq!(first_key, second_key);
+    assert!(first_key.ends_with(".jpg"));
+    // 64 hex characters, a dot and the extension
+    assert_eq!(first_key.len(), 64 + 4);
+}
+
+#[tokio::test]
+async fn test_upload_key_uses_filename_by_default() {
+    assert_eq!(
+        upload_key("/tmp/memes/cat.jpg", false).await.unwrap(),
+        "cat.jpg"
+    );
+    // hashing needs the file to be there
+    assert!(upload_key("/tmp/memes/not-here.jpg", true).await.is_err());
+}
+
+#[test]
+fn test_key_with_prefix() {
+    assert_eq!(
+        key_with_prefix("uploads/cat.jpg", "archive/2024/"),
+        "archive/2024/cat.jpg"
+    );
+    assert_eq!(
+        key_with_prefix("cat.jpg", "/archive/2024"),
+        "archive/2024/cat.jpg"
+    );
+    // no prefix puts it at the top of the bucket
+    assert_eq!(key_with_prefix("uploads/cat.jpg", ""), "cat.jpg");
+}
+
+#[test]
+fn test_temp_path_for_key() {
+    let path = temp_path_for_key("uploads/2024/cat.jpg");
+    assert_eq!(path.parent(), Some(std::env::temp_dir().as_path()));
+    // the prefix doesn't turn into directories
+    assert_eq!(
+        path.file_name().unwrap().to_string_lossy(),
+        "memetool-uploads_2024_cat.jpg"
+    );
+}
+
+#[test]
+fn test_renamed_key() {
+    assert_eq!(
+        renamed_key("dog.png", "/tmp/memes/dog.png", "/tmp/memes/good-dog.png"),
+        Some("good-dog.png".to_string())
+    );
+    // copied somewhere else in the bucket, but still named after the file
+    assert_eq!(
+        renamed_key(
+            "archive/2024/dog.png",
+            "/tmp/memes/dog.png",
+            "/tmp/memes/good-dog.png"
+        ),
+        Some("archive/2024/good-dog.png".to_string())
+    );
+    // a content hash doesn't change when the file's renamed
+    assert_eq!(
+        renamed_key(
+            "3a7bd3e2360a3d29eea436fcfb7e44c735d117c42d1c1835420b6b9942dd4f1b.png",
+            "/tmp/memes/dog.png",
+            "/tmp/memes/good-dog.png"
+        ),
+        None
+    );
+    // moved to another directory, the name's the same
+    assert_eq!(
+        renamed_key("dog.png", "/tmp/memes/dog.png", "/tmp/archive/dog.png"),
+        None
+    );
+}
+
+#[test]
+fn test_retryable_statuses() {
+    assert!(is_retryable_status(429));
+    assert!(is_retryable_status(503));
+    assert!(!is_retryable_status(403));
+    assert!(!is_retryable_status(404));
+}
+
+#[test]
+fn test_part_count() {
+    assert_eq!(part_count(0, MULTIPART_PART_SIZE), 1);
+    assert_eq!(part_count(MULTIPART_PART_SIZE, MULTIPART_PART_SIZE), 1);
+    assert_eq!(part_count(MULTIPART_PART_SIZE + 1, MULTIPART_PART_SIZE), 2);
+    assert_eq!(part_count(3 * MULTIPART_PART_SIZE, MULTIPART_PART_SIZE), 3);
+    assert_eq!(part_count(100, 0), 1);
+}
+
+#[test]
+fn test_upload_status_progress() {
+    let mut status = UploadStatus::new();
+    assert_eq!(status.fraction(), None);
+    assert_eq!(status.remaining(), None);
+
+    status.total_bytes = Some(400);
+    assert_eq!(status.fraction(), Some(0.0));
+    // nothing's gone yet, so there's no telling how long it'll be
+    assert_eq!(status.remaining(), None);
+
+    status.started -= Duration::from_secs(10);
+    status.bytes_sent = 100;
+    assert_eq!(status.fraction(), Some(0.25));
+    let remaining = status.remaining().unwrap().as_secs_f64();
+    assert!((29.0..=31.0).contains(&remaining), "{remaining}");
+
+    status.total_bytes = Some(0);
+    assert_eq!(status.fraction(), Some(1.0));
+}
+
+/// what S3 sends back for a failed HEAD bucket, `code` is missing when there's no body
+fn service_error(code: Option<&str>, status: u16) -> SdkError<HeadBucketError, HttpResponse> {
+    let mut metadata = ErrorMetadata::builder();
+    if let Some(code) = code {
+        metadata = metadata.code(code);
+    }
+    SdkError::service_error(
+        HeadBucketError::generic(metadata.build()),
+        HttpResponse::new(StatusCode::try_from(status).unwrap(), SdkBody::empty()),
+    )
+}
+
+fn dispatch_failure(error: ConnectorError) -> SdkError<HeadBucketError, HttpResponse> {
+    SdkError::dispatch_failure(error)
+}
+
+#[test]
+fn test_s3_error_kind_from_service_errors() {
+    let cases = [
+        (
+            Some("InvalidAccessKeyId"),
+            403,
+            S3ErrorKind::InvalidCredentials,
+        ),
+        (
+            Some("SignatureDoesNotMatch"),
+            403,
+            S3ErrorKind::InvalidCredentials,
+        ),
+        (Some("AccessDenied"), 403, S3ErrorKind::AccessDenied),
+        (Some("NoSuchBucket"), 404, S3ErrorKind::NoSuchBucket),
+        (Some("SlowDown"), 503, S3ErrorKind::Throttled),
+        // HEAD bucket only has the status to go on
+        (None, 403, S3ErrorKind::AccessDenied),
+        (None, 404, S3ErrorKind::NoSuchBucket),
+        (None, 500, S3ErrorKind::Throttled),
+        (Some("NoSuchKey"), 404, S3ErrorKind::Other),
+        (None, 400, S3ErrorKind::Other),
+    ];
+    for (code, status, expected) in cases {
+        assert_eq!(
+            S3ErrorKind::from_sdk_error(&service_error(code, status)),
+            expected,
+            "{:?} {}",
+            code,
+            status
+        );
+    }
+}
+
+#[test]
+fn test_s3_error_kind_from_connection_errors() {
+    let refused = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "connection refused");
+    assert_eq!(
+        S3ErrorKind::from_sdk_error(&dispatch_failure(ConnectorError::io(refused.into()))),
+        S3ErrorKind::NetworkUnreachable
+    );
+    assert_eq!(
+        S3ErrorKind::from_sdk_error(&dispatch_failure(ConnectorError::timeout(
+            "connect timed out".into()
+        ))),
+        S3ErrorKind::Timeout
+    );
+    assert_eq!(
+        S3ErrorKind::from_sdk_error(&SdkError::<HeadBucketError, HttpResponse>::timeout_error(
+            "operation timed out"
+        )),
+        S3ErrorKind::Timeout
+    );
+    assert_eq!(
+        S3ErrorKind::from_sdk_error(&dispatch_failure(ConnectorError::other(
+            "invalid peer certificate: UnknownIssuer".into(),
+            None
+        ))),
+        S3ErrorKind::Tls
+    );
+    assert_eq!(
+        S3ErrorKind::from_sdk_error(&dispatch_failure(ConnectorError::other(
+            "something else".into(),
+            None
+        ))),
+        S3ErrorKind::Other
+    );
+}
+
+#[test]
+fn test_s3_result_messages() {
+    let error = S3Error::from_sdk_error("memes", &service_error(Some("NoSuchBucket"), 404));
+    assert_eq!(error.kind, S3ErrorKind::NoSuchBucket);
+    assert!(error.details.starts_with("memes: "));
+
+    let result = S3Result::BucketNotAccessible(error);
+    // the user gets the short version, the SDK's Debug output is kept for the details
+    assert_eq!(result.to_string(), S3ErrorKind::NoSuchBucket.user_message());
+    assert!(result.details().contains("NoSuchBucket"));
+    assert!(std::error::Error::source(&result).is_some());
+
+    assert!(!S3Result::FileNotFound.details().is_empty());
+}
@@ -0,0 +1,66 @@
+use memetool::search::SearchQuery;
+
+#[test]
+fn test_empty_search() {
+    assert_eq!(SearchQuery::new("", false), None);
+    assert_eq!(SearchQuery::new("   ", true), None);
+}
+
+#[test]
+fn test_all_terms_have_to_match() {
+    let query = SearchQuery::new("cat  hat", false).unwrap();
+    assert!(query.matches("Cat_in_the_HAT.jpg"));
+    assert!(!query.matches("cat.jpg"));
+}
+
+#[test]
+fn test_match_case() {
+    let query = SearchQuery::new("Cat", true).unwrap();
+    assert!(query.matches("Cat.jpg"));
+    assert!(!query.matches("cat.jpg"));
+}
+
+#[test]
+fn test_match_ranges() {
+    let query = SearchQuery::new("a cat", false).unwrap();
+    // "a" is inside both "cat"s, so they're joined up
+    assert_eq!(
+        query.match_ranges("CAT_and_cat.png"),
+        Some(vec![0..3, 4..5, 8..11])
+    );
+    assert_eq!(query.match_ranges("dog.png"), None);
+}
+
+#[test]
+fn test_match_ranges_overlapping_terms() {
+    let query = SearchQuery::new("abc bcd", true).unwrap();
+    assert_eq!(query.match_ranges("xabcdx"), Some(vec![1..5]));
+}
+
+#[test]
+fn test_match_ranges_non_ascii() {
+    // "İ" lowercases to two characters, the range still has to cover it in the filename
+    let query = SearchQuery::new("i\u{307}x", false).unwrap();
+    let filename = "aİx.jpg";
+    let ranges = query.match_ranges(filename).unwrap();
+    assert_eq!(ranges, vec![1..4]);
+    assert_eq!(&filename[ranges[0].clone()], "İx");
+
+    let query = SearchQuery::new("über", false).unwrap();
+    let filename = "ÜBER meme.png";
+    let ranges = query.match_ranges(filename).unwrap();
+    assert_eq!(&filename[ranges[0].clone()], "ÜBER");
+}
+
+#[test]
+fn test_matches_tagged() {
+    let query = SearchQuery::new("cat Memes", false).unwrap();
+    assert!(query.matches_tagged("cat.jpg", &["memes"]));
+    assert!(query.matches_tagged("cat memes.jpg", &[]));
+    // the tag has to be the whole term
+    assert!(!query.matches_tagged("cat.jpg", &["memes2"]));
+    assert!(!query.matches_tagged("dog.jpg", &["memes"]));
+
+    let query = SearchQuery::new("Memes", true).unwrap();
+    assert!(!query.matches_tagged("cat.jpg", &["memes"]));
+}
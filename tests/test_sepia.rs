@@ -0,0 +1,33 @@
+use memetool::image_utils::{apply_sepia, ImageAction};
+
+#[test]
+fn test_sepia_known_colours() {
+    let image = image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(2, 1, |x, _| {
+        if x == 0 {
+            image::Rgb([50, 100, 150])
+        } else {
+            image::Rgb([255, 255, 255])
+        }
+    }));
+    let sepia = apply_sepia(&image);
+    assert_eq!(sepia.color(), image::ColorType::Rgb8);
+    let sepia = sepia.to_rgb8();
+    assert_eq!(sepia.get_pixel(0, 0), &image::Rgb([125, 111, 87]));
+    // red and green go over, so they're clamped
+    assert_eq!(sepia.get_pixel(1, 0), &image::Rgb([255, 255, 239]));
+}
+
+#[test]
+fn test_sepia_saves_over_the_file_and_keeps_transparency() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("clear.png");
+    image::RgbaImage::from_pixel(4, 4, image::Rgba([50, 100, 150, 40]))
+        .save(&filepath)
+        .unwrap();
+
+    assert!(ImageAction::Sepia.overwrites());
+    ImageAction::Sepia.apply(&filepath).unwrap();
+
+    let sepia = image::open(&filepath).unwrap().to_rgba8();
+    assert_eq!(sepia.get_pixel(2, 2), &image::Rgba([125, 111, 87, 40]));
+}
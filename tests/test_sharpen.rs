@@ -0,0 +1,80 @@
+use memetool::image_utils::{apply_unsharp_mask, ImageAction};
+
+/// dark on the left, light on the right
+fn edge() -> image::DynamicImage {
+    image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(8, 4, |x, _| {
+        if x < 4 {
+            image::Rgb([80, 80, 80])
+        } else {
+            image::Rgb([160, 160, 160])
+        }
+    }))
+}
+
+#[test]
+fn test_unsharp_mask_increases_contrast_at_edges() {
+    let mut image = edge();
+    apply_unsharp_mask(&mut image, 1.0, 1.0, 2);
+    let sharpened = image.to_rgb8();
+    assert!(sharpened.get_pixel(3, 1)[0] < 80);
+    assert!(sharpened.get_pixel(4, 1)[0] > 160);
+    // far enough from the edge that nothing changes
+    assert_eq!(sharpened.get_pixel(0, 1), &image::Rgb([80, 80, 80]));
+}
+
+#[test]
+fn test_unsharp_mask_threshold() {
+    let mut image = edge();
+    apply_unsharp_mask(&mut image, 1.0, 1.0, 100);
+    assert_eq!(image, edge());
+}
+
+#[test]
+fn test_unsharp_mask_does_nothing_without_sigma_or_amount() {
+    let mut image = edge();
+    apply_unsharp_mask(&mut image, 0.0, 1.0, 0);
+    apply_unsharp_mask(&mut image, 1.0, 0.0, 0);
+    assert_eq!(image, edge());
+}
+
+#[test]
+fn test_sharpen_keeps_transparency() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("clear.png");
+    image::RgbaImage::from_fn(8, 4, |x, _| {
+        if x < 4 {
+            image::Rgba([80, 80, 80, 40])
+        } else {
+            image::Rgba([160, 160, 160, 200])
+        }
+    })
+    .save(&filepath)
+    .unwrap();
+
+    ImageAction::Sharpen {
+        sigma: 1.0,
+        amount: 1.0,
+        threshold: 0,
+    }
+    .apply(&filepath)
+    .unwrap();
+
+    let sharpened = image::open(&filepath).unwrap().to_rgba8();
+    assert_eq!(sharpened.get_pixel(3, 1)[3], 40);
+    assert_eq!(sharpened.get_pixel(4, 1)[3], 200);
+    assert!(sharpened.get_pixel(4, 1)[0] > 160);
+}
+
+#[test]
+fn test_sharpen_needs_a_radius() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("edge.png");
+    edge().save(&filepath).unwrap();
+    assert!(ImageAction::Sharpen {
+        sigma: 0.0,
+        amount: 1.0,
+        threshold: 0,
+    }
+    .apply(&filepath)
+    .is_err());
+}
@@ -0,0 +1,87 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use memetool::single_instance::{forward_to, listen_at};
+use memetool::AppMsg;
+
+#[test]
+fn test_forward_without_a_running_instance() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let socket = tempdir.path().join("memetool.sock");
+    assert!(!forward_to(&socket, tempdir.path()).unwrap());
+}
+
+#[tokio::test]
+async fn test_forward_to_running_instance() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let socket = tempdir.path().join("memetool.sock");
+    let image = tempdir.path().join("cat.jpg");
+    std::fs::write(&image, b"not really a jpeg").unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let listener = tokio::spawn(listen_at(socket.clone(), tx));
+    // give it a chance to bind
+    while !socket.exists() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let (forward_socket, forward_image) = (socket.clone(), image.clone());
+    let forwarded =
+        tokio::task::spawn_blocking(move || forward_to(&forward_socket, &forward_image))
+            .await
+            .unwrap()
+            .unwrap();
+    assert!(forwarded);
+
+    match tokio::time::timeout(Duration::from_secs(5), rx.recv()).await {
+        Ok(Some(AppMsg::OpenPath(path))) => {
+            assert_eq!(path, std::fs::canonicalize(&image).unwrap())
+        }
+        other => panic!("Expected OpenPath, got {:?}", other),
+    }
+
+    // stopping it tidies up after itself
+    listener.abort();
+    let _ = listener.await;
+    assert!(!socket.exists());
+}
+
+#[tokio::test]
+async fn test_second_listener_leaves_the_running_one_alone() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let socket = tempdir.path().join("memetool.sock");
+    let image = tempdir.path().join("cat.jpg");
+    std::fs::write(&image, b"not really a jpeg").unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let first = tokio::spawn(listen_at(socket.clone(), tx));
+    while !socket.exists() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // started without a path, so it never checked for the first one
+    let (second_tx, _second_rx) = tokio::sync::mpsc::channel(10);
+    tokio::time::timeout(Duration::from_secs(5), listen_at(socket.clone(), second_tx))
+        .await
+        .expect("The second one should give up straight away");
+    assert!(socket.exists());
+
+    let (forward_socket, forward_image) = (socket.clone(), image.clone());
+    let forwarded =
+        tokio::task::spawn_blocking(move || forward_to(&forward_socket, &forward_image))
+            .await
+            .unwrap()
+            .unwrap();
+    assert!(forwarded);
+    // the second one's check connected without sending anything, so this is the first message
+    match tokio::time::timeout(Duration::from_secs(5), rx.recv()).await {
+        Ok(Some(AppMsg::OpenPath(path))) => {
+            assert_eq!(path, std::fs::canonicalize(&image).unwrap())
+        }
+        other => panic!("Expected OpenPath, got {:?}", other),
+    }
+
+    first.abort();
+    let _ = first.await;
+}
@@ -0,0 +1,11 @@
+use memetool::slideshow_index;
+
+#[test]
+fn test_slideshow_index_wraps() {
+    assert_eq!(slideshow_index(0, 1, 3), 1);
+    assert_eq!(slideshow_index(2, 1, 3), 0);
+    assert_eq!(slideshow_index(0, -1, 3), 2);
+    // files went away while it was running
+    assert_eq!(slideshow_index(7, 1, 3), 2);
+    assert_eq!(slideshow_index(4, 1, 0), 0);
+}
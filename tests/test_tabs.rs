@@ -0,0 +1,102 @@
+use memetool::tabs::{Tabs, WorkdirTab};
+
+fn tabs(workdirs: &[&str], active: usize) -> Tabs {
+    Tabs::new(
+        workdirs.iter().map(WorkdirTab::new).collect(),
+        active,
+        "~/Downloads",
+    )
+}
+
+fn workdirs(tabs: &Tabs) -> Vec<&str> {
+    tabs.tabs().iter().map(|tab| tab.workdir.as_str()).collect()
+}
+
+#[test]
+fn test_new_without_saved_tabs() {
+    let tabs = Tabs::new(vec![], 3, "~/Downloads");
+    assert_eq!(workdirs(&tabs), vec!["~/Downloads"]);
+    assert_eq!(tabs.active_index(), 0);
+}
+
+#[test]
+fn test_new_clamps_active() {
+    let tabs = tabs(&["/a", "/b"], 5);
+    assert_eq!(tabs.active_index(), 1);
+    assert_eq!(tabs.active().workdir, "/b");
+}
+
+#[test]
+fn test_open_goes_after_active() {
+    let mut tabs = tabs(&["/a", "/b", "/c"], 0);
+    assert_eq!(tabs.open(WorkdirTab::new("/d")), 1);
+    assert_eq!(workdirs(&tabs), vec!["/a", "/d", "/b", "/c"]);
+    assert_eq!(tabs.active().workdir, "/d");
+}
+
+#[test]
+fn test_switch() {
+    let mut tabs = tabs(&["/a", "/b"], 0);
+    assert!(tabs.switch(1));
+    assert_eq!(tabs.active().workdir, "/b");
+    assert!(!tabs.switch(2));
+    assert_eq!(tabs.active_index(), 1);
+}
+
+#[test]
+fn test_close_last_tab() {
+    let mut tabs = tabs(&["/a"], 0);
+    assert!(tabs.close(0).is_none());
+    assert_eq!(tabs.len(), 1);
+}
+
+#[test]
+fn test_close_keeps_active_tab() {
+    let mut tabs = tabs(&["/a", "/b", "/c"], 2);
+    assert_eq!(tabs.close(0).map(|tab| tab.workdir), Some("/a".to_string()));
+    assert_eq!(tabs.active().workdir, "/c");
+
+    let mut tabs = self::tabs(&["/a", "/b", "/c"], 0);
+    tabs.close(2);
+    assert_eq!(tabs.active().workdir, "/a");
+}
+
+#[test]
+fn test_close_active_tab() {
+    let mut tabs = tabs(&["/a", "/b", "/c"], 1);
+    tabs.close(1);
+    assert_eq!(tabs.active().workdir, "/c");
+
+    // the one before takes over when it was at the end
+    tabs.close(1);
+    assert_eq!(tabs.active().workdir, "/a");
+    assert!(tabs.close(7).is_none());
+}
+
+#[test]
+fn test_tab_ids_are_unique() {
+    let first = WorkdirTab::new("/a");
+    let second = WorkdirTab::new("/a");
+    assert_ne!(first.id, second.id);
+}
+
+#[test]
+fn test_tab_serde_round_trip() {
+    let mut tab = WorkdirTab::new("~/memes");
+    tab.search = "cat".to_string();
+    tab.context.current_page = 3;
+    let json = serde_json::to_string(&tab).expect("Failed to serialize tab");
+    assert!(!json.contains("\"id\""));
+
+    let loaded: WorkdirTab = serde_json::from_str(&json).expect("Failed to parse tab");
+    assert_eq!(loaded.workdir, "~/memes");
+    assert_eq!(loaded.search, "cat");
+    assert_eq!(loaded.context.current_page, 3);
+    // ids are only for while memetool's running
+    assert_ne!(loaded.id, tab.id);
+
+    let minimal: WorkdirTab =
+        serde_json::from_str(r#"{"workdir": "/tmp"}"#).expect("Failed to parse minimal tab");
+    assert_eq!(minimal.search, "");
+    assert_eq!(minimal.label(), "tmp");
+}
@@ -0,0 +1,61 @@
+use memetool::tags::{directory_tag, TagStore};
+
+#[test]
+fn test_directory_tag() {
+    assert_eq!(
+        directory_tag("/home/user/Pictures/memes"),
+        Some("memes".to_string())
+    );
+    // trailing slashes don't make it empty
+    assert_eq!(
+        directory_tag("/home/user/Pictures/memes/"),
+        Some("memes".to_string())
+    );
+    assert_eq!(directory_tag("/"), None);
+}
+
+#[test]
+fn test_add_and_count_missing() {
+    let mut tags = TagStore::default();
+    assert!(tags.add("/tmp/memes/cat.jpg", "memes"));
+    // adding it again doesn't change anything
+    assert!(!tags.add("/tmp/memes/cat.jpg", "memes"));
+    assert!(tags.add("/tmp/memes/cat.jpg", "cats"));
+    assert_eq!(tags.tags("/tmp/memes/cat.jpg"), vec!["cats", "memes"]);
+    assert!(tags.tags("/tmp/memes/dog.jpg").is_empty());
+
+    let filepaths = [
+        "/tmp/memes/cat.jpg",
+        "/tmp/memes/dog.jpg",
+        "/tmp/memes/owl.jpg",
+    ];
+    assert_eq!(tags.count_missing(filepaths, "memes"), 2);
+    assert_eq!(tags.count_missing(filepaths, "birds"), 3);
+}
+
+#[test]
+fn test_tags_follow_moves() {
+    let mut tags = TagStore::default();
+    tags.add("/tmp/memes/cat.jpg", "memes");
+    tags.add("/tmp/sorted/cat.jpg", "sorted");
+    tags.move_file("/tmp/memes/cat.jpg", "/tmp/sorted/cat.jpg");
+    assert!(!tags.has_tag("/tmp/memes/cat.jpg", "memes"));
+    assert_eq!(tags.tags("/tmp/sorted/cat.jpg"), vec!["memes", "sorted"]);
+    assert_eq!(tags.len(), 1);
+}
+
+#[test]
+fn test_save_and_load() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("tags.json");
+
+    // no file yet is no tags
+    assert!(TagStore::load(&path).unwrap().is_empty());
+
+    let mut tags = TagStore::default();
+    tags.add("/tmp/memes/cat.jpg", "memes");
+    tags.save(&path).unwrap();
+
+    let loaded = TagStore::load(&path).unwrap();
+    assert!(loaded.has_tag("/tmp/memes/cat.jpg", "memes"));
+}
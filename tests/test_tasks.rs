@@ -0,0 +1,73 @@
+use memetool::tasks::{TaskPurpose, TaskTracker};
+use memetool::AppMsg;
+
+#[test]
+fn test_task_ids_go_up() {
+    let mut tasks = TaskTracker::default();
+    let first = tasks.start(TaskPurpose::Thumbnail);
+    let second = tasks.start(TaskPurpose::Preview);
+    let third = tasks.start(TaskPurpose::Thumbnail);
+    assert!(first < second && second < third);
+    assert_eq!(tasks.len(), 3);
+}
+
+#[test]
+fn test_out_of_order_responses() {
+    let mut tasks = TaskTracker::default();
+    let thumbnail = tasks.start(TaskPurpose::Thumbnail);
+    let preview = tasks.start(TaskPurpose::Preview);
+    let other_thumbnail = tasks.start(TaskPurpose::Thumbnail);
+
+    // the responses come back in a different order to the requests
+    assert_eq!(tasks.finish(other_thumbnail), Some(TaskPurpose::Thumbnail));
+    assert_eq!(tasks.finish(preview), Some(TaskPurpose::Preview));
+    assert!(tasks.is_outstanding(thumbnail));
+    assert_eq!(tasks.finish(thumbnail), Some(TaskPurpose::Thumbnail));
+    assert!(tasks.is_empty());
+
+    // a second response for the same task is stale
+    assert_eq!(tasks.finish(preview), None);
+}
+
+#[test]
+fn test_forgotten_tasks_are_stale() {
+    let mut tasks = TaskTracker::default();
+    let old_preview = tasks.start(TaskPurpose::Preview);
+    let thumbnail = tasks.start(TaskPurpose::Thumbnail);
+
+    tasks.forget(TaskPurpose::Preview);
+    let new_preview = tasks.start(TaskPurpose::Preview);
+    assert_eq!(tasks.outstanding(TaskPurpose::Preview), 1);
+
+    assert_eq!(tasks.finish(new_preview), Some(TaskPurpose::Preview));
+    assert_eq!(tasks.finish(old_preview), None);
+    assert_eq!(tasks.finish(thumbnail), Some(TaskPurpose::Thumbnail));
+}
+
+#[test]
+fn test_track_tags_batched_requests() {
+    let mut tasks = TaskTracker::default();
+    let mut msg = AppMsg::Batch(vec![
+        AppMsg::LoadPreview {
+            filepath: "/tmp/memes/01.jpg".to_string(),
+            task: None,
+        },
+        AppMsg::Echo("hello".to_string()),
+        AppMsg::Batch(vec![AppMsg::LoadImageFromUrl {
+            url: "https://example.com/02.jpg".to_string(),
+            page: 0,
+            task: None,
+        }]),
+    ]);
+    msg.track(&mut tasks);
+
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks.outstanding(TaskPurpose::Preview), 1);
+    assert_eq!(tasks.outstanding(TaskPurpose::Thumbnail), 1);
+    match msg {
+        AppMsg::Batch(msgs) => {
+            assert!(matches!(msgs[0], AppMsg::LoadPreview { task: Some(_), .. }));
+        }
+        other => panic!("Expected Batch, got {:?}", other),
+    }
+}
@@ -0,0 +1,83 @@
+use memetool::thumbnail_cache::ThumbnailCache;
+
+#[test]
+fn test_lru_eviction() {
+    let mut cache: ThumbnailCache<u8> = ThumbnailCache::new(30);
+    cache.insert("/a.jpg".to_string(), None, 1, 10);
+    cache.insert("/b.jpg".to_string(), None, 2, 10);
+    cache.insert("/c.jpg".to_string(), None, 3, 10);
+    // use a, so b's the oldest
+    assert_eq!(cache.get("/a.jpg"), Some(&1));
+    cache.insert("/d.jpg".to_string(), None, 4, 10);
+
+    assert!(cache.contains("/a.jpg"));
+    assert!(!cache.contains("/b.jpg"));
+    assert!(cache.contains("/c.jpg"));
+    assert!(cache.contains("/d.jpg"));
+    assert_eq!(cache.used_bytes(), 30);
+}
+
+#[test]
+fn test_prune_stale() {
+    let tempdir = tempfile::tempdir().expect("Failed to create tempdir");
+    let kept = tempdir.path().join("kept.jpg");
+    let deleted = tempdir.path().join("deleted.jpg");
+    let modified = tempdir.path().join("modified.jpg");
+    for filepath in [&kept, &deleted, &modified] {
+        std::fs::write(filepath, b"hello").expect("Failed to write test file");
+    }
+
+    let mut cache: ThumbnailCache<u8> = ThumbnailCache::new(1024);
+    for filepath in [&kept, &deleted, &modified] {
+        cache.insert(
+            filepath.display().to_string(),
+            ThumbnailCache::<u8>::modified_time(filepath),
+            0,
+            1,
+        );
+    }
+
+    std::fs::remove_file(&deleted).expect("Failed to delete test file");
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    filetime::set_file_mtime(&modified, filetime::FileTime::from_system_time(later))
+        .expect("Failed to set modified time");
+
+    assert_eq!(cache.prune_stale(), 2);
+    assert!(cache.contains(&kept.display().to_string()));
+    assert!(!cache.contains(&deleted.display().to_string()));
+    assert!(!cache.contains(&modified.display().to_string()));
+}
+
+#[test]
+fn test_prune_stale_among() {
+    let tempdir = tempfile::tempdir().expect("Failed to create tempdir");
+    let on_page = tempdir.path().join("on_page.jpg");
+    let elsewhere = tempdir.path().join("elsewhere.jpg");
+
+    let mut cache: ThumbnailCache<u8> = ThumbnailCache::new(1024);
+    // neither of them exist, but only the one that's asked about gets checked
+    for filepath in [&on_page, &elsewhere] {
+        cache.insert(filepath.display().to_string(), None, 0, 1);
+    }
+    let on_page = on_page.display().to_string();
+    assert_eq!(
+        cache.prune_stale_among([on_page.as_str(), "/not/cached.jpg"]),
+        1
+    );
+    assert!(!cache.contains(&on_page));
+    assert!(cache.contains(&elsewhere.display().to_string()));
+}
+
+#[test]
+fn test_retain() {
+    let mut cache: ThumbnailCache<u8> = ThumbnailCache::new(100);
+    cache.insert("/page1/a.jpg".to_string(), None, 1, 10);
+    cache.insert("/page1/b.jpg".to_string(), None, 2, 10);
+    cache.insert("/page2/c.jpg".to_string(), None, 3, 10);
+
+    assert_eq!(cache.retain(|filepath| filepath.starts_with("/page1/")), 1);
+    assert!(cache.contains("/page1/a.jpg"));
+    assert!(cache.contains("/page1/b.jpg"));
+    assert!(!cache.contains("/page2/c.jpg"));
+    assert_eq!(cache.used_bytes(), 20);
+}
@@ -0,0 +1,17 @@
+use memetool::thumbnail_label;
+
+#[test]
+fn test_thumbnail_label() {
+    assert_eq!(
+        thumbnail_label("/tmp/memes/cat.jpg", true, false, false),
+        "cat.jpg"
+    );
+    assert_eq!(
+        thumbnail_label("/tmp/memes/cat.jpg", false, false, false),
+        "cat.jpg, loading"
+    );
+    assert_eq!(
+        thumbnail_label("/tmp/memes/cat.jpg", true, true, true),
+        "cat.jpg, uploaded, appears to be corrupt"
+    );
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use memetool::trash::TrashJournal;
+
+fn setup() -> (tempfile::TempDir, String) {
+    let tempdir = tempfile::tempdir().unwrap();
+    let filepath = tempdir.path().join("meme.png");
+    std::fs::write(&filepath, b"not really a png").unwrap();
+    (tempdir, filepath.display().to_string())
+}
+
+#[test]
+fn test_trash_and_restore() {
+    let (tempdir, filepath) = setup();
+    let mut trash = TrashJournal::default();
+    let entry = trash
+        .trash(&filepath, &tempdir.path().join("trash"))
+        .unwrap();
+    assert!(!Path::new(&filepath).exists());
+    assert!(entry.available());
+    assert_eq!(entry.original_path, filepath);
+    assert_eq!(
+        entry.restore_path(),
+        Some(Path::new(&filepath).to_path_buf())
+    );
+
+    trash
+        .restore(&entry.trash_path, Path::new(&filepath))
+        .unwrap();
+    assert!(trash.is_empty());
+    assert_eq!(std::fs::read(&filepath).unwrap(), b"not really a png");
+}
+
+#[test]
+fn test_restore_when_original_is_taken() {
+    let (tempdir, filepath) = setup();
+    let mut trash = TrashJournal::default();
+    let entry = trash
+        .trash(&filepath, &tempdir.path().join("trash"))
+        .unwrap();
+    std::fs::write(&filepath, b"something new").unwrap();
+
+    let destination = entry.restore_path().unwrap();
+    assert_eq!(destination, tempdir.path().join("meme_restored1.png"));
+    assert!(trash
+        .restore(&entry.trash_path, Path::new(&filepath))
+        .is_err());
+    trash.restore(&entry.trash_path, &destination).unwrap();
+    assert_eq!(std::fs::read(&filepath).unwrap(), b"something new");
+    assert_eq!(std::fs::read(&destination).unwrap(), b"not really a png");
+}
+
+#[test]
+fn test_purged_elsewhere_is_unavailable() {
+    let (tempdir, filepath) = setup();
+    let mut trash = TrashJournal::default();
+    let entry = trash
+        .trash(&filepath, &tempdir.path().join("trash"))
+        .unwrap();
+    std::fs::remove_file(&entry.trash_path).unwrap();
+
+    assert!(!trash.entries()[0].available());
+    assert!(trash
+        .restore(&entry.trash_path, Path::new(&filepath))
+        .is_err());
+    // purging something that's already gone just forgets it
+    trash.purge(&entry.trash_path).unwrap();
+    assert!(trash.is_empty());
+}
+
+#[test]
+fn test_take_expired() {
+    let (tempdir, filepath) = setup();
+    let mut trash = TrashJournal::default();
+    trash
+        .trash(&filepath, &tempdir.path().join("trash"))
+        .unwrap();
+
+    let now = SystemTime::now();
+    assert!(trash.take_expired(30, now).is_empty());
+    // zero days keeps things forever
+    let later = now + Duration::from_secs(365 * 86400);
+    assert!(trash.take_expired(0, later).is_empty());
+    assert_eq!(trash.take_expired(30, later).len(), 1);
+    assert!(trash.is_empty());
+}
+
+#[test]
+fn test_journal_round_trip() {
+    let (tempdir, filepath) = setup();
+    let mut trash = TrashJournal::default();
+    trash
+        .trash(&filepath, &tempdir.path().join("trash"))
+        .unwrap();
+    let journal = tempdir.path().join("trash.json");
+    trash.save(&journal).unwrap();
+
+    let loaded = TrashJournal::load(&journal).unwrap();
+    assert_eq!(loaded.entries(), trash.entries());
+    assert!(TrashJournal::load(&tempdir.path().join("missing.json"))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_keep_and_restore_backup() {
+    let (tempdir, filepath) = setup();
+    let trash_dir = tempdir.path().join("trash");
+    let mut trash = TrashJournal::default();
+    let first = trash.keep_backup(&filepath, &trash_dir).unwrap();
+    assert!(first.backup);
+    // it's a copy, the file's still being edited
+    assert!(Path::new(&filepath).exists());
+
+    std::fs::write(&filepath, b"rotated").unwrap();
+    let second = trash.keep_backup(&filepath, &trash_dir).unwrap();
+    std::fs::write(&filepath, b"rotated and bordered").unwrap();
+
+    assert_eq!(trash.latest_backup(&filepath), Some(&second));
+    trash.restore_backup(&second.trash_path).unwrap();
+    assert_eq!(std::fs::read(&filepath).unwrap(), b"rotated");
+
+    assert_eq!(trash.latest_backup(&filepath), Some(&first));
+    trash.restore_backup(&first.trash_path).unwrap();
+    assert_eq!(std::fs::read(&filepath).unwrap(), b"not really a png");
+    assert_eq!(trash.latest_backup(&filepath), None);
+    assert!(trash.is_empty());
+}
+
+#[test]
+fn test_deleted_files_arent_backups() {
+    let (tempdir, filepath) = setup();
+    let mut trash = TrashJournal::default();
+    let entry = trash
+        .trash(&filepath, &tempdir.path().join("trash"))
+        .unwrap();
+    assert!(!entry.backup);
+    assert_eq!(trash.latest_backup(&filepath), None);
+    assert!(trash.restore_backup(&entry.trash_path).is_err());
+}
+
+#[test]
+fn test_journal_from_before_backups() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let journal = tempdir.path().join("trash.json");
+    std::fs::write(
+        &journal,
+        r#"[{"original_path": "/memes/cat.jpg", "trash_path": "/trash/1-0-cat.jpg", "deleted_at": 1}]"#,
+    )
+    .unwrap();
+    let trash = TrashJournal::load(&journal).unwrap();
+    assert!(!trash.entries()[0].backup);
+}
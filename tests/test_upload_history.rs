@@ -0,0 +1,42 @@
+use memetool::upload_history::{UploadHistory, UploadRecord};
+
+fn record(filepath: &str, key: &str, uploaded_at: u64) -> UploadRecord {
+    UploadRecord {
+        filepath: filepath.to_string(),
+        key: key.to_string(),
+        uploaded_at,
+        checksum: None,
+    }
+}
+
+#[test]
+fn test_lookup_returns_latest_upload() {
+    let mut history = UploadHistory::default();
+    history.record(record("/tmp/memes/cat.jpg", "cat.jpg", 100));
+    history.record(record("/tmp/memes/dog.jpg", "dog.jpg", 200));
+    history.record(record("/tmp/memes/cat.jpg", "abc123.jpg", 300));
+
+    assert!(history.contains("/tmp/memes/dog.jpg"));
+    assert!(!history.contains("/tmp/memes/bird.jpg"));
+    assert_eq!(history.get("/tmp/memes/cat.jpg").unwrap().key, "abc123.jpg");
+    // everything's kept
+    assert_eq!(history.len(), 3);
+}
+
+#[test]
+fn test_save_and_load() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("uploads.json");
+
+    // no file yet is an empty history
+    assert!(UploadHistory::load(&path).unwrap().is_empty());
+
+    let mut history = UploadHistory::default();
+    history.record(record("/tmp/memes/cat.jpg", "cat.jpg", 100));
+    history.record(record("/tmp/memes/cat.jpg", "cat2.jpg", 200));
+    history.save(&path).unwrap();
+
+    let loaded = UploadHistory::load(&path).unwrap();
+    assert_eq!(loaded.records(), history.records());
+    assert_eq!(loaded.get("/tmp/memes/cat.jpg").unwrap().key, "cat2.jpg");
+}
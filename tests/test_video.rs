@@ -0,0 +1,50 @@
+use std::path::Path;
+use std::time::Duration;
+
+use memetool::video::{format_duration, is_video, parse_probe_output, VideoInfo};
+use memetool::OK_EXTENSIONS;
+
+#[test]
+fn test_is_video() {
+    assert!(is_video(Path::new("/tmp/memes/reaction.mp4")));
+    assert!(is_video(Path::new("/tmp/memes/reaction.WEBM")));
+    assert!(is_video(Path::new("/tmp/memes/IMG_0001.mov")));
+    assert!(!is_video(Path::new("/tmp/memes/cat.gif")));
+    assert!(!is_video(Path::new("/tmp/memes/mp4")));
+}
+
+#[test]
+fn test_video_extensions_follow_the_feature() {
+    assert_eq!(OK_EXTENSIONS.contains(&"mp4"), cfg!(feature = "video"));
+    assert_eq!(OK_EXTENSIONS.contains(&"webm"), cfg!(feature = "video"));
+}
+
+#[test]
+fn test_parse_probe_output() {
+    assert_eq!(
+        parse_probe_output("width=1280\nheight=720\nduration=7.507000\n"),
+        Some(VideoInfo {
+            width: 1280,
+            height: 720,
+            duration: Some(Duration::from_millis(7507)),
+        })
+    );
+    // streams without a duration
+    assert_eq!(
+        parse_probe_output("width=640\nheight=480\nduration=N/A\n"),
+        Some(VideoInfo {
+            width: 640,
+            height: 480,
+            duration: None,
+        })
+    );
+    // audio only
+    assert_eq!(parse_probe_output("duration=183.2\n"), None);
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(Duration::from_millis(7507)), "0:07");
+    assert_eq!(format_duration(Duration::from_secs(65)), "1:05");
+    assert_eq!(format_duration(Duration::from_secs(3723)), "1:02:03");
+}
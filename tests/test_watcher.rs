@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use memetool::watcher::WatcherEventKind;
+use notify::event::{CreateKind, EventKind, ModifyKind, RemoveKind, RenameMode};
+use notify::Event;
+
+#[test]
+fn test_from_event() {
+    let cat = PathBuf::from("/tmp/memes/cat.jpg");
+    let dog = PathBuf::from("/tmp/memes/dog.png");
+
+    let created = Event::new(EventKind::Create(CreateKind::File)).add_path(cat.clone());
+    assert_eq!(
+        WatcherEventKind::from_event(&created),
+        vec![WatcherEventKind::Created(cat.clone())]
+    );
+
+    let removed = Event::new(EventKind::Remove(RemoveKind::File)).add_path(cat.clone());
+    assert_eq!(
+        WatcherEventKind::from_event(&removed),
+        vec![WatcherEventKind::Removed(cat.clone())]
+    );
+
+    let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+        .add_path(cat.clone())
+        .add_path(dog.clone());
+    assert_eq!(
+        WatcherEventKind::from_event(&renamed),
+        vec![
+            WatcherEventKind::Removed(cat.clone()),
+            WatcherEventKind::Created(dog)
+        ]
+    );
+
+    let modified = Event::new(EventKind::Modify(ModifyKind::Any)).add_path(cat.clone());
+    assert_eq!(
+        WatcherEventKind::from_event(&modified),
+        vec![WatcherEventKind::Modified(cat)]
+    );
+}
+
+#[test]
+fn test_from_event_skips_other_files() {
+    let event = Event::new(EventKind::Create(CreateKind::File))
+        .add_path(PathBuf::from("/tmp/memes/notes.txt"))
+        .add_path(PathBuf::from("/tmp/memes/CAT.JPG"));
+    assert_eq!(
+        WatcherEventKind::from_event(&event),
+        vec![WatcherEventKind::Created(PathBuf::from(
+            "/tmp/memes/CAT.JPG"
+        ))]
+    );
+}